@@ -8,7 +8,7 @@ fn get_git_hash() -> String {
     use std::process::Command;
     
     let output = Command::new("git")
-        .args(&["rev-parse", "--short", "HEAD"])
+        .args(["rev-parse", "--short", "HEAD"])
         .output();
     
     match output {
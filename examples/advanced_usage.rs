@@ -1,19 +1,50 @@
-use c2md::{convert, Options};
+use c2md::{convert, register_converter, FormatConverter, Options};
+use c2md::cli::Args;
+use c2md::document::{Block, Document};
 use c2md::validator::validate_file;
 use c2md::metadata::extract_metadata;
 use std::path::Path;
 
+// A converter for Org-mode notes, registered alongside the built-in ones
+struct OrgConverter;
+
+impl FormatConverter for OrgConverter {
+    fn name(&self) -> &str {
+        "org"
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["org"]
+    }
+
+    fn read(&self, path: &Path, _config: &c2md::Config, _args: &Args) -> c2md::Result<Document> {
+        let content = std::fs::read_to_string(path)?;
+        let mut document = Document::new();
+        for line in content.lines().filter(|line| !line.trim().is_empty()) {
+            let level = line.chars().take_while(|c| *c == '*').count();
+            if level > 0 && line[level..].starts_with(' ') {
+                document.push(Block::heading(level as u8, line[level..].trim()));
+            } else {
+                document.push(Block::paragraph(line.trim()));
+            }
+        }
+        Ok(document)
+    }
+}
+
 fn main() -> Result<(), Box<dyn std::error::Error>> {
     // Basic conversion with enhanced options
-    let mut options = Options::default();
-    options.title = Some("My Document".to_string());
-    options.author = Some("John Doe".to_string());
-    options.frontmatter = "yaml".to_string();
-    options.wrap = "hard".to_string();
-    options.width = 80;
-    options.tables = "grid".to_string();
-    options.ocr = true;
-    options.ocr_lang = Some("eng".to_string());
+    let options = Options {
+        title: Some("My Document".to_string()),
+        author: Some("John Doe".to_string()),
+        frontmatter: "yaml".to_string(),
+        wrap: "hard".to_string(),
+        width: 80,
+        tables: "grid".to_string(),
+        ocr: true,
+        ocr_lang: Some("eng".to_string()),
+        ..Options::default()
+    };
     
     let markdown = convert("document.pdf", options)?;
     std::fs::write("output.md", markdown)?;
@@ -27,18 +58,22 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     println!("Document metadata: {:?}", metadata);
     
     // Advanced image processing with OCR
-    let mut image_options = Options::default();
-    image_options.ocr = true;
-    image_options.ocr_lang = Some("eng+tur".to_string());
-    image_options.images = "download".to_string();
+    let image_options = Options {
+        ocr: true,
+        ocr_lang: Some("eng+tur".to_string()),
+        images: "download".to_string(),
+        ..Options::default()
+    };
     
     let image_markdown = convert("scanned_document.png", image_options)?;
     std::fs::write("image_output.md", image_markdown)?;
     
     // Office document conversion
-    let mut office_options = Options::default();
-    office_options.tables = "grid".to_string();
-    office_options.frontmatter = "yaml".to_string();
+    let office_options = Options {
+        tables: "grid".to_string(),
+        frontmatter: "yaml".to_string(),
+        ..Options::default()
+    };
     
     let office_markdown = convert("report.docx", office_options)?;
     std::fs::write("office_output.md", office_markdown)?;
@@ -51,6 +86,11 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     let csv_markdown = convert("data.csv", Options::default())?;
     std::fs::write("csv_output.md", csv_markdown)?;
     
+    // Custom formats plug into the same dispatch
+    register_converter(OrgConverter);
+    let org_markdown = convert("notes.org", Options::default())?;
+    std::fs::write("org_output.md", org_markdown)?;
+    
     println!("All conversions completed successfully!");
     Ok(())
 }
\ No newline at end of file
@@ -105,6 +105,26 @@ pub struct Args {
     #[arg(long, default_value = "auto")]
     pub pdf_layout: String,
 
+    /// Only these pages of PDFs and multi-page TIFFs (e.g. 1-5,10,20-)
+    #[arg(long)]
+    pub pages: Option<String>,
+
+    /// PDF highlights and comments: include, none or only
+    #[arg(long, default_value = "include")]
+    pub annotations: String,
+
+    /// PDF form fields: table, list or none
+    #[arg(long, default_value = "table")]
+    pub forms: String,
+
+    /// Password of encrypted PDFs (user or owner password)
+    #[arg(long, env = "C2MD_PDF_PASSWORD", hide_env_values = true)]
+    pub password: Option<String>,
+
+    /// Read the PDF password from the first line of FILE
+    #[arg(long, value_name = "FILE")]
+    pub password_file: Option<PathBuf>,
+
     /// Custom LibreOffice binary path
     #[arg(long)]
     pub libreoffice_bin: Option<PathBuf>,
@@ -113,10 +133,22 @@ pub struct Args {
     #[arg(long)]
     pub sheet: Option<String>,
 
-    /// Select sheets for xlsx/csv
+    /// Select sheets for xlsx: all, 1-based indexes, names or globs (e.g. 1,3 or Sheet*)
     #[arg(long, default_value = "all")]
     pub sheets: String,
 
+    /// Write one file per chapter into a directory named after the output: none|chapters
+    #[arg(long, default_value = "none")]
+    pub split: String,
+
+    /// Part of HTML pages to convert: main, article, full or selector:<css>
+    #[arg(long, default_value = "main")]
+    pub html_extract: String,
+
+    /// HTML without a Markdown equivalent: keep, strip or escape
+    #[arg(long, default_value = "keep")]
+    pub raw_html: String,
+
     /// Convert equations to $...$ or leave
     #[arg(long, default_value = "auto")]
     pub math: String,
@@ -189,9 +221,17 @@ impl Clone for Args {
             ocr: self.ocr,
             ocr_lang: self.ocr_lang.clone(),
             pdf_layout: self.pdf_layout.clone(),
+            pages: self.pages.clone(),
+            annotations: self.annotations.clone(),
+            forms: self.forms.clone(),
+            password: self.password.clone(),
+            password_file: self.password_file.clone(),
             libreoffice_bin: self.libreoffice_bin.clone(),
             sheet: self.sheet.clone(),
             sheets: self.sheets.clone(),
+            split: self.split.clone(),
+            html_extract: self.html_extract.clone(),
+            raw_html: self.raw_html.clone(),
             math: self.math.clone(),
             math_block: self.math_block.clone(),
             watch: self.watch,
@@ -205,4 +245,21 @@ impl Clone for Args {
             metadata_only: self.metadata_only,
         }
     }
-}
\ No newline at end of file
+}
+
+impl Args {
+    /// The PDF password from `--password` (or `C2MD_PDF_PASSWORD`), else
+    /// from `--password-file`.
+    pub fn pdf_password(&self) -> crate::error::Result<Option<String>> {
+        if let Some(password) = &self.password {
+            return Ok(Some(password.clone()));
+        }
+        let Some(path) = &self.password_file else {
+            return Ok(None);
+        };
+        let contents = std::fs::read_to_string(path).map_err(|e| {
+            crate::error::C2mdError::Config(format!("Cannot read password file {}: {}", path.display(), e))
+        })?;
+        Ok(Some(contents.lines().next().unwrap_or_default().to_string()))
+    }
+}
@@ -1,5 +1,9 @@
-use crate::error::{C2mdError, Result};
+use crate::cli::Args;
+use crate::error::Result;
+use clap::parser::ValueSource;
+use clap::ArgMatches;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::path::PathBuf;
 use std::fs;
 
@@ -16,6 +20,12 @@ pub struct Config {
     pub ocr: OcrConfig,
     pub math: MathConfig,
     pub batch: BatchConfig,
+    #[serde(default)]
+    pub slides: SlidesConfig,
+    #[serde(default)]
+    pub cleanup: CleanupConfig,
+    #[serde(default)]
+    pub html: HtmlConfig,
     pub ignore: Vec<String>,
 }
 
@@ -27,7 +37,35 @@ pub struct ImageConfig {
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PdfConfig {
+    /// Reading order: `auto`, `raw` (content stream order) or `smart`.
     pub layout: String,
+    /// Page ranges to extract, such as `1-5,10,20-`; also applies to OCR of
+    /// multi-page TIFFs. Unset or `all` reads every page.
+    #[serde(default)]
+    pub pages: Option<String>,
+    /// Prepend a table of contents built from the document outline
+    /// (bookmarks), when there is one.
+    #[serde(default = "default_true")]
+    pub toc: bool,
+    /// Highlights and comments: `include` (marked text and footnotes),
+    /// `none`, or `only` the review notes.
+    #[serde(default = "default_annotations")]
+    pub annotations: String,
+    /// Filled-in form fields: `table`, `list` (a definition list) or `none`.
+    #[serde(default = "default_forms")]
+    pub forms: String,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+fn default_annotations() -> String {
+    "include".to_string()
+}
+
+fn default_forms() -> String {
+    "table".to_string()
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -46,6 +84,82 @@ pub struct BatchConfig {
     pub jobs: String,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SlidesConfig {
+    /// Speaker notes: `quote`, `section` (a "Notes" subsection) or `none`.
+    pub notes: String,
+}
+
+/// Cleanup of text extracted from PDFs and OCR.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CleanupConfig {
+    /// Rejoin words hyphenated at line ends, keeping compound hyphens.
+    pub dehyphenate: bool,
+    /// Expand typographic ligatures such as `ﬁ` and `ﬂ`.
+    pub ligatures: bool,
+    /// Drop soft hyphens, joining words broken at them.
+    pub soft_hyphens: bool,
+    /// Turn non-breaking spaces into plain spaces.
+    pub nbsp: bool,
+}
+
+/// HTML pages.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HtmlConfig {
+    /// Part of the page to convert: `main` (the main landmark, or the page
+    /// without navigation, banners and other chrome), `article` (the densest
+    /// block of text, scored as readability does), `full`, or
+    /// `selector:<css>` for what a CSS selector matches.
+    pub extract: String,
+    /// Elements kept when sanitizing, replacing the default set of safe
+    /// formatting, structure and table elements. Others are dropped with
+    /// their tags, keeping their content.
+    pub tags: Option<Vec<String>>,
+    /// Attributes kept on top of `id`, `class` and `data-*`, by tag name;
+    /// `*` is any tag, and a name ending in `*` is a prefix.
+    pub attributes: HashMap<String, Vec<String>>,
+    /// URL schemes kept in links and images, replacing the default list of
+    /// safe schemes. Relative URLs are always kept.
+    pub url_schemes: Option<Vec<String>>,
+    /// HTML without a Markdown equivalent, such as `<sup>` or `<details>`:
+    /// `keep` it as HTML, `strip` the tags to the content, or `escape` the
+    /// tags to literal text.
+    pub raw_html: String,
+}
+
+impl Default for HtmlConfig {
+    fn default() -> Self {
+        Self {
+            extract: "main".to_string(),
+            tags: None,
+            attributes: HashMap::new(),
+            url_schemes: None,
+            raw_html: "keep".to_string(),
+        }
+    }
+}
+
+impl Default for CleanupConfig {
+    fn default() -> Self {
+        Self {
+            dehyphenate: true,
+            ligatures: true,
+            soft_hyphens: true,
+            nbsp: true,
+        }
+    }
+}
+
+impl Default for SlidesConfig {
+    fn default() -> Self {
+        Self {
+            notes: "quote".to_string(),
+        }
+    }
+}
+
 impl Default for Config {
     fn default() -> Self {
         Self {
@@ -61,6 +175,10 @@ impl Default for Config {
             },
             pdf: PdfConfig {
                 layout: "smart".to_string(),
+                pages: None,
+                toc: true,
+                annotations: default_annotations(),
+                forms: default_forms(),
             },
             ocr: OcrConfig {
                 enabled: false,
@@ -72,6 +190,9 @@ impl Default for Config {
             batch: BatchConfig {
                 jobs: "auto".to_string(),
             },
+            slides: SlidesConfig::default(),
+            cleanup: CleanupConfig::default(),
+            html: HtmlConfig::default(),
             ignore: vec![
                 "**/node_modules/**".to_string(),
                 "**/.git/**".to_string(),
@@ -112,6 +233,55 @@ impl Config {
         Ok(config)
     }
 
+    /// Apply options given explicitly on the command line; config file values
+    /// win over CLI defaults, explicit flags win over both.
+    pub fn apply_args(&mut self, args: &Args, matches: &ArgMatches) {
+        let explicit = |id: &str| matches.value_source(id) == Some(ValueSource::CommandLine);
+
+        if explicit("to") {
+            self.to = args.to.clone();
+        }
+        if explicit("wrap") {
+            self.wrap = args.wrap.clone();
+        }
+        if explicit("width") {
+            self.width = args.width;
+        }
+        if explicit("frontmatter") {
+            self.frontmatter = args.frontmatter.clone();
+        }
+        if explicit("slug") {
+            self.slug = args.slug.clone();
+        }
+        if explicit("tables") {
+            self.tables = args.tables.clone();
+        }
+        if explicit("images") {
+            self.images.mode = args.images.clone();
+        }
+        if explicit("pdf_layout") {
+            self.pdf.layout = args.pdf_layout.clone();
+        }
+        if explicit("annotations") {
+            self.pdf.annotations = args.annotations.clone();
+        }
+        if explicit("forms") {
+            self.pdf.forms = args.forms.clone();
+        }
+        if explicit("html_extract") {
+            self.html.extract = args.html_extract.clone();
+        }
+        if explicit("raw_html") {
+            self.html.raw_html = args.raw_html.clone();
+        }
+        if let Some(pages) = &args.pages {
+            self.pdf.pages = Some(pages.clone());
+        }
+        if let Some(assets_dir) = &args.assets_dir {
+            self.images.assets_dir = assets_dir.display().to_string();
+        }
+    }
+
     pub fn save(&self, path: &PathBuf) -> Result<()> {
         let content = serde_yaml::to_string(self)?;
         fs::write(path, content)?;
@@ -0,0 +1,115 @@
+use crate::converter::xml::Element;
+use crate::error::{C2mdError, Result};
+use std::fs;
+use std::io::{Cursor, Read};
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+/// No single part may inflate past this, whatever its header declares.
+const MAX_ENTRY_SIZE: u64 = 512 * 1024 * 1024;
+/// Nor may all parts read from one archive together.
+const MAX_ARCHIVE_SIZE: u64 = 2 * 1024 * 1024 * 1024;
+
+/// Returns true if `header` starts like a zip file (OOXML, EPUB, ...).
+pub fn is_zip(header: &[u8]) -> bool {
+    header.starts_with(b"PK\x03\x04")
+}
+
+/// A read-only, in-memory zip archive, as used by OOXML and EPUB containers.
+///
+/// Parts inflate at most to their declared size, and to [`MAX_ENTRY_SIZE`]
+/// and [`MAX_ARCHIVE_SIZE`] in total, so a zip bomb fails instead of
+/// exhausting memory.
+pub struct ZipArchive {
+    zip: zip::ZipArchive<Cursor<Arc<[u8]>>>,
+    inflated: AtomicU64,
+}
+
+impl ZipArchive {
+    pub fn open(path: &Path) -> Result<Self> {
+        Self::from_bytes(fs::read(path)?)
+    }
+
+    pub fn from_bytes(data: Vec<u8>) -> Result<Self> {
+        let zip = zip::ZipArchive::new(Cursor::new(Arc::from(data)))
+            .map_err(|e| C2mdError::Conversion(format!("Not a zip archive: {}", e)))?;
+        Ok(Self { zip, inflated: AtomicU64::new(0) })
+    }
+
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.zip.file_names()
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.index(name).is_some()
+    }
+
+    // Part names are case-insensitive in OOXML, so fall back to a loose match
+    fn index(&self, name: &str) -> Option<usize> {
+        let name = name.trim_start_matches('/');
+        self.zip.index_for_name(name).or_else(|| {
+            let found = self.names().find(|entry| entry.eq_ignore_ascii_case(name))?;
+            self.zip.index_for_name(found)
+        })
+    }
+
+    /// The decompressed contents of `name`.
+    pub fn read(&self, name: &str) -> Result<Vec<u8>> {
+        let index = self
+            .index(name)
+            .ok_or_else(|| C2mdError::Conversion(format!("Missing archive entry: {}", name)))?;
+        let too_large = || C2mdError::Conversion(format!("Archive entry too large: {}", name));
+        let corrupt = |e: &dyn std::fmt::Display| C2mdError::Conversion(format!("Cannot read {}: {}", name, e));
+
+        // Clones share the data; only the read position is per clone
+        let mut zip = self.zip.clone();
+        let entry = zip.by_index(index).map_err(|e| corrupt(&e))?;
+        let declared = entry.size();
+        let budget = MAX_ARCHIVE_SIZE.saturating_sub(self.inflated.load(Ordering::Relaxed));
+        if declared > MAX_ENTRY_SIZE || declared > budget {
+            return Err(too_large());
+        }
+
+        // One byte more than declared tells a lying header from an exact fit
+        let mut out = Vec::with_capacity(declared as usize);
+        entry.take(declared + 1).read_to_end(&mut out).map_err(|e| corrupt(&e))?;
+        if out.len() as u64 > declared {
+            return Err(too_large());
+        }
+        self.inflated.fetch_add(out.len() as u64, Ordering::Relaxed);
+        Ok(out)
+    }
+
+    /// The contents of `name` as text, without a byte order mark.
+    pub fn read_string(&self, name: &str) -> Result<String> {
+        let bytes = self.read(name)?;
+        let text = String::from_utf8_lossy(&bytes);
+        Ok(text.trim_start_matches('\u{feff}').to_string())
+    }
+
+    pub fn read_xml(&self, name: &str) -> Result<Element> {
+        Element::parse(&self.read_string(name)?)
+    }
+}
+
+/// Resolve a relationship or manifest `target` against the directory of the
+/// part that references it, e.g. `media/a.png` from `word/document.xml`.
+pub fn resolve_part(base: &str, target: &str) -> String {
+    let target = target.split('#').next().unwrap_or_default();
+    let mut parts: Vec<&str> = if target.starts_with('/') {
+        Vec::new()
+    } else {
+        base.rsplit_once('/').map(|(dir, _)| dir.split('/').collect()).unwrap_or_default()
+    };
+    for segment in target.split('/') {
+        match segment {
+            "" | "." => {}
+            ".." => {
+                parts.pop();
+            }
+            segment => parts.push(segment),
+        }
+    }
+    parts.join("/")
+}
@@ -0,0 +1,152 @@
+//! Cleanup of extracted PDF and OCR text: line-end hyphenation, ligatures,
+//! soft hyphens and non-breaking spaces.
+
+use crate::config::CleanupConfig;
+use std::collections::HashSet;
+
+const SOFT_HYPHEN: char = '\u{ad}';
+
+/// Hyphen characters that may end a hyphenated line.
+const HYPHENS: [char; 3] = ['-', '\u{2010}', SOFT_HYPHEN];
+
+const LIGATURES: [(char, &str); 7] = [
+    ('\u{fb00}', "ff"),
+    ('\u{fb01}', "fi"),
+    ('\u{fb02}', "fl"),
+    ('\u{fb03}', "ffi"),
+    ('\u{fb04}', "ffl"),
+    ('\u{fb05}', "st"),
+    ('\u{fb06}', "st"),
+];
+
+const NON_BREAKING_SPACES: [char; 3] = ['\u{a0}', '\u{202f}', '\u{2007}'];
+
+/// How two lines of a paragraph join.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Joint {
+    /// An ordinary line break; the lines join with a space.
+    Space,
+    /// A word hyphenated across the break; the hyphen goes.
+    Join,
+    /// A compound broken at its hyphen; the hyphen stays, without a space.
+    Hyphen,
+}
+
+/// Cleanup for one document. Words the document spells out in full, with or
+/// without a hyphen, decide how hyphenated line ends rejoin.
+pub struct Cleanup<'a> {
+    config: &'a CleanupConfig,
+    words: HashSet<String>,
+}
+
+impl<'a> Cleanup<'a> {
+    /// Learn the vocabulary from the document's lines.
+    pub fn new<'t>(config: &'a CleanupConfig, lines: impl IntoIterator<Item = &'t str>) -> Self {
+        let mut cleanup = Self { config, words: HashSet::new() };
+        if config.dehyphenate {
+            for line in lines {
+                let line = cleanup.chars(line);
+                // A line's last word may itself be a hyphenation fragment
+                let mut words: Vec<&str> = line.split_whitespace().collect();
+                if words.last().is_some_and(|word| word.ends_with(HYPHENS)) {
+                    words.pop();
+                }
+                for word in words {
+                    let word = word.trim_matches(|c: char| !c.is_alphanumeric());
+                    if !word.is_empty() {
+                        cleanup.words.insert(word.to_lowercase());
+                    }
+                }
+            }
+        }
+        cleanup
+    }
+
+    /// Expand ligatures, drop soft hyphens inside lines and turn
+    /// non-breaking spaces into spaces, as configured. A soft hyphen ending
+    /// the text is kept for [`Cleanup::joint`].
+    pub fn chars(&self, text: &str) -> String {
+        let mut out = String::with_capacity(text.len());
+        let mut chars = text.chars().peekable();
+        while let Some(c) = chars.next() {
+            match c {
+                SOFT_HYPHEN if self.config.soft_hyphens && chars.peek().is_some() => {}
+                c if self.config.nbsp && NON_BREAKING_SPACES.contains(&c) => out.push(' '),
+                c => match LIGATURES.iter().find(|(ligature, _)| *ligature == c) {
+                    Some((_, expanded)) if self.config.ligatures => out.push_str(expanded),
+                    _ => out.push(c),
+                },
+            }
+        }
+        out
+    }
+
+    /// How `previous` and `next`, consecutive lines of a paragraph, join.
+    ///
+    /// A soft hyphen always marks hyphenation. For a hyphen, the document's
+    /// own spelling decides. Without any, breaks before a capital letter or a
+    /// digit, or after a one-letter or multi-part compound keep the hyphen
+    /// (`e-mail`, `Anglo-Saxon`, `COVID-19`, `state-of-the-art`); the rest
+    /// are hyphenation, the common case at line ends.
+    pub fn joint(&self, previous: &str, next: &str) -> Joint {
+        let previous = previous.trim_end();
+        let next = next.trim_start();
+        if previous.ends_with(SOFT_HYPHEN) && self.config.soft_hyphens {
+            return Joint::Join;
+        }
+        if !self.config.dehyphenate || !previous.ends_with(HYPHENS) {
+            return Joint::Space;
+        }
+        let stem = &previous[..previous.len() - previous.chars().last().map_or(0, char::len_utf8)];
+        let head = stem.rsplit(char::is_whitespace).next().unwrap_or_default();
+        let head = head.trim_start_matches(|c: char| !c.is_alphanumeric());
+        let tail: String = next.chars().take_while(|c| c.is_alphanumeric()).collect();
+        if !head.ends_with(char::is_alphabetic) {
+            return Joint::Space;
+        }
+        if tail.starts_with(|c: char| c.is_ascii_digit()) {
+            return Joint::Hyphen;
+        }
+        if !tail.starts_with(char::is_alphabetic) {
+            return Joint::Space;
+        }
+
+        let head = head.to_lowercase();
+        let lower_tail = tail.to_lowercase();
+        if self.words.contains(&format!("{}-{}", head, lower_tail)) {
+            return Joint::Hyphen;
+        }
+        if self.words.contains(&format!("{}{}", head, lower_tail)) {
+            return Joint::Join;
+        }
+        if head.contains(HYPHENS) || head.chars().count() < 2 || tail.starts_with(char::is_uppercase) {
+            return Joint::Hyphen;
+        }
+        Joint::Join
+    }
+
+    /// Clean plain text such as OCR output, rejoining hyphenated lines.
+    pub fn text(&self, text: &str) -> String {
+        let lines: Vec<&str> = text.lines().collect();
+        let mut out = String::with_capacity(text.len());
+        let mut glued = false;
+        for (i, line) in lines.iter().enumerate() {
+            let joint = match lines.get(i + 1) {
+                Some(next) if !line.trim().is_empty() && !next.trim().is_empty() => self.joint(line, next),
+                _ => Joint::Space,
+            };
+            let line = if glued { line.trim() } else { line.trim_end() };
+            let line = self.chars(line);
+            match joint {
+                Joint::Space if self.config.soft_hyphens => out.push_str(line.trim_end_matches(SOFT_HYPHEN)),
+                Joint::Space | Joint::Hyphen => out.push_str(&line),
+                Joint::Join => out.push_str(line.trim_end_matches(HYPHENS)),
+            }
+            glued = joint != Joint::Space;
+            if !glued {
+                out.push('\n');
+            }
+        }
+        out
+    }
+}
@@ -1,29 +1,49 @@
 use crate::cli::Args;
 use crate::config::Config;
-use crate::error::{C2mdError, Result};
+use crate::converter::registry::FormatConverter;
+use crate::document::{Block, Document, Table};
+use crate::renderer::render;
+use crate::error::Result;
+use crate::utils::decode_text;
 use std::fs;
 use std::path::Path;
 use csv::ReaderBuilder;
 
-pub fn convert_csv(path: &Path, config: &Config, args: &Args) -> Result<String> {
-    let mut markdown = String::new();
-    
-    // Add front matter
-    if config.frontmatter != "none" {
-        markdown.push_str("---\n");
-        if let Some(title) = &args.title {
-            markdown.push_str(&format!("title: {}\n", title));
-        }
-        markdown.push_str("---\n\n");
+pub struct CsvConverter;
+
+impl FormatConverter for CsvConverter {
+    fn name(&self) -> &str {
+        "csv"
     }
+
+    fn extensions(&self) -> &[&str] {
+        &["csv"]
+    }
+
+    fn mime_types(&self) -> &[&str] {
+        &["text/csv"]
+    }
+
+    fn read(&self, path: &Path, config: &Config, args: &Args) -> Result<Document> {
+        read_csv(path, config, args)
+    }
+}
+
+pub fn convert_csv(path: &Path, config: &Config, args: &Args) -> Result<String> {
+    let document = read_csv(path, config, args)?;
+    Ok(render(&document, config, args))
+}
+
+pub fn read_csv(path: &Path, _config: &Config, args: &Args) -> Result<Document> {
+    let mut document = Document::new();
     
     // Read CSV file
-    let content = fs::read_to_string(path)?;
+    let content = decode_text(&fs::read(path)?, args.encoding.as_deref(), None)?;
     let mut reader = ReaderBuilder::new()
         .has_headers(true)
         .from_reader(content.as_bytes());
     
-    let headers = reader.headers()?.clone();
+    let headers: Vec<String> = reader.headers()?.iter().map(|field| field.to_string()).collect();
     let mut rows = Vec::new();
     
     for result in reader.records() {
@@ -32,110 +52,8 @@ pub fn convert_csv(path: &Path, config: &Config, args: &Args) -> Result<String>
         rows.push(row);
     }
     
-    // Convert to markdown table
-    let header_vec: Vec<&str> = headers.iter().collect();
-    markdown.push_str(&format_table(&header_vec, &rows, &config.tables));
+    // Convert to a table; the renderer applies the configured table style
+    document.push(Block::Table(Table::from_strings(&headers, &rows)));
     
-    Ok(markdown)
-}
-
-fn format_table(headers: &[&str], rows: &[Vec<String>], style: &str) -> String {
-    match style {
-        "grid" => format_grid_table(headers, rows),
-        "pipe" => format_pipe_table(headers, rows),
-        _ => format_simple_table(headers, rows),
-    }
+    Ok(document)
 }
-
-fn format_grid_table(headers: &[&str], rows: &[Vec<String>]) -> String {
-    let mut result = String::new();
-    
-    // Header row
-    result.push('|');
-    for header in headers {
-        result.push_str(&format!(" {} |", header));
-    }
-    result.push('\n');
-    
-    // Separator row
-    result.push('|');
-    for _ in headers {
-        result.push_str(" --- |");
-    }
-    result.push('\n');
-    
-    // Data rows
-    for row in rows {
-        result.push('|');
-        for cell in row {
-            result.push_str(&format!(" {} |", cell));
-        }
-        result.push('\n');
-    }
-    
-    result
-}
-
-fn format_pipe_table(headers: &[&str], rows: &[Vec<String>]) -> String {
-    let mut result = String::new();
-    
-    // Header row
-    result.push('|');
-    for header in headers {
-        result.push_str(&format!(" {} |", header));
-    }
-    result.push('\n');
-    
-    // Separator row
-    result.push('|');
-    for _ in headers {
-        result.push_str(" --- |");
-    }
-    result.push('\n');
-    
-    // Data rows
-    for row in rows {
-        result.push('|');
-        for cell in row {
-            result.push_str(&format!(" {} |", cell));
-        }
-        result.push('\n');
-    }
-    
-    result
-}
-
-fn format_simple_table(headers: &[&str], rows: &[Vec<String>]) -> String {
-    let mut result = String::new();
-    
-    // Header row
-    for (i, header) in headers.iter().enumerate() {
-        if i > 0 {
-            result.push_str(" | ");
-        }
-        result.push_str(header);
-    }
-    result.push('\n');
-    
-    // Separator row
-    for (i, _) in headers.iter().enumerate() {
-        if i > 0 {
-            result.push_str(" | ");
-        }
-        result.push_str("---");
-    }
-    result.push('\n');
-    
-    // Data rows
-    for row in rows {
-        for (i, cell) in row.iter().enumerate() {
-            if i > 0 {
-                result.push_str(" | ");
-            }
-            result.push_str(cell);
-        }
-        result.push('\n');
-    }
-    
-    result
-}
\ No newline at end of file
@@ -1,38 +1,536 @@
 use crate::cli::Args;
 use crate::config::Config;
+use crate::converter::archive::{is_zip, resolve_part, ZipArchive};
+use crate::converter::html::html_to_document;
+use crate::converter::image::store_image;
+use crate::converter::registry::{read_header, FormatConverter};
+use crate::converter::xml::Element;
+use crate::document::{for_each_inline_mut, inlines_plain_text, Block, Document, Footnote, Inline, ListItem, Section};
 use crate::error::{C2mdError, Result};
+use crate::metadata::DocumentMetadata;
+use crate::renderer::render;
+use crate::utils::{sanitize_filename, slugify_heading};
+use chrono::{DateTime, NaiveDate, Utc};
+use std::collections::HashMap;
 use std::path::Path;
 
+pub struct EpubConverter;
+
+impl FormatConverter for EpubConverter {
+    fn name(&self) -> &str {
+        "epub"
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["epub"]
+    }
+
+    fn mime_types(&self) -> &[&str] {
+        &["application/epub+zip"]
+    }
+
+    fn read(&self, path: &Path, config: &Config, args: &Args) -> Result<Document> {
+        read_epub(path, config, args)
+    }
+}
+
 pub fn convert_epub(path: &Path, config: &Config, args: &Args) -> Result<String> {
-    // Placeholder for EPUB conversion
-    let mut markdown = String::new();
-    
-    // Add front matter
-    if config.frontmatter != "none" {
-        markdown.push_str("---\n");
-        if let Some(title) = &args.title {
-            markdown.push_str(&format!("title: {}\n", title));
-        }
-        if let Some(author) = &args.author {
-            markdown.push_str(&format!("author: {}\n", author));
-        }
-        if let Some(date) = &args.date {
-            markdown.push_str(&format!("date: {}\n", date));
-        } else {
-            markdown.push_str(&format!("date: {}\n", chrono::Utc::now().format("%Y-%m-%d")));
-        }
-        markdown.push_str("---\n\n");
-    }
-    
-    markdown.push_str("# EPUB Book\n\n");
-    markdown.push_str("**Note**: EPUB conversion requires external tools.\n\n");
-    markdown.push_str("To enable EPUB conversion, install:\n");
-    markdown.push_str("- `pandoc` (for universal conversion)\n");
-    markdown.push_str("- `calibre` (for EPUB processing)\n\n");
-    
-    markdown.push_str("## File Information\n\n");
-    markdown.push_str(&format!("- **File**: {}\n", path.file_name().unwrap().to_string_lossy()));
-    markdown.push_str("- **Format**: EPUB Book\n");
-    
-    Ok(markdown)
-}
\ No newline at end of file
+    let document = read_epub(path, config, args)?;
+    Ok(render(&document, config, args))
+}
+
+/// Read the spine items in order, each through the HTML converter. Every
+/// spine item becomes a [`Section`] so `--split chapters` can write it alone.
+pub fn read_epub(path: &Path, config: &Config, args: &Args) -> Result<Document> {
+    let archive = ZipArchive::open(path)?;
+    let package = Package::read(&archive)?;
+    let split = args.split == "chapters";
+
+    let mut book = BookReader {
+        archive: &archive,
+        config,
+        args,
+        stem: path.file_stem().unwrap_or_default().to_string_lossy().to_string(),
+        split,
+        names: HashMap::new(),
+        slugs: HashMap::new(),
+        images: HashMap::new(),
+    };
+
+    let toc = package.read_toc(&archive);
+    let mut chapters = Vec::new();
+    if !toc.is_empty() {
+        let mut contents = book.start_chapter("", "index");
+        let heading = Block::heading(2, "Contents");
+        contents.add_headings(std::slice::from_ref(&heading), &mut book);
+        contents.blocks.push(heading);
+        contents.blocks.push(toc_list(&toc));
+        chapters.push(contents);
+    }
+    for part in &package.spine {
+        let item = &package.manifest[part];
+        let is_html = matches!(item.media_type.as_str(), "application/xhtml+xml" | "text/html");
+        // The navigation document is already rendered as the contents list
+        if !is_html || (!toc.is_empty() && item.properties.split_whitespace().any(|p| p == "nav")) {
+            continue;
+        }
+        chapters.push(book.read_chapter(part)?);
+    }
+
+    let index: HashMap<String, usize> = chapters.iter().enumerate().map(|(i, c)| (c.part.clone(), i)).collect();
+    let mut document = Document::new();
+    for current in 0..chapters.len() {
+        let mut blocks = std::mem::take(&mut chapters[current].blocks);
+        // Chapters number their footnotes from 1; renumber them across the book
+        let mut labels = HashMap::new();
+        for footnote in std::mem::take(&mut chapters[current].footnotes) {
+            let label = (document.footnotes.len() + 1).to_string();
+            labels.insert(footnote.label, label.clone());
+            document.footnotes.push(Footnote { label, blocks: footnote.blocks });
+        }
+        for_each_inline_mut(&mut blocks, &mut |inline| match inline {
+            Inline::Link { url, content, .. } => {
+                if let Some(target) = book.link_target(url, &chapters, current, &index) {
+                    match target {
+                        Some(target) => *url = target,
+                        None => *inline = Inline::Text(inlines_plain_text(content)),
+                    }
+                }
+            }
+            Inline::Image { url, alt, .. } => {
+                if let Some(stored) = book.image(&chapters[current].part, url) {
+                    *url = stored;
+                }
+                if alt.is_empty() {
+                    *alt = "Image".to_string();
+                }
+            }
+            Inline::FootnoteRef(label) => {
+                if let Some(renumbered) = labels.get(label) {
+                    *label = renumbered.clone();
+                }
+            }
+            _ => {}
+        });
+        document.sections.push(Section { name: chapters[current].name.clone(), start: document.blocks.len() });
+        document.blocks.extend(blocks);
+    }
+
+    let metadata = &package.metadata;
+    document.title = metadata.title.clone();
+    document.author = (!metadata.creators.is_empty()).then(|| metadata.creators.join(", "));
+    document.date = metadata.date.as_ref().map(|date| date.split('T').next().unwrap_or(date).to_string());
+    Ok(document)
+}
+
+/// EPUB metadata for `path`, used by [`crate::metadata::extract_metadata`].
+pub(crate) fn read_epub_metadata(path: &Path, metadata: &mut DocumentMetadata) -> Result<()> {
+    if !is_zip(&read_header(path)?) {
+        return Ok(());
+    }
+    let package = Package::read(&ZipArchive::open(path)?)?;
+    let book = package.metadata;
+    metadata.title = book.title;
+    metadata.author = (!book.creators.is_empty()).then(|| book.creators.join(", "));
+    metadata.subject = book.description;
+    metadata.keywords = (!book.subjects.is_empty()).then(|| book.subjects.join(", "));
+    metadata.language = book.language;
+    metadata.publisher = book.publisher;
+    metadata.identifier = book.identifier;
+    metadata.creation_date = book.date.as_deref().and_then(parse_date);
+    Ok(())
+}
+
+/// `dc:date` is a full timestamp or just a year, month or day.
+pub(crate) fn parse_date(date: &str) -> Option<DateTime<Utc>> {
+    if let Ok(date) = DateTime::parse_from_rfc3339(date) {
+        return Some(date.with_timezone(&Utc));
+    }
+    let padded = match date.len() {
+        4 => format!("{}-01-01", date),
+        7 => format!("{}-01", date),
+        _ => date.to_string(),
+    };
+    NaiveDate::parse_from_str(&padded, "%Y-%m-%d")
+        .ok()
+        .and_then(|day| day.and_hms_opt(0, 0, 0))
+        .map(|time| time.and_utc())
+}
+
+#[derive(Default)]
+struct BookMetadata {
+    title: Option<String>,
+    creators: Vec<String>,
+    language: Option<String>,
+    publisher: Option<String>,
+    identifier: Option<String>,
+    date: Option<String>,
+    description: Option<String>,
+    subjects: Vec<String>,
+}
+
+struct ManifestItem {
+    media_type: String,
+    properties: String,
+}
+
+/// The OPF package document: Dublin Core metadata, manifest and spine.
+struct Package {
+    metadata: BookMetadata,
+    /// Manifest items by part name.
+    manifest: HashMap<String, ManifestItem>,
+    /// Part names in reading order.
+    spine: Vec<String>,
+    /// EPUB 3 navigation document.
+    nav: Option<String>,
+    /// EPUB 2 NCX table of contents.
+    ncx: Option<String>,
+}
+
+impl Package {
+    fn read(archive: &ZipArchive) -> Result<Self> {
+        let container = archive.read_xml("META-INF/container.xml")?;
+        let opf_part = container
+            .find("rootfile")
+            .and_then(|rootfile| rootfile.attr("full-path"))
+            .ok_or_else(|| C2mdError::Conversion("EPUB container names no package document".to_string()))?
+            .to_string();
+        let opf = archive.read_xml(&opf_part)?;
+
+        let mut metadata = BookMetadata::default();
+        if let Some(dc) = opf.child("metadata") {
+            let texts = |name: &str| -> Vec<String> {
+                dc.children_named(name)
+                    .map(|element| element.text().trim().to_string())
+                    .filter(|text| !text.is_empty())
+                    .collect()
+            };
+            metadata.title = texts("title").into_iter().next();
+            metadata.creators = texts("creator");
+            metadata.language = texts("language").into_iter().next();
+            metadata.publisher = texts("publisher").into_iter().next();
+            metadata.date = texts("date").into_iter().next();
+            metadata.description = texts("description").into_iter().next();
+            metadata.subjects = texts("subject");
+            // Prefer the identifier the package declares as unique
+            let unique = opf.attr("unique-identifier");
+            metadata.identifier = dc
+                .children_named("identifier")
+                .find(|id| unique.is_some() && id.attr("id") == unique)
+                .map(|id| id.text().trim().to_string())
+                .or_else(|| texts("identifier").into_iter().next());
+        }
+
+        let mut ids = HashMap::new();
+        let mut manifest = HashMap::new();
+        let mut nav = None;
+        for item in opf.child("manifest").into_iter().flat_map(|m| m.children_named("item")) {
+            let (Some(id), Some(href)) = (item.attr("id"), item.attr("href")) else {
+                continue;
+            };
+            let part = resolve_part(&opf_part, &percent_decode(href));
+            let properties = item.attr("properties").unwrap_or_default().to_string();
+            if properties.split_whitespace().any(|p| p == "nav") {
+                nav = Some(part.clone());
+            }
+            ids.insert(id.to_string(), part.clone());
+            manifest.insert(
+                part,
+                ManifestItem { media_type: item.attr("media-type").unwrap_or_default().to_string(), properties },
+            );
+        }
+
+        let spine_element = opf.child("spine");
+        let spine = spine_element
+            .into_iter()
+            .flat_map(|spine| spine.children_named("itemref"))
+            .filter_map(|itemref| ids.get(itemref.attr("idref")?).cloned())
+            .collect();
+        let ncx = spine_element
+            .and_then(|spine| spine.attr("toc"))
+            .and_then(|id| ids.get(id).cloned())
+            .or_else(|| {
+                manifest
+                    .iter()
+                    .find(|(_, item)| item.media_type == "application/x-dtbncx+xml")
+                    .map(|(part, _)| part.clone())
+            });
+
+        Ok(Self { metadata, manifest, spine, nav, ncx })
+    }
+
+    /// Table of contents from the navigation document, falling back to the NCX.
+    fn read_toc(&self, archive: &ZipArchive) -> Vec<TocEntry> {
+        if let Some(nav) = &self.nav {
+            if let Ok(root) = archive.read_xml(nav) {
+                let toc = root
+                    .find_all("nav")
+                    .into_iter()
+                    .find(|nav| nav.attr("epub:type").is_some_and(|t| t.split_whitespace().any(|t| t == "toc")))
+                    .and_then(|nav| nav.child("ol"));
+                if let Some(list) = toc {
+                    return nav_entries(list, nav);
+                }
+            }
+        }
+        if let Some(ncx) = &self.ncx {
+            if let Ok(root) = archive.read_xml(ncx) {
+                if let Some(map) = root.find("navMap") {
+                    return ncx_entries(map, ncx);
+                }
+            }
+        }
+        Vec::new()
+    }
+}
+
+struct TocEntry {
+    label: String,
+    /// Link target relative to the book root, e.g. `OEBPS/ch1.xhtml#intro`.
+    target: Option<String>,
+    children: Vec<TocEntry>,
+}
+
+fn nav_entries(list: &Element, part: &str) -> Vec<TocEntry> {
+    list.children_named("li")
+        .filter_map(|li| {
+            let label_element = li.child("a").or_else(|| li.child("span"))?;
+            Some(TocEntry {
+                label: collapse_whitespace(&label_element.text()),
+                target: label_element.attr("href").map(|href| resolve_link(part, href)),
+                children: li.child("ol").map(|ol| nav_entries(ol, part)).unwrap_or_default(),
+            })
+        })
+        .collect()
+}
+
+fn ncx_entries(parent: &Element, part: &str) -> Vec<TocEntry> {
+    parent
+        .children_named("navPoint")
+        .map(|point| TocEntry {
+            label: collapse_whitespace(&point.child("navLabel").map(Element::text).unwrap_or_default()),
+            target: point.child("content").and_then(|c| c.attr("src")).map(|src| resolve_link(part, src)),
+            children: ncx_entries(point, part),
+        })
+        .collect()
+}
+
+fn toc_list(entries: &[TocEntry]) -> Block {
+    Block::bullet_list(
+        entries
+            .iter()
+            .map(|entry| {
+                let label = Inline::text(entry.label.clone());
+                let content = match &entry.target {
+                    // Made relative to the book root so it resolves like any other link
+                    Some(target) => Inline::Link { url: format!("/{}", target), title: None, content: vec![label] },
+                    None => label,
+                };
+                let mut blocks = vec![Block::Paragraph(vec![content])];
+                if !entry.children.is_empty() {
+                    blocks.push(toc_list(&entry.children));
+                }
+                ListItem::new(blocks)
+            })
+            .collect(),
+    )
+}
+
+/// A link target resolved against `part`, keeping its fragment.
+fn resolve_link(part: &str, href: &str) -> String {
+    let (path, fragment) = match href.split_once('#') {
+        Some((path, fragment)) => (path, Some(fragment)),
+        None => (href, None),
+    };
+    let resolved = if path.is_empty() { part.to_string() } else { resolve_part(part, &percent_decode(path)) };
+    match fragment {
+        Some(fragment) => format!("{}#{}", resolved, fragment),
+        None => resolved,
+    }
+}
+
+struct Chapter {
+    part: String,
+    /// Output file stem, unique within the book.
+    name: String,
+    blocks: Vec<Block>,
+    footnotes: Vec<Footnote>,
+    /// Anchor slug of each top-level heading, in order.
+    slugs: Vec<String>,
+    /// Element ids and the index of the heading they belong to.
+    ids: HashMap<String, usize>,
+}
+
+impl Chapter {
+    fn add_headings(&mut self, blocks: &[Block], book: &mut BookReader) {
+        for block in blocks {
+            if let Block::Heading { content, .. } = block {
+                let slug = slugify_heading(&inlines_plain_text(content), &book.config.slug);
+                let scope = if book.split { self.name.clone() } else { String::new() };
+                let count = book.slugs.entry((scope, slug.clone())).or_insert(0);
+                self.slugs.push(if *count == 0 { slug } else { format!("{}-{}", slug, count) });
+                *count += 1;
+            }
+        }
+    }
+}
+
+struct BookReader<'a> {
+    archive: &'a ZipArchive,
+    config: &'a Config,
+    args: &'a Args,
+    stem: String,
+    split: bool,
+    /// How often each file stem was used.
+    names: HashMap<String, usize>,
+    /// How often each heading slug was used, per output file.
+    slugs: HashMap<(String, String), usize>,
+    /// Stored image URLs by part name.
+    images: HashMap<String, String>,
+}
+
+impl BookReader<'_> {
+    fn start_chapter(&mut self, part: &str, name: &str) -> Chapter {
+        let name = sanitize_filename(name);
+        let count = self.names.entry(name.clone()).or_insert(0);
+        *count += 1;
+        let name = if *count == 1 { name } else { format!("{}-{}", name, count) };
+        Chapter { part: part.to_string(), name, blocks: Vec::new(), footnotes: Vec::new(), slugs: Vec::new(), ids: HashMap::new() }
+    }
+
+    fn read_chapter(&mut self, part: &str) -> Result<Chapter> {
+        let file_name = part.rsplit('/').next().unwrap_or(part);
+        let stem = file_name.rsplit_once('.').map_or(file_name, |(stem, _)| stem);
+        let mut chapter = self.start_chapter(part, stem);
+
+        let xhtml = self.archive.read_string(part)?;
+        if let Ok(root) = Element::parse(&xhtml) {
+            let mut headings = 0;
+            let mut current = None;
+            collect_ids(root.find("body").unwrap_or(&root), &mut headings, &mut current, &mut chapter.ids);
+        }
+
+        let body_pattern = regex::Regex::new(r"(?is)<body[^>]*>(.*)</body>")?;
+        let body = body_pattern.captures(&xhtml).map_or(xhtml.as_str(), |caps| caps.get(1).map_or("", |m| m.as_str()));
+        // SVG cover pages reference their image from an <image> element
+        let svg_image = regex::Regex::new(r#"<(?:svg:)?image\s[^>]*?(?:xlink:)?href="([^"]*)"[^>]*>"#)?;
+        let body = svg_image.replace_all(body, r#"<img src="$1" alt="">"#);
+
+        let document = html_to_document(&body, &self.config.html)?;
+        chapter.add_headings(&document.blocks, self);
+        chapter.blocks = document.blocks;
+        chapter.footnotes = document.footnotes;
+        Ok(chapter)
+    }
+
+    /// Where an intra-book link should point: `None` for links left as they
+    /// are, `Some(None)` for links with no anchor to point to.
+    fn link_target(
+        &self,
+        url: &str,
+        chapters: &[Chapter],
+        current: usize,
+        index: &HashMap<String, usize>,
+    ) -> Option<Option<String>> {
+        if has_scheme(url) {
+            return None;
+        }
+        let resolved = match url.strip_prefix('/') {
+            Some(from_root) => from_root.to_string(),
+            None => resolve_link(&chapters[current].part, url),
+        };
+        let (part, fragment) = match resolved.split_once('#') {
+            Some((part, fragment)) => (part, Some(fragment)),
+            None => (resolved.as_str(), None),
+        };
+        let target = *index.get(part)?;
+        let chapter = &chapters[target];
+
+        // Anchors in the chapter before its first heading point to that heading
+        let heading = fragment.and_then(|id| chapter.ids.get(id).copied()).unwrap_or(0);
+        let slug = chapter.slugs.get(heading);
+        Some(match (self.split && target != current, slug) {
+            (true, Some(slug)) => Some(format!("{}.md#{}", chapter.name, slug)),
+            (true, None) => Some(format!("{}.md", chapter.name)),
+            (false, Some(slug)) => Some(format!("#{}", slug)),
+            (false, None) => None,
+        })
+    }
+
+    /// Store an image referenced from `part`, returning its new URL.
+    fn image(&mut self, part: &str, url: &str) -> Option<String> {
+        if has_scheme(url) {
+            return None;
+        }
+        let target = resolve_part(part, &percent_decode(url));
+        if !self.images.contains_key(&target) {
+            let file_name = format!("{}-{}", self.stem, target.rsplit('/').next().unwrap_or_default());
+            let stored = self
+                .archive
+                .read(&target)
+                .and_then(|data| store_image(&data, &file_name, self.config, self.args));
+            match stored {
+                Ok(url) => {
+                    self.images.insert(target.clone(), url);
+                }
+                Err(e) => {
+                    tracing::warn!("Skipping image {}: {}", target, e);
+                    return None;
+                }
+            }
+        }
+        self.images.get(&target).cloned()
+    }
+}
+
+/// Record which heading each element id belongs to: headings and anything
+/// inside them map to themselves, other elements to the preceding heading.
+fn collect_ids(element: &Element, headings: &mut usize, current: &mut Option<usize>, ids: &mut HashMap<String, usize>) {
+    for child in element.elements() {
+        if matches!(child.local_name(), "h1" | "h2" | "h3" | "h4" | "h5" | "h6") {
+            *current = Some(*headings);
+            *headings += 1;
+            let mut inner = vec![child];
+            while let Some(element) = inner.pop() {
+                if let (Some(id), Some(heading)) = (element.attr("id"), *current) {
+                    ids.insert(id.to_string(), heading);
+                }
+                inner.extend(element.elements());
+            }
+            continue;
+        }
+        if let (Some(id), Some(heading)) = (child.attr("id"), *current) {
+            ids.insert(id.to_string(), heading);
+        }
+        collect_ids(child, headings, current, ids);
+    }
+}
+
+fn has_scheme(url: &str) -> bool {
+    url.split_once(':')
+        .is_some_and(|(scheme, _)| !scheme.is_empty() && scheme.chars().all(|c| c.is_ascii_alphanumeric() || "+.-".contains(c)))
+}
+
+fn percent_decode(text: &str) -> String {
+    let bytes = text.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = text.get(i + 1..i + 3).and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match (bytes[i], hex) {
+            (b'%', Some(byte)) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            (byte, _) => {
+                decoded.push(byte);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&decoded).to_string()
+}
+
+fn collapse_whitespace(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
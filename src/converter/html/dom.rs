@@ -0,0 +1,235 @@
+//! HTML5 parsing into the [`Element`] tree the XML-based readers use, so
+//! pages get the same error recovery as in a browser: implied and misnested
+//! tags, entities and unclosed elements.
+
+use crate::converter::xml::{Element, Node};
+use html5ever::tendril::{StrTendril, TendrilSink};
+use html5ever::tree_builder::{ElementFlags, NodeOrText, QuirksMode, TreeSink};
+use html5ever::{parse_document, Attribute, ExpandedName, QualName};
+use std::borrow::Cow;
+
+/// Parse a page or a fragment of one; the result is the `html` element with
+/// the implied `head` and `body`. Names are the local names, in lower case.
+pub fn parse(html: &str) -> Element {
+    parse_document(Sink::default(), Default::default()).one(html)
+}
+
+enum Kind {
+    Document,
+    Element(QualName, Vec<Attribute>),
+    Text(String),
+    /// Comments and processing instructions.
+    Other,
+}
+
+struct SinkNode {
+    kind: Kind,
+    parent: Option<usize>,
+    children: Vec<usize>,
+}
+
+/// Nodes in an arena, addressed by index; the document is node 0.
+struct Sink {
+    nodes: Vec<SinkNode>,
+}
+
+impl Default for Sink {
+    fn default() -> Self {
+        Self { nodes: vec![SinkNode { kind: Kind::Document, parent: None, children: Vec::new() }] }
+    }
+}
+
+impl Sink {
+    fn create(&mut self, kind: Kind) -> usize {
+        self.nodes.push(SinkNode { kind, parent: None, children: Vec::new() });
+        self.nodes.len() - 1
+    }
+
+    fn detach(&mut self, node: usize) {
+        if let Some(parent) = self.nodes[node].parent.take() {
+            self.nodes[parent].children.retain(|&child| child != node);
+        }
+    }
+
+    /// Insert at `index` among the children of `parent`, merging text into a
+    /// preceding text node.
+    fn insert(&mut self, parent: usize, index: usize, child: NodeOrText<usize>) {
+        let node = match child {
+            NodeOrText::AppendText(text) => {
+                let previous = index.checked_sub(1).map(|i| self.nodes[parent].children[i]);
+                if let Some(Kind::Text(existing)) = previous.map(|i| &mut self.nodes[i].kind) {
+                    existing.push_str(&text);
+                    return;
+                }
+                self.create(Kind::Text(text.to_string()))
+            }
+            NodeOrText::AppendNode(node) => node,
+        };
+        self.nodes[node].parent = Some(parent);
+        self.nodes[parent].children.insert(index, node);
+    }
+
+    fn element(&self, node: usize) -> Element {
+        let (name, attributes) = match &self.nodes[node].kind {
+            Kind::Element(name, attributes) => (name, attributes),
+            _ => return Element::default(),
+        };
+        let children = self.nodes[node]
+            .children
+            .iter()
+            .filter_map(|&child| match &self.nodes[child].kind {
+                Kind::Element(..) => Some(Node::Element(self.element(child))),
+                Kind::Text(text) => Some(Node::Text(text.clone())),
+                Kind::Document | Kind::Other => None,
+            })
+            .collect();
+        Element {
+            name: name.local.to_string(),
+            attributes: attributes.iter().map(|a| (a.name.local.to_string(), a.value.to_string())).collect(),
+            children,
+        }
+    }
+}
+
+impl TreeSink for Sink {
+    type Handle = usize;
+    type Output = Element;
+
+    fn finish(self) -> Element {
+        self.nodes[0]
+            .children
+            .iter()
+            .find(|&&child| matches!(self.nodes[child].kind, Kind::Element(..)))
+            .map(|&root| self.element(root))
+            .unwrap_or_default()
+    }
+
+    fn parse_error(&mut self, _msg: Cow<'static, str>) {}
+
+    fn get_document(&mut self) -> usize {
+        0
+    }
+
+    fn elem_name<'a>(&'a self, target: &'a usize) -> ExpandedName<'a> {
+        match &self.nodes[*target].kind {
+            Kind::Element(name, _) => name.expanded(),
+            _ => panic!("not an element"),
+        }
+    }
+
+    fn create_element(&mut self, name: QualName, attrs: Vec<Attribute>, _flags: ElementFlags) -> usize {
+        self.create(Kind::Element(name, attrs))
+    }
+
+    fn create_comment(&mut self, _text: StrTendril) -> usize {
+        self.create(Kind::Other)
+    }
+
+    fn create_pi(&mut self, _target: StrTendril, _data: StrTendril) -> usize {
+        self.create(Kind::Other)
+    }
+
+    fn append(&mut self, parent: &usize, child: NodeOrText<usize>) {
+        if let NodeOrText::AppendNode(node) = child {
+            self.detach(node);
+        }
+        let index = self.nodes[*parent].children.len();
+        self.insert(*parent, index, child);
+    }
+
+    fn append_based_on_parent_node(&mut self, element: &usize, prev_element: &usize, child: NodeOrText<usize>) {
+        if self.nodes[*element].parent.is_some() {
+            self.append_before_sibling(element, child);
+        } else {
+            self.append(prev_element, child);
+        }
+    }
+
+    fn append_doctype_to_document(&mut self, _name: StrTendril, _public_id: StrTendril, _system_id: StrTendril) {}
+
+    // Template contents stay children of the template, which readers skip
+    fn get_template_contents(&mut self, target: &usize) -> usize {
+        *target
+    }
+
+    fn same_node(&self, x: &usize, y: &usize) -> bool {
+        x == y
+    }
+
+    fn set_quirks_mode(&mut self, _mode: QuirksMode) {}
+
+    fn append_before_sibling(&mut self, sibling: &usize, new_node: NodeOrText<usize>) {
+        if let NodeOrText::AppendNode(node) = new_node {
+            self.detach(node);
+        }
+        let Some(parent) = self.nodes[*sibling].parent else {
+            return;
+        };
+        let Some(index) = self.nodes[parent].children.iter().position(|child| child == sibling) else {
+            return;
+        };
+        self.insert(parent, index, new_node);
+    }
+
+    fn add_attrs_if_missing(&mut self, target: &usize, attrs: Vec<Attribute>) {
+        if let Kind::Element(_, existing) = &mut self.nodes[*target].kind {
+            for attr in attrs {
+                if !existing.iter().any(|a| a.name == attr.name) {
+                    existing.push(attr);
+                }
+            }
+        }
+    }
+
+    fn remove_from_parent(&mut self, target: &usize) {
+        self.detach(*target);
+    }
+
+    fn reparent_children(&mut self, node: &usize, new_parent: &usize) {
+        let children = std::mem::take(&mut self.nodes[*node].children);
+        for &child in &children {
+            self.nodes[child].parent = Some(*new_parent);
+        }
+        self.nodes[*new_parent].children.extend(children);
+    }
+}
+
+/// Elements without content or an end tag.
+const VOID_TAGS: &[&str] =
+    &["area", "base", "br", "col", "embed", "hr", "img", "input", "link", "meta", "param", "source", "track", "wbr"];
+
+/// Write `element` back as HTML, leaving out scripts, styles and templates.
+pub fn serialize(element: &Element) -> String {
+    let mut html = String::new();
+    write_element(element, &mut html);
+    html
+}
+
+fn write_element(element: &Element, html: &mut String) {
+    let name = element.local_name();
+    if matches!(name, "script" | "style" | "template") {
+        return;
+    }
+    html.push('<');
+    html.push_str(name);
+    for (attribute, value) in &element.attributes {
+        html.push_str(&format!(" {}=\"{}\"", attribute, value.replace('&', "&amp;").replace('"', "&quot;")));
+    }
+    html.push('>');
+    if VOID_TAGS.contains(&name) {
+        return;
+    }
+    for (i, child) in element.children.iter().enumerate() {
+        match child {
+            Node::Element(child) => write_element(child, html),
+            Node::Text(text) => {
+                // The parser drops a newline right after these start tags
+                if i == 0 && matches!(name, "pre" | "textarea" | "listing") && text.starts_with('\n') {
+                    html.push('\n');
+                }
+                html.push_str(&text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;"));
+            }
+        }
+    }
+    html.push_str(&format!("</{}>", name));
+}
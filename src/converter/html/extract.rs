@@ -0,0 +1,326 @@
+//! The part of a page worth converting: the main content without the
+//! navigation, banners, sidebars and footers around it, and what `<head>`
+//! says about the page.
+
+use super::select::Selector;
+use crate::converter::xml::{Element, Node};
+use crate::error::Result;
+use regex::Regex;
+use std::collections::HashMap;
+
+/// Paragraphs shorter than this, in characters, do not score.
+const MIN_PARAGRAPH: usize = 25;
+
+/// An article shorter than this is likely a wrong pick; the page is used
+/// instead.
+const MIN_ARTICLE: usize = 250;
+
+/// Landmark roles of page chrome.
+const CHROME_ROLES: &[&str] =
+    &["navigation", "banner", "contentinfo", "complementary", "search", "dialog", "alertdialog", "menu", "menubar"];
+
+/// Elements that are never content.
+const CHROME_TAGS: &[&str] = &[
+    "nav", "aside", "script", "style", "template", "noscript", "iframe", "button", "input", "select", "textarea",
+    "dialog",
+];
+
+/// Class and id words of page chrome, such as cookie banners.
+const CHROME: &str = r"(?i)cookie|consent|gdpr|newsletter|subscribe|popup|modal|share|social|skip-?link|breadcrumb|sidebar|advert|sponsor|promo";
+
+/// More class and id words that make an element an unlikely article.
+const UNLIKELY: &str = r"(?i)-ad-|banner|combx|comment|community|disqus|extra|footer|header|legends|menu|related|remark|replies|rss|shoutbox|skyscraper|supplemental|ad-break|agegate|pagination|pager|yom-remote";
+
+/// Class and id words of content, which keep an element that also looks
+/// like chrome.
+const CONTENT: &str = r"(?i)and|article|body|column|content|main|shadow|page|wrapper|container";
+
+/// Class and id words that weigh for or against an element as the article.
+const POSITIVE: &str = r"(?i)article|body|content|entry|hentry|h-entry|main|page|post|text|blog|story";
+const NEGATIVE: &str = r"(?i)-ad-|hidden|^hid$|\shid$|\shid\s|^hid\s|banner|combx|comment|com-|contact|footer|gdpr|masthead|media|meta|outbrain|promo|related|scroll|share|shoutbox|sidebar|skyscraper|sponsor|shopping|tags|tool|widget";
+
+/// What `<head>` says about the page: its title and `<meta>` tags,
+/// including Open Graph (`og:`) and `article:` properties.
+#[derive(Debug, Default)]
+pub struct PageInfo {
+    pub title: Option<String>,
+    pub author: Option<String>,
+    pub description: Option<String>,
+    pub keywords: Option<String>,
+    pub language: Option<String>,
+    pub site_name: Option<String>,
+    /// The canonical URL.
+    pub url: Option<String>,
+    pub published: Option<String>,
+    pub modified: Option<String>,
+}
+
+impl PageInfo {
+    pub fn read(root: &Element) -> Self {
+        let mut meta: HashMap<String, String> = HashMap::new();
+        for tag in root.find_all("meta") {
+            let key = tag.attr("property").or_else(|| tag.attr("name")).or_else(|| tag.attr("itemprop"));
+            if let (Some(key), Some(content)) = (key, tag.attr("content")) {
+                let content = collapse(content);
+                if !content.is_empty() {
+                    meta.entry(key.trim().to_ascii_lowercase()).or_insert(content);
+                }
+            }
+        }
+        let get = |keys: &[&str]| keys.iter().find_map(|key| meta.get(*key).cloned());
+        let head = root.child("head");
+        let title = head.and_then(|head| head.child("title")).map(|title| collapse(&title.text())).filter(|t| !t.is_empty());
+        let canonical = root
+            .find_all("link")
+            .into_iter()
+            .find(|link| link.attr("rel").is_some_and(|rel| rel.split_whitespace().any(|r| r.eq_ignore_ascii_case("canonical"))))
+            .and_then(|link| link.attr("href"))
+            .map(str::to_string);
+        // Authors given as profile URLs are not names
+        let author = get(&["author", "article:author", "dc.creator", "twitter:creator"]).filter(|author| !author.contains("://"));
+        Self {
+            title: get(&["og:title", "twitter:title"]).or(title),
+            author,
+            description: get(&["og:description", "description", "twitter:description"]),
+            keywords: get(&["keywords", "news_keywords"]),
+            language: root.attr("lang").map(str::to_string).filter(|lang| !lang.is_empty()).or_else(|| get(&["og:locale"])),
+            site_name: get(&["og:site_name", "application-name"]),
+            url: get(&["og:url"]).or(canonical),
+            published: get(&["article:published_time", "datepublished", "date", "dc.date", "dcterms.date"]),
+            modified: get(&["article:modified_time", "og:updated_time", "datemodified", "dcterms.modified"]),
+        }
+    }
+}
+
+/// The content of `body` to convert, as an element, for `mode`: `main`,
+/// `article`, `full` or `selector:<css>`.
+pub fn extract(body: &Element, mode: &str) -> Result<Element> {
+    if let Some(css) = mode.strip_prefix("selector:") {
+        let selector = Selector::parse(css)?;
+        let found = selector.select(body);
+        if found.is_empty() {
+            tracing::warn!("No element matches `{}`; converting the whole page", css);
+            return Ok(body.clone());
+        }
+        return Ok(wrap(found.into_iter().cloned().collect()));
+    }
+    match mode {
+        "full" => Ok(body.clone()),
+        "article" => {
+            let page = prune(body, &Patterns::new(true), false);
+            Ok(article(&page).unwrap_or(page))
+        }
+        _ => {
+            let landmark = body.find_all("main").into_iter().next().or_else(|| find_role(body, "main"));
+            let patterns = Patterns::new(false);
+            Ok(match landmark {
+                Some(main) => prune(main, &patterns, true),
+                None => prune(body, &patterns, false),
+            })
+        }
+    }
+}
+
+fn wrap(elements: Vec<Element>) -> Element {
+    Element {
+        name: "body".to_string(),
+        attributes: Vec::new(),
+        children: elements.into_iter().map(Node::Element).collect(),
+    }
+}
+
+fn find_role<'a>(element: &'a Element, role: &str) -> Option<&'a Element> {
+    element.elements().find_map(|child| if child.attr("role") == Some(role) { Some(child) } else { find_role(child, role) })
+}
+
+struct Patterns {
+    chrome: Regex,
+    content: Regex,
+    /// For `article`, which drops more.
+    unlikely: Option<Regex>,
+}
+
+impl Patterns {
+    fn new(article: bool) -> Self {
+        let regex = |pattern: &str| Regex::new(pattern).expect("valid pattern");
+        Self { chrome: regex(CHROME), content: regex(CONTENT), unlikely: article.then(|| regex(UNLIKELY)) }
+    }
+
+    fn is_chrome(&self, element: &Element) -> bool {
+        let words = format!("{} {}", element.attr("class").unwrap_or_default(), element.attr("id").unwrap_or_default());
+        if words.trim().is_empty() || self.content.is_match(&words) {
+            return false;
+        }
+        self.chrome.is_match(&words) || self.unlikely.as_ref().is_some_and(|unlikely| unlikely.is_match(&words))
+    }
+}
+
+/// A copy of `element` without chrome: navigation and other elements that
+/// are never content, hidden elements, landmark roles of chrome, page-level
+/// headers and footers, and elements whose class or id names chrome.
+/// Headers and footers inside `in_content` (an article or section) stay.
+fn prune(element: &Element, patterns: &Patterns, in_content: bool) -> Element {
+    let in_content = in_content || matches!(element.local_name(), "article" | "main" | "section");
+    let children = element
+        .children
+        .iter()
+        .filter_map(|node| match node {
+            Node::Element(child) => {
+                let name = child.local_name();
+                let hidden = child.attr("hidden").is_some()
+                    || child.attr("aria-hidden") == Some("true")
+                    || child.attr("style").is_some_and(|style| {
+                        let style = style.replace(' ', "").to_ascii_lowercase();
+                        style.contains("display:none") || style.contains("visibility:hidden")
+                    });
+                let chrome = CHROME_TAGS.contains(&name)
+                    || (!in_content && matches!(name, "header" | "footer"))
+                    || child.attr("role").is_some_and(|role| CHROME_ROLES.contains(&role))
+                    || patterns.is_chrome(child);
+                (!hidden && !chrome).then(|| Node::Element(prune(child, patterns, in_content)))
+            }
+            Node::Text(text) => Some(Node::Text(text.clone())),
+        })
+        .collect();
+    Element { name: element.name.clone(), attributes: element.attributes.clone(), children }
+}
+
+/// The article as readability finds it: paragraphs score for their parent
+/// and grandparent by length and commas, scores are discounted by link
+/// density, and the best element is taken with the siblings that score
+/// well or read like paragraphs. `None` when nothing long enough stands out.
+fn article(body: &Element) -> Option<Element> {
+    let positive = Regex::new(POSITIVE).ok()?;
+    let negative = Regex::new(NEGATIVE).ok()?;
+    let mut scores: HashMap<*const Element, Candidate> = HashMap::new();
+    let mut ancestors = vec![body];
+    score(body, &mut ancestors, &mut scores, &|element: &Element| initial_score(element, &positive, &negative));
+
+    let best = scores
+        .values()
+        .map(|candidate| (candidate, candidate.score * (1.0 - link_density(candidate.element))))
+        .max_by(|(_, a), (_, b)| a.total_cmp(b))?;
+    let (top, top_score) = best;
+    if text_length(top.element) < MIN_ARTICLE {
+        return None;
+    }
+    let Some(parent) = top.parent.filter(|_| !std::ptr::eq(top.element, body)) else {
+        return Some(top.element.clone());
+    };
+
+    // Siblings that score close to the article or read like paragraphs are
+    // part of it, such as an introduction before the main text
+    let threshold = (top_score * 0.2).max(10.0);
+    let siblings = parent
+        .elements()
+        .filter(|sibling| {
+            if std::ptr::eq(*sibling, top.element) {
+                return true;
+            }
+            let key: *const Element = *sibling;
+            if scores.get(&key).is_some_and(|candidate| candidate.score * (1.0 - link_density(sibling)) >= threshold) {
+                return true;
+            }
+            if !sibling.is("p") {
+                return false;
+            }
+            let length = text_length(sibling);
+            let density = link_density(sibling);
+            (length > 80 && density < 0.25) || (length > 0 && density == 0.0 && collapse(&sibling.text()).contains(". "))
+        })
+        .cloned()
+        .collect();
+    Some(wrap(siblings))
+}
+
+struct Candidate<'a> {
+    element: &'a Element,
+    parent: Option<&'a Element>,
+    score: f64,
+}
+
+fn score<'a>(
+    element: &'a Element,
+    ancestors: &mut Vec<&'a Element>,
+    scores: &mut HashMap<*const Element, Candidate<'a>>,
+    initial: &dyn Fn(&Element) -> f64,
+) {
+    for child in element.elements() {
+        let paragraph = matches!(child.local_name(), "p" | "pre" | "td")
+            || (child.is("div") && !child.elements().any(is_block));
+        if paragraph {
+            let text = collapse(&child.text());
+            let length = text.chars().count();
+            if length >= MIN_PARAGRAPH {
+                let points = 1.0 + text.matches(',').count() as f64 + (length as f64 / 100.0).floor().min(3.0);
+                // The parent gets the points, the grandparent half, further ancestors less
+                ancestors.push(child);
+                for level in 1..=3.min(ancestors.len() - 1) {
+                    let index = ancestors.len() - 1 - level;
+                    let ancestor = ancestors[index];
+                    let parent = index.checked_sub(1).map(|i| ancestors[i]);
+                    let divider = match level {
+                        1 => 1.0,
+                        2 => 2.0,
+                        _ => level as f64 * 3.0,
+                    };
+                    scores
+                        .entry(ancestor as *const Element)
+                        .or_insert_with(|| Candidate { element: ancestor, parent, score: initial(ancestor) })
+                        .score += points / divider;
+                }
+                ancestors.pop();
+            }
+        }
+        ancestors.push(child);
+        score(child, ancestors, scores, initial);
+        ancestors.pop();
+    }
+}
+
+fn initial_score(element: &Element, positive: &Regex, negative: &Regex) -> f64 {
+    let tag = match element.local_name() {
+        "div" | "article" => 5.0,
+        "pre" | "td" | "blockquote" => 3.0,
+        "address" | "ol" | "ul" | "dl" | "dd" | "dt" | "li" | "form" => -3.0,
+        "h1" | "h2" | "h3" | "h4" | "h5" | "h6" | "th" => -5.0,
+        _ => 0.0,
+    };
+    let mut weight = 0.0;
+    for words in [element.attr("class"), element.attr("id")].into_iter().flatten() {
+        if negative.is_match(words) {
+            weight -= 25.0;
+        }
+        if positive.is_match(words) {
+            weight += 25.0;
+        }
+    }
+    tag + weight
+}
+
+fn is_block(element: &Element) -> bool {
+    matches!(
+        element.local_name(),
+        "address" | "article" | "aside" | "blockquote" | "div" | "dl" | "fieldset" | "figure" | "footer" | "form"
+            | "h1" | "h2" | "h3" | "h4" | "h5" | "h6" | "header" | "hr" | "main" | "nav" | "ol" | "p" | "pre"
+            | "section" | "table" | "ul"
+    )
+}
+
+fn text_length(element: &Element) -> usize {
+    collapse(&element.text()).chars().count()
+}
+
+/// The share of the element's text that is link text.
+fn link_density(element: &Element) -> f64 {
+    let length = text_length(element);
+    if length == 0 {
+        return 0.0;
+    }
+    let links: usize = element.find_all("a").iter().map(|link| text_length(link)).sum();
+    links as f64 / length as f64
+}
+
+fn collapse(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
@@ -0,0 +1,758 @@
+//! HTML reader: the page is parsed like a browser would into an element
+//! tree, which is walked into the document model.
+
+mod dom;
+mod extract;
+mod select;
+
+use crate::cli::Args;
+use crate::config::{Config, HtmlConfig};
+use crate::converter::registry::FormatConverter;
+use crate::converter::xml::{Element, Node};
+use crate::document::{blocks_to_inlines, Alignment, Block, Document, Footnote, Inline, ListItem, Table};
+use crate::renderer::{escape_html, render, render_inlines_html};
+use crate::converter::epub::parse_date;
+use crate::error::Result;
+use crate::metadata::DocumentMetadata;
+use crate::utils::decode_text;
+use encoding_rs::Encoding;
+use regex::Regex;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::Path;
+
+pub struct HtmlConverter;
+
+impl FormatConverter for HtmlConverter {
+    fn name(&self) -> &str {
+        "html"
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["html", "htm"]
+    }
+
+    fn mime_types(&self) -> &[&str] {
+        &["text/html", "application/xhtml+xml"]
+    }
+
+    fn read(&self, path: &Path, config: &Config, args: &Args) -> Result<Document> {
+        read_html(path, config, args)
+    }
+}
+
+pub fn convert_html(path: &Path, config: &Config, args: &Args) -> Result<String> {
+    let document = read_html(path, config, args)?;
+    Ok(render(&document, config, args))
+}
+
+pub fn read_html(path: &Path, config: &Config, args: &Args) -> Result<Document> {
+    let html_content = read_page(path, args.encoding.as_deref())?;
+    let root = dom::parse(&html_content);
+    let info = extract::PageInfo::read(&root);
+    let body = root.child("body").unwrap_or(&root);
+    let content = extract::extract(body, &config.html.extract)?;
+    let mut document = html_to_document(&dom::serialize(&content), &config.html)?;
+    document.title = info.title;
+    document.author = info.author;
+    document.date = info.published.map(|date| date.split('T').next().unwrap_or(&date).to_string());
+    Ok(document)
+}
+
+/// HTML metadata for `path` from its `<head>`, decoded as [`read_page`]
+/// does, used by [`crate::metadata::extract_metadata`].
+pub(crate) fn read_html_metadata(path: &Path, encoding: Option<&str>, metadata: &mut DocumentMetadata) -> Result<()> {
+    let root = dom::parse(&read_page(path, encoding)?);
+    let info = extract::PageInfo::read(&root);
+    metadata.title = info.title;
+    metadata.author = info.author;
+    metadata.subject = info.description;
+    metadata.keywords = info.keywords;
+    metadata.language = info.language;
+    metadata.publisher = info.site_name;
+    metadata.identifier = info.url;
+    metadata.creation_date = info.published.as_deref().and_then(parse_date);
+    metadata.modification_date = info.modified.as_deref().and_then(parse_date);
+    Ok(())
+}
+
+/// The page at `path` as text, decoded with `encoding` if given, else as
+/// the page declares or as it looks.
+pub(crate) fn read_page(path: &Path, encoding: Option<&str>) -> Result<String> {
+    let data = fs::read(path)?;
+    decode_text(&data, encoding, declared_encoding(&data))
+}
+
+/// Pages declare their encoding this far in.
+const CHARSET_PRESCAN_BYTES: usize = 4096;
+
+/// The encoding declared in `<meta charset>`, a `<meta http-equiv>` content
+/// type or an XML declaration near the start of the page. A page readable
+/// this way is not UTF-16, whatever it says.
+fn declared_encoding(data: &[u8]) -> Option<&'static Encoding> {
+    let head = String::from_utf8_lossy(&data[..data.len().min(CHARSET_PRESCAN_BYTES)]);
+    let meta = Regex::new(r#"(?i)<meta\b[^>]*?charset\s*=\s*["']?\s*([\w.:-]+)"#).ok()?;
+    let xml = Regex::new(r#"^\s*<\?xml[^>]*?encoding\s*=\s*["']([\w.:-]+)"#).ok()?;
+    let label = meta.captures(&head).or_else(|| xml.captures(&head))?.get(1)?.as_str().to_string();
+    let encoding = Encoding::for_label(label.as_bytes())?;
+    Some(match encoding {
+        e if e == encoding_rs::UTF_16LE || e == encoding_rs::UTF_16BE => encoding_rs::UTF_8,
+        e if e == encoding_rs::X_USER_DEFINED => encoding_rs::WINDOWS_1252,
+        e => e,
+    })
+}
+
+/// Convert an HTML fragment or page, e.g. an EPUB chapter, into the
+/// document model, sanitized and with raw HTML as `config` says.
+pub fn html_to_document(html: &str, config: &HtmlConfig) -> Result<Document> {
+    let clean_html = sanitizer(config).clean(html).to_string();
+    let root = dom::parse(&clean_html);
+    let body = root.child("body").unwrap_or(&root);
+
+    let mut reader = HtmlReader::new(body, RawHtml::new(&config.raw_html));
+    let mut document = Document::new();
+    document.blocks = reader.blocks(body);
+    // Footnote sections are often set off by a rule
+    while matches!(document.blocks.last(), Some(Block::ThematicBreak)) {
+        document.blocks.pop();
+    }
+    reader.footnotes.sort_by_key(|footnote| footnote.label.parse::<usize>().unwrap_or(0));
+    document.footnotes = reader.footnotes;
+    Ok(document)
+}
+
+/// The sanitizer of the configured policy: ammonia's safe defaults unless
+/// tags or URL schemes are given. Ids are kept for footnotes, classes for
+/// code languages, and `data-*` attributes for raw HTML; the page title is
+/// not body text.
+fn sanitizer(config: &HtmlConfig) -> ammonia::Builder<'_> {
+    let mut builder = ammonia::Builder::default();
+    builder
+        .add_clean_content_tags(&["title"])
+        .add_generic_attributes(&["id", "class"])
+        .add_generic_attribute_prefixes(&["data-"])
+        // The policy decides about `rel`, which ammonia would set on links
+        .link_rel(None);
+    if let Some(tags) = &config.tags {
+        // ammonia does not allow a tag to be kept and emptied both
+        let emptied = builder.clone_clean_content_tags();
+        builder.tags(tags.iter().map(String::as_str).filter(|tag| !emptied.contains(tag)).collect());
+    }
+    for (tag, attributes) in &config.attributes {
+        let (prefixes, names): (Vec<&str>, Vec<&str>) =
+            attributes.iter().map(String::as_str).partition(|name| name.ends_with('*'));
+        if tag == "*" {
+            builder.add_generic_attributes(names);
+            builder.add_generic_attribute_prefixes(prefixes.into_iter().map(|prefix| prefix.trim_end_matches('*')));
+        } else {
+            builder.add_tag_attributes(tag.as_str(), names);
+        }
+    }
+    if let Some(schemes) = &config.url_schemes {
+        builder.url_schemes(schemes.iter().map(String::as_str).collect());
+    }
+    builder
+}
+
+/// What becomes of HTML without a Markdown equivalent.
+#[derive(Clone, Copy, PartialEq)]
+enum RawHtml {
+    Keep,
+    /// Only the content stays.
+    Strip,
+    /// The tags show as literal text around the content.
+    Escape,
+}
+
+impl RawHtml {
+    fn new(policy: &str) -> Self {
+        match policy {
+            "strip" => RawHtml::Strip,
+            "escape" => RawHtml::Escape,
+            _ => RawHtml::Keep,
+        }
+    }
+}
+
+/// Inline elements without a Markdown equivalent.
+const RAW_INLINE_TAGS: &[&str] = &["sup", "sub", "u", "ins", "abbr"];
+
+/// Embedded content, which only the HTML can show; its fallback content
+/// stays when stripped.
+const EMBEDDED_TAGS: &[&str] = &["video", "audio", "iframe", "object", "embed"];
+
+/// Elements a footnote reference can point to.
+const NOTE_TAGS: &[&str] = &["li", "p", "div", "aside", "section"];
+
+/// Elements that are never content.
+const SKIPPED_TAGS: &[&str] = &["head", "title", "script", "style", "template", "noscript"];
+
+struct HtmlReader {
+    raw_html: RawHtml,
+    /// Footnote labels, numbered in reference order, by the id of the note.
+    notes: HashMap<String, String>,
+    /// Ids of the references, which notes link back to.
+    backlinks: HashSet<String>,
+    footnotes: Vec<Footnote>,
+}
+
+impl HtmlReader {
+    /// Find the footnotes: links to a note element from a `<sup>`, or
+    /// containing one, as in `<sup><a href="#fn1">1</a></sup>`.
+    fn new(body: &Element, raw_html: RawHtml) -> Self {
+        let mut targets = HashSet::new();
+        let mut pending = vec![body];
+        while let Some(element) = pending.pop() {
+            if let Some(id) = element.attr("id").filter(|_| NOTE_TAGS.contains(&element.local_name())) {
+                targets.insert(id.to_string());
+            }
+            pending.extend(element.elements());
+        }
+        let mut reader = Self { raw_html, notes: HashMap::new(), backlinks: HashSet::new(), footnotes: Vec::new() };
+        reader.find_references(body, None, &targets);
+        reader
+    }
+
+    /// `sup` is the innermost enclosing `<sup>`, if any.
+    fn find_references<'a>(&mut self, element: &'a Element, sup: Option<&'a Element>, targets: &HashSet<String>) {
+        for child in element.elements() {
+            let sup = if child.is("sup") { Some(child) } else { sup };
+            let target = child.attr("href").and_then(|href| href.strip_prefix('#')).filter(|id| targets.contains(*id));
+            if let Some(target) = target.filter(|_| child.is("a") && (sup.is_some() || child.child("sup").is_some())) {
+                let label = (self.notes.len() + 1).to_string();
+                self.notes.entry(target.to_string()).or_insert(label);
+                for id in [child.attr("id"), sup.and_then(|sup| sup.attr("id"))].into_iter().flatten() {
+                    self.backlinks.insert(id.to_string());
+                }
+            }
+            self.find_references(child, sup, targets);
+        }
+    }
+
+    /// Flow content: block elements, and runs of inline content between them
+    /// as paragraphs.
+    fn blocks(&mut self, container: &Element) -> Vec<Block> {
+        let mut blocks = Vec::new();
+        let mut inlines = Vec::new();
+        for node in &container.children {
+            match node {
+                Node::Text(text) => inlines.push(Inline::Text(collapse_whitespace(text))),
+                Node::Element(element) if is_block(element) => {
+                    push_paragraph(&mut blocks, std::mem::take(&mut inlines));
+                    self.block(element, &mut blocks);
+                }
+                Node::Element(element) => self.inline(element, &mut inlines),
+            }
+        }
+        push_paragraph(&mut blocks, inlines);
+        blocks
+    }
+
+    fn block(&mut self, element: &Element, blocks: &mut Vec<Block>) {
+        if self.footnote(element) {
+            return;
+        }
+        match element.local_name() {
+            name @ ("h1" | "h2" | "h3" | "h4" | "h5" | "h6") => {
+                let content = self.inlines(element);
+                if !content.is_empty() {
+                    blocks.push(Block::Heading { level: name[1..].parse().unwrap_or(1), content });
+                }
+            }
+            "p" => push_paragraph(blocks, self.inline_children(element)),
+            "ul" | "ol" | "menu" => blocks.extend(self.list(element)),
+            "dl" => self.definition_list(element, blocks),
+            "details" => self.details(element, blocks),
+            "blockquote" => {
+                let inner = self.blocks(element);
+                if !inner.is_empty() {
+                    blocks.push(Block::BlockQuote(inner));
+                }
+            }
+            "pre" => blocks.extend(code_block(element)),
+            "table" => self.table(element, blocks),
+            "hr" => blocks.push(Block::ThematicBreak),
+            name if SKIPPED_TAGS.contains(&name) => {}
+            // Sections, divisions, figures and the like only group blocks
+            _ => blocks.extend(self.blocks(element)),
+        }
+    }
+
+    /// Read `element` as a footnote if references point to it.
+    fn footnote(&mut self, element: &Element) -> bool {
+        if !NOTE_TAGS.contains(&element.local_name()) {
+            return false;
+        }
+        let Some(label) = element.attr("id").and_then(|id| self.notes.get(id)).cloned() else {
+            return false;
+        };
+        let blocks = self.blocks(element);
+        self.footnotes.push(Footnote { label, blocks });
+        true
+    }
+
+    fn list(&mut self, element: &Element) -> Option<Block> {
+        let ordered = element.is("ol");
+        let start = element.attr("start").and_then(|start| start.trim().parse().ok()).filter(|_| ordered).unwrap_or(1);
+        let mut items: Vec<ListItem> = Vec::new();
+        for child in element.elements() {
+            if self.footnote(child) {
+                continue;
+            }
+            if child.is("li") {
+                items.push(ListItem::new(self.blocks(child)));
+                continue;
+            }
+            // A list nested without an item of its own belongs to the previous item
+            let mut blocks = Vec::new();
+            self.block(child, &mut blocks);
+            match items.last_mut() {
+                Some(item) => item.blocks.extend(blocks),
+                None if !blocks.is_empty() => items.push(ListItem::new(blocks)),
+                None => {}
+            }
+        }
+        (!items.is_empty()).then_some(Block::List { ordered, start, items })
+    }
+
+    /// Markdown has no definition lists; each term becomes a list item in
+    /// bold, its definitions the item's indented paragraphs.
+    fn definition_list(&mut self, element: &Element, blocks: &mut Vec<Block>) {
+        // Term and definition pairs may be grouped in a <div>
+        let children: Vec<&Element> = element
+            .elements()
+            .flat_map(|child| if child.is("div") { child.elements().collect() } else { vec![child] })
+            .collect();
+        let mut items: Vec<ListItem> = Vec::new();
+        let mut defined = true;
+        for child in children {
+            match child.local_name() {
+                "dt" => {
+                    let term = vec![Inline::Strong(self.inlines(child))];
+                    match items.last_mut() {
+                        // Several terms sharing a definition go on one line each
+                        Some(item) if !defined => {
+                            if let Some(Block::Paragraph(terms)) = item.blocks.first_mut() {
+                                terms.push(Inline::LineBreak);
+                                terms.extend(term);
+                            }
+                        }
+                        _ => items.push(ListItem::new(vec![Block::Paragraph(term)])),
+                    }
+                    defined = false;
+                }
+                "dd" => {
+                    let definition = self.blocks(child);
+                    match items.last_mut() {
+                        Some(item) => item.blocks.extend(definition),
+                        None if !definition.is_empty() => items.push(ListItem::new(definition)),
+                        None => {}
+                    }
+                    defined = true;
+                }
+                _ => {}
+            }
+        }
+        if !items.is_empty() {
+            blocks.push(Block::List { ordered: false, start: 1, items });
+        }
+    }
+
+    /// The summary and the content of a disclosure widget; as raw HTML, the
+    /// content stays Markdown between the tags, as GitHub renders it.
+    fn details(&mut self, element: &Element, blocks: &mut Vec<Block>) {
+        let summary = element.child("summary").map(|summary| self.inlines(summary)).unwrap_or_default();
+        let content = Element {
+            children: element
+                .children
+                .iter()
+                .filter(|node| !matches!(node, Node::Element(child) if child.is("summary")))
+                .cloned()
+                .collect(),
+            ..element.clone()
+        };
+        let inner = self.blocks(&content);
+        match self.raw_html {
+            RawHtml::Keep => {
+                let summary = format!("<summary>{}</summary>", render_inlines_html(&summary));
+                blocks.push(Block::Html(format!("{}\n{}", start_tag(element), summary)));
+                blocks.extend(inner);
+                blocks.push(Block::Html("</details>".to_string()));
+            }
+            RawHtml::Strip => {
+                push_paragraph(blocks, summary);
+                blocks.extend(inner);
+            }
+            RawHtml::Escape => {
+                let mut open = vec![Inline::text(start_tag(element)), Inline::SoftBreak, Inline::text("<summary>")];
+                open.extend(summary);
+                open.push(Inline::text("</summary>"));
+                blocks.push(Block::Paragraph(open));
+                blocks.extend(inner);
+                blocks.push(Block::paragraph("</details>"));
+            }
+        }
+    }
+
+    /// Spanned cells are written once and padded with empty cells, as for
+    /// DOCX, and the first row is the header.
+    fn table(&mut self, element: &Element, blocks: &mut Vec<Block>) {
+        let mut rows: Vec<&Element> = Vec::new();
+        let mut footer: Vec<&Element> = Vec::new();
+        for child in element.elements() {
+            match child.local_name() {
+                "caption" => push_paragraph(blocks, self.inline_children(child)),
+                "thead" | "tbody" => rows.extend(child.children_named("tr")),
+                "tfoot" => footer.extend(child.children_named("tr")),
+                "tr" => rows.push(child),
+                _ => {}
+            }
+        }
+        rows.extend(footer);
+        let Some(first) = rows.first() else {
+            return;
+        };
+        let alignments: Vec<Alignment> = cells(first)
+            .flat_map(|cell| std::iter::repeat_n(alignment(cell), span(cell, "colspan")))
+            .collect();
+
+        // Rows still covered by a cell from above, per column
+        let mut covered: Vec<usize> = Vec::new();
+        let mut grid: Vec<Vec<Vec<Inline>>> = Vec::new();
+        for row in rows {
+            let mut out: Vec<Vec<Inline>> = Vec::new();
+            let mut cells = cells(row);
+            loop {
+                let column = out.len();
+                if let Some(rows) = covered.get_mut(column).filter(|rows| **rows > 0) {
+                    *rows -= 1;
+                    out.push(Vec::new());
+                    continue;
+                }
+                let Some(cell) = cells.next() else {
+                    if covered.iter().skip(column).any(|&rows| rows > 0) {
+                        out.push(Vec::new());
+                        continue;
+                    }
+                    break;
+                };
+                let columns = span(cell, "colspan");
+                if covered.len() < column + columns {
+                    covered.resize(column + columns, 0);
+                }
+                covered[column..column + columns].fill(span(cell, "rowspan") - 1);
+                out.push(blocks_to_inlines(&self.blocks(cell)));
+                out.extend(std::iter::repeat_n(Vec::new(), columns - 1));
+            }
+            grid.push(out);
+        }
+
+        let header = grid.remove(0);
+        let columns = grid.iter().map(Vec::len).chain(std::iter::once(header.len())).max().unwrap_or(0);
+        let mut alignments = alignments;
+        alignments.resize(columns, Alignment::None);
+        blocks.push(Block::Table(Table { alignments, header, rows: grid }));
+    }
+
+    /// The element's content as inlines, with whitespace collapsed.
+    fn inlines(&mut self, element: &Element) -> Vec<Inline> {
+        trim_inlines(self.inline_children(element))
+    }
+
+    fn inline_children(&mut self, element: &Element) -> Vec<Inline> {
+        let mut inlines = Vec::new();
+        for node in &element.children {
+            match node {
+                Node::Text(text) => inlines.push(Inline::Text(collapse_whitespace(text))),
+                Node::Element(child) => self.inline(child, &mut inlines),
+            }
+        }
+        inlines
+    }
+
+    fn inline(&mut self, element: &Element, out: &mut Vec<Inline>) {
+        match element.local_name() {
+            "strong" | "b" => out.push(Inline::Strong(self.inline_children(element))),
+            "em" | "i" | "cite" | "dfn" | "var" => out.push(Inline::Emphasis(self.inline_children(element))),
+            "del" | "s" | "strike" => out.push(Inline::Strikethrough(self.inline_children(element))),
+            "mark" => out.push(Inline::Highlight(self.inline_children(element))),
+            "code" | "kbd" | "samp" | "tt" => {
+                let code = collapse_whitespace(&element.text());
+                if !code.trim().is_empty() {
+                    out.push(Inline::Code(code.trim().to_string()));
+                }
+            }
+            "a" => self.link(element, out),
+            "img" => {
+                if let Some(src) = element.attr("src").filter(|src| !src.is_empty()) {
+                    out.push(Inline::Image {
+                        url: src.to_string(),
+                        alt: element.attr("alt").unwrap_or_default().to_string(),
+                        title: element.attr("title").map(str::to_string),
+                    });
+                }
+            }
+            "br" => out.push(Inline::LineBreak),
+            "sup" | "sub" => {
+                if let Some(label) = self.note_reference(element) {
+                    out.push(Inline::FootnoteRef(label));
+                    return;
+                }
+                let content = trim_inlines(self.inline_children(element));
+                self.raw_inline(element, content, out);
+            }
+            tag if RAW_INLINE_TAGS.contains(&tag) => {
+                let content = trim_inlines(self.inline_children(element));
+                self.raw_inline(element, content, out);
+            }
+            tag if EMBEDDED_TAGS.contains(&tag) => match self.raw_html {
+                RawHtml::Keep => out.push(Inline::Html(dom::serialize(element))),
+                RawHtml::Strip => out.extend(self.inline_children(element)),
+                RawHtml::Escape => out.push(Inline::text(dom::serialize(element))),
+            },
+            name if SKIPPED_TAGS.contains(&name) => {}
+            // Blocks inside inline content, as in a heading, are separated by spaces
+            _ if is_block(element) => {
+                out.push(Inline::text(" "));
+                out.extend(self.inline_children(element));
+                out.push(Inline::text(" "));
+            }
+            _ => out.extend(self.inline_children(element)),
+        }
+    }
+
+    fn link(&mut self, element: &Element, out: &mut Vec<Inline>) {
+        let href = element.attr("href").unwrap_or_default();
+        if let Some(id) = href.strip_prefix('#') {
+            if let Some(label) = self.notes.get(id) {
+                out.push(Inline::FootnoteRef(label.clone()));
+                return;
+            }
+            if self.backlinks.contains(id) {
+                return;
+            }
+        }
+        let content = self.inline_children(element);
+        if href.is_empty() {
+            out.extend(content);
+            return;
+        }
+        out.push(Inline::Link { url: href.to_string(), title: element.attr("title").map(str::to_string), content });
+    }
+
+    /// `element` with `content` converted, as the raw HTML policy wants it.
+    fn raw_inline(&self, element: &Element, content: Vec<Inline>, out: &mut Vec<Inline>) {
+        if content.is_empty() {
+            return;
+        }
+        let end = format!("</{}>", element.local_name());
+        match self.raw_html {
+            RawHtml::Keep => {
+                out.push(Inline::Html(format!("{}{}{}", start_tag(element), render_inlines_html(&content), end)))
+            }
+            RawHtml::Strip => out.extend(content),
+            RawHtml::Escape => {
+                out.push(Inline::text(start_tag(element)));
+                out.extend(content);
+                out.push(Inline::text(end));
+            }
+        }
+    }
+
+    /// The label of the note a `<sup>` refers to.
+    fn note_reference(&self, sup: &Element) -> Option<String> {
+        sup.find_all("a")
+            .into_iter()
+            .find_map(|a| self.notes.get(a.attr("href")?.strip_prefix('#')?).cloned())
+    }
+}
+
+fn is_block(element: &Element) -> bool {
+    matches!(
+        element.local_name(),
+        "address" | "article" | "aside" | "blockquote" | "body" | "center" | "dd" | "details" | "dialog" | "div" | "dl"
+            | "dt" | "fieldset" | "figcaption" | "figure" | "footer" | "form" | "h1" | "h2" | "h3" | "h4" | "h5"
+            | "h6" | "header" | "hgroup" | "hr" | "li" | "main" | "menu" | "nav" | "ol" | "p" | "pre" | "section"
+            | "summary" | "table" | "ul" | "caption" | "tr" | "td" | "th" | "thead" | "tbody" | "tfoot"
+    ) || SKIPPED_TAGS.contains(&element.local_name())
+}
+
+fn start_tag(element: &Element) -> String {
+    let attributes: String = element
+        .attributes
+        .iter()
+        .map(|(name, value)| format!(" {}=\"{}\"", name, escape_html(value)))
+        .collect();
+    format!("<{}{}>", element.local_name(), attributes)
+}
+
+fn push_paragraph(blocks: &mut Vec<Block>, inlines: Vec<Inline>) {
+    let content = trim_inlines(inlines);
+    if !content.is_empty() {
+        blocks.push(Block::Paragraph(content));
+    }
+}
+
+/// `<pre>`, with the language from a `language-x` or `lang-x` class on it
+/// or its `<code>`, or Pandoc's `sourceCode x`.
+fn code_block(element: &Element) -> Option<Block> {
+    let language = std::iter::once(element)
+        .chain(element.child("code"))
+        .find_map(|element| code_language(element.attr("class")?));
+    let mut code = String::new();
+    preformatted_text(element, &mut code);
+    let code = code.strip_prefix('\n').unwrap_or(&code).trim_end();
+    (!code.trim().is_empty()).then(|| Block::code(language, code))
+}
+
+fn code_language(class: &str) -> Option<String> {
+    let classes: Vec<&str> = class.split_whitespace().collect();
+    let prefixed = classes
+        .iter()
+        .find_map(|class| class.strip_prefix("language-").or_else(|| class.strip_prefix("lang-")));
+    let pandoc = || classes.contains(&"sourceCode").then(|| classes.iter().find(|&&class| class != "sourceCode")).flatten().copied();
+    prefixed.or_else(pandoc).filter(|language| !language.is_empty()).map(str::to_string)
+}
+
+fn preformatted_text(element: &Element, out: &mut String) {
+    for node in &element.children {
+        match node {
+            Node::Text(text) => out.push_str(text),
+            Node::Element(child) if child.is("br") => out.push('\n'),
+            Node::Element(child) => preformatted_text(child, out),
+        }
+    }
+}
+
+fn cells(row: &Element) -> impl Iterator<Item = &Element> {
+    row.elements().filter(|cell| cell.is("td") || cell.is("th"))
+}
+
+/// `colspan` or `rowspan`, at least 1.
+fn span(cell: &Element, attribute: &str) -> usize {
+    cell.attr(attribute).and_then(|span| span.trim().parse().ok()).unwrap_or(1).clamp(1, 1000)
+}
+
+/// From `align` or a `text-align` style.
+fn alignment(cell: &Element) -> Alignment {
+    let style = cell.attr("style").unwrap_or_default().split(';').find_map(|declaration| {
+        let (property, value) = declaration.split_once(':')?;
+        (property.trim().eq_ignore_ascii_case("text-align")).then(|| value.trim())
+    });
+    match cell.attr("align").or(style).map(str::to_ascii_lowercase).as_deref() {
+        Some("left") => Alignment::Left,
+        Some("center") => Alignment::Center,
+        Some("right") => Alignment::Right,
+        _ => Alignment::None,
+    }
+}
+
+/// Runs of HTML whitespace as one space; other spaces, such as `&nbsp;`, stay.
+fn collapse_whitespace(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut space = false;
+    for c in text.chars() {
+        if matches!(c, ' ' | '\t' | '\n' | '\r' | '\x0c') {
+            if !space {
+                out.push(' ');
+            }
+            space = true;
+        } else {
+            out.push(c);
+            space = false;
+        }
+    }
+    out
+}
+
+/// Drop the spaces a browser would not show: at the start and end, after a
+/// line break and after another space, across element boundaries. Spaces at
+/// the edges of emphasis and links move outside them.
+fn trim_inlines(inlines: Vec<Inline>) -> Vec<Inline> {
+    let mut out = Vec::new();
+    squeeze(inlines, &mut true, &mut out);
+    loop {
+        trim_end(&mut out);
+        if !matches!(out.last(), Some(Inline::LineBreak)) {
+            break;
+        }
+        out.pop();
+    }
+    out
+}
+
+/// `space` is whether the output so far ends with a space.
+fn squeeze(inlines: Vec<Inline>, space: &mut bool, out: &mut Vec<Inline>) {
+    for inline in inlines {
+        match inline {
+            Inline::Text(text) => push_text(out, &text, space),
+            Inline::LineBreak => {
+                trim_end(out);
+                if !out.is_empty() {
+                    out.push(Inline::LineBreak);
+                }
+                *space = true;
+            }
+            Inline::Emphasis(content) => squeeze_container(content, space, out, Inline::Emphasis),
+            Inline::Strong(content) => squeeze_container(content, space, out, Inline::Strong),
+            Inline::Strikethrough(content) => squeeze_container(content, space, out, Inline::Strikethrough),
+            Inline::Highlight(content) => squeeze_container(content, space, out, Inline::Highlight),
+            Inline::Link { url, title, content } => {
+                squeeze_container(content, space, out, |content| Inline::Link { url, title, content })
+            }
+            other => {
+                out.push(other);
+                *space = false;
+            }
+        }
+    }
+}
+
+fn squeeze_container(content: Vec<Inline>, space: &mut bool, out: &mut Vec<Inline>, wrap: impl FnOnce(Vec<Inline>) -> Inline) {
+    let mut inner = Vec::new();
+    squeeze(content, space, &mut inner);
+    if let Some(Inline::Text(first)) = inner.first_mut() {
+        if let Some(rest) = first.strip_prefix(' ') {
+            *first = rest.to_string();
+            if first.is_empty() {
+                inner.remove(0);
+            }
+            push_text(out, " ", &mut false);
+        }
+    }
+    let trailing = trim_end(&mut inner);
+    if !inner.is_empty() {
+        out.push(wrap(inner));
+    }
+    if trailing {
+        push_text(out, " ", &mut false);
+    }
+}
+
+fn push_text(out: &mut Vec<Inline>, text: &str, space: &mut bool) {
+    let text = if *space { text.trim_start_matches(' ') } else { text };
+    if text.is_empty() {
+        return;
+    }
+    *space = text.ends_with(' ');
+    match out.last_mut() {
+        Some(Inline::Text(previous)) => previous.push_str(text),
+        _ => out.push(Inline::text(text)),
+    }
+}
+
+/// Remove trailing spaces; whether there were any.
+fn trim_end(inlines: &mut Vec<Inline>) -> bool {
+    let Some(Inline::Text(text)) = inlines.last_mut() else {
+        return false;
+    };
+    let length = text.trim_end_matches(' ').len();
+    if length == text.len() {
+        return false;
+    }
+    text.truncate(length);
+    if text.is_empty() {
+        inlines.pop();
+    }
+    true
+}
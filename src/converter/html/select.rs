@@ -0,0 +1,206 @@
+//! CSS selectors, enough to pick the content out of a page: type, `*`,
+//! `#id`, `.class` and attribute selectors (`[a]`, `[a=v]`, `[a~=v]`,
+//! `[a^=v]`, `[a$=v]`, `[a*=v]`), descendant and child combinators, and
+//! comma-separated lists.
+
+use crate::converter::xml::Element;
+use crate::error::{C2mdError, Result};
+
+pub struct Selector {
+    /// Alternatives of the list, each a chain of compounds from the
+    /// outermost; the combinator joins a compound to the one before it.
+    alternatives: Vec<Vec<(Combinator, Compound)>>,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Combinator {
+    Descendant,
+    Child,
+}
+
+#[derive(Default)]
+struct Compound {
+    tag: Option<String>,
+    id: Option<String>,
+    classes: Vec<String>,
+    attributes: Vec<(String, Option<(char, String)>)>,
+}
+
+impl Selector {
+    pub fn parse(selector: &str) -> Result<Self> {
+        let invalid = |reason: &str| C2mdError::Config(format!("Invalid CSS selector `{}`: {}", selector, reason));
+        let mut alternatives = Vec::new();
+        for alternative in selector.split(',') {
+            let mut chain = Vec::new();
+            let mut combinator = Combinator::Descendant;
+            let mut chars = alternative.trim().chars().peekable();
+            while let Some(&c) = chars.peek() {
+                if c.is_whitespace() {
+                    chars.next();
+                    continue;
+                }
+                if c == '>' {
+                    if chain.is_empty() {
+                        return Err(invalid("`>` without a left-hand side"));
+                    }
+                    chars.next();
+                    combinator = Combinator::Child;
+                    continue;
+                }
+                let mut compound = Compound::default();
+                while let Some(&c) = chars.peek() {
+                    match c {
+                        '*' => {
+                            chars.next();
+                        }
+                        '#' | '.' => {
+                            chars.next();
+                            let name = take_name(&mut chars);
+                            if name.is_empty() {
+                                return Err(invalid("missing name"));
+                            }
+                            if c == '#' {
+                                compound.id = Some(name);
+                            } else {
+                                compound.classes.push(name);
+                            }
+                        }
+                        '[' => {
+                            chars.next();
+                            compound.attributes.push(parse_attribute(&mut chars).ok_or_else(|| invalid("bad attribute selector"))?);
+                        }
+                        c if is_name_char(c) => compound.tag = Some(take_name(&mut chars).to_ascii_lowercase()),
+                        c if c.is_whitespace() || c == '>' => break,
+                        c => return Err(invalid(&format!("`{}` is not supported", c))),
+                    }
+                }
+                chain.push((combinator, compound));
+                combinator = Combinator::Descendant;
+            }
+            if chain.is_empty() || combinator == Combinator::Child {
+                return Err(invalid("empty selector"));
+            }
+            alternatives.push(chain);
+        }
+        Ok(Self { alternatives })
+    }
+
+    /// The outermost matching elements below `root`, in document order;
+    /// matches inside a match are part of it.
+    pub fn select<'a>(&self, root: &'a Element) -> Vec<&'a Element> {
+        let mut found = Vec::new();
+        let mut ancestors = vec![root];
+        self.collect(root, &mut ancestors, &mut found);
+        found
+    }
+
+    fn collect<'a>(&self, element: &'a Element, ancestors: &mut Vec<&'a Element>, found: &mut Vec<&'a Element>) {
+        for child in element.elements() {
+            if self.matches(child, ancestors) {
+                found.push(child);
+                continue;
+            }
+            ancestors.push(child);
+            self.collect(child, ancestors, found);
+            ancestors.pop();
+        }
+    }
+
+    fn matches(&self, element: &Element, ancestors: &[&Element]) -> bool {
+        self.alternatives.iter().any(|chain| matches_chain(chain, element, ancestors))
+    }
+}
+
+/// Whether `element`, below `ancestors` (the closest last), matches the
+/// chain's last compound and the rest of the chain matches its ancestors.
+fn matches_chain(chain: &[(Combinator, Compound)], element: &Element, ancestors: &[&Element]) -> bool {
+    let Some(((combinator, compound), rest)) = chain.split_last() else {
+        return true;
+    };
+    if !compound.matches(element) {
+        return false;
+    }
+    if rest.is_empty() {
+        return true;
+    }
+    match combinator {
+        Combinator::Child => {
+            ancestors.split_last().is_some_and(|(parent, above)| matches_chain(rest, parent, above))
+        }
+        Combinator::Descendant => {
+            (0..ancestors.len()).rev().any(|i| matches_chain(rest, ancestors[i], &ancestors[..i]))
+        }
+    }
+}
+
+impl Compound {
+    fn matches(&self, element: &Element) -> bool {
+        if self.tag.as_ref().is_some_and(|tag| !element.local_name().eq_ignore_ascii_case(tag)) {
+            return false;
+        }
+        if self.id.as_ref().is_some_and(|id| element.attr("id") != Some(id.as_str())) {
+            return false;
+        }
+        let classes: Vec<&str> = element.attr("class").unwrap_or_default().split_whitespace().collect();
+        if !self.classes.iter().all(|class| classes.contains(&class.as_str())) {
+            return false;
+        }
+        self.attributes.iter().all(|(name, test)| {
+            let Some(value) = element.attr(name) else {
+                return false;
+            };
+            match test {
+                None => true,
+                Some(('=', expected)) => value == expected,
+                Some(('~', expected)) => value.split_whitespace().any(|word| word == expected),
+                Some(('^', expected)) => !expected.is_empty() && value.starts_with(expected.as_str()),
+                Some(('$', expected)) => !expected.is_empty() && value.ends_with(expected.as_str()),
+                Some(('*', expected)) => !expected.is_empty() && value.contains(expected.as_str()),
+                Some(_) => false,
+            }
+        })
+    }
+}
+
+fn is_name_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '-' || c == '_' || !c.is_ascii()
+}
+
+fn take_name(chars: &mut std::iter::Peekable<std::str::Chars>) -> String {
+    let mut name = String::new();
+    while let Some(&c) = chars.peek().filter(|&&c| is_name_char(c)) {
+        name.push(c);
+        chars.next();
+    }
+    name
+}
+
+/// `name]`, `name=value]` and the like, after the `[`.
+fn parse_attribute(chars: &mut std::iter::Peekable<std::str::Chars>) -> Option<(String, Option<(char, String)>)> {
+    let skip_spaces = |chars: &mut std::iter::Peekable<std::str::Chars>| {
+        while chars.next_if(|c| c.is_whitespace()).is_some() {}
+    };
+    skip_spaces(chars);
+    let name = take_name(chars);
+    if name.is_empty() {
+        return None;
+    }
+    skip_spaces(chars);
+    let operator = match chars.next()? {
+        ']' => return Some((name, None)),
+        '=' => '=',
+        c @ ('~' | '^' | '$' | '*') if chars.next() == Some('=') => c,
+        _ => return None,
+    };
+    skip_spaces(chars);
+    let value = match chars.peek() {
+        Some(&quote @ ('"' | '\'')) => {
+            chars.next();
+            let value: String = chars.by_ref().take_while(|&c| c != quote).collect();
+            value
+        }
+        _ => take_name(chars),
+    };
+    skip_spaces(chars);
+    (chars.next()? == ']').then_some((name, Some((operator, value))))
+}
@@ -1,50 +1,92 @@
 use crate::cli::Args;
-use crate::config::Config;
+use crate::config::{CleanupConfig, Config};
+use crate::converter::registry::FormatConverter;
+use crate::converter::text::text_to_paragraphs;
+use crate::converter::cleanup::Cleanup;
+use crate::converter::labeled_item;
+use crate::converter::pages::{describe, strip_running_text, PageSelection};
+use crate::document::{Block, Document, Inline};
+use crate::renderer::render;
 use crate::error::{C2mdError, Result};
 use std::fs;
 use std::path::Path;
-use image::{DynamicImage, ImageFormat};
 use base64::Engine;
+use rayon::prelude::*;
 use std::process::Command;
 
-pub fn convert_image(path: &Path, config: &Config, args: &Args) -> Result<String> {
-    let mut markdown = String::new();
-    
-    // Add front matter
-    if config.frontmatter != "none" {
-        markdown.push_str("---\n");
-        if let Some(title) = &args.title {
-            markdown.push_str(&format!("title: {}\n", title));
-        }
-        markdown.push_str("---\n\n");
+pub struct ImageConverter;
+
+impl FormatConverter for ImageConverter {
+    fn name(&self) -> &str {
+        "image"
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["jpg", "jpeg", "png", "gif", "bmp", "tif", "tiff", "webp"]
+    }
+
+    fn mime_types(&self) -> &[&str] {
+        &[
+            "image/jpeg",
+            "image/png",
+            "image/gif",
+            "image/bmp",
+            "image/tiff",
+            "image/webp",
+        ]
+    }
+
+    fn sniff(&self, header: &[u8]) -> bool {
+        header.starts_with(b"\x89PNG")
+            || header.starts_with(b"\xFF\xD8\xFF")
+            || header.starts_with(b"GIF87a")
+            || header.starts_with(b"GIF89a")
+            || header.starts_with(b"BM")
     }
+
+    fn read(&self, path: &Path, config: &Config, args: &Args) -> Result<Document> {
+        read_image(path, config, args)
+    }
+}
+
+pub fn convert_image(path: &Path, config: &Config, args: &Args) -> Result<String> {
+    let document = read_image(path, config, args)?;
+    Ok(render(&document, config, args))
+}
+
+pub fn read_image(path: &Path, config: &Config, args: &Args) -> Result<Document> {
+    let mut document = Document::new();
     
     // Handle image based on strategy
-    match config.images.mode.as_str() {
-        "keep" => {
-            markdown.push_str(&format!("![Image]({})\n", path.display()));
-        }
-        "download" => {
-            let asset_path = download_image(path, config, args)?;
-            markdown.push_str(&format!("![Image]({})\n", asset_path.display()));
-        }
-        "inline" => {
-            let data_uri = inline_image(path)?;
-            markdown.push_str(&format!("![Image]({})\n", data_uri));
-        }
+    let url = match config.images.mode.as_str() {
+        "keep" => path.display().to_string(),
+        "download" => download_image(path, config, args)?.display().to_string(),
+        "inline" => inline_image(path)?,
         _ => {
             return Err(C2mdError::Generic("Invalid image mode".to_string()));
         }
-    }
+    };
+    document.push(Block::Paragraph(vec![Inline::Image {
+        url,
+        alt: "Image".to_string(),
+        title: None,
+    }]));
     
     // If OCR is enabled, try to extract text
     if args.ocr || config.ocr.enabled {
-        match extract_text_from_image(path, &config.ocr.lang) {
-            Ok(text) => {
-                if !text.trim().is_empty() {
-                    markdown.push_str("\n## Extracted Text\n\n");
-                    markdown.push_str(&text);
-                    markdown.push('\n');
+        let selection = PageSelection::configured(config)?.filter(|_| is_tiff(path));
+        let extracted = match &selection {
+            Some(selection) => ocr_tiff_pages(path, selection, config).map(|(numbers, blocks)| {
+                document.pages = Some(describe(&numbers));
+                blocks
+            }),
+            None => extract_text_from_image(path, &config.ocr.lang, &config.cleanup).map(|text| text_to_paragraphs(&text)),
+        };
+        match extracted {
+            Ok(blocks) => {
+                if !blocks.is_empty() {
+                    document.push(Block::heading(2, "Extracted Text"));
+                    document.blocks.extend(blocks);
                 }
             }
             Err(e) => {
@@ -56,17 +98,19 @@ pub fn convert_image(path: &Path, config: &Config, args: &Args) -> Result<String
     
     // Add image metadata
     if let Ok(metadata) = extract_image_metadata(path) {
-        markdown.push_str("\n## Image Metadata\n\n");
-        markdown.push_str(&format!("- **File**: {}\n", path.file_name().unwrap().to_string_lossy()));
-        markdown.push_str(&format!("- **Format**: {}\n", metadata.format));
-        markdown.push_str(&format!("- **Dimensions**: {}x{}\n", metadata.width, metadata.height));
+        let mut items = vec![
+            labeled_item("File", &path.file_name().unwrap().to_string_lossy()),
+            labeled_item("Format", &metadata.format),
+            labeled_item("Dimensions", &format!("{}x{}", metadata.width, metadata.height)),
+        ];
         if let Some(size) = metadata.file_size {
-            markdown.push_str(&format!("- **File Size**: {}\n", format_file_size(size)));
+            items.push(labeled_item("File Size", &format_file_size(size)));
         }
-        markdown.push('\n');
+        document.push(Block::heading(2, "Image Metadata"));
+        document.push(Block::bullet_list(items));
     }
     
-    Ok(markdown)
+    Ok(document)
 }
 
 fn download_image(path: &Path, config: &Config, args: &Args) -> Result<std::path::PathBuf> {
@@ -100,22 +144,56 @@ fn download_image(path: &Path, config: &Config, args: &Args) -> Result<std::path
 
 fn inline_image(path: &Path) -> Result<String> {
     let image_data = fs::read(path)?;
-    let mime_type = mime_guess::from_path(path)
+    Ok(data_uri(&image_data, path))
+}
+
+fn data_uri(data: &[u8], name: &Path) -> String {
+    let mime_type = mime_guess::from_path(name)
         .first_or_octet_stream()
         .to_string();
     
-    let encoded = base64::engine::general_purpose::STANDARD.encode(&image_data);
-    Ok(format!("data:{};base64,{}", mime_type, encoded))
+    let encoded = base64::engine::general_purpose::STANDARD.encode(data);
+    format!("data:{};base64,{}", mime_type, encoded)
 }
 
-fn extract_text_from_image(path: &Path, lang: &str) -> Result<String> {
+/// Store an image embedded in another document (DOCX media, EPUB assets, ...)
+/// according to the image mode and return the URL to reference it by.
+///
+/// Embedded images have no original location to keep, so `keep` extracts
+/// them to the assets directory like `download`.
+pub fn store_image(data: &[u8], file_name: &str, config: &Config, args: &Args) -> Result<String> {
+    match config.images.mode.as_str() {
+        "inline" => Ok(data_uri(data, Path::new(file_name))),
+        "keep" | "download" => {
+            let default_assets_dir = std::path::PathBuf::from(&config.images.assets_dir);
+            let assets_dir = args.assets_dir.as_ref()
+                .unwrap_or(&default_assets_dir);
+            fs::create_dir_all(assets_dir)?;
+
+            let asset_path = assets_dir.join(file_name);
+            fs::write(&asset_path, data)?;
+
+            if let Some(max_width) = args.image_max_width {
+                // Vector formats such as EMF cannot be resized; keep them as they are
+                if let Err(e) = resize_image(&asset_path, max_width) {
+                    tracing::warn!("Cannot resize {}: {}", asset_path.display(), e);
+                }
+            }
+
+            Ok(asset_path.display().to_string())
+        }
+        _ => Err(C2mdError::Generic("Invalid image mode".to_string())),
+    }
+}
+
+pub(crate) fn extract_text_from_image(path: &Path, lang: &str, cleanup: &CleanupConfig) -> Result<String> {
     // Check if tesseract is available
     if which::which("tesseract").is_err() {
         return Err(C2mdError::MissingDependency("Tesseract OCR not found. Please install tesseract-ocr".to_string()));
     }
 
     let output = Command::new("tesseract")
-        .args(&[path.to_str().unwrap(), "stdout", "-l", lang])
+        .args([path.to_str().unwrap(), "stdout", "-l", lang])
         .output()?;
 
     if !output.status.success() {
@@ -124,9 +202,92 @@ fn extract_text_from_image(path: &Path, lang: &str) -> Result<String> {
 
     let text = String::from_utf8(output.stdout)?;
     
-    // Clean up the OCR text
-    let cleaned_text = clean_ocr_text(&text);
-    Ok(cleaned_text)
+    // Clean up the OCR text; tesseract ends each page of multi-page images with a form feed
+    let pages: Vec<String> = text.split('\x0c').filter(|page| !page.trim().is_empty()).map(clean_ocr_text).collect();
+    let numbers: Vec<usize> = (1..=pages.len()).collect();
+    let pages = strip_running_text(&pages, &numbers);
+    let cleanup = Cleanup::new(cleanup, pages.iter().flat_map(|page| page.lines()));
+    Ok(pages.iter().map(|page| cleanup.text(page)).collect::<Vec<_>>().join("\n\n"))
+}
+
+fn is_tiff(path: &Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("tif") || ext.eq_ignore_ascii_case("tiff"))
+}
+
+/// OCR only the selected pages of a multi-page TIFF, in parallel, each after a
+/// page marker.
+fn ocr_tiff_pages(path: &Path, selection: &PageSelection, config: &Config) -> Result<(Vec<usize>, Vec<Block>)> {
+    let numbers = selection.pages(tiff_page_count(path)?)?;
+    let dir = tempfile::tempdir()?;
+    let texts: Vec<Result<String>> = numbers
+        .par_iter()
+        .map(|&number| {
+            let page = dir.path().join(format!("page-{}.png", number));
+            save_tiff_page(path, number, &page)?;
+            extract_text_from_image(&page, &config.ocr.lang, &config.cleanup)
+        })
+        .collect();
+
+    let texts = strip_running_text(&texts.into_iter().collect::<Result<Vec<_>>>()?, &numbers);
+
+    let mut blocks = Vec::new();
+    for (number, text) in numbers.iter().zip(texts) {
+        blocks.push(Block::Html(format!("<!-- page {} -->", number)));
+        blocks.extend(text_to_paragraphs(&text));
+    }
+    Ok((numbers, blocks))
+}
+
+fn tiff_error(e: tiff::TiffError) -> C2mdError {
+    C2mdError::Image(format!("TIFF: {}", e))
+}
+
+fn tiff_decoder(path: &Path) -> Result<tiff::decoder::Decoder<std::io::BufReader<fs::File>>> {
+    tiff::decoder::Decoder::new(std::io::BufReader::new(fs::File::open(path)?)).map_err(tiff_error)
+}
+
+fn tiff_page_count(path: &Path) -> Result<usize> {
+    let mut decoder = tiff_decoder(path)?;
+    let mut count = 1;
+    while decoder.more_images() {
+        decoder.next_image().map_err(tiff_error)?;
+        count += 1;
+    }
+    Ok(count)
+}
+
+/// Decode one 1-based page of a TIFF and write it where tesseract can read it.
+fn save_tiff_page(path: &Path, number: usize, target: &Path) -> Result<()> {
+    use image::{DynamicImage, ImageBuffer};
+    use tiff::decoder::DecodingResult;
+    use tiff::ColorType;
+
+    let mut decoder = tiff_decoder(path)?;
+    decoder.seek_to_image(number - 1).map_err(tiff_error)?;
+    let (width, height) = decoder.dimensions().map_err(tiff_error)?;
+    let color = decoder.colortype().map_err(tiff_error)?;
+    let image = match (color, decoder.read_image().map_err(tiff_error)?) {
+        (ColorType::Gray(1), DecodingResult::U8(bits)) => {
+            // Bilevel scans: rows are packed eight pixels to a byte. The
+            // decoder flips WhiteIsZero samples itself, so a set bit is white
+            // under either PhotometricInterpretation
+            let stride = (width as usize).div_ceil(8);
+            Some(DynamicImage::ImageLuma8(ImageBuffer::from_fn(width, height, |x, y| {
+                let byte = bits.get(y as usize * stride + x as usize / 8).copied().unwrap_or(0xff);
+                image::Luma([if byte & (0x80 >> (x % 8)) != 0 { 255 } else { 0 }])
+            })))
+        }
+        (ColorType::Gray(8), DecodingResult::U8(data)) => ImageBuffer::from_raw(width, height, data).map(DynamicImage::ImageLuma8),
+        (ColorType::Gray(16), DecodingResult::U16(data)) => ImageBuffer::from_raw(width, height, data).map(DynamicImage::ImageLuma16),
+        (ColorType::RGB(8), DecodingResult::U8(data)) => ImageBuffer::from_raw(width, height, data).map(DynamicImage::ImageRgb8),
+        (ColorType::RGBA(8), DecodingResult::U8(data)) => ImageBuffer::from_raw(width, height, data).map(DynamicImage::ImageRgba8),
+        _ => None,
+    };
+    let image = image.ok_or_else(|| C2mdError::Image(format!("Unsupported TIFF page format {:?}", color)))?;
+    image.save(target)?;
+    Ok(())
 }
 
 fn clean_ocr_text(text: &str) -> String {
@@ -142,11 +303,6 @@ fn clean_ocr_text(text: &str) -> String {
         cleaned = re.replace_all(&cleaned, "$1 $2").to_string();
     }
     
-    // Remove page numbers
-    if let Ok(re) = regex::Regex::new(r"^\s*\d+\s*$") {
-        cleaned = re.replace_all(&cleaned, "").to_string();
-    }
-    
     cleaned
 }
 
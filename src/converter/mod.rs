@@ -6,13 +6,22 @@ pub mod html;
 pub mod epub;
 pub mod csv;
 pub mod rtf;
+pub mod pages;
+pub mod cleanup;
+pub mod registry;
+pub mod archive;
+pub mod xml;
+
+pub use registry::{register_converter, registry, ConverterRegistry, FormatConverter};
 
 use crate::cli::Args;
 use crate::config::Config;
+use crate::document::{Document, Inline, ListItem};
 use crate::error::{C2mdError, Result};
-use crate::validator::{validate_file, FileInfo};
-use crate::metadata::{extract_metadata, format_metadata_as_markdown};
-use crate::template::{TemplateEngine, TemplateContext, create_template_context};
+use crate::validator::{validate_file_with, FileInfo, ValidateOptions};
+use crate::metadata::{extract_metadata_with, MetadataOptions};
+use crate::renderer::{MarkdownRenderer, RenderOptions};
+use crate::template::{TemplateEngine, create_template_context};
 use std::path::Path;
 use std::fs;
 use walkdir::WalkDir;
@@ -22,6 +31,14 @@ use rayon::prelude::*;
 use tracing::{info, error, warn};
 use std::collections::HashMap;
 
+// `*` must not cross directory separators, so `**/.*` only matches dotfiles
+// rather than any path below a dot-directory such as `/tmp/.tmpXXXX/`.
+const IGNORE_MATCH_OPTIONS: glob::MatchOptions = glob::MatchOptions {
+    case_sensitive: true,
+    require_literal_separator: true,
+    require_literal_leading_dot: false,
+};
+
 pub struct Converter {
     config: Config,
     args: Args,
@@ -94,25 +111,14 @@ impl Converter {
         let path_str = path.to_string_lossy();
         for pattern in &self.config.ignore {
             if glob::Pattern::new(pattern)
-                .map(|p| p.matches(&path_str))
+                .map(|p| p.matches_with(&path_str, IGNORE_MATCH_OPTIONS))
                 .unwrap_or(false)
             {
                 return false;
             }
         }
 
-        // Check file extension
-        if let Some(ext) = path.extension() {
-            let ext_str = ext.to_string_lossy().to_lowercase();
-            matches!(
-                ext_str.as_str(),
-                "pdf" | "doc" | "docx" | "xls" | "xlsx" | "ppt" | "pptx" |
-                "rtf" | "txt" | "html" | "htm" | "epub" | "csv" |
-                "jpg" | "jpeg" | "png" | "gif" | "bmp" | "tiff" | "webp"
-            )
-        } else {
-            false
-        }
+        registry().supports_path(path)
     }
 
     fn show_plan(&self, paths: &[std::path::PathBuf]) -> Result<()> {
@@ -148,7 +154,7 @@ impl Converter {
     }
 
     async fn batch_convert(&self, paths: &[std::path::PathBuf]) -> Result<()> {
-        let _jobs = self.args.jobs.unwrap_or_else(|| num_cpus::get());
+        let _jobs = self.args.jobs.unwrap_or_else(num_cpus::get);
         let pb = ProgressBar::new(paths.len() as u64);
         pb.set_style(
             ProgressStyle::default_bar()
@@ -171,15 +177,22 @@ impl Converter {
         pb.finish_with_message("Conversion completed");
 
         let mut errors = 0;
+        let mut encrypted = Vec::new();
         for (i, result) in results.into_iter().enumerate() {
             if let Err(e) = result {
                 error!("Failed to convert {}: {}", paths[i].display(), e);
                 errors += 1;
+                if matches!(e, C2mdError::Encrypted(_)) {
+                    encrypted.push(paths[i].display().to_string());
+                }
             }
         }
 
+        if !encrypted.is_empty() {
+            warn!("{} encrypted files need a password: {}", encrypted.len(), encrypted.join(", "));
+        }
         if errors > 0 {
-            warn!("{} files failed to convert", errors);
+            return Err(C2mdError::Generic(format!("{} of {} files failed to convert", errors, paths.len())));
         }
 
         Ok(())
@@ -237,50 +250,35 @@ impl Converter {
     fn convert_single_file(&self, input_path: &Path) -> Result<()> {
         let output_path = self.determine_output_path(input_path)?;
         
+        // Pick a converter, honoring --from when given; validation and
+        // metadata follow its format
+        let converter = self.resolve_converter(input_path)?;
+        let format = Some(converter.name().to_string());
+        let password = self.args.pdf_password()?;
+
         // Validate file first
-        let file_info = validate_file(input_path)?;
+        let encoding = self.args.encoding.clone();
+        let options = ValidateOptions { password: password.clone(), format: format.clone(), encoding: encoding.clone() };
+        let file_info = validate_file_with(input_path, &options)?;
         if !file_info.is_valid {
             return Err(C2mdError::Generic(format!("Invalid file: {}", file_info.error.unwrap_or("Unknown error".to_string()))));
         }
         
         // Extract metadata
-        let metadata = extract_metadata(input_path)?;
-        
-        // Detect file format
-        let format = self.detect_format(input_path)?;
+        let metadata = extract_metadata_with(input_path, &MetadataOptions { format, password, encoding })?;
         
-        // Convert based on format
-        let mut markdown = match format.as_str() {
-            "pdf" => self.convert_pdf(input_path)?,
-            "doc" | "docx" => self.convert_office(input_path)?,
-            "xls" | "xlsx" => self.convert_excel(input_path)?,
-            "ppt" | "pptx" => self.convert_powerpoint(input_path)?,
-            "rtf" => self.convert_rtf(input_path)?,
-            "txt" => self.convert_text(input_path)?,
-            "html" | "htm" => self.convert_html(input_path)?,
-            "epub" => self.convert_epub(input_path)?,
-            "csv" => self.convert_csv(input_path)?,
-            "jpg" | "jpeg" | "png" | "gif" | "bmp" | "tiff" | "webp" => {
-                self.convert_image(input_path)?
-            }
-            _ => return Err(C2mdError::UnsupportedFormat(format)),
-        };
+        let document = converter.read(input_path, &self.config, &self.args)?;
 
-        // Apply template if requested
-        if self.args.frontmatter != "none" {
-            let template_context = create_template_context(
-                self.args.title.clone(),
-                self.args.author.clone(),
-                self.args.date.clone(),
-                markdown,
-                Some(self.metadata_to_hashmap(&metadata)),
-                Some(self.file_info_to_template_info(&file_info)),
-            );
-            
-            let template_name = self.get_template_name();
-            markdown = self.template_engine.render(&template_name, &template_context)?;
+        if self.args.split == "chapters" {
+            if document.sections.is_empty() {
+                warn!("{} has no chapters to split; writing a single file", input_path.display());
+            } else {
+                return self.write_sections(input_path, &output_path, &document, &metadata, &file_info);
+            }
         }
 
+        let markdown = self.render_output(&document, &metadata, &file_info)?;
+
         // Write output
         if output_path.to_string_lossy() == "-" {
             print!("{}", markdown);
@@ -296,54 +294,84 @@ impl Converter {
         Ok(())
     }
 
-    fn detect_format(&self, path: &Path) -> Result<String> {
-        if let Some(ext) = path.extension() {
-            Ok(ext.to_string_lossy().to_lowercase())
-        } else {
-            Err(C2mdError::Generic("Cannot detect file format".to_string()))
+    /// Render a document, through the template when front matter is wanted.
+    fn render_output(
+        &self,
+        document: &Document,
+        metadata: &crate::metadata::DocumentMetadata,
+        file_info: &FileInfo,
+    ) -> Result<String> {
+        // The template writes the front matter, so render only the body
+        let use_template = self.config.frontmatter != "none";
+        let mut render_options = RenderOptions::new(&self.config, &self.args);
+        if use_template {
+            render_options.frontmatter = "none".to_string();
         }
-    }
-
-    fn convert_pdf(&self, path: &Path) -> Result<String> {
-        pdf::convert_pdf(path, &self.config, &self.args)
-    }
-
-    fn convert_office(&self, path: &Path) -> Result<String> {
-        office::convert_office(path, &self.config, &self.args)
-    }
+        let mut markdown = MarkdownRenderer::new(render_options).render(document);
 
-    fn convert_excel(&self, path: &Path) -> Result<String> {
-        office::convert_excel(path, &self.config, &self.args)
-    }
-
-    fn convert_powerpoint(&self, path: &Path) -> Result<String> {
-        office::convert_powerpoint(path, &self.config, &self.args)
-    }
+        // Apply template if requested
+        if use_template {
+            // Explicit flags win over metadata found in the document
+            let template_context = create_template_context(
+                self.args.title.clone().or_else(|| document.title.clone()),
+                self.args.author.clone().or_else(|| document.author.clone()),
+                self.args.date.clone().or_else(|| document.date.clone()),
+                document.pages.clone(),
+                markdown,
+                Some(self.metadata_to_hashmap(metadata)),
+                Some(self.file_info_to_template_info(file_info)),
+            );
+            
+            let template_name = self.get_template_name();
+            markdown = self.template_engine.render(&template_name, &template_context)?;
+        }
 
-    fn convert_rtf(&self, path: &Path) -> Result<String> {
-        rtf::convert_rtf(path, &self.config, &self.args)
+        Ok(markdown)
     }
 
-    fn convert_text(&self, path: &Path) -> Result<String> {
-        text::convert_text(path, &self.config, &self.args)
-    }
+    /// `--split chapters`: one file per section in a directory named after the
+    /// output file (or the input file when writing to stdout).
+    fn write_sections(
+        &self,
+        input_path: &Path,
+        output_path: &Path,
+        document: &Document,
+        metadata: &crate::metadata::DocumentMetadata,
+        file_info: &FileInfo,
+    ) -> Result<()> {
+        let dir = if output_path.to_string_lossy() == "-" {
+            std::path::PathBuf::from(input_path.file_stem().unwrap_or_default())
+        } else {
+            output_path.with_extension("")
+        };
+        fs::create_dir_all(&dir)?;
 
-    fn convert_html(&self, path: &Path) -> Result<String> {
-        html::convert_html(path, &self.config, &self.args)
-    }
+        for (name, part) in document.split_sections() {
+            let markdown = self.render_output(&part, metadata, file_info)?;
+            let path = dir.join(format!("{}.md", name));
+            fs::write(&path, markdown)?;
+            info!("Converted: {} -> {}", input_path.display(), path.display());
+        }
 
-    fn convert_epub(&self, path: &Path) -> Result<String> {
-        epub::convert_epub(path, &self.config, &self.args)
+        Ok(())
     }
 
-    fn convert_csv(&self, path: &Path) -> Result<String> {
-        csv::convert_csv(path, &self.config, &self.args)
+    fn resolve_converter(&self, path: &Path) -> Result<std::sync::Arc<dyn FormatConverter>> {
+        let registry = registry();
+        if let Some(from) = &self.args.from {
+            return registry
+                .by_hint(from)
+                .ok_or_else(|| C2mdError::UnsupportedFormat(from.clone()));
+        }
+        registry.detect(path)?.ok_or_else(|| {
+            C2mdError::UnsupportedFormat(
+                path.extension()
+                    .map(|ext| ext.to_string_lossy().to_string())
+                    .unwrap_or_else(|| path.display().to_string()),
+            )
+        })
     }
 
-    fn convert_image(&self, path: &Path) -> Result<String> {
-        image::convert_image(path, &self.config, &self.args)
-    }
-    
     fn metadata_to_hashmap(&self, metadata: &crate::metadata::DocumentMetadata) -> HashMap<String, String> {
         let mut map = HashMap::new();
         
@@ -368,6 +396,12 @@ impl Converter {
         if let Some(language) = &metadata.language {
             map.insert("language".to_string(), language.clone());
         }
+        if let Some(publisher) = &metadata.publisher {
+            map.insert("publisher".to_string(), publisher.clone());
+        }
+        if let Some(identifier) = &metadata.identifier {
+            map.insert("identifier".to_string(), identifier.clone());
+        }
         if let Some(page_count) = &metadata.page_count {
             map.insert("pages".to_string(), page_count.to_string());
         }
@@ -377,6 +411,12 @@ impl Converter {
         if let Some(character_count) = &metadata.character_count {
             map.insert("characters".to_string(), character_count.to_string());
         }
+        if let Some(encrypted) = &metadata.encrypted {
+            map.insert("encrypted".to_string(), encrypted.to_string());
+        }
+        for field in &metadata.form_fields {
+            map.insert(format!("form.{}", field.name), field.value.clone());
+        }
         
         map.insert("file_size".to_string(), format_file_size(metadata.file_size));
         map.insert("format".to_string(), metadata.format.clone());
@@ -403,6 +443,14 @@ impl Converter {
     }
 }
 
+/// A list item of the form `**Label**: value`.
+pub(crate) fn labeled_item(label: &str, value: &str) -> ListItem {
+    ListItem::inlines(vec![
+        Inline::Strong(vec![Inline::text(label)]),
+        Inline::text(format!(": {}", value)),
+    ])
+}
+
 fn format_file_size(bytes: u64) -> String {
     const UNITS: &[&str] = &["B", "KB", "MB", "GB", "TB"];
     let mut size = bytes as f64;
@@ -1,98 +0,0 @@
-use crate::cli::Args;
-use crate::config::Config;
-use crate::error::{C2mdError, Result};
-use std::path::Path;
-
-pub fn convert_office(path: &Path, config: &Config, args: &Args) -> Result<String> {
-    // Placeholder for Office document conversion
-    let mut markdown = String::new();
-    
-    // Add front matter
-    if config.frontmatter != "none" {
-        markdown.push_str("---\n");
-        if let Some(title) = &args.title {
-            markdown.push_str(&format!("title: {}\n", title));
-        }
-        if let Some(author) = &args.author {
-            markdown.push_str(&format!("author: {}\n", author));
-        }
-        if let Some(date) = &args.date {
-            markdown.push_str(&format!("date: {}\n", date));
-        } else {
-            markdown.push_str(&format!("date: {}\n", chrono::Utc::now().format("%Y-%m-%d")));
-        }
-        markdown.push_str("---\n\n");
-    }
-    
-    markdown.push_str("# Office Document\n\n");
-    markdown.push_str("**Note**: Office document conversion requires external tools.\n\n");
-    markdown.push_str("To enable Office conversion, install:\n");
-    markdown.push_str("- `libreoffice` (for headless conversion)\n");
-    markdown.push_str("- `pandoc` (for universal conversion)\n\n");
-    
-    markdown.push_str("## File Information\n\n");
-    markdown.push_str(&format!("- **File**: {}\n", path.file_name().unwrap().to_string_lossy()));
-    markdown.push_str("- **Format**: Office Document\n");
-    
-    Ok(markdown)
-}
-
-pub fn convert_excel(path: &Path, config: &Config, args: &Args) -> Result<String> {
-    // Placeholder for Excel conversion
-    let mut markdown = String::new();
-    
-    // Add front matter
-    if config.frontmatter != "none" {
-        markdown.push_str("---\n");
-        if let Some(title) = &args.title {
-            markdown.push_str(&format!("title: {}\n", title));
-        }
-        markdown.push_str("---\n\n");
-    }
-    
-    markdown.push_str("# Excel Spreadsheet\n\n");
-    markdown.push_str("**Note**: Excel conversion requires external tools.\n\n");
-    markdown.push_str("To enable Excel conversion, install:\n");
-    markdown.push_str("- `libreoffice` (for headless conversion)\n");
-    markdown.push_str("- `pandoc` (for universal conversion)\n\n");
-    
-    markdown.push_str("## File Information\n\n");
-    markdown.push_str(&format!("- **File**: {}\n", path.file_name().unwrap().to_string_lossy()));
-    markdown.push_str("- **Format**: Excel Spreadsheet\n");
-    
-    Ok(markdown)
-}
-
-pub fn convert_powerpoint(path: &Path, config: &Config, args: &Args) -> Result<String> {
-    // Placeholder for PowerPoint conversion
-    let mut markdown = String::new();
-    
-    // Add front matter
-    if config.frontmatter != "none" {
-        markdown.push_str("---\n");
-        if let Some(title) = &args.title {
-            markdown.push_str(&format!("title: {}\n", title));
-        }
-        if let Some(author) = &args.author {
-            markdown.push_str(&format!("author: {}\n", author));
-        }
-        if let Some(date) = &args.date {
-            markdown.push_str(&format!("date: {}\n", date));
-        } else {
-            markdown.push_str(&format!("date: {}\n", chrono::Utc::now().format("%Y-%m-%d")));
-        }
-        markdown.push_str("---\n\n");
-    }
-    
-    markdown.push_str("# PowerPoint Presentation\n\n");
-    markdown.push_str("**Note**: PowerPoint conversion requires external tools.\n\n");
-    markdown.push_str("To enable PowerPoint conversion, install:\n");
-    markdown.push_str("- `libreoffice` (for headless conversion)\n");
-    markdown.push_str("- `pandoc` (for universal conversion)\n\n");
-    
-    markdown.push_str("## File Information\n\n");
-    markdown.push_str(&format!("- **File**: {}\n", path.file_name().unwrap().to_string_lossy()));
-    markdown.push_str("- **Format**: PowerPoint Presentation\n");
-    
-    Ok(markdown)
-}
\ No newline at end of file
@@ -0,0 +1,584 @@
+//! WordprocessingML (`.docx`) reader.
+
+use super::{main_part, read_relationships, Media, PackageProperties, Relationship};
+use crate::cli::Args;
+use crate::config::Config;
+use crate::converter::archive::ZipArchive;
+use crate::converter::xml::Element;
+use crate::document::{blocks_to_inlines, build_lists, inlines_plain_text, Alignment, Block, Document, Footnote, Inline, ListEntry, Table};
+use crate::error::Result;
+use std::collections::HashMap;
+use std::path::Path;
+
+pub fn read_docx(archive: &ZipArchive, path: &Path, config: &Config, args: &Args) -> Result<Document> {
+    let part = main_part(archive, "word/document.xml");
+    let root = archive.read_xml(&part)?;
+    let body = root
+        .child("body")
+        .ok_or_else(|| crate::error::C2mdError::Conversion("DOCX has no document body".to_string()))?;
+
+    let mut reader = DocxReader {
+        archive,
+        media: Media::new(archive, path, config, args),
+        rels: HashMap::new(),
+        styles: Styles::read(archive),
+        numbering: Numbering::read(archive),
+        counters: HashMap::new(),
+        notes: HashMap::new(),
+        note_order: Vec::new(),
+        title: None,
+    };
+    reader.read_notes("word/footnotes.xml", "footnote", "");
+    reader.read_notes("word/endnotes.xml", "endnote", "e");
+    reader.rels = read_relationships(archive, &part);
+
+    let mut document = Document::new();
+    document.blocks = reader.read_blocks(body);
+    document.footnotes = reader
+        .note_order
+        .iter()
+        .filter_map(|label| {
+            reader.notes.get(label).map(|blocks| Footnote { label: label.clone(), blocks: blocks.clone() })
+        })
+        .collect();
+    document.title = reader.title.take();
+    PackageProperties::read(archive).apply_to_document(&mut document);
+
+    Ok(document)
+}
+
+struct DocxReader<'a> {
+    archive: &'a ZipArchive,
+    media: Media<'a>,
+    /// Relationships of the part being read.
+    rels: HashMap<String, Relationship>,
+    styles: Styles,
+    numbering: Numbering,
+    /// Running item numbers per list definition and level.
+    counters: HashMap<(String, u8), u64>,
+    notes: HashMap<String, Vec<Block>>,
+    note_order: Vec<String>,
+    title: Option<String>,
+}
+
+impl DocxReader<'_> {
+    fn read_notes(&mut self, part: &str, kind: &str, prefix: &str) {
+        let Ok(root) = self.archive.read_xml(part) else {
+            return;
+        };
+        self.rels = read_relationships(self.archive, part);
+        for note in root.children_named(kind) {
+            // Separators and continuation notices carry a type and are not real notes
+            if note.attr("w:type").is_some() {
+                continue;
+            }
+            if let Some(id) = note.attr("w:id") {
+                let blocks = self.read_blocks(note);
+                self.notes.insert(format!("{}{}", prefix, id), blocks);
+            }
+        }
+    }
+
+    fn read_blocks(&mut self, container: &Element) -> Vec<Block> {
+        let mut blocks = Vec::new();
+        let mut list: Vec<ListEntry> = Vec::new();
+
+        for child in container.elements() {
+            match child.local_name() {
+                "p" => {
+                    let properties = child.child("pPr");
+                    let style = properties
+                        .and_then(|p| p.child("pStyle"))
+                        .and_then(|s| s.attr("w:val"))
+                        .unwrap_or_default();
+                    let content = self.read_inlines(child);
+
+                    if let Some(entry) = self.list_entry(properties, style, &content) {
+                        list.push(entry);
+                        continue;
+                    }
+                    blocks.extend(build_lists(std::mem::take(&mut list)));
+
+                    if inlines_plain_text(&content).trim().is_empty() && !has_media(&content) {
+                        continue;
+                    }
+                    let outline_level = properties
+                        .and_then(|p| p.child("outlineLvl"))
+                        .and_then(|o| o.attr("w:val"))
+                        .and_then(|v| v.parse::<u8>().ok());
+                    let level = self
+                        .styles
+                        .heading_level(style)
+                        .or_else(|| outline_level.filter(|&l| l < 6).map(|l| l + 1));
+                    if self.styles.is_title(style) && self.title.is_none() {
+                        self.title = Some(inlines_plain_text(&content).trim().to_string());
+                    }
+                    match level {
+                        Some(level) => blocks.push(Block::Heading { level, content }),
+                        None => blocks.push(Block::Paragraph(content)),
+                    }
+                }
+                "tbl" => {
+                    blocks.extend(build_lists(std::mem::take(&mut list)));
+                    blocks.push(Block::Table(self.read_table(child)));
+                }
+                // Content controls and custom XML wrap ordinary block content
+                "sdt" | "customXml" => {
+                    if let Some(content) = child.child("sdtContent").or(Some(child).filter(|c| c.is("customXml"))) {
+                        blocks.extend(build_lists(std::mem::take(&mut list)));
+                        blocks.extend(self.read_blocks(content));
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        blocks.extend(build_lists(list));
+        blocks
+    }
+
+    fn list_entry(&mut self, properties: Option<&Element>, style: &str, content: &[Inline]) -> Option<ListEntry> {
+        let direct = properties.and_then(|p| p.child("numPr")).map(|num| {
+            let id = num.child("numId").and_then(|n| n.attr("w:val")).map(str::to_string);
+            let level = num.child("ilvl").and_then(|l| l.attr("w:val")).and_then(|v| v.parse().ok());
+            (id, level)
+        });
+        let (style_id, style_level) = self.styles.numbering(style).unwrap_or_default();
+        let id = direct
+            .as_ref()
+            .and_then(|(id, _)| id.clone())
+            .or(style_id)?;
+        let level = direct.and_then(|(_, level)| level).or(style_level).unwrap_or(0);
+        // numId 0 explicitly removes numbering
+        if id == "0" {
+            return None;
+        }
+
+        let format = self.numbering.level(&id, level);
+        let counter = self.counters.entry((id.clone(), level)).or_insert(format.start);
+        let number = *counter;
+        *counter += 1;
+        // Starting a new item resets the numbering of deeper levels
+        self.counters.retain(|(list, l), _| list != &id || *l <= level);
+
+        Some(ListEntry { level, ordered: format.ordered, number, content: content.to_vec() })
+    }
+
+    fn read_table(&mut self, table: &Element) -> Table {
+        let mut rows: Vec<Vec<Vec<Inline>>> = Vec::new();
+        let grid = table.child("tblGrid").map_or(0, |grid| grid.children_named("gridCol").count());
+        for row in table.children_named("tr") {
+            let mut cells = Vec::new();
+            for cell in row.children_named("tc") {
+                let properties = cell.child("tcPr");
+                // A span never reaches past the grid, whatever the file claims
+                let span = properties
+                    .and_then(|p| p.child("gridSpan"))
+                    .and_then(|g| g.attr("w:val"))
+                    .and_then(|v| v.parse::<usize>().ok())
+                    .unwrap_or(1)
+                    .min(grid.saturating_sub(cells.len()).max(1));
+                // Vertically merged cells repeat nothing below the first one
+                let merged = properties
+                    .and_then(|p| p.child("vMerge"))
+                    .is_some_and(|m| m.attr("w:val").unwrap_or("continue") == "continue");
+                let content = if merged { Vec::new() } else { blocks_to_inlines(&self.read_blocks(cell)) };
+                cells.push(content);
+                cells.extend(std::iter::repeat_n(Vec::new(), span.saturating_sub(1)));
+            }
+            rows.push(cells);
+        }
+
+        let header = if rows.is_empty() { Vec::new() } else { rows.remove(0) };
+        let columns = rows.iter().map(Vec::len).chain(std::iter::once(header.len())).max().unwrap_or(0);
+        Table { alignments: vec![Alignment::None; columns], header, rows }
+    }
+
+    /// Inline content of a paragraph, hyperlink or other run container.
+    fn read_inlines(&mut self, container: &Element) -> Vec<Inline> {
+        let mut spans = Vec::new();
+        let mut fields = Vec::new();
+        self.read_runs(container, &mut spans, &mut fields);
+        merge_spans(spans)
+    }
+
+    fn read_runs(&mut self, container: &Element, spans: &mut Vec<Span>, fields: &mut Vec<Field>) {
+        for child in container.elements() {
+            match child.local_name() {
+                "r" => self.read_run(child, spans, fields),
+                "hyperlink" => {
+                    let content = self.read_inlines(child);
+                    let url = child
+                        .attr("r:id")
+                        .and_then(|id| self.rels.get(id))
+                        .map(|rel| rel.target.clone())
+                        .or_else(|| child.attr("w:anchor").map(|anchor| format!("#{}", anchor)));
+                    let title = child.attr("w:tooltip").map(str::to_string);
+                    match url {
+                        Some(url) => spans.push(Span::plain(Inline::Link { url, title, content })),
+                        None => spans.extend(content.into_iter().map(Span::plain)),
+                    }
+                }
+                "fldSimple" => {
+                    let content = self.read_inlines(child);
+                    match child.attr("w:instr").and_then(hyperlink_target) {
+                        Some(url) => spans.push(Span::plain(Inline::Link { url, title: None, content })),
+                        None => spans.extend(content.into_iter().map(Span::plain)),
+                    }
+                }
+                "ins" | "smartTag" | "customXml" | "sdt" | "sdtContent" => self.read_runs(child, spans, fields),
+                _ => {}
+            }
+        }
+    }
+
+    fn read_run(&mut self, run: &Element, spans: &mut Vec<Span>, fields: &mut Vec<Field>) {
+        let format = self.run_format(run.child("rPr"));
+        // Runs between a field's begin and separate marks hold its instruction
+        let in_instruction = fields.last().is_some_and(|field| field.result_start.is_none());
+
+        for child in run.elements() {
+            match child.local_name() {
+                "fldChar" => match child.attr("w:fldCharType") {
+                    Some("begin") => fields.push(Field::default()),
+                    Some("separate") => {
+                        if let Some(field) = fields.last_mut() {
+                            field.result_start = Some(spans.len());
+                        }
+                    }
+                    Some("end") => {
+                        if let Some(field) = fields.pop() {
+                            let start = field.result_start.unwrap_or(spans.len()).min(spans.len());
+                            if let Some(url) = hyperlink_target(&field.instruction) {
+                                let content = merge_spans(spans.drain(start..).collect());
+                                spans.push(Span::plain(Inline::Link { url, title: None, content }));
+                            }
+                        }
+                    }
+                    _ => {}
+                },
+                "instrText" => {
+                    if let Some(field) = fields.last_mut() {
+                        field.instruction.push_str(&child.text());
+                    }
+                }
+                _ if in_instruction => {}
+                "t" => spans.push(Span { format, inline: Inline::Text(child.text()) }),
+                "tab" | "ptab" => spans.push(Span { format, inline: Inline::text(" ") }),
+                "noBreakHyphen" => spans.push(Span { format, inline: Inline::text("-") }),
+                "br" if child.attr("w:type").is_some_and(|kind| kind != "textWrapping") => {}
+                "br" | "cr" => spans.push(Span::plain(Inline::LineBreak)),
+                "drawing" | "pict" | "object" => {
+                    if let Some(image) = self.read_image(child) {
+                        spans.push(Span::plain(image));
+                    }
+                }
+                "footnoteReference" | "endnoteReference" => {
+                    let prefix = if child.is("endnoteReference") { "e" } else { "" };
+                    if let Some(id) = child.attr("w:id") {
+                        let label = format!("{}{}", prefix, id);
+                        if self.notes.contains_key(&label) {
+                            if !self.note_order.contains(&label) {
+                                self.note_order.push(label.clone());
+                            }
+                            spans.push(Span::plain(Inline::FootnoteRef(label)));
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn run_format(&self, properties: Option<&Element>) -> Format {
+        let Some(properties) = properties else {
+            return Format::default();
+        };
+        let style = properties
+            .child("rStyle")
+            .and_then(|s| s.attr("w:val"))
+            .map(|id| self.styles.name(id).to_lowercase())
+            .unwrap_or_default();
+        let font = properties
+            .child("rFonts")
+            .and_then(|f| f.attr("w:ascii").or_else(|| f.attr("w:hAnsi")))
+            .unwrap_or_default()
+            .to_lowercase();
+
+        Format {
+            bold: toggle(properties, "b") || style == "strong",
+            italic: toggle(properties, "i") || style == "emphasis",
+            strike: toggle(properties, "strike") || toggle(properties, "dstrike"),
+            code: style.contains("code")
+                || ["courier", "consolas", "menlo", "monaco", "mono"].iter().any(|name| font.contains(name)),
+        }
+    }
+
+    fn read_image(&mut self, drawing: &Element) -> Option<Inline> {
+        let (id, alt) = match drawing.find("blip") {
+            Some(blip) => {
+                let id = blip.attr("r:embed").or_else(|| blip.attr("r:link"))?;
+                let properties = drawing.find("docPr");
+                let alt = properties
+                    .and_then(|p| p.attr("descr").filter(|d| !d.is_empty()).or_else(|| p.attr("title")))
+                    .unwrap_or_default();
+                (id, alt)
+            }
+            None => {
+                let data = drawing.find("imagedata")?;
+                (data.attr("r:id")?, data.attr("o:title").unwrap_or_default())
+            }
+        };
+        let rel = self.rels.get(id)?;
+        self.media.image(rel, alt)
+    }
+}
+
+/// An in-progress complex field (`w:fldChar`).
+#[derive(Default)]
+struct Field {
+    instruction: String,
+    /// Index of the first span of the field result, once `separate` was seen.
+    result_start: Option<usize>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+struct Format {
+    bold: bool,
+    italic: bool,
+    strike: bool,
+    code: bool,
+}
+
+struct Span {
+    format: Format,
+    inline: Inline,
+}
+
+impl Span {
+    fn plain(inline: Inline) -> Self {
+        Self { format: Format::default(), inline }
+    }
+}
+
+/// Group runs with the same formatting so `**a****b**` becomes `**ab**`.
+fn merge_spans(spans: Vec<Span>) -> Vec<Inline> {
+    let mut out = Vec::new();
+    let mut spans = spans.into_iter().peekable();
+    while let Some(first) = spans.next() {
+        let format = first.format;
+        let mut group = vec![first.inline];
+        while let Some(next) = spans.next_if(|span| span.format == format) {
+            group.push(next.inline);
+        }
+
+        let mut content: Vec<Inline> = Vec::new();
+        for inline in group {
+            match (content.last_mut(), inline) {
+                (Some(Inline::Text(previous)), Inline::Text(text)) => previous.push_str(&text),
+                (_, inline) => content.push(inline),
+            }
+        }
+        if format.code && content.iter().all(|inline| matches!(inline, Inline::Text(_))) {
+            content = vec![Inline::Code(inlines_plain_text(&content))];
+        }
+        if format.strike {
+            content = vec![Inline::Strikethrough(content)];
+        }
+        if format.italic {
+            content = vec![Inline::Emphasis(content)];
+        }
+        if format.bold {
+            content = vec![Inline::Strong(content)];
+        }
+        out.extend(content);
+    }
+    out
+}
+
+fn has_media(content: &[Inline]) -> bool {
+    content.iter().any(|inline| matches!(inline, Inline::Image { .. }))
+}
+
+/// On/off run properties such as `<w:b/>` or `<w:b w:val="0"/>`.
+fn toggle(properties: &Element, name: &str) -> bool {
+    properties
+        .child(name)
+        .is_some_and(|element| !matches!(element.attr("w:val"), Some("0" | "false" | "off")))
+}
+
+/// The URL of a `HYPERLINK "url"` field instruction.
+fn hyperlink_target(instruction: &str) -> Option<String> {
+    let rest = instruction.trim().strip_prefix("HYPERLINK")?.trim();
+    let mut parts = rest.split('"').filter(|part| !part.trim().is_empty());
+    let first = parts.next()?.trim();
+    // `\l "bookmark"` links inside the document
+    if first == "\\l" {
+        return parts.next().map(|anchor| format!("#{}", anchor.trim()));
+    }
+    Some(first.to_string())
+}
+
+#[derive(Debug, Default)]
+struct Style {
+    name: String,
+    based_on: Option<String>,
+    outline_level: Option<u8>,
+    numbering: Option<(Option<String>, Option<u8>)>,
+}
+
+/// Paragraph and character styles from `word/styles.xml`.
+#[derive(Debug, Default)]
+struct Styles {
+    styles: HashMap<String, Style>,
+}
+
+impl Styles {
+    fn read(archive: &ZipArchive) -> Self {
+        let Ok(root) = archive.read_xml("word/styles.xml") else {
+            return Self::default();
+        };
+        let styles = root
+            .children_named("style")
+            .filter_map(|style| {
+                let id = style.attr("w:styleId")?.to_string();
+                let properties = style.child("pPr");
+                let value = |element: Option<&Element>| element.and_then(|e| e.attr("w:val")).map(str::to_string);
+                Some((
+                    id,
+                    Style {
+                        name: value(style.child("name")).unwrap_or_default(),
+                        based_on: value(style.child("basedOn")),
+                        outline_level: value(properties.and_then(|p| p.child("outlineLvl")))
+                            .and_then(|v| v.parse().ok()),
+                        numbering: properties.and_then(|p| p.child("numPr")).map(|num| {
+                            (
+                                value(num.child("numId")),
+                                value(num.child("ilvl")).and_then(|v| v.parse().ok()),
+                            )
+                        }),
+                    },
+                ))
+            })
+            .collect();
+        Self { styles }
+    }
+
+    /// The style and the styles it is based on, nearest first.
+    fn chain<'a>(&'a self, id: &'a str) -> impl Iterator<Item = (&'a str, &'a Style)> + 'a {
+        let mut next = Some(id);
+        std::iter::from_fn(move || {
+            let id = next?;
+            let style = self.styles.get(id)?;
+            next = style.based_on.as_deref();
+            Some((id, style))
+        })
+        .take(16)
+    }
+
+    fn name(&self, id: &str) -> String {
+        self.styles.get(id).map(|style| style.name.clone()).unwrap_or_else(|| id.to_string())
+    }
+
+    fn heading_level(&self, id: &str) -> Option<u8> {
+        if id.is_empty() {
+            return None;
+        }
+        let from_name = |name: &str| {
+            let name = name.to_lowercase().replace(' ', "");
+            name.strip_prefix("heading")
+                .and_then(|level| level.parse::<u8>().ok())
+                .filter(|level| (1..=6).contains(level))
+        };
+        if self.is_title(id) {
+            return Some(1);
+        }
+        from_name(id).or_else(|| {
+            self.chain(id).find_map(|(style_id, style)| {
+                from_name(&style.name)
+                    .or_else(|| from_name(style_id))
+                    .or_else(|| style.outline_level.filter(|&l| l < 6).map(|l| l + 1))
+            })
+        })
+    }
+
+    fn is_title(&self, id: &str) -> bool {
+        id.eq_ignore_ascii_case("title") || self.name(id).eq_ignore_ascii_case("title")
+    }
+
+    fn numbering(&self, id: &str) -> Option<(Option<String>, Option<u8>)> {
+        self.chain(id).find_map(|(_, style)| style.numbering.clone())
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct LevelFormat {
+    ordered: bool,
+    start: u64,
+}
+
+impl Default for LevelFormat {
+    fn default() -> Self {
+        Self { ordered: false, start: 1 }
+    }
+}
+
+/// List definitions from `word/numbering.xml`, keyed by `numId` and level.
+#[derive(Debug, Default)]
+struct Numbering {
+    levels: HashMap<(String, u8), LevelFormat>,
+}
+
+impl Numbering {
+    fn read(archive: &ZipArchive) -> Self {
+        let Ok(root) = archive.read_xml("word/numbering.xml") else {
+            return Self::default();
+        };
+        let read_level = |level: &Element| -> Option<(u8, LevelFormat)> {
+            let index = level.attr("w:ilvl")?.parse().ok()?;
+            let format = level.child("numFmt").and_then(|f| f.attr("w:val")).unwrap_or("decimal");
+            let start = level
+                .child("start")
+                .and_then(|s| s.attr("w:val"))
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(1);
+            Some((index, LevelFormat { ordered: !matches!(format, "bullet" | "none"), start }))
+        };
+
+        let abstract_levels: HashMap<&str, Vec<(u8, LevelFormat)>> = root
+            .children_named("abstractNum")
+            .filter_map(|definition| {
+                let id = definition.attr("w:abstractNumId")?;
+                Some((id, definition.children_named("lvl").filter_map(read_level).collect()))
+            })
+            .collect();
+
+        let mut levels = HashMap::new();
+        for num in root.children_named("num") {
+            let Some(id) = num.attr("w:numId") else {
+                continue;
+            };
+            let abstract_id = num.child("abstractNumId").and_then(|a| a.attr("w:val")).unwrap_or_default();
+            for (index, format) in abstract_levels.get(abstract_id).into_iter().flatten() {
+                levels.insert((id.to_string(), *index), *format);
+            }
+            for over in num.children_named("lvlOverride") {
+                let Some(index) = over.attr("w:ilvl").and_then(|v| v.parse::<u8>().ok()) else {
+                    continue;
+                };
+                let format = levels.entry((id.to_string(), index)).or_default();
+                if let Some((_, level)) = over.child("lvl").and_then(read_level) {
+                    *format = level;
+                }
+                if let Some(start) = over.child("startOverride").and_then(|s| s.attr("w:val")).and_then(|v| v.parse().ok()) {
+                    format.start = start;
+                }
+            }
+        }
+        Self { levels }
+    }
+
+    fn level(&self, id: &str, level: u8) -> LevelFormat {
+        self.levels.get(&(id.to_string(), level)).copied().unwrap_or_default()
+    }
+}
@@ -0,0 +1,293 @@
+mod docx;
+mod pptx;
+mod xlsx;
+
+use crate::cli::Args;
+use crate::config::Config;
+use crate::converter::archive::{is_zip, resolve_part, ZipArchive};
+use crate::converter::image::store_image;
+use crate::converter::registry::{read_header, FormatConverter};
+use crate::document::{Document, Inline};
+use crate::metadata::DocumentMetadata;
+use crate::renderer::render;
+use crate::error::{C2mdError, Result};
+use chrono::{DateTime, Utc};
+use std::collections::HashMap;
+use std::path::Path;
+
+pub struct WordConverter;
+
+impl FormatConverter for WordConverter {
+    fn name(&self) -> &str {
+        "office"
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["docx"]
+    }
+
+    fn mime_types(&self) -> &[&str] {
+        &["application/vnd.openxmlformats-officedocument.wordprocessingml.document"]
+    }
+
+    fn read(&self, path: &Path, config: &Config, args: &Args) -> Result<Document> {
+        read_office(path, config, args)
+    }
+}
+
+pub struct ExcelConverter;
+
+impl FormatConverter for ExcelConverter {
+    fn name(&self) -> &str {
+        "excel"
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["xlsx"]
+    }
+
+    fn mime_types(&self) -> &[&str] {
+        &["application/vnd.openxmlformats-officedocument.spreadsheetml.sheet"]
+    }
+
+    fn read(&self, path: &Path, _config: &Config, args: &Args) -> Result<Document> {
+        read_excel(path, args)
+    }
+}
+
+pub struct PowerPointConverter;
+
+impl FormatConverter for PowerPointConverter {
+    fn name(&self) -> &str {
+        "powerpoint"
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["pptx"]
+    }
+
+    fn mime_types(&self) -> &[&str] {
+        &["application/vnd.openxmlformats-officedocument.presentationml.presentation"]
+    }
+
+    fn read(&self, path: &Path, config: &Config, args: &Args) -> Result<Document> {
+        read_powerpoint(path, config, args)
+    }
+}
+
+#[deprecated(note = "use `WordConverter` through `FormatConverter::convert`")]
+pub fn convert_office(path: &Path, config: &Config, args: &Args) -> Result<String> {
+    let document = read_office(path, config, args)?;
+    Ok(render(&document, config, args))
+}
+
+pub fn read_office(path: &Path, config: &Config, args: &Args) -> Result<Document> {
+    let archive = open_package(path, "doc")?;
+    docx::read_docx(&archive, path, config, args)
+}
+
+#[deprecated(note = "use `ExcelConverter` through `FormatConverter::convert`")]
+pub fn convert_excel(path: &Path, config: &Config, args: &Args) -> Result<String> {
+    let document = read_excel(path, args)?;
+    Ok(render(&document, config, args))
+}
+
+pub fn read_excel(path: &Path, args: &Args) -> Result<Document> {
+    let archive = open_package(path, "xls")?;
+    xlsx::read_xlsx(&archive, args)
+}
+
+#[deprecated(note = "use `PowerPointConverter` through `FormatConverter::convert`")]
+pub fn convert_powerpoint(path: &Path, config: &Config, args: &Args) -> Result<String> {
+    let document = read_powerpoint(path, config, args)?;
+    Ok(render(&document, config, args))
+}
+
+pub fn read_powerpoint(path: &Path, config: &Config, args: &Args) -> Result<Document> {
+    let archive = open_package(path, "ppt")?;
+    pptx::read_pptx(&archive, path, config, args)
+}
+
+/// Open an OOXML package, rejecting the legacy binary formats up front.
+fn open_package(path: &Path, legacy_extension: &str) -> Result<ZipArchive> {
+    if !is_zip(&read_header(path)?) {
+        return Err(C2mdError::UnsupportedFormat(format!(
+            "legacy binary .{} files; save {} in the Office Open XML format",
+            legacy_extension,
+            path.display()
+        )));
+    }
+    ZipArchive::open(path)
+}
+
+/// A target of an OOXML relationship (`r:id`).
+#[derive(Debug, Clone)]
+pub(crate) struct Relationship {
+    /// Archive path for internal targets, the URL for external ones.
+    pub target: String,
+    pub external: bool,
+    /// Last segment of the relationship type, e.g. `image` or `hyperlink`.
+    pub kind: String,
+}
+
+/// Relationships of `part`, keyed by id, read from its `_rels/*.rels` sibling.
+pub(crate) fn read_relationships(archive: &ZipArchive, part: &str) -> HashMap<String, Relationship> {
+    // The package itself is the empty part name, with its rels in `_rels/.rels`
+    let (dir, file) = part.rsplit_once('/').unwrap_or(("", part));
+    let rels_part = if dir.is_empty() {
+        format!("_rels/{}.rels", file)
+    } else {
+        format!("{}/_rels/{}.rels", dir, file)
+    };
+    let Ok(rels) = archive.read_xml(&rels_part) else {
+        return HashMap::new();
+    };
+
+    rels.children_named("Relationship")
+        .filter_map(|rel| {
+            let id = rel.attr("Id")?;
+            let target = rel.attr("Target")?;
+            let external = rel.attr("TargetMode") == Some("External");
+            let target = if external { target.to_string() } else { resolve_part(part, target) };
+            let kind = rel.attr("Type").unwrap_or_default().rsplit('/').next().unwrap_or_default().to_string();
+            Some((id.to_string(), Relationship { target, external, kind }))
+        })
+        .collect()
+}
+
+/// Document properties from `docProps/core.xml` and `docProps/app.xml`.
+#[derive(Debug, Default)]
+pub(crate) struct PackageProperties {
+    pub title: Option<String>,
+    pub creator: Option<String>,
+    pub subject: Option<String>,
+    pub keywords: Option<String>,
+    pub language: Option<String>,
+    pub created: Option<DateTime<Utc>>,
+    pub modified: Option<DateTime<Utc>>,
+    pub pages: Option<u32>,
+    pub words: Option<u32>,
+    pub characters: Option<u32>,
+}
+
+impl PackageProperties {
+    pub fn read(archive: &ZipArchive) -> Self {
+        let mut properties = Self::default();
+        if let Ok(core) = archive.read_xml("docProps/core.xml") {
+            let text = |name: &str| {
+                core.child(name)
+                    .map(|element| element.text().trim().to_string())
+                    .filter(|text| !text.is_empty())
+            };
+            let date = |name: &str| {
+                text(name)
+                    .and_then(|value| DateTime::parse_from_rfc3339(&value).ok())
+                    .map(|date| date.with_timezone(&Utc))
+            };
+            properties.title = text("title");
+            properties.creator = text("creator");
+            properties.subject = text("subject");
+            properties.keywords = text("keywords");
+            properties.language = text("language");
+            properties.created = date("created");
+            properties.modified = date("modified");
+        }
+        if let Ok(app) = archive.read_xml("docProps/app.xml") {
+            let number = |name: &str| app.child(name).and_then(|element| element.text().trim().parse().ok());
+            properties.pages = number("Pages").or_else(|| number("Slides"));
+            properties.words = number("Words");
+            properties.characters = number("Characters");
+        }
+        properties
+    }
+
+    /// Fill the document's front matter fields.
+    pub fn apply_to_document(&self, document: &mut Document) {
+        document.title = document.title.take().or_else(|| self.title.clone());
+        document.author = document.author.take().or_else(|| self.creator.clone());
+        document.date = document
+            .date
+            .take()
+            .or_else(|| self.created.map(|date| date.format("%Y-%m-%d").to_string()));
+    }
+
+    pub fn apply_to_metadata(&self, metadata: &mut DocumentMetadata) {
+        metadata.title = self.title.clone().or(metadata.title.take());
+        metadata.author = self.creator.clone().or(metadata.author.take());
+        metadata.subject = self.subject.clone();
+        metadata.keywords = self.keywords.clone();
+        metadata.language = self.language.clone();
+        metadata.creation_date = self.created;
+        metadata.modification_date = self.modified.or(metadata.modification_date);
+        metadata.page_count = self.pages;
+        metadata.word_count = self.words;
+        metadata.character_count = self.characters;
+    }
+}
+
+/// Office metadata for `path`, used by [`crate::metadata::extract_metadata`].
+pub(crate) fn read_package_metadata(path: &Path, metadata: &mut DocumentMetadata) -> Result<()> {
+    if is_zip(&read_header(path)?) {
+        PackageProperties::read(&ZipArchive::open(path)?).apply_to_metadata(metadata);
+    }
+    Ok(())
+}
+
+/// The main part of a package, such as `word/document.xml`.
+pub(crate) fn main_part(archive: &ZipArchive, fallback: &str) -> String {
+    read_relationships(archive, "")
+        .into_values()
+        .find(|rel| rel.kind == "officeDocument")
+        .map(|rel| rel.target)
+        .unwrap_or_else(|| fallback.to_string())
+}
+
+/// Extracts package media such as `word/media/image1.png` once each,
+/// following the `--images` strategy.
+struct Media<'a> {
+    archive: &'a ZipArchive,
+    config: &'a Config,
+    args: &'a Args,
+    /// Prefix for extracted file names, so images of different documents do not collide.
+    stem: String,
+    stored: HashMap<String, String>,
+}
+
+impl<'a> Media<'a> {
+    fn new(archive: &'a ZipArchive, path: &Path, config: &'a Config, args: &'a Args) -> Self {
+        Self {
+            archive,
+            config,
+            args,
+            stem: path.file_stem().unwrap_or_default().to_string_lossy().to_string(),
+            stored: HashMap::new(),
+        }
+    }
+
+    /// An image for the relationship target, or `None` if it cannot be read.
+    fn image(&mut self, rel: &Relationship, alt: &str) -> Option<Inline> {
+        let alt = if alt.is_empty() { "Image".to_string() } else { alt.to_string() };
+        if rel.external {
+            return Some(Inline::Image { url: rel.target.clone(), alt, title: None });
+        }
+
+        if !self.stored.contains_key(&rel.target) {
+            let file_name = format!("{}-{}", self.stem, rel.target.rsplit('/').next().unwrap_or_default());
+            let stored = self
+                .archive
+                .read(&rel.target)
+                .and_then(|data| store_image(&data, &file_name, self.config, self.args));
+            match stored {
+                Ok(url) => {
+                    self.stored.insert(rel.target.clone(), url);
+                }
+                Err(e) => {
+                    tracing::warn!("Skipping image {}: {}", rel.target, e);
+                    return None;
+                }
+            }
+        }
+        let url = self.stored.get(&rel.target)?.clone();
+        Some(Inline::Image { url, alt, title: None })
+    }
+}
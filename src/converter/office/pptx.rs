@@ -0,0 +1,271 @@
+//! PresentationML (`.pptx`) reader.
+
+use super::{main_part, read_relationships, Media, PackageProperties, Relationship};
+use crate::cli::Args;
+use crate::config::Config;
+use crate::converter::archive::ZipArchive;
+use crate::converter::xml::Element;
+use crate::document::{blocks_to_inlines, build_lists, inlines_plain_text, Alignment, Block, Document, Inline, ListEntry, Table};
+use crate::error::Result;
+use std::collections::HashMap;
+use std::path::Path;
+
+pub fn read_pptx(archive: &ZipArchive, path: &Path, config: &Config, args: &Args) -> Result<Document> {
+    let part = main_part(archive, "ppt/presentation.xml");
+    let presentation = archive.read_xml(&part)?;
+    let rels = read_relationships(archive, &part);
+
+    // Presentation order comes from the slide id list, not from part names
+    let slides: Vec<String> = presentation
+        .child("sldIdLst")
+        .map(|list| {
+            list.children_named("sldId")
+                .filter_map(|slide| rels.get(slide.attr("r:id")?))
+                .map(|rel| rel.target.clone())
+                .collect()
+        })
+        .unwrap_or_default();
+
+    let mut reader = SlideReader {
+        archive,
+        config,
+        media: Media::new(archive, path, config, args),
+        rels: HashMap::new(),
+    };
+
+    let mut document = Document::new();
+    for (index, slide) in slides.iter().enumerate() {
+        document.blocks.extend(reader.read_slide(slide, index + 1)?);
+    }
+    PackageProperties::read(archive).apply_to_document(&mut document);
+
+    Ok(document)
+}
+
+struct SlideReader<'a> {
+    archive: &'a ZipArchive,
+    config: &'a Config,
+    media: Media<'a>,
+    /// Relationships of the slide being read.
+    rels: HashMap<String, Relationship>,
+}
+
+impl SlideReader<'_> {
+    /// A `##` section for the slide, anchored as `#slide-N`.
+    fn read_slide(&mut self, part: &str, number: usize) -> Result<Vec<Block>> {
+        let root = self.archive.read_xml(part)?;
+        self.rels = read_relationships(self.archive, part);
+
+        let mut title = None;
+        let mut body = Vec::new();
+        if let Some(tree) = root.find("spTree") {
+            self.read_shapes(tree, &mut title, &mut body);
+        }
+
+        let mut heading = vec![Inline::Html(format!("<a id=\"slide-{}\"></a>", number))];
+        match title {
+            Some(content) => heading.extend(content),
+            None => heading.push(Inline::text(format!("Slide {}", number))),
+        }
+        let mut blocks = vec![Block::Heading { level: 2, content: heading }];
+        blocks.extend(body);
+
+        let notes = self
+            .rels
+            .values()
+            .find(|rel| rel.kind == "notesSlide")
+            .map(|rel| rel.target.clone());
+        if let Some(notes) = notes {
+            blocks.extend(self.read_notes(&notes)?);
+        }
+        Ok(blocks)
+    }
+
+    fn read_shapes(&mut self, tree: &Element, title: &mut Option<Vec<Inline>>, blocks: &mut Vec<Block>) {
+        for shape in tree.elements() {
+            match shape.local_name() {
+                "sp" => {
+                    let placeholder = shape.find("nvPr").and_then(|p| p.child("ph"));
+                    let kind = placeholder.map(|ph| ph.attr("type").unwrap_or("body"));
+                    let Some(text) = shape.child("txBody") else {
+                        continue;
+                    };
+                    match kind {
+                        Some("title" | "ctrTitle") if title.is_none() => {
+                            let paragraphs: Vec<Vec<Inline>> = text
+                                .children_named("p")
+                                .map(|p| self.read_runs(p))
+                                .filter(|content| !content.is_empty())
+                                .collect();
+                            *title = Some(paragraphs.join(&Inline::text(" ")));
+                        }
+                        // Footers, dates and slide numbers repeat on every slide
+                        Some("dt" | "ftr" | "sldNum" | "hdr") => {}
+                        _ => blocks.extend(self.read_text_body(text, matches!(kind, Some("body" | "obj")))),
+                    }
+                }
+                "graphicFrame" => {
+                    if let Some(table) = shape.find("tbl") {
+                        blocks.push(Block::Table(self.read_table(table)));
+                    }
+                }
+                "pic" => {
+                    if let Some(image) = self.read_picture(shape) {
+                        blocks.push(Block::Paragraph(vec![image]));
+                    }
+                }
+                "grpSp" => self.read_shapes(shape, title, blocks),
+                _ => {}
+            }
+        }
+    }
+
+    /// Paragraphs of a text body; bulleted ones become nested lists by level.
+    /// Body placeholders are bulleted unless they say otherwise.
+    fn read_text_body(&mut self, body: &Element, bulleted: bool) -> Vec<Block> {
+        let mut blocks = Vec::new();
+        let mut list: Vec<ListEntry> = Vec::new();
+        let mut counters: HashMap<u8, u64> = HashMap::new();
+
+        for paragraph in body.children_named("p") {
+            let content = self.read_runs(paragraph);
+            if inlines_plain_text(&content).trim().is_empty() {
+                continue;
+            }
+            let properties = paragraph.child("pPr");
+            let level = properties
+                .and_then(|p| p.attr("lvl"))
+                .and_then(|l| l.parse::<u8>().ok())
+                .unwrap_or(0);
+            let numbered = properties.and_then(|p| p.child("buAutoNum"));
+            let has_bullet = properties.is_some_and(|p| p.child("buChar").is_some() || p.child("buBlip").is_some());
+            let no_bullet = properties.is_some_and(|p| p.child("buNone").is_some());
+
+            if no_bullet || !(bulleted || has_bullet || numbered.is_some()) {
+                blocks.extend(build_lists(std::mem::take(&mut list)));
+                blocks.push(Block::Paragraph(content));
+                continue;
+            }
+
+            let start = numbered
+                .and_then(|n| n.attr("startAt"))
+                .and_then(|s| s.parse().ok())
+                .unwrap_or(1);
+            let counter = counters.entry(level).or_insert(start);
+            let number = *counter;
+            *counter += 1;
+            counters.retain(|&l, _| l <= level);
+            list.push(ListEntry { level, ordered: numbered.is_some(), number, content });
+        }
+
+        blocks.extend(build_lists(list));
+        blocks
+    }
+
+    fn read_runs(&mut self, paragraph: &Element) -> Vec<Inline> {
+        let mut content: Vec<Inline> = Vec::new();
+        for child in paragraph.elements() {
+            let inline = match child.local_name() {
+                "r" | "fld" => {
+                    let text = child.child("t").map(Element::text).unwrap_or_default();
+                    if text.is_empty() {
+                        continue;
+                    }
+                    self.format_run(child.child("rPr"), text)
+                }
+                "br" => Inline::LineBreak,
+                _ => continue,
+            };
+            match (content.last_mut(), inline) {
+                (Some(Inline::Text(previous)), Inline::Text(text)) => previous.push_str(&text),
+                (_, inline) => content.push(inline),
+            }
+        }
+        content
+    }
+
+    fn format_run(&self, properties: Option<&Element>, text: String) -> Inline {
+        let Some(properties) = properties else {
+            return Inline::Text(text);
+        };
+        let on = |name: &str| properties.attr(name).is_some_and(|v| v == "1" || v == "true");
+        let mut inline = Inline::Text(text);
+        if properties.attr("strike").is_some_and(|v| v != "noStrike") {
+            inline = Inline::Strikethrough(vec![inline]);
+        }
+        if on("i") {
+            inline = Inline::Emphasis(vec![inline]);
+        }
+        if on("b") {
+            inline = Inline::Strong(vec![inline]);
+        }
+        let link = properties
+            .child("hlinkClick")
+            .and_then(|link| link.attr("r:id"))
+            .and_then(|id| self.rels.get(id))
+            .filter(|rel| rel.external);
+        match link {
+            Some(rel) => Inline::Link { url: rel.target.clone(), title: None, content: vec![inline] },
+            None => inline,
+        }
+    }
+
+    fn read_table(&mut self, table: &Element) -> Table {
+        let mut rows: Vec<Vec<Vec<Inline>>> = table
+            .children_named("tr")
+            .map(|row| {
+                row.children_named("tc")
+                    .map(|cell| {
+                        // Merged-away cells are still present; keep them empty
+                        let merged = cell.attr("hMerge").is_some() || cell.attr("vMerge").is_some();
+                        match cell.child("txBody") {
+                            Some(body) if !merged => blocks_to_inlines(&self.read_text_body(body, false)),
+                            _ => Vec::new(),
+                        }
+                    })
+                    .collect()
+            })
+            .collect();
+
+        let header = if rows.is_empty() { Vec::new() } else { rows.remove(0) };
+        let columns = rows.iter().map(Vec::len).chain(std::iter::once(header.len())).max().unwrap_or(0);
+        Table { alignments: vec![Alignment::None; columns], header, rows }
+    }
+
+    fn read_picture(&mut self, picture: &Element) -> Option<Inline> {
+        let id = picture.find("blip")?.attr("r:embed")?;
+        let rel = self.rels.get(id)?;
+        let properties = picture.find("cNvPr");
+        let alt = properties
+            .and_then(|p| p.attr("descr").filter(|d| !d.is_empty()).or_else(|| p.attr("title")))
+            .unwrap_or_default();
+        self.media.image(rel, alt)
+    }
+
+    fn read_notes(&mut self, part: &str) -> Result<Vec<Block>> {
+        if self.config.slides.notes == "none" {
+            return Ok(Vec::new());
+        }
+        let root = self.archive.read_xml(part)?;
+        self.rels = read_relationships(self.archive, part);
+
+        // Only the notes body; the other placeholders show the slide image and number
+        let notes: Vec<Block> = root
+            .find_all("sp")
+            .into_iter()
+            .filter(|shape| {
+                shape.find("nvPr").and_then(|p| p.child("ph")).and_then(|ph| ph.attr("type")) == Some("body")
+            })
+            .filter_map(|shape| shape.child("txBody"))
+            .flat_map(|body| self.read_text_body(body, false))
+            .collect();
+        if notes.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        Ok(match self.config.slides.notes.as_str() {
+            "section" => std::iter::once(Block::heading(3, "Notes")).chain(notes).collect(),
+            _ => vec![Block::BlockQuote(notes)],
+        })
+    }
+}
@@ -0,0 +1,404 @@
+//! SpreadsheetML (`.xlsx`) reader.
+
+use super::{main_part, read_relationships, PackageProperties};
+use crate::cli::Args;
+use crate::converter::archive::ZipArchive;
+use crate::converter::xml::Element;
+use crate::document::{Block, Document, Table};
+use crate::error::{C2mdError, Result};
+use chrono::{Duration, NaiveDate};
+use std::collections::HashMap;
+
+pub fn read_xlsx(archive: &ZipArchive, args: &Args) -> Result<Document> {
+    let part = main_part(archive, "xl/workbook.xml");
+    let workbook = archive.read_xml(&part)?;
+    let rels = read_relationships(archive, &part);
+    let date1904 = workbook
+        .child("workbookPr")
+        .and_then(|p| p.attr("date1904"))
+        .is_some_and(|v| v == "1" || v == "true");
+
+    let sheets: Vec<SheetRef> = workbook
+        .child("sheets")
+        .map(|sheets| {
+            sheets
+                .children_named("sheet")
+                .filter_map(|sheet| {
+                    Some(SheetRef {
+                        name: sheet.attr("name")?.to_string(),
+                        part: rels.get(sheet.attr("r:id")?)?.target.clone(),
+                        hidden: sheet.attr("state").is_some_and(|state| state != "visible"),
+                    })
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+
+    let selected = select_sheets(&sheets, args.sheet.as_deref(), &args.sheets)?;
+    let shared_strings = read_shared_strings(archive);
+    let formats = NumberFormats::read(archive);
+
+    let mut document = Document::new();
+    for sheet in selected {
+        let root = archive.read_xml(&sheet.part)?;
+        let grid = read_sheet(&root, &shared_strings, &formats, date1904);
+
+        document.push(Block::heading(2, sheet.name.clone()));
+        match grid_to_table(grid) {
+            Some(table) => document.push(Block::Table(table)),
+            None => document.push(Block::paragraph("This sheet is empty.")),
+        }
+    }
+    PackageProperties::read(archive).apply_to_document(&mut document);
+
+    Ok(document)
+}
+
+struct SheetRef {
+    name: String,
+    part: String,
+    hidden: bool,
+}
+
+/// Apply `--sheet` (a name or 1-based index, which wins) or `--sheets`
+/// (`all`, or a comma separated list of indexes, names and glob patterns).
+/// `all` skips hidden sheets; naming a hidden sheet selects it.
+fn select_sheets<'a>(sheets: &'a [SheetRef], sheet: Option<&str>, selection: &str) -> Result<Vec<&'a SheetRef>> {
+    if let Some(wanted) = sheet {
+        let found = match wanted.parse::<usize>() {
+            Ok(index) => index.checked_sub(1).and_then(|i| sheets.get(i)),
+            Err(_) => sheets.iter().find(|s| s.name == wanted),
+        }
+        .or_else(|| sheets.iter().find(|s| s.name.eq_ignore_ascii_case(wanted)));
+        return found
+            .map(|s| vec![s])
+            .ok_or_else(|| C2mdError::Conversion(format!("No sheet named {}", wanted)));
+    }
+
+    let selection = selection.trim();
+    if selection.is_empty() || selection.eq_ignore_ascii_case("all") {
+        return Ok(sheets.iter().filter(|s| !s.hidden).collect());
+    }
+
+    let mut selected: Vec<&SheetRef> = Vec::new();
+    for item in selection.split(',').map(str::trim).filter(|item| !item.is_empty()) {
+        let matches: Vec<&SheetRef> = match item.parse::<usize>() {
+            Ok(index) => index.checked_sub(1).and_then(|i| sheets.get(i)).into_iter().collect(),
+            Err(_) => {
+                let pattern = glob::Pattern::new(item)?;
+                sheets.iter().filter(|s| pattern.matches(&s.name)).collect()
+            }
+        };
+        if matches.is_empty() {
+            return Err(C2mdError::Conversion(format!("No sheet matches {}", item)));
+        }
+        for sheet in matches {
+            if !selected.iter().any(|s| std::ptr::eq(*s, sheet)) {
+                selected.push(sheet);
+            }
+        }
+    }
+    Ok(selected)
+}
+
+fn read_shared_strings(archive: &ZipArchive) -> Vec<String> {
+    let Ok(root) = archive.read_xml("xl/sharedStrings.xml") else {
+        return Vec::new();
+    };
+    root.children_named("si").map(rich_text).collect()
+}
+
+/// Text of a string item, skipping phonetic runs.
+fn rich_text(item: &Element) -> String {
+    item.elements()
+        .map(|child| match child.local_name() {
+            "t" => child.text(),
+            "r" => child.child("t").map(Element::text).unwrap_or_default(),
+            _ => String::new(),
+        })
+        .collect()
+}
+
+/// Cell values keyed by zero-based (row, column), with the top-left value
+/// of each merged area copied into all of its cells.
+type Grid = HashMap<(usize, usize), String>;
+
+fn read_sheet(root: &Element, shared_strings: &[String], formats: &NumberFormats, date1904: bool) -> Grid {
+    let mut grid = Grid::new();
+    let Some(data) = root.child("sheetData") else {
+        return grid;
+    };
+
+    let mut next_row = 0;
+    for row in data.children_named("row") {
+        let row_index = row
+            .attr("r")
+            .and_then(|r| r.parse::<usize>().ok())
+            .map(|r| r.saturating_sub(1))
+            .unwrap_or(next_row);
+        next_row = row_index + 1;
+
+        let mut next_column = 0;
+        for cell in row.children_named("c") {
+            let column = cell
+                .attr("r")
+                .and_then(parse_reference)
+                .map(|(_, column)| column)
+                .unwrap_or(next_column);
+            next_column = column + 1;
+
+            let value = cell.child("v").map(Element::text);
+            let text = match cell.attr("t").unwrap_or("n") {
+                "s" => value
+                    .and_then(|v| v.trim().parse::<usize>().ok())
+                    .and_then(|index| shared_strings.get(index).cloned()),
+                "inlineStr" => cell.child("is").map(rich_text),
+                "b" => value.map(|v| if v.trim() == "1" { "TRUE" } else { "FALSE" }.to_string()),
+                "str" | "e" | "d" => value,
+                _ => value.map(|v| {
+                    let style = cell.attr("s").and_then(|s| s.parse::<usize>().ok()).unwrap_or(0);
+                    match v.trim().parse::<f64>() {
+                        Ok(number) => formats.format(style, number, date1904),
+                        Err(_) => v,
+                    }
+                }),
+            };
+            if let Some(text) = text.filter(|t| !t.is_empty()) {
+                grid.insert((row_index, column), text);
+            }
+        }
+    }
+
+    for range in root.find_all("mergeCell").iter().filter_map(|m| m.attr("ref")) {
+        let Some((start, end)) = range.split_once(':') else {
+            continue;
+        };
+        if let (Some((top, left)), Some((bottom, right))) = (parse_reference(start), parse_reference(end)) {
+            let in_range = |row: usize, column: usize| row >= top && row <= bottom && column >= left && column <= right;
+            match grid.get(&(top, left)).cloned() {
+                Some(value) => {
+                    for row in top..=bottom {
+                        for column in left..=right {
+                            grid.insert((row, column), value.clone());
+                        }
+                    }
+                }
+                None => grid.retain(|&(row, column), _| !in_range(row, column)),
+            }
+        }
+    }
+    grid
+}
+
+/// Turn the used range into a table whose first row is the header.
+fn grid_to_table(grid: Grid) -> Option<Table> {
+    let top = grid.keys().map(|&(row, _)| row).min()?;
+    let bottom = grid.keys().map(|&(row, _)| row).max()?;
+    let left = grid.keys().map(|&(_, column)| column).min()?;
+    let right = grid.keys().map(|&(_, column)| column).max()?;
+
+    let row = |index: usize| -> Vec<String> {
+        (left..=right)
+            .map(|column| grid.get(&(index, column)).cloned().unwrap_or_default())
+            .collect()
+    };
+    let header = row(top);
+    let rows: Vec<Vec<String>> = (top + 1..=bottom)
+        .map(row)
+        .filter(|cells| cells.iter().any(|cell| !cell.is_empty()))
+        .collect();
+    Some(Table::from_strings(&header, &rows))
+}
+
+/// Zero-based (row, column) of an `A1` style reference.
+fn parse_reference(reference: &str) -> Option<(usize, usize)> {
+    let reference = reference.replace('$', "");
+    let split = reference.find(|c: char| c.is_ascii_digit())?;
+    let (letters, digits) = reference.split_at(split);
+    if letters.is_empty() {
+        return None;
+    }
+    let column = letters
+        .chars()
+        .try_fold(0usize, |acc, c| c.is_ascii_alphabetic().then(|| acc * 26 + (c.to_ascii_uppercase() as usize - 'A' as usize + 1)))?;
+    let row = digits.parse::<usize>().ok()?;
+    Some((row.checked_sub(1)?, column - 1))
+}
+
+/// Number formats per cell style (`cellXfs` index), from `xl/styles.xml`.
+#[derive(Default)]
+struct NumberFormats {
+    styles: Vec<String>,
+}
+
+impl NumberFormats {
+    fn read(archive: &ZipArchive) -> Self {
+        let Ok(root) = archive.read_xml("xl/styles.xml") else {
+            return Self::default();
+        };
+        let custom: HashMap<u32, String> = root
+            .child("numFmts")
+            .map(|formats| {
+                formats
+                    .children_named("numFmt")
+                    .filter_map(|f| Some((f.attr("numFmtId")?.parse().ok()?, f.attr("formatCode")?.to_string())))
+                    .collect()
+            })
+            .unwrap_or_default();
+        let styles = root
+            .child("cellXfs")
+            .map(|xfs| {
+                xfs.children_named("xf")
+                    .map(|xf| {
+                        let id = xf.attr("numFmtId").and_then(|id| id.parse().ok()).unwrap_or(0);
+                        custom
+                            .get(&id)
+                            .cloned()
+                            .unwrap_or_else(|| builtin_format(id).to_string())
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+        Self { styles }
+    }
+
+    fn format(&self, style: usize, value: f64, date1904: bool) -> String {
+        let code = self.styles.get(style).map(String::as_str).unwrap_or("General");
+        format_number(value, code, date1904)
+    }
+}
+
+fn builtin_format(id: u32) -> &'static str {
+    match id {
+        1 => "0",
+        2 => "0.00",
+        3 => "#,##0",
+        4 => "#,##0.00",
+        9 => "0%",
+        10 => "0.00%",
+        11 => "0.00E+00",
+        14 => "yyyy-mm-dd",
+        15..=17 => "d-mmm-yy",
+        18 | 20 => "h:mm",
+        19 | 21 => "h:mm:ss",
+        22 => "yyyy-mm-dd h:mm",
+        37..=40 => "#,##0.00",
+        45 => "mm:ss",
+        46 => "[h]:mm:ss",
+        47 => "mm:ss.0",
+        48 => "##0.0E+0",
+        49 => "@",
+        _ => "General",
+    }
+}
+
+/// Render a numeric cell value the way its format code asks, with dates
+/// normalized to ISO 8601.
+fn format_number(value: f64, code: &str, date1904: bool) -> String {
+    let section = code.split(';').next().unwrap_or_default();
+    let bare = strip_literals(section).to_lowercase();
+
+    if bare.is_empty() || bare == "general" || bare == "@" {
+        return format_general(value);
+    }
+    let has_date = bare.contains('y') || bare.contains('d') || (bare.contains('m') && !bare.contains('h') && !bare.contains('s'));
+    let has_time = bare.contains('h') || bare.contains('s');
+    if has_date || has_time {
+        if let Some(formatted) = format_serial_date(value, has_date, has_time, date1904) {
+            return formatted;
+        }
+    }
+
+    let percent = bare.contains('%');
+    let value = if percent { value * 100.0 } else { value };
+    let decimals = bare
+        .split_once('.')
+        .map(|(_, fraction)| fraction.chars().take_while(|c| matches!(c, '0' | '#' | '?')).count())
+        .unwrap_or(0);
+    if bare.contains("e+") || bare.contains("e-") {
+        return format!("{:.*E}", decimals, value);
+    }
+
+    let mut formatted = format!("{:.*}", decimals, value);
+    if bare.contains(',') && bare.contains('0') {
+        formatted = group_thousands(&formatted);
+    }
+    if percent {
+        formatted.push('%');
+    }
+    formatted
+}
+
+/// Drop quoted text, escapes and bracketed colors or locales from a format code.
+fn strip_literals(code: &str) -> String {
+    let mut out = String::new();
+    let mut chars = code.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => {
+                for c in chars.by_ref() {
+                    if c == '"' {
+                        break;
+                    }
+                }
+            }
+            '[' => {
+                let inner: String = chars.by_ref().take_while(|&c| c != ']').collect();
+                // Elapsed time such as `[h]` still counts as a time
+                if matches!(inner.to_lowercase().as_str(), "h" | "hh" | "m" | "mm" | "s" | "ss") {
+                    out.push_str(&inner);
+                }
+            }
+            '\\' | '_' | '*' => {
+                chars.next();
+            }
+            c => out.push(c),
+        }
+    }
+    out.trim().to_string()
+}
+
+fn format_general(value: f64) -> String {
+    if value.fract() == 0.0 && value.abs() < 1e15 {
+        return format!("{}", value as i64);
+    }
+    // Excel shows at most about ten significant digits in General
+    let formatted = format!("{:.10}", value);
+    formatted.trim_end_matches('0').trim_end_matches('.').to_string()
+}
+
+fn group_thousands(number: &str) -> String {
+    let (sign, number) = number.strip_prefix('-').map(|n| ("-", n)).unwrap_or(("", number));
+    let (integer, fraction) = number.split_once('.').map(|(i, f)| (i, Some(f))).unwrap_or((number, None));
+    let mut grouped = String::new();
+    for (i, digit) in integer.chars().enumerate() {
+        if i > 0 && (integer.len() - i) % 3 == 0 {
+            grouped.push(',');
+        }
+        grouped.push(digit);
+    }
+    match fraction {
+        Some(fraction) => format!("{}{}.{}", sign, grouped, fraction),
+        None => format!("{}{}", sign, grouped),
+    }
+}
+
+fn format_serial_date(serial: f64, has_date: bool, has_time: bool, date1904: bool) -> Option<String> {
+    if !(0.0..2_958_466.0).contains(&serial) {
+        return None;
+    }
+    // The 1900 system counts a fictional 1900-02-29, which the 1899-12-30 epoch absorbs
+    let epoch = if date1904 {
+        NaiveDate::from_ymd_opt(1904, 1, 1)?
+    } else {
+        NaiveDate::from_ymd_opt(1899, 12, 30)?
+    };
+    let seconds = (serial * 86_400.0).round() as i64;
+    let datetime = epoch.and_hms_opt(0, 0, 0)? + Duration::seconds(seconds);
+
+    Some(match (has_date, has_time) {
+        (true, true) => datetime.format("%Y-%m-%d %H:%M:%S").to_string(),
+        (false, true) => datetime.format("%H:%M:%S").to_string(),
+        _ => datetime.format("%Y-%m-%d").to_string(),
+    })
+}
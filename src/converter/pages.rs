@@ -0,0 +1,277 @@
+//! Paginated sources: page range selection (`--pages 1-5,10,20-`) and
+//! running headers, footers and page numbers.
+
+use crate::config::Config;
+use crate::error::{C2mdError, Result};
+use regex::Regex;
+use std::collections::HashSet;
+use std::sync::OnceLock;
+
+/// Lines nearest the top and bottom edge that may be running headers or
+/// footers.
+const EDGE_LINES: usize = 3;
+
+/// Rank offset that puts lines counted from the bottom of a plain text page
+/// below any line counted from the top.
+const BOTTOM_RANK: f64 = 10_000.0;
+
+/// 1-based, inclusive page ranges; a range without an end runs to the last
+/// page.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PageSelection {
+    ranges: Vec<(usize, Option<usize>)>,
+}
+
+impl PageSelection {
+    /// Parse a comma separated list of pages (`10`), ranges (`1-5`) and
+    /// open ranges (`20-`, `-3`).
+    pub fn parse(spec: &str) -> Result<Self> {
+        let invalid = |item: &str| C2mdError::Config(format!("Invalid page range: {}", item));
+        let number = |text: &str, item: &str| match text.trim().parse::<usize>() {
+            Ok(n) if n >= 1 => Ok(n),
+            _ => Err(invalid(item)),
+        };
+
+        let mut ranges = Vec::new();
+        for item in spec.split(',').map(str::trim).filter(|item| !item.is_empty()) {
+            let range = match item.split_once('-') {
+                None => {
+                    let page = number(item, item)?;
+                    (page, Some(page))
+                }
+                Some((start, end)) => {
+                    let start = if start.trim().is_empty() { 1 } else { number(start, item)? };
+                    let end = if end.trim().is_empty() { None } else { Some(number(end, item)?) };
+                    if end.is_some_and(|end| end < start) {
+                        return Err(invalid(item));
+                    }
+                    (start, end)
+                }
+            };
+            ranges.push(range);
+        }
+        if ranges.is_empty() {
+            return Err(invalid(spec));
+        }
+        Ok(Self { ranges })
+    }
+
+    /// The selection configured in `pdf.pages`, if any; `all` selects every
+    /// page.
+    pub fn configured(config: &Config) -> Result<Option<Self>> {
+        match config.pdf.pages.as_deref().map(str::trim) {
+            None | Some("") => Ok(None),
+            Some(spec) if spec.eq_ignore_ascii_case("all") => Ok(None),
+            Some(spec) => Self::parse(spec).map(Some),
+        }
+    }
+
+    pub fn contains(&self, page: usize) -> bool {
+        self.ranges.iter().any(|&(start, end)| page >= start && end.is_none_or(|end| page <= end))
+    }
+
+    /// Selected pages of a document with `count` pages, in order. Fails when
+    /// the selection misses the document entirely.
+    pub fn pages(&self, count: usize) -> Result<Vec<usize>> {
+        let pages: Vec<usize> = (1..=count).filter(|&page| self.contains(page)).collect();
+        if pages.is_empty() {
+            return Err(C2mdError::Conversion(format!("No selected pages; the document has {} pages", count)));
+        }
+        Ok(pages)
+    }
+}
+
+/// Compact form of ascending page numbers for front matter: `1-3,5`.
+pub fn describe(pages: &[usize]) -> String {
+    let mut parts: Vec<String> = Vec::new();
+    let mut i = 0;
+    while i < pages.len() {
+        let start = pages[i];
+        while i + 1 < pages.len() && pages[i + 1] == pages[i] + 1 {
+            i += 1;
+        }
+        parts.push(if pages[i] == start { start.to_string() } else { format!("{}-{}", start, pages[i]) });
+        i += 1;
+    }
+    parts.join(",")
+}
+
+/// Lines of `pages`, given as text and vertical position, that repeat near
+/// the top or bottom edge of many pages, digits aside, that carry a page
+/// number advancing with the page (`numbers`, 1-based), or that are a lone
+/// page number at an edge of many pages, advancing the same way. Returns
+/// `(page, line)` indexes.
+pub fn running_lines(pages: &[Vec<(String, f64)>], numbers: &[usize], tolerance: f64) -> HashSet<(usize, usize)> {
+    let candidates: Vec<Vec<Candidate>> = pages
+        .iter()
+        .zip(numbers)
+        .map(|(lines, &number)| {
+            let mut order: Vec<usize> = (0..lines.len()).filter(|&i| !lines[i].0.trim().is_empty()).collect();
+            order.sort_by(|&a, &b| lines[a].1.total_cmp(&lines[b].1));
+            if order.len() < 2 {
+                // A lone line is the page's content
+                order.clear();
+            }
+            let edge = order.len().min(EDGE_LINES);
+            let mut picked: Vec<usize> = order[..edge].to_vec();
+            picked.extend(order[order.len().saturating_sub(edge)..].iter().filter(|i| !order[..edge].contains(i)));
+            picked
+                .into_iter()
+                .map(|i| Candidate {
+                    index: i,
+                    key: normalize(&lines[i].0),
+                    offset: folio(&lines[i].0).map(|folio| folio as i64 - number as i64),
+                    y: lines[i].1,
+                })
+                .collect()
+        })
+        .collect();
+
+    // Alternating headers on odd and even pages each cover half the pages
+    let threshold = (pages.len() * 2).div_ceil(5).max(2);
+    let repeats = |matches: &dyn Fn(&Candidate) -> bool| candidates.iter().filter(|other| other.iter().any(matches)).count();
+    let mut running = HashSet::new();
+    for (page, lines) in candidates.iter().enumerate() {
+        for line in lines {
+            let near = |other: &Candidate| (other.y - line.y).abs() <= tolerance;
+            let same_text = repeats(&|other| near(other) && other.key == line.key);
+            // Chapter titles in headers change, the page number keeps pace with the page
+            let same_folio = line.offset.map_or(0, |offset| repeats(&|other| near(other) && other.offset == Some(offset)));
+            if same_text.max(same_folio) >= threshold {
+                running.insert((page, line.index));
+            }
+        }
+    }
+
+    // A page number alone on the first or last line, once it keeps pace with
+    // the pages, roman numerals included
+    let lone: Vec<Vec<(usize, i64)>> = candidates
+        .iter()
+        .zip(pages)
+        .zip(numbers)
+        .map(|((lines, page_lines), &number)| {
+            lines
+                .first()
+                .into_iter()
+                .chain(lines.last())
+                .filter_map(|line| page_number(&page_lines[line.index].0).map(|value| (line.index, value as i64 - number as i64)))
+                .collect()
+        })
+        .collect();
+    for (page, lines) in lone.iter().enumerate() {
+        for &(index, offset) in lines {
+            if lone.iter().filter(|other| other.iter().any(|&(_, o)| o == offset)).count() >= threshold {
+                running.insert((page, index));
+            }
+        }
+    }
+    running
+}
+
+struct Candidate {
+    index: usize,
+    key: String,
+    /// Printed page number less the page's own number.
+    offset: Option<i64>,
+    y: f64,
+}
+
+/// Remove running headers, footers and page numbers from plain text pages
+/// such as OCR output. Plain text has no geometry, so line ranks stand in for
+/// positions, counted from the nearer edge so that footers line up across
+/// pages of different lengths.
+pub fn strip_running_text(pages: &[String], numbers: &[usize]) -> Vec<String> {
+    let lines: Vec<Vec<&str>> = pages.iter().map(|page| page.lines().collect()).collect();
+    let positioned: Vec<Vec<(String, f64)>> = lines
+        .iter()
+        .map(|page| {
+            let content: Vec<usize> = (0..page.len()).filter(|&i| !page[i].trim().is_empty()).collect();
+            let mut positions = vec![0.0; page.len()];
+            for (rank, &i) in content.iter().enumerate() {
+                let from_bottom = content.len() - 1 - rank;
+                positions[i] = if rank <= from_bottom { rank as f64 } else { BOTTOM_RANK - from_bottom as f64 };
+            }
+            page.iter().map(|line| line.to_string()).zip(positions).collect()
+        })
+        .collect();
+
+    let running = running_lines(&positioned, numbers, 0.0);
+    lines
+        .iter()
+        .enumerate()
+        .map(|(page, page_lines)| {
+            let kept: Vec<&str> = page_lines
+                .iter()
+                .enumerate()
+                .filter(|(index, _)| !running.contains(&(page, *index)))
+                .map(|(_, line)| *line)
+                .collect();
+            kept.join("\n").trim().to_string()
+        })
+        .collect()
+}
+
+/// Lowercase with runs of digits masked, so that "Page 12 of 80" and
+/// "Page 13 of 80" compare equal.
+fn normalize(text: &str) -> String {
+    let mut masked = String::new();
+    for c in text.split_whitespace().collect::<Vec<_>>().join(" ").chars().flat_map(char::to_lowercase) {
+        if !c.is_ascii_digit() {
+            masked.push(c);
+        } else if !masked.ends_with('#') {
+            masked.push('#');
+        }
+    }
+    masked
+}
+
+/// A number leading or ending the line, as printed page numbers do.
+fn folio(text: &str) -> Option<usize> {
+    let text = text.trim();
+    let leading: String = text.chars().take_while(char::is_ascii_digit).collect();
+    let trailing: String = text.chars().rev().take_while(char::is_ascii_digit).collect::<Vec<_>>().into_iter().rev().collect();
+    [leading, trailing].iter().find(|digits| !digits.is_empty() && digits.len() <= 5).and_then(|digits| digits.parse().ok())
+}
+
+/// The number of a line such as "12", "- 12 -", "Page 12", "12 of 80",
+/// "12/80" or a roman numeral.
+fn page_number(text: &str) -> Option<usize> {
+    static PAGE_NUMBER: OnceLock<Regex> = OnceLock::new();
+    static ROMAN: OnceLock<Regex> = OnceLock::new();
+    let page_number = PAGE_NUMBER.get_or_init(|| {
+        Regex::new(r"(?i)^(?:page|p\.|pg\.?)?\s*[-–—(\[]?\s*(\d+|[ivxlcdm]+)\s*(?:(?:of|/)\s*\d+)?\s*[-–—)\]]?$").unwrap()
+    });
+    let roman = ROMAN.get_or_init(|| Regex::new(r"(?i)^m{0,3}(cm|cd|d?c{0,3})(xc|xl|l?x{0,3})(ix|iv|v?i{0,3})$").unwrap());
+    let captures = page_number.captures(text.trim())?;
+    let number = &captures[1];
+    if number.chars().all(|c| c.is_ascii_digit()) {
+        number.parse().ok()
+    } else if roman.is_match(number) {
+        Some(roman_value(number))
+    } else {
+        None
+    }
+}
+
+/// Value of a well-formed roman numeral: each digit counts negative before
+/// a larger one.
+fn roman_value(numeral: &str) -> usize {
+    let digits: Vec<i64> = numeral
+        .chars()
+        .map(|c| match c.to_ascii_lowercase() {
+            'i' => 1,
+            'v' => 5,
+            'x' => 10,
+            'l' => 50,
+            'c' => 100,
+            'd' => 500,
+            _ => 1000,
+        })
+        .collect();
+    let total: i64 = digits
+        .iter()
+        .enumerate()
+        .map(|(i, &digit)| if digits.get(i + 1).is_some_and(|&next| next > digit) { -digit } else { digit })
+        .sum();
+    total.max(0) as usize
+}
@@ -1,67 +0,0 @@
-use crate::cli::Args;
-use crate::config::Config;
-use crate::error::{C2mdError, Result};
-use std::fs;
-use std::path::Path;
-use regex::Regex;
-
-pub fn convert_pdf(path: &Path, config: &Config, args: &Args) -> Result<String> {
-    // For now, this is a placeholder that indicates PDF conversion needs external tools
-    // In a real implementation, you would use pdf-extract or similar
-    
-    let mut markdown = String::new();
-    
-    // Add front matter
-    if config.frontmatter != "none" {
-        markdown.push_str("---\n");
-        if let Some(title) = &args.title {
-            markdown.push_str(&format!("title: {}\n", title));
-        }
-        if let Some(author) = &args.author {
-            markdown.push_str(&format!("author: {}\n", author));
-        }
-        if let Some(date) = &args.date {
-            markdown.push_str(&format!("date: {}\n", date));
-        } else {
-            markdown.push_str(&format!("date: {}\n", chrono::Utc::now().format("%Y-%m-%d")));
-        }
-        markdown.push_str("---\n\n");
-    }
-    
-    markdown.push_str("# PDF Document\n\n");
-    markdown.push_str("**Note**: PDF conversion requires external tools like `pdftotext` or `pandoc`.\n\n");
-    markdown.push_str("To enable PDF conversion, install:\n");
-    markdown.push_str("- `poppler-utils` (for pdftotext)\n");
-    markdown.push_str("- `pandoc` (for universal conversion)\n\n");
-    
-    if args.ocr || config.ocr.enabled {
-        markdown.push_str("**OCR Note**: OCR functionality requires `tesseract` to be installed.\n\n");
-    }
-    
-    markdown.push_str("## File Information\n\n");
-    markdown.push_str(&format!("- **File**: {}\n", path.file_name().unwrap().to_string_lossy()));
-    markdown.push_str("- **Format**: PDF\n");
-    
-    if let Ok(metadata) = fs::metadata(path) {
-        markdown.push_str(&format!("- **Size**: {}\n", format_file_size(metadata.len())));
-    }
-    
-    Ok(markdown)
-}
-
-fn format_file_size(bytes: u64) -> String {
-    const UNITS: &[&str] = &["B", "KB", "MB", "GB", "TB"];
-    let mut size = bytes as f64;
-    let mut unit_index = 0;
-    
-    while size >= 1024.0 && unit_index < UNITS.len() - 1 {
-        size /= 1024.0;
-        unit_index += 1;
-    }
-    
-    if unit_index == 0 {
-        format!("{} {}", size as u64, UNITS[unit_index])
-    } else {
-        format!("{:.1} {}", size, UNITS[unit_index])
-    }
-}
\ No newline at end of file
@@ -0,0 +1,242 @@
+//! Annotations: reviewers' highlights and other text markup, sticky notes and
+//! free text comments.
+
+use super::content::TextChar;
+use super::file::{Page, PdfFile};
+use super::layout;
+use super::object::{text_string, Dictionary, Object, ObjectId};
+use crate::converter::cleanup::Cleanup;
+use crate::document::{Block, Footnote, Inline, ListEntry};
+use std::collections::HashMap;
+
+/// Replies answer replies; this bounds malformed chains.
+const MAX_REPLY_DEPTH: usize = 16;
+
+/// Markup drawn over text.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mark {
+    /// Highlights and (squiggly) underlines.
+    Highlight,
+    StrikeOut,
+}
+
+/// `[x0, y0, x1, y1]` in display space.
+type Rect = [f64; 4];
+
+#[derive(Debug, Clone)]
+pub struct Annotation {
+    /// `None` for sticky notes and free text.
+    mark: Option<Mark>,
+    author: Option<String>,
+    contents: Option<String>,
+    rect: Rect,
+    /// Marked areas, from `/QuadPoints` or else `/Rect`.
+    quads: Vec<Rect>,
+    replies: Vec<Annotation>,
+}
+
+/// The page's annotations from top to bottom, with replies (`/IRT`) folded
+/// into the annotation the thread starts with.
+pub fn read(file: &PdfFile, page: &Page) -> Vec<Annotation> {
+    let Object::Array(references) = file.entry(&page.dict, "Annots") else {
+        return Vec::new();
+    };
+    let mut ids: Vec<Option<ObjectId>> = Vec::new();
+    let mut parents: Vec<Option<ObjectId>> = Vec::new();
+    let mut annotations: Vec<Option<Annotation>> = Vec::new();
+    for reference in &references {
+        let Some(dict) = file.resolve(reference).as_dict().cloned() else {
+            continue;
+        };
+        if let Some(annotation) = Annotation::read(file, &dict, page) {
+            ids.push(reference.as_reference());
+            parents.push(dict.get("IRT").and_then(Object::as_reference));
+            annotations.push(Some(annotation));
+        }
+    }
+
+    let index: HashMap<ObjectId, usize> = ids.iter().enumerate().filter_map(|(i, id)| Some(((*id)?, i))).collect();
+    let thread = |i: usize| {
+        let mut root = None;
+        let mut parent = parents[i];
+        for _ in 0..MAX_REPLY_DEPTH {
+            match parent.and_then(|id| index.get(&id)) {
+                Some(&j) if j != i => {
+                    root = Some(j);
+                    parent = parents[j];
+                }
+                _ => break,
+            }
+        }
+        root
+    };
+    let roots: Vec<Option<usize>> = (0..annotations.len()).map(thread).collect();
+    for (i, root) in roots.iter().enumerate() {
+        if let Some(root) = *root {
+            if let Some(reply) = annotations[i].take() {
+                if let Some(thread) = annotations[root].as_mut() {
+                    thread.replies.push(reply);
+                }
+            }
+        }
+    }
+
+    let mut annotations: Vec<Annotation> = annotations.into_iter().flatten().collect();
+    annotations.sort_by(|a, b| a.rect[1].total_cmp(&b.rect[1]).then(a.rect[0].total_cmp(&b.rect[0])));
+    annotations
+}
+
+/// Mark the glyphs under text markup and add a footnote, anchored after the
+/// marked text or near the note's position, for every comment.
+pub fn apply(annotations: &[Annotation], chars: &mut [TextChar], footnotes: &mut Vec<Footnote>) {
+    for annotation in annotations {
+        let mut anchor = None;
+        if let Some(mark) = annotation.mark {
+            for (i, c) in chars.iter_mut().enumerate() {
+                if annotation.covers(c) {
+                    c.mark = Some(mark);
+                    if !c.text.trim().is_empty() {
+                        anchor = Some(i);
+                    }
+                }
+            }
+        }
+        let blocks: Vec<Block> = std::iter::once(annotation)
+            .chain(&annotation.replies)
+            .map(Annotation::comment)
+            .filter(|comment| !comment.is_empty())
+            .map(Block::Paragraph)
+            .collect();
+        if blocks.is_empty() {
+            continue;
+        }
+        let label = (footnotes.len() + 1).to_string();
+        if let Some(i) = anchor.or_else(|| nearest(chars, &annotation.rect)) {
+            chars[i].notes.push(label.clone());
+        }
+        footnotes.push(Footnote { label, blocks });
+    }
+}
+
+/// The page's review notes as a list: the marked text followed by its
+/// comment, with replies nested.
+pub fn review(annotations: &[Annotation], chars: &[TextChar], cleanup: &Cleanup) -> Vec<ListEntry> {
+    let mut entries = Vec::new();
+    for annotation in annotations {
+        let mut content = Vec::new();
+        if let Some(mark) = annotation.mark {
+            let marked: Vec<TextChar> = chars.iter().filter(|c| annotation.covers(c)).cloned().collect();
+            let text: Vec<String> = layout::runs(&marked).iter().map(|run| cleanup.chars(&run.text())).collect();
+            let text = text.join(" ").split_whitespace().collect::<Vec<_>>().join(" ");
+            if !text.is_empty() {
+                let text = vec![Inline::text(text)];
+                content.push(match mark {
+                    Mark::Highlight => Inline::Highlight(text),
+                    Mark::StrikeOut => Inline::Strikethrough(text),
+                });
+            }
+        }
+        let comment = annotation.comment();
+        if !content.is_empty() && !comment.is_empty() {
+            content.push(Inline::text(" — "));
+        }
+        content.extend(comment);
+        if content.is_empty() {
+            continue;
+        }
+        entries.push(ListEntry { level: 0, ordered: false, number: 1, content });
+        for reply in &annotation.replies {
+            let content = reply.comment();
+            if !content.is_empty() {
+                entries.push(ListEntry { level: 1, ordered: false, number: 1, content });
+            }
+        }
+    }
+    entries
+}
+
+impl Annotation {
+    fn read(file: &PdfFile, dict: &Dictionary, page: &Page) -> Option<Self> {
+        let mark = match file.entry(dict, "Subtype").as_name()? {
+            "Highlight" | "Underline" | "Squiggly" => Some(Mark::Highlight),
+            "StrikeOut" => Some(Mark::StrikeOut),
+            "Text" | "FreeText" => None,
+            _ => return None,
+        };
+        // Review states such as "Accepted" are replies without a comment
+        if !matches!(file.entry(dict, "StateModel"), Object::Null) {
+            return None;
+        }
+        let numbers = |key: &str| -> Vec<f64> {
+            match file.entry(dict, key) {
+                Object::Array(values) => values.iter().filter_map(|value| file.resolve(value).as_f64()).collect(),
+                _ => Vec::new(),
+            }
+        };
+        let rect = numbers("Rect");
+        let rect = (rect.len() == 4).then(|| bounds(page, &rect))?;
+        let mut quads: Vec<Rect> = numbers("QuadPoints").chunks_exact(8).map(|quad| bounds(page, quad)).collect();
+        if quads.is_empty() {
+            quads.push(rect);
+        }
+        let text = |key: &str| {
+            let text = text_string(file.entry(dict, key).as_bytes()?).replace("\r\n", "\n").replace('\r', "\n");
+            let text = text.trim();
+            (!text.is_empty()).then(|| text.to_string())
+        };
+        Some(Self { mark, author: text("T"), contents: text("Contents"), rect, quads, replies: Vec::new() })
+    }
+
+    /// Whether the middle of the glyph lies in a marked area.
+    fn covers(&self, c: &TextChar) -> bool {
+        let x = c.x + c.width / 2.0;
+        let y = c.y - c.size * 0.3;
+        self.quads.iter().any(|quad| quad[0] <= x && x <= quad[2] && quad[1] <= y && y <= quad[3])
+    }
+
+    /// "**Author:** comment", with the comment's line breaks; empty without
+    /// a comment.
+    fn comment(&self) -> Vec<Inline> {
+        let Some(contents) = &self.contents else {
+            return Vec::new();
+        };
+        let mut out = Vec::new();
+        if let Some(author) = &self.author {
+            out.push(Inline::Strong(vec![Inline::text(format!("{}:", author))]));
+            out.push(Inline::text(" "));
+        }
+        for (i, line) in contents.lines().map(str::trim).filter(|line| !line.is_empty()).enumerate() {
+            if i > 0 {
+                out.push(Inline::LineBreak);
+            }
+            out.push(Inline::text(line));
+        }
+        out
+    }
+}
+
+/// Bounding box in display space of the points `[x0 y0 x1 y1 ...]`.
+fn bounds(page: &Page, points: &[f64]) -> Rect {
+    let mut rect = [f64::INFINITY, f64::INFINITY, f64::NEG_INFINITY, f64::NEG_INFINITY];
+    for point in points.chunks_exact(2) {
+        let (x, y) = page.to_display(point[0], point[1]);
+        rect = [rect[0].min(x), rect[1].min(y), rect[2].max(x), rect[3].max(y)];
+    }
+    rect
+}
+
+/// The glyph a note at `rect` follows: on the line nearest the note's middle,
+/// the last glyph left of the note's right edge, or the end of the line for
+/// notes in the left margin.
+fn nearest(chars: &[TextChar], rect: &Rect) -> Option<usize> {
+    let middle = (rect[1] + rect[3]) / 2.0;
+    let visible = || chars.iter().enumerate().filter(|(_, c)| !c.text.trim().is_empty());
+    let distance = |c: &TextChar| (c.y - c.size * 0.3 - middle).abs();
+    let (_, closest) = visible().min_by(|(_, a), (_, b)| distance(a).total_cmp(&distance(b)))?;
+    let line: Vec<(usize, &TextChar)> =
+        visible().filter(|(_, c)| (c.y - closest.y).abs() < 0.3 * c.size.max(closest.size)).collect();
+    let last = |candidates: &mut dyn Iterator<Item = &(usize, &TextChar)>| {
+        candidates.max_by(|(_, a), (_, b)| a.x.total_cmp(&b.x)).map(|(i, _)| *i)
+    };
+    last(&mut line.iter().filter(|(_, c)| c.x <= rect[2])).or_else(|| last(&mut line.iter()))
+}
@@ -0,0 +1,447 @@
+//! Content stream interpreter: positions and sizes of shown glyphs.
+
+use super::annots::Mark;
+use super::file::{find, Page, PdfFile};
+use super::font::Font;
+use super::object::{is_whitespace, Dictionary, Object, Parser, Stream};
+use std::collections::HashMap;
+use std::rc::Rc;
+
+/// Form XObjects nest; stop runaway or cyclic nesting.
+const MAX_FORM_DEPTH: usize = 8;
+
+type Matrix = [f64; 6];
+
+const IDENTITY: Matrix = [1.0, 0.0, 0.0, 1.0, 0.0, 0.0];
+
+fn multiply(m: &Matrix, n: &Matrix) -> Matrix {
+    [
+        m[0] * n[0] + m[1] * n[2],
+        m[0] * n[1] + m[1] * n[3],
+        m[2] * n[0] + m[3] * n[2],
+        m[2] * n[1] + m[3] * n[3],
+        m[4] * n[0] + m[5] * n[2] + n[4],
+        m[4] * n[1] + m[5] * n[3] + n[5],
+    ]
+}
+
+fn transform_point(m: &Matrix, x: f64, y: f64) -> (f64, f64) {
+    (m[0] * x + m[2] * y + m[4], m[1] * x + m[3] * y + m[5])
+}
+
+/// A shown glyph in page space: origin at the top left, y growing downwards,
+/// `y` on the baseline.
+#[derive(Debug, Clone)]
+pub struct TextChar {
+    pub text: String,
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+    pub size: f64,
+    pub bold: bool,
+    pub italic: bool,
+    pub monospace: bool,
+    /// Reviewer markup over the glyph.
+    pub mark: Option<Mark>,
+    /// Footnote labels of notes anchored after the glyph.
+    pub notes: Vec<String>,
+}
+
+#[derive(Clone)]
+struct State {
+    ctm: Matrix,
+    font: Option<Rc<Font>>,
+    font_size: f64,
+    char_spacing: f64,
+    word_spacing: f64,
+    horizontal_scale: f64,
+    leading: f64,
+    rise: f64,
+}
+
+impl Default for State {
+    fn default() -> Self {
+        Self {
+            ctm: IDENTITY,
+            font: None,
+            font_size: 0.0,
+            char_spacing: 0.0,
+            word_spacing: 0.0,
+            horizontal_scale: 1.0,
+            leading: 0.0,
+            rise: 0.0,
+        }
+    }
+}
+
+/// A horizontal or vertical line segment in display space, `x0 <= x1` and
+/// `y0 <= y1`; table rulings and cell borders.
+#[derive(Debug, Clone, Copy)]
+pub struct Rule {
+    pub x0: f64,
+    pub y0: f64,
+    pub x1: f64,
+    pub y1: f64,
+}
+
+impl Rule {
+    pub fn is_horizontal(&self) -> bool {
+        self.y1 - self.y0 < 1.0
+    }
+}
+
+/// A painted image: its bounding box in display space and, for image
+/// XObjects, the image stream. Inline images carry no stream.
+#[derive(Debug, Clone)]
+pub struct PlacedImage {
+    pub x0: f64,
+    pub y0: f64,
+    pub x1: f64,
+    pub y1: f64,
+    pub stream: Option<Stream>,
+}
+
+impl PlacedImage {
+    pub fn area(&self) -> f64 {
+        (self.x1 - self.x0) * (self.y1 - self.y0)
+    }
+}
+
+/// Points of the path under construction; `closed` marks subpaths ended with
+/// `h` or built by `re`.
+#[derive(Default)]
+struct Subpath {
+    points: Vec<(f64, f64)>,
+    closed: bool,
+    curved: bool,
+}
+
+pub struct Interpreter<'a> {
+    file: &'a PdfFile,
+    page: &'a Page,
+    fonts: HashMap<String, Rc<Font>>,
+    path: Vec<Subpath>,
+    pub chars: Vec<TextChar>,
+    pub rules: Vec<Rule>,
+    pub images: Vec<PlacedImage>,
+}
+
+impl<'a> Interpreter<'a> {
+    pub fn new(file: &'a PdfFile, page: &'a Page) -> Self {
+        Self {
+            file,
+            page,
+            fonts: HashMap::new(),
+            path: Vec::new(),
+            chars: Vec::new(),
+            rules: Vec::new(),
+            images: Vec::new(),
+        }
+    }
+
+    /// Interpret the page's content streams.
+    pub fn run(mut self) -> Self {
+        let content = self.file.page_content(self.page);
+        let resources = self.page.resources.clone();
+        self.execute(&content, &resources, State::default(), 0);
+        self
+    }
+
+    fn execute(&mut self, content: &[u8], resources: &Dictionary, state: State, depth: usize) {
+        let mut parser = Parser::new(content, 0);
+        let mut stack: Vec<State> = Vec::new();
+        let mut state = state;
+        let mut text_matrix = IDENTITY;
+        let mut line_matrix = IDENTITY;
+        let mut operands: Vec<Object> = Vec::new();
+
+        while !parser.at_end() {
+            let Ok(object) = parser.object() else {
+                break;
+            };
+            let Object::Operator(operator) = object else {
+                operands.push(object);
+                continue;
+            };
+            let number = |i: usize| operands.get(i).and_then(Object::as_f64).unwrap_or(0.0);
+            match operator.as_str() {
+                "q" => stack.push(state.clone()),
+                "Q" => {
+                    if let Some(saved) = stack.pop() {
+                        state = saved;
+                    }
+                }
+                "cm" if operands.len() >= 6 => {
+                    let m = [number(0), number(1), number(2), number(3), number(4), number(5)];
+                    state.ctm = multiply(&m, &state.ctm);
+                }
+                "BT" => {
+                    text_matrix = IDENTITY;
+                    line_matrix = IDENTITY;
+                }
+                "Tf" if operands.len() >= 2 => {
+                    if let Some(name) = operands[0].as_name() {
+                        state.font = self.font(resources, name);
+                    }
+                    state.font_size = number(1);
+                }
+                "Tc" => state.char_spacing = number(0),
+                "Tw" => state.word_spacing = number(0),
+                "Tz" => state.horizontal_scale = number(0) / 100.0,
+                "TL" => state.leading = number(0),
+                "Ts" => state.rise = number(0),
+                "Td" | "TD" => {
+                    if operator == "TD" {
+                        state.leading = -number(1);
+                    }
+                    line_matrix = multiply(&[1.0, 0.0, 0.0, 1.0, number(0), number(1)], &line_matrix);
+                    text_matrix = line_matrix;
+                }
+                "Tm" if operands.len() >= 6 => {
+                    line_matrix = [number(0), number(1), number(2), number(3), number(4), number(5)];
+                    text_matrix = line_matrix;
+                }
+                "T*" => {
+                    line_matrix = multiply(&[1.0, 0.0, 0.0, 1.0, 0.0, -state.leading], &line_matrix);
+                    text_matrix = line_matrix;
+                }
+                "Tj" | "'" | "\"" => {
+                    if operator == "\"" {
+                        state.word_spacing = number(0);
+                        state.char_spacing = number(1);
+                    }
+                    if operator != "Tj" {
+                        line_matrix = multiply(&[1.0, 0.0, 0.0, 1.0, 0.0, -state.leading], &line_matrix);
+                        text_matrix = line_matrix;
+                    }
+                    if let Some(bytes) = operands.last().and_then(Object::as_bytes) {
+                        self.show(bytes, &state, &mut text_matrix);
+                    }
+                }
+                "TJ" => {
+                    if let Some(items) = operands.first().and_then(Object::as_array) {
+                        for item in items {
+                            match item {
+                                Object::String(bytes) => self.show(bytes, &state, &mut text_matrix),
+                                other => {
+                                    let adjust = other.as_f64().unwrap_or(0.0);
+                                    let tx = -adjust / 1000.0 * state.font_size * state.horizontal_scale;
+                                    text_matrix = multiply(&[1.0, 0.0, 0.0, 1.0, tx, 0.0], &text_matrix);
+                                }
+                            }
+                        }
+                    }
+                }
+                "Do" => {
+                    if let Some(name) = operands.first().and_then(Object::as_name) {
+                        self.draw_xobject(resources, name, &state, depth);
+                    }
+                }
+                "m" if operands.len() >= 2 => {
+                    let point = self.transform(&state.ctm, number(0), number(1));
+                    self.path.push(Subpath { points: vec![point], ..Subpath::default() });
+                }
+                "l" if operands.len() >= 2 => {
+                    let point = self.transform(&state.ctm, number(0), number(1));
+                    match self.path.last_mut() {
+                        Some(subpath) => subpath.points.push(point),
+                        None => self.path.push(Subpath { points: vec![point], ..Subpath::default() }),
+                    }
+                }
+                "c" | "v" | "y" => {
+                    let n = operands.len();
+                    let end = (n >= 2).then(|| self.transform(&state.ctm, number(n - 2), number(n - 1)));
+                    if let (Some(subpath), Some(end)) = (self.path.last_mut(), end) {
+                        subpath.points.push(end);
+                        subpath.curved = true;
+                    }
+                }
+                "h" => {
+                    if let Some(subpath) = self.path.last_mut() {
+                        subpath.closed = true;
+                    }
+                }
+                "re" if operands.len() >= 4 => {
+                    let (x, y, w, h) = (number(0), number(1), number(2), number(3));
+                    let points = [(x, y), (x + w, y), (x + w, y + h), (x, y + h)]
+                        .iter()
+                        .map(|&(px, py)| self.transform(&state.ctm, px, py))
+                        .collect();
+                    self.path.push(Subpath { points, closed: true, curved: false });
+                }
+                "S" | "s" | "f" | "F" | "f*" | "B" | "B*" | "b" | "b*" => self.paint_path(),
+                "n" => self.path.clear(),
+                "BI" => {
+                    skip_inline_image(&mut parser, content);
+                    self.place_image(&state.ctm, None);
+                }
+                _ => {}
+            }
+            operands.clear();
+        }
+    }
+
+    /// User space to display space.
+    fn transform(&self, ctm: &Matrix, x: f64, y: f64) -> (f64, f64) {
+        let (x, y) = transform_point(ctm, x, y);
+        self.page.to_display(x, y)
+    }
+
+    /// Keep the axis-aligned straight edges of the painted path. Thin filled
+    /// rectangles, a common way to draw rulings, become a single line.
+    fn paint_path(&mut self) {
+        for subpath in std::mem::take(&mut self.path) {
+            if subpath.curved {
+                continue;
+            }
+            let points = &subpath.points;
+            if subpath.closed && points.len() == 4 {
+                let x0 = points.iter().map(|p| p.0).fold(f64::INFINITY, f64::min);
+                let x1 = points.iter().map(|p| p.0).fold(f64::NEG_INFINITY, f64::max);
+                let y0 = points.iter().map(|p| p.1).fold(f64::INFINITY, f64::min);
+                let y1 = points.iter().map(|p| p.1).fold(f64::NEG_INFINITY, f64::max);
+                let rectangular = points.iter().all(|p| ((p.0 - x0).abs() < 0.5 || (p.0 - x1).abs() < 0.5) && ((p.1 - y0).abs() < 0.5 || (p.1 - y1).abs() < 0.5));
+                if rectangular && (x1 - x0 < 3.0 || y1 - y0 < 3.0) {
+                    let (cx, cy) = ((x0 + x1) / 2.0, (y0 + y1) / 2.0);
+                    if x1 - x0 >= y1 - y0 {
+                        self.push_rule(x0, cy, x1, cy);
+                    } else {
+                        self.push_rule(cx, y0, cx, y1);
+                    }
+                    continue;
+                }
+            }
+            let mut edges: Vec<((f64, f64), (f64, f64))> = points.windows(2).map(|pair| (pair[0], pair[1])).collect();
+            if subpath.closed && points.len() > 2 {
+                edges.push((points[points.len() - 1], points[0]));
+            }
+            for (a, b) in edges {
+                self.push_rule(a.0, a.1, b.0, b.1);
+            }
+        }
+    }
+
+    fn push_rule(&mut self, xa: f64, ya: f64, xb: f64, yb: f64) {
+        let (x0, x1) = (xa.min(xb), xa.max(xb));
+        let (y0, y1) = (ya.min(yb), ya.max(yb));
+        let horizontal = y1 - y0 < 1.0 && x1 - x0 > 2.0;
+        let vertical = x1 - x0 < 1.0 && y1 - y0 > 2.0;
+        if horizontal || vertical {
+            self.rules.push(Rule { x0, y0, x1, y1 });
+        }
+    }
+
+    fn font(&mut self, resources: &Dictionary, name: &str) -> Option<Rc<Font>> {
+        let fonts = self.file.dict_entry(resources, "Font")?;
+        let reference = fonts.get(name)?;
+        let key = match reference {
+            Object::Reference((number, generation)) => format!("{} {}", number, generation),
+            _ => format!("/{}", name),
+        };
+        if let Some(font) = self.fonts.get(&key) {
+            return Some(font.clone());
+        }
+        let dict = self.file.resolve(reference).as_dict()?.clone();
+        let font = Rc::new(Font::load(self.file, &dict));
+        // Inline font dictionaries are rare; their names are only unique per resource dictionary
+        if matches!(reference, Object::Reference(_)) {
+            self.fonts.insert(key, font.clone());
+        }
+        Some(font)
+    }
+
+    fn show(&mut self, bytes: &[u8], state: &State, text_matrix: &mut Matrix) {
+        let Some(font) = state.font.clone() else {
+            return;
+        };
+        for glyph in font.decode(bytes) {
+            let render = multiply(
+                &[state.font_size * state.horizontal_scale, 0.0, 0.0, state.font_size, 0.0, state.rise],
+                &multiply(text_matrix, &state.ctm),
+            );
+            let size = (render[2] * render[2] + render[3] * render[3]).sqrt();
+            let x_scale = (render[0] * render[0] + render[1] * render[1]).sqrt();
+            if !glyph.text.is_empty() && size > 0.0 {
+                let (x, y) = self.page.to_display(render[4], render[5]);
+                self.chars.push(TextChar {
+                    text: glyph.text,
+                    x,
+                    y,
+                    width: glyph.width * x_scale,
+                    size,
+                    bold: font.bold,
+                    italic: font.italic,
+                    monospace: font.monospace,
+                    mark: None,
+                    notes: Vec::new(),
+                });
+            }
+            let spacing = state.char_spacing + if glyph.is_space { state.word_spacing } else { 0.0 };
+            let tx = (glyph.width * state.font_size + spacing) * state.horizontal_scale;
+            *text_matrix = multiply(&[1.0, 0.0, 0.0, 1.0, tx, 0.0], text_matrix);
+        }
+    }
+
+    /// Images are drawn into the unit square of user space.
+    fn place_image(&mut self, ctm: &Matrix, stream: Option<Stream>) {
+        let corners = [(0.0, 0.0), (1.0, 0.0), (0.0, 1.0), (1.0, 1.0)].map(|(x, y)| self.transform(ctm, x, y));
+        let xs = corners.map(|(x, _)| x);
+        let ys = corners.map(|(_, y)| y);
+        let min = |values: [f64; 4]| values.iter().copied().fold(f64::INFINITY, f64::min);
+        let max = |values: [f64; 4]| values.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+        self.images.push(PlacedImage { x0: min(xs), y0: min(ys), x1: max(xs), y1: max(ys), stream });
+    }
+
+    fn draw_xobject(&mut self, resources: &Dictionary, name: &str, state: &State, depth: usize) {
+        if depth >= MAX_FORM_DEPTH {
+            return;
+        }
+        let Some(xobjects) = self.file.dict_entry(resources, "XObject") else {
+            return;
+        };
+        let Object::Stream(stream) = self.file.entry(&xobjects, name) else {
+            return;
+        };
+        match stream.dict.get("Subtype").and_then(Object::as_name) {
+            Some("Form") => {}
+            Some("Image") => return self.place_image(&state.ctm, Some(stream)),
+            _ => return,
+        }
+        let Ok(content) = self.file.decode_stream(&stream) else {
+            return;
+        };
+        let mut state = state.clone();
+        if let Object::Array(matrix) = self.file.entry(&stream.dict, "Matrix") {
+            let values: Vec<f64> = matrix.iter().filter_map(|v| self.file.resolve(v).as_f64()).collect();
+            if let [a, b, c, d, e, f] = values[..] {
+                state.ctm = multiply(&[a, b, c, d, e, f], &state.ctm);
+            }
+        }
+        let form_resources = self.file.dict_entry(&stream.dict, "Resources").unwrap_or_else(|| resources.clone());
+        self.execute(&content, &form_resources, state, depth + 1);
+    }
+}
+
+/// Skip an inline image (`BI ... ID <data> EI`); its data is not tokenizable.
+fn skip_inline_image(parser: &mut Parser, content: &[u8]) {
+    while !parser.at_end() {
+        match parser.object() {
+            Ok(Object::Operator(operator)) if operator == "ID" => break,
+            Ok(_) => {}
+            Err(_) => return,
+        }
+    }
+    let start = parser.pos + 1;
+    let mut offset = start;
+    while let Some(found) = content.get(offset..).and_then(|rest| find(rest, b"EI")) {
+        let at = offset + found;
+        let before = at.checked_sub(1).map(|i| content[i]);
+        let after = content.get(at + 2).copied();
+        if before.is_none_or(is_whitespace) && after.is_none_or(is_whitespace) {
+            parser.pos = at + 2;
+            return;
+        }
+        offset = at + 2;
+    }
+    parser.pos = content.len();
+}
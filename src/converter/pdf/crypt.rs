@@ -0,0 +1,322 @@
+//! The Standard security handler: file keys from the user or owner password
+//! (revisions 2 to 6) and RC4 or AES decryption of strings and streams.
+
+use super::object::{Dictionary, Object, ObjectId};
+use crate::error::{C2mdError, Result};
+use aes::cipher::block_padding::NoPadding;
+use aes::cipher::{BlockDecryptMut, BlockEncryptMut, KeyIvInit};
+use aes::{Aes128, Aes256};
+use rc4::consts::{U10, U11, U12, U13, U14, U15, U16, U32, U5, U6, U7, U8, U9};
+use rc4::{KeyInit, Rc4, StreamCipher};
+use sha2::{Digest, Sha256, Sha384, Sha512};
+
+/// Pads passwords to 32 bytes in revisions 2 to 4.
+const PADDING: [u8; 32] = [
+    0x28, 0xBF, 0x4E, 0x5E, 0x4E, 0x75, 0x8A, 0x41, 0x64, 0x00, 0x4E, 0x56, 0xFF, 0xFA, 0x01, 0x08, 0x2E, 0x2E, 0x00, 0xB6,
+    0xD0, 0x68, 0x3E, 0x80, 0x2F, 0x0C, 0xA9, 0xFE, 0x64, 0x53, 0x69, 0x7A,
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Method {
+    Identity,
+    Rc4,
+    Aes,
+}
+
+/// Decrypts the objects of one file.
+pub struct Decryptor {
+    key: Vec<u8>,
+    revision: i64,
+    strings: Method,
+    streams: Method,
+    encrypt_metadata: bool,
+}
+
+impl Decryptor {
+    /// Authenticate `password` as the user or the owner password. Without a
+    /// password the empty user password is tried, which opens files that only
+    /// restrict permissions.
+    pub fn new(encrypt: &Dictionary, id: &[u8], password: Option<&str>) -> Result<Self> {
+        let name = |key: &str| encrypt.get(key).and_then(Object::as_name);
+        let int = |key: &str, default: i64| encrypt.get(key).and_then(Object::as_i64).unwrap_or(default);
+        let bytes = |key: &str| encrypt.get(key).and_then(Object::as_bytes).unwrap_or_default().to_vec();
+
+        let filter = name("Filter").unwrap_or("Standard");
+        if filter != "Standard" {
+            return Err(C2mdError::Encrypted(format!("unsupported security handler {}", filter)));
+        }
+        let version = int("V", 0);
+        let revision = int("R", 2);
+        let encrypt_metadata = !matches!(encrypt.get("EncryptMetadata"), Some(Object::Boolean(false)));
+        let (strings, streams) = if version >= 4 {
+            let filters = encrypt.get("CF").and_then(Object::as_dict);
+            let method = |key: &str| match name(key).unwrap_or("Identity") {
+                "Identity" => Method::Identity,
+                filter => match filters.and_then(|f| f.get(filter)).and_then(Object::as_dict).and_then(|f| f.get("CFM")).and_then(Object::as_name) {
+                    Some("AESV2" | "AESV3") => Method::Aes,
+                    Some("V2") => Method::Rc4,
+                    _ => Method::Identity,
+                },
+            };
+            (method("StrF"), method("StmF"))
+        } else {
+            (Method::Rc4, Method::Rc4)
+        };
+        let length = match version {
+            1 => 5,
+            4.. => 16,
+            _ => (int("Length", 40) / 8).clamp(5, 16) as usize,
+        };
+
+        let handler = Handler { o: bytes("O"), u: bytes("U"), p: int("P", 0) as i32, id, revision, length, encrypt_metadata };
+        let secret = password.unwrap_or_default();
+        let key = match revision {
+            5 | 6 => handler.key_aes256(secret.as_bytes(), &bytes("OE"), &bytes("UE")),
+            _ => handler.key_md5(&latin1(secret)),
+        };
+        let key = key.ok_or_else(|| {
+            C2mdError::Encrypted(match password {
+                None => "a password is required; use --password, C2MD_PDF_PASSWORD or --password-file".to_string(),
+                Some(_) => "incorrect password".to_string(),
+            })
+        })?;
+        Ok(Self { key, revision, strings, streams, encrypt_metadata })
+    }
+
+    /// Decrypt the strings and stream data of object `id` in place.
+    pub fn decrypt(&self, id: ObjectId, object: &mut Object) {
+        match object {
+            Object::String(bytes) => *bytes = self.decrypt_bytes(id, self.strings, bytes),
+            Object::Array(items) => items.iter_mut().for_each(|item| self.decrypt(id, item)),
+            Object::Dictionary(dict) => dict.values_mut().for_each(|value| self.decrypt(id, value)),
+            Object::Stream(stream) => {
+                stream.dict.values_mut().for_each(|value| self.decrypt(id, value));
+                // Cross-reference streams are never encrypted, metadata optionally not
+                match stream.dict.get("Type").and_then(Object::as_name) {
+                    Some("XRef") => {}
+                    Some("Metadata") if !self.encrypt_metadata => {}
+                    _ => stream.data = self.decrypt_bytes(id, self.streams, &stream.data),
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn decrypt_bytes(&self, id: ObjectId, method: Method, data: &[u8]) -> Vec<u8> {
+        match method {
+            Method::Identity => data.to_vec(),
+            Method::Rc4 => rc4(&self.object_key(id, method), data),
+            Method::Aes => aes_cbc_decrypt(&self.object_key(id, method), data),
+        }
+    }
+
+    /// Revisions before 5 derive a key per object from the file key.
+    fn object_key(&self, id: ObjectId, method: Method) -> Vec<u8> {
+        if self.revision >= 5 {
+            return self.key.clone();
+        }
+        let mut hash = md5::Context::new();
+        hash.consume(&self.key);
+        hash.consume(&id.0.to_le_bytes()[..3]);
+        hash.consume(id.1.to_le_bytes());
+        if method == Method::Aes {
+            hash.consume(b"sAlT");
+        }
+        hash.compute()[..(self.key.len() + 5).min(16)].to_vec()
+    }
+}
+
+/// The `/Encrypt` entries that derive and check keys.
+struct Handler<'a> {
+    o: Vec<u8>,
+    u: Vec<u8>,
+    p: i32,
+    id: &'a [u8],
+    revision: i64,
+    /// Key length in bytes.
+    length: usize,
+    encrypt_metadata: bool,
+}
+
+impl Handler<'_> {
+    /// Revisions 2 to 4: the password is the user password, or the owner
+    /// password, which decrypts `/O` to the user password.
+    fn key_md5(&self, password: &[u8]) -> Option<Vec<u8>> {
+        let key = self.user_key(password);
+        if self.authenticates(&key) {
+            return Some(key);
+        }
+        let mut digest = md5::compute(pad(password)).0;
+        if self.revision >= 3 {
+            for _ in 0..50 {
+                digest = md5::compute(digest).0;
+            }
+        }
+        let owner_key = &digest[..self.length];
+        let mut user = self.o.clone();
+        if self.revision == 2 {
+            user = rc4(owner_key, &user);
+        } else {
+            for i in (0..20).rev() {
+                user = rc4(&xor(owner_key, i), &user);
+            }
+        }
+        let key = self.user_key(&user);
+        self.authenticates(&key).then_some(key)
+    }
+
+    fn user_key(&self, password: &[u8]) -> Vec<u8> {
+        let mut hash = md5::Context::new();
+        hash.consume(pad(password));
+        hash.consume(&self.o);
+        hash.consume(self.p.to_le_bytes());
+        hash.consume(self.id);
+        if self.revision >= 4 && !self.encrypt_metadata {
+            hash.consume([0xFF; 4]);
+        }
+        let mut digest = hash.compute().0;
+        if self.revision >= 3 {
+            for _ in 0..50 {
+                digest = md5::compute(&digest[..self.length]).0;
+            }
+        }
+        digest[..self.length].to_vec()
+    }
+
+    fn authenticates(&self, key: &[u8]) -> bool {
+        if self.revision == 2 {
+            return rc4(key, &PADDING) == self.u;
+        }
+        let mut hash = md5::Context::new();
+        hash.consume(PADDING);
+        hash.consume(self.id);
+        let mut check = hash.compute().to_vec();
+        for i in 0..20 {
+            check = rc4(&xor(key, i), &check);
+        }
+        self.u.get(..16) == Some(&check[..])
+    }
+
+    /// Revisions 5 and 6: `/U` and `/O` hold a hash, a validation salt and a
+    /// key salt; `/UE` and `/OE` the file key, encrypted.
+    fn key_aes256(&self, password: &[u8], oe: &[u8], ue: &[u8]) -> Option<Vec<u8>> {
+        let password = &password[..password.len().min(127)];
+        let (u, o) = (self.u.get(..48)?, self.o.get(..48)?);
+        let (hash, encrypted) = if self.hash(password, &u[32..40], &[]) == u[..32] {
+            (self.hash(password, &u[40..48], &[]), ue)
+        } else if self.hash(password, &o[32..40], u) == o[..32] {
+            (self.hash(password, &o[40..48], u), oe)
+        } else {
+            return None;
+        };
+        let key = cbc_decrypt(&hash, &[0; 16], encrypted.get(..32)?);
+        Some(key)
+    }
+
+    /// SHA-256 in revision 5, the iterated hash of revision 6.
+    fn hash(&self, password: &[u8], salt: &[u8], udata: &[u8]) -> Vec<u8> {
+        let mut k = Sha256::new().chain_update(password).chain_update(salt).chain_update(udata).finalize().to_vec();
+        if self.revision < 6 {
+            return k;
+        }
+        let mut round = 0;
+        loop {
+            let mut block = Vec::with_capacity(64 * (password.len() + k.len() + udata.len()));
+            for _ in 0..64 {
+                block.extend_from_slice(password);
+                block.extend_from_slice(&k);
+                block.extend_from_slice(udata);
+            }
+            let e = cbc_encrypt(&k[..16], &k[16..32], &block);
+            let sum: u32 = e[..16].iter().map(|&b| b as u32).sum();
+            k = match sum % 3 {
+                0 => Sha256::digest(&e).to_vec(),
+                1 => Sha384::digest(&e).to_vec(),
+                _ => Sha512::digest(&e).to_vec(),
+            };
+            round += 1;
+            if round >= 64 && e.last().is_some_and(|&last| last as usize + 32 <= round) {
+                break;
+            }
+        }
+        k.truncate(32);
+        k
+    }
+}
+
+/// Passwords before revision 5 are PDFDocEncoding; Latin-1 covers most.
+fn latin1(password: &str) -> Vec<u8> {
+    password.chars().map(|c| u8::try_from(u32::from(c)).unwrap_or(b'?')).collect()
+}
+
+fn pad(password: &[u8]) -> [u8; 32] {
+    let mut padded = PADDING;
+    let length = password.len().min(32);
+    padded[..length].copy_from_slice(&password[..length]);
+    padded[length..].copy_from_slice(&PADDING[..32 - length]);
+    padded
+}
+
+fn xor(key: &[u8], value: u8) -> Vec<u8> {
+    key.iter().map(|b| b ^ value).collect()
+}
+
+/// RC4 with a 5 to 16 byte key (32 in revision 5 handlers that still ask
+/// for it); the cipher takes its key size as a type.
+fn rc4(key: &[u8], data: &[u8]) -> Vec<u8> {
+    macro_rules! apply {
+        ($($len:literal => $size:ty),*) => {
+            match key.len() {
+                $($len => {
+                    let mut out = data.to_vec();
+                    Rc4::<$size>::new(key.into()).apply_keystream(&mut out);
+                    out
+                })*
+                _ => Vec::new(),
+            }
+        };
+    }
+    apply!(5 => U5, 6 => U6, 7 => U7, 8 => U8, 9 => U9, 10 => U10, 11 => U11, 12 => U12, 13 => U13, 14 => U14,
+        15 => U15, 16 => U16, 32 => U32)
+}
+
+/// AES-CBC without padding, AES-128 or AES-256 by key length.
+fn cbc_decrypt(key: &[u8], iv: &[u8], data: &[u8]) -> Vec<u8> {
+    let mut buffer = data.to_vec();
+    let plain = match key.len() {
+        32 => cbc::Decryptor::<Aes256>::new_from_slices(key, iv).ok().map(|cipher| cipher.decrypt_padded_mut::<NoPadding>(&mut buffer)),
+        _ => cbc::Decryptor::<Aes128>::new_from_slices(key, iv).ok().map(|cipher| cipher.decrypt_padded_mut::<NoPadding>(&mut buffer)),
+    };
+    match plain {
+        Some(Ok(plain)) => plain.to_vec(),
+        _ => Vec::new(),
+    }
+}
+
+/// AES-128-CBC without padding, for the revision 6 hash.
+fn cbc_encrypt(key: &[u8], iv: &[u8], data: &[u8]) -> Vec<u8> {
+    let mut buffer = data.to_vec();
+    let length = buffer.len();
+    cbc::Encryptor::<Aes128>::new_from_slices(key, iv)
+        .ok()
+        .and_then(|cipher| cipher.encrypt_padded_mut::<NoPadding>(&mut buffer, length).ok())
+        .map(<[u8]>::to_vec)
+        .unwrap_or_default()
+}
+
+/// AES-CBC with the IV in the first block and PKCS#7 padding, as strings and
+/// streams store it.
+// `is_multiple_of` needs Rust 1.87
+#[allow(clippy::manual_is_multiple_of)]
+fn aes_cbc_decrypt(key: &[u8], data: &[u8]) -> Vec<u8> {
+    if data.len() < 32 || data.len() % 16 != 0 {
+        // An empty string is just the IV
+        return Vec::new();
+    }
+    let mut plain = cbc_decrypt(key, &data[..16], &data[16..]);
+    let padding = plain.last().copied().unwrap_or(0) as usize;
+    if (1..=16).contains(&padding) && padding <= plain.len() {
+        plain.truncate(plain.len() - padding);
+    }
+    plain
+}
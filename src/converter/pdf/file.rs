@@ -0,0 +1,468 @@
+//! Cross-reference tables, indirect objects and the page tree.
+
+use super::crypt::Decryptor;
+use super::filter;
+use super::object::{Dictionary, Object, ObjectId, Parser, Stream};
+use crate::error::{C2mdError, Result};
+use std::cell::RefCell;
+use std::collections::HashMap;
+
+#[derive(Debug, Clone, Copy)]
+enum XrefEntry {
+    Offset(usize),
+    /// Stored in an object stream at the given index.
+    Compressed(u32, usize),
+}
+
+pub struct PdfFile {
+    data: Vec<u8>,
+    xref: HashMap<u32, XrefEntry>,
+    pub trailer: Dictionary,
+    cache: RefCell<HashMap<u32, Object>>,
+    decryptor: Option<Decryptor>,
+    /// The `/Encrypt` dictionary, which is stored unencrypted.
+    encrypt_id: Option<ObjectId>,
+}
+
+/// A page with its inherited attributes resolved.
+#[derive(Debug, Clone)]
+pub struct Page {
+    /// The page object, which destinations refer to.
+    pub id: Option<ObjectId>,
+    pub dict: Dictionary,
+    pub resources: Dictionary,
+    /// `[x0, y0, x1, y1]` of the visible area.
+    pub media_box: [f64; 4],
+    pub rotate: i64,
+}
+
+impl Page {
+    /// Page space to display space: origin at the top left of the page as
+    /// shown, after `/Rotate`, with y growing downwards.
+    pub fn to_display(&self, x: f64, y: f64) -> (f64, f64) {
+        let [x0, y0, x1, y1] = self.media_box;
+        match self.rotate {
+            90 => (y - y0, x - x0),
+            180 => (x1 - x, y - y0),
+            270 => (y1 - y, x1 - x),
+            _ => (x - x0, y1 - y),
+        }
+    }
+
+    /// Width and height as displayed.
+    pub fn size(&self) -> (f64, f64) {
+        let [x0, y0, x1, y1] = self.media_box;
+        let (width, height) = ((x1 - x0).abs(), (y1 - y0).abs());
+        if self.rotate == 90 || self.rotate == 270 {
+            (height, width)
+        } else {
+            (width, height)
+        }
+    }
+}
+
+impl PdfFile {
+    /// Open a file, decrypting it with `password` when it is encrypted.
+    pub fn open(data: Vec<u8>, password: Option<&str>) -> Result<Self> {
+        let mut file = Self {
+            data,
+            xref: HashMap::new(),
+            trailer: Dictionary::new(),
+            cache: RefCell::new(HashMap::new()),
+            decryptor: None,
+            encrypt_id: None,
+        };
+        if file.read_xref_chain().is_err() || !file.trailer.contains_key("Root") {
+            // Damaged or missing cross-reference data: scan for objects instead
+            file.rebuild_xref()?;
+        }
+        if let Some(encrypt) = file.dict_entry(&file.trailer, "Encrypt") {
+            let id = match file.entry(&file.trailer, "ID") {
+                Object::Array(ids) => ids.first().and_then(Object::as_bytes).unwrap_or_default().to_vec(),
+                _ => Vec::new(),
+            };
+            file.encrypt_id = file.trailer.get("Encrypt").and_then(Object::as_reference);
+            file.decryptor = Some(Decryptor::new(&encrypt, &id, password)?);
+            // Objects read so far are still encrypted
+            file.cache.borrow_mut().clear();
+        }
+        Ok(file)
+    }
+
+    pub fn is_encrypted(&self) -> bool {
+        self.decryptor.is_some()
+    }
+
+    fn read_xref_chain(&mut self) -> Result<()> {
+        let start = find_startxref(&self.data).ok_or_else(|| C2mdError::Pdf("No startxref".to_string()))?;
+        let mut next = Some(start);
+        let mut seen = Vec::new();
+        while let Some(offset) = next {
+            if seen.contains(&offset) || offset >= self.data.len() {
+                break;
+            }
+            seen.push(offset);
+            let trailer = self.read_xref_section(offset)?;
+            next = trailer.get("Prev").and_then(Object::as_i64).map(|prev| prev as usize);
+            // Hybrid files keep extra entries in an xref stream
+            if let Some(stream) = trailer.get("XRefStm").and_then(Object::as_i64) {
+                let _ = self.read_xref_section(stream as usize);
+            }
+            for (key, value) in trailer {
+                self.trailer.entry(key).or_insert(value);
+            }
+        }
+        Ok(())
+    }
+
+    /// Read a classic table or an xref stream; earlier sections (read first)
+    /// take precedence over the ones they update.
+    fn read_xref_section(&mut self, offset: usize) -> Result<Dictionary> {
+        let mut parser = Parser::new(&self.data, offset);
+        if parser.keyword("xref") {
+            loop {
+                let saved = parser.pos;
+                let first = parser.word();
+                let count = parser.word();
+                let (Ok(first), Ok(count)) = (parse_usize(first), parse_usize(count)) else {
+                    parser.pos = saved;
+                    break;
+                };
+                // Each entry takes 20 bytes, so a damaged count cannot outrun the data
+                let count = count.min(self.data.len().saturating_sub(parser.pos) / 20);
+                for index in 0..count {
+                    let offset = parser.word();
+                    let _generation = parser.word();
+                    let kind = parser.word();
+                    if kind.is_empty() {
+                        break;
+                    }
+                    if kind == b"n" {
+                        if let (Ok(offset), Some(number)) = (parse_usize(offset), first.checked_add(index).and_then(|n| u32::try_from(n).ok())) {
+                            self.xref.entry(number).or_insert(XrefEntry::Offset(offset));
+                        }
+                    }
+                }
+            }
+            if !parser.keyword("trailer") {
+                return Err(C2mdError::Pdf("Missing trailer".to_string()));
+            }
+            return match parser.object()? {
+                Object::Dictionary(trailer) => Ok(trailer),
+                _ => Err(C2mdError::Pdf("Invalid trailer".to_string())),
+            };
+        }
+
+        let (_, object) = self.parse_indirect(offset)?;
+        let Object::Stream(stream) = object else {
+            return Err(C2mdError::Pdf("Invalid xref stream".to_string()));
+        };
+        let data = self.decode_stream(&stream)?;
+        let widths: Vec<usize> = stream
+            .dict
+            .get("W")
+            .and_then(Object::as_array)
+            .map(|w| w.iter().filter_map(Object::as_i64).map(|w| w.max(0) as usize).collect())
+            .unwrap_or_default();
+        if widths.len() < 3 {
+            return Err(C2mdError::Pdf("Invalid xref stream widths".to_string()));
+        }
+        let size = stream.dict.get("Size").and_then(Object::as_i64).unwrap_or(0);
+        let index: Vec<i64> = stream
+            .dict
+            .get("Index")
+            .and_then(Object::as_array)
+            .map(|index| index.iter().filter_map(Object::as_i64).collect())
+            .unwrap_or_else(|| vec![0, size]);
+
+        let entry_length: usize = widths.iter().sum();
+        let mut rows = data.chunks_exact(entry_length.max(1));
+        for range in index.chunks(2) {
+            let [first, count] = range else { break };
+            for number in *first..first.saturating_add(*count) {
+                let Some(row) = rows.next() else { break };
+                let field = |i: usize| {
+                    let start: usize = widths[..i].iter().sum();
+                    row[start..start + widths[i]].iter().fold(0usize, |acc, &b| (acc << 8) | b as usize)
+                };
+                let kind = if widths[0] == 0 { 1 } else { field(0) };
+                let entry = match kind {
+                    1 => XrefEntry::Offset(field(1)),
+                    2 => XrefEntry::Compressed(field(1) as u32, field(2)),
+                    _ => continue,
+                };
+                self.xref.entry(number as u32).or_insert(entry);
+            }
+        }
+        Ok(stream.dict)
+    }
+
+    fn rebuild_xref(&mut self) -> Result<()> {
+        let pattern = regex::bytes::Regex::new(r"(?m)(\d+)\s+(\d+)\s+obj\b")?;
+        let mut found = Vec::new();
+        for caps in pattern.captures_iter(&self.data) {
+            if let (Some(number), Some(whole)) = (caps.get(1), caps.get(0)) {
+                if let Ok(number) = parse_usize(number.as_bytes()) {
+                    found.push((number as u32, whole.start()));
+                }
+            }
+        }
+        // Later definitions are updates
+        for (number, offset) in found {
+            self.xref.insert(number, XrefEntry::Offset(offset));
+        }
+
+        let trailer_pattern = regex::bytes::Regex::new(r"trailer\s*<<")?;
+        let trailers: Vec<usize> = trailer_pattern.find_iter(&self.data).map(|m| m.start() + 7).collect();
+        for offset in trailers.into_iter().rev() {
+            if let Ok(Object::Dictionary(trailer)) = Parser::new(&self.data, offset).object() {
+                for (key, value) in trailer {
+                    self.trailer.entry(key).or_insert(value);
+                }
+            }
+        }
+        if !self.trailer.contains_key("Root") {
+            // Find the catalog by type
+            let numbers: Vec<u32> = self.xref.keys().copied().collect();
+            for number in numbers {
+                if let Ok(object) = self.get((number, 0)) {
+                    if object.as_dict().and_then(|d| d.get("Type")).and_then(Object::as_name) == Some("Catalog") {
+                        self.trailer.insert("Root".to_string(), Object::Reference((number, 0)));
+                        break;
+                    }
+                }
+            }
+        }
+        if self.trailer.contains_key("Root") {
+            Ok(())
+        } else {
+            Err(C2mdError::Pdf("No document catalog found".to_string()))
+        }
+    }
+
+    /// Parse `n g obj ... endobj` at `offset`.
+    fn parse_indirect(&self, offset: usize) -> Result<(ObjectId, Object)> {
+        let mut parser = Parser::new(&self.data, offset);
+        let number = parse_usize(parser.word()).map_err(|_| C2mdError::Pdf(format!("No object at offset {}", offset)))?;
+        let generation = parse_usize(parser.word()).unwrap_or(0);
+        if !parser.keyword("obj") {
+            return Err(C2mdError::Pdf(format!("No object at offset {}", offset)));
+        }
+        let object = parser.object()?;
+        let id = (number as u32, generation as u16);
+
+        let Object::Dictionary(dict) = object else {
+            return Ok((id, object));
+        };
+        if !parser.keyword("stream") {
+            return Ok((id, Object::Dictionary(dict)));
+        }
+        // The keyword is followed by CRLF or LF
+        let mut start = parser.pos;
+        if self.data.get(start) == Some(&b'\r') {
+            start += 1;
+        }
+        if self.data.get(start) == Some(&b'\n') {
+            start += 1;
+        }
+        let declared = match dict.get("Length") {
+            Some(Object::Reference(length_id)) if *length_id != id => self.get(*length_id).ok().and_then(|l| l.as_i64()),
+            Some(length) => length.as_i64(),
+            None => None,
+        };
+        let end = declared
+            .map(|length| start + length.max(0) as usize)
+            .filter(|&end| end <= self.data.len() && self.data[end..].trim_ascii_start().starts_with(b"endstream"))
+            .or_else(|| find(&self.data[start..], b"endstream").map(|at| start + at))
+            .unwrap_or(self.data.len());
+        let mut data = &self.data[start..end.max(start)];
+        if declared.is_none() {
+            data = data.trim_ascii_end();
+        }
+        Ok((id, Object::Stream(Stream { dict, data: data.to_vec() })))
+    }
+
+    /// An indirect object, or `Null` when it does not exist.
+    pub fn get(&self, id: ObjectId) -> Result<Object> {
+        if let Some(object) = self.cache.borrow().get(&id.0) {
+            return Ok(object.clone());
+        }
+        let object = match self.xref.get(&id.0) {
+            Some(XrefEntry::Offset(offset)) => {
+                let (id, mut object) = self.parse_indirect(*offset)?;
+                // Objects in object streams are decrypted with their stream
+                if let Some(decryptor) = self.decryptor.as_ref().filter(|_| self.encrypt_id != Some(id)) {
+                    decryptor.decrypt(id, &mut object);
+                }
+                object
+            }
+            Some(XrefEntry::Compressed(stream, index)) => self.read_compressed(*stream, *index)?,
+            None => Object::Null,
+        };
+        self.cache.borrow_mut().insert(id.0, object.clone());
+        Ok(object)
+    }
+
+    fn read_compressed(&self, stream_number: u32, index: usize) -> Result<Object> {
+        let Object::Stream(stream) = self.get((stream_number, 0))? else {
+            return Ok(Object::Null);
+        };
+        let data = self.decode_stream(&stream)?;
+        let count = stream.dict.get("N").and_then(Object::as_i64).unwrap_or(0).max(0) as usize;
+        let first = stream.dict.get("First").and_then(Object::as_i64).unwrap_or(0).max(0) as usize;
+        let mut header = Parser::new(&data, 0);
+        let mut offsets = Vec::with_capacity(count);
+        for _ in 0..count {
+            let _number = header.word();
+            offsets.push(parse_usize(header.word()).unwrap_or(0));
+        }
+        match offsets.get(index) {
+            Some(offset) => Parser::new(&data, first + offset).object(),
+            None => Ok(Object::Null),
+        }
+    }
+
+    /// Follow references until a direct object.
+    pub fn resolve(&self, object: &Object) -> Object {
+        let mut object = object.clone();
+        for _ in 0..32 {
+            match object {
+                Object::Reference(id) => object = self.get(id).unwrap_or(Object::Null),
+                _ => break,
+            }
+        }
+        object
+    }
+
+    /// A dictionary entry, resolved.
+    pub fn entry(&self, dict: &Dictionary, key: &str) -> Object {
+        dict.get(key).map_or(Object::Null, |value| self.resolve(value))
+    }
+
+    pub fn dict_entry(&self, dict: &Dictionary, key: &str) -> Option<Dictionary> {
+        self.entry(dict, key).as_dict().cloned()
+    }
+
+    pub fn catalog(&self) -> Dictionary {
+        self.dict_entry(&self.trailer, "Root").unwrap_or_default()
+    }
+
+    /// Decoded stream data, leaving image codecs encoded.
+    pub fn decode_stream(&self, stream: &Stream) -> Result<Vec<u8>> {
+        Ok(self.decode_stream_with_codec(stream)?.0)
+    }
+
+    pub fn decode_stream_with_codec(&self, stream: &Stream) -> Result<(Vec<u8>, Option<String>)> {
+        let filters = match self.entry(&stream.dict, "Filter") {
+            Object::Name(name) => vec![name],
+            Object::Array(names) => names.iter().filter_map(|n| self.resolve(n).as_name().map(str::to_string)).collect(),
+            _ => Vec::new(),
+        };
+        let params: Vec<Option<Dictionary>> = match self.entry(&stream.dict, "DecodeParms") {
+            Object::Array(params) => params.iter().map(|p| self.resolve(p).as_dict().cloned()).collect(),
+            Object::Dictionary(params) => vec![Some(params)],
+            _ => Vec::new(),
+        };
+        let filters: Vec<(String, Option<Dictionary>)> = filters
+            .into_iter()
+            .enumerate()
+            .map(|(i, filter)| (filter, params.get(i).cloned().flatten()))
+            .collect();
+        filter::decode(&stream.data, &filters)
+    }
+
+    /// Pages in order, with inherited resources and boxes.
+    pub fn pages(&self) -> Vec<Page> {
+        let mut pages = Vec::new();
+        let root = self.catalog().get("Pages").cloned();
+        if let Some(root) = root {
+            let mut visited = Vec::new();
+            self.collect_pages(&root, &Dictionary::new(), &mut pages, &mut visited);
+        }
+        pages
+    }
+
+    fn collect_pages(&self, node: &Object, inherited: &Dictionary, pages: &mut Vec<Page>, visited: &mut Vec<ObjectId>) {
+        let id = node.as_reference();
+        if let Some(id) = id {
+            if visited.contains(&id) {
+                return;
+            }
+            visited.push(id);
+        }
+        let Some(dict) = self.resolve(node).as_dict().cloned() else {
+            return;
+        };
+        let mut attributes = inherited.clone();
+        for key in ["Resources", "MediaBox", "CropBox", "Rotate"] {
+            if let Some(value) = dict.get(key) {
+                attributes.insert(key.to_string(), value.clone());
+            }
+        }
+
+        match self.entry(&dict, "Kids") {
+            Object::Array(kids) if dict.get("Type").and_then(Object::as_name) != Some("Page") => {
+                for kid in &kids {
+                    self.collect_pages(kid, &attributes, pages, visited);
+                }
+            }
+            _ => {
+                let rect = |key: &str| -> Option<[f64; 4]> {
+                    let values: Vec<f64> = self.entry(&attributes, key).as_array()?.iter().filter_map(|v| self.resolve(v).as_f64()).collect();
+                    (values.len() == 4).then(|| [values[0].min(values[2]), values[1].min(values[3]), values[0].max(values[2]), values[1].max(values[3])])
+                };
+                pages.push(Page {
+                    id,
+                    resources: self.dict_entry(&attributes, "Resources").unwrap_or_default(),
+                    media_box: rect("CropBox").or_else(|| rect("MediaBox")).unwrap_or([0.0, 0.0, 612.0, 792.0]),
+                    rotate: self.entry(&attributes, "Rotate").as_i64().unwrap_or(0).rem_euclid(360),
+                    dict,
+                });
+            }
+        }
+    }
+
+    /// Concatenated, decoded content streams of a page.
+    pub fn page_content(&self, page: &Page) -> Vec<u8> {
+        let streams = match page.dict.get("Contents") {
+            Some(Object::Array(items)) => items.clone(),
+            Some(contents) => match self.resolve(contents) {
+                Object::Array(items) => items,
+                _ => vec![contents.clone()],
+            },
+            None => Vec::new(),
+        };
+        let mut content = Vec::new();
+        for stream in streams {
+            if let Object::Stream(stream) = self.resolve(&stream) {
+                match self.decode_stream(&stream) {
+                    Ok(data) => {
+                        content.extend_from_slice(&data);
+                        content.push(b'\n');
+                    }
+                    Err(e) => tracing::warn!("Skipping page content stream: {}", e),
+                }
+            }
+        }
+        content
+    }
+}
+
+fn find_startxref(data: &[u8]) -> Option<usize> {
+    let tail_start = data.len().saturating_sub(2048);
+    let at = rfind(&data[tail_start..], b"startxref")? + tail_start;
+    let mut parser = Parser::new(data, at + "startxref".len());
+    parse_usize(parser.word()).ok()
+}
+
+fn parse_usize(word: &[u8]) -> std::result::Result<usize, ()> {
+    std::str::from_utf8(word).ok().and_then(|w| w.parse().ok()).ok_or(())
+}
+
+pub fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack.windows(needle.len()).position(|window| window == needle)
+}
+
+fn rfind(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack.windows(needle.len()).rposition(|window| window == needle)
+}
@@ -0,0 +1,245 @@
+//! Stream filters. Image codecs (DCT, JPX, CCITT, JBIG2) are left encoded for
+//! the caller to pass through.
+
+use super::object::{Dictionary, Object};
+use crate::error::{C2mdError, Result};
+use std::io::Read;
+
+const IMAGE_FILTERS: &[&str] = &["DCTDecode", "JPXDecode", "CCITTFaxDecode", "JBIG2Decode", "DCT", "CCF"];
+
+/// Apply `filters` in order, stopping before the first image codec, which is
+/// returned alongside the data.
+pub fn decode(data: &[u8], filters: &[(String, Option<Dictionary>)]) -> Result<(Vec<u8>, Option<String>)> {
+    let mut data = data.to_vec();
+    for (filter, params) in filters {
+        if IMAGE_FILTERS.contains(&filter.as_str()) {
+            return Ok((data, Some(filter.clone())));
+        }
+        data = match filter.as_str() {
+            "FlateDecode" | "Fl" => predict(inflate(&data)?, params.as_ref())?,
+            "LZWDecode" | "LZW" => {
+                let early_change = params
+                    .as_ref()
+                    .and_then(|p| p.get("EarlyChange"))
+                    .and_then(Object::as_i64)
+                    .unwrap_or(1);
+                predict(lzw(&data, early_change != 0), params.as_ref())?
+            }
+            "ASCIIHexDecode" | "AHx" => ascii_hex(&data),
+            "ASCII85Decode" | "A85" => ascii85(&data),
+            "RunLengthDecode" | "RL" => run_length(&data),
+            "Crypt" => data,
+            other => return Err(C2mdError::Pdf(format!("Unsupported filter {}", other))),
+        };
+    }
+    Ok((data, None))
+}
+
+fn inflate(data: &[u8]) -> Result<Vec<u8>> {
+    let mut out = Vec::new();
+    let zlib = flate2::read::ZlibDecoder::new(data).read_to_end(&mut out);
+    if zlib.is_ok() || !out.is_empty() {
+        // Truncated streams are common; keep what was decoded
+        return Ok(out);
+    }
+    out.clear();
+    match flate2::read::DeflateDecoder::new(data).read_to_end(&mut out) {
+        Ok(_) => Ok(out),
+        Err(_) if !out.is_empty() => Ok(out),
+        Err(e) => Err(C2mdError::Pdf(format!("Corrupt Flate stream: {}", e))),
+    }
+}
+
+/// Undo PNG (10-15) and TIFF (2) predictors.
+fn predict(data: Vec<u8>, params: Option<&Dictionary>) -> Result<Vec<u8>> {
+    let Some(params) = params else {
+        return Ok(data);
+    };
+    let number = |key: &str, default: i64| params.get(key).and_then(Object::as_i64).unwrap_or(default).max(0) as usize;
+    let predictor = number("Predictor", 1);
+    if predictor < 2 {
+        return Ok(data);
+    }
+    let colors = number("Colors", 1).max(1);
+    let bits = number("BitsPerComponent", 8).max(1);
+    let columns = number("Columns", 1).max(1);
+    let bytes_per_pixel = (colors * bits).div_ceil(8).max(1);
+    let row_length = (colors * bits * columns).div_ceil(8);
+
+    if predictor == 2 {
+        let mut out = data;
+        if bits == 8 {
+            for row in out.chunks_mut(row_length) {
+                for i in bytes_per_pixel..row.len() {
+                    row[i] = row[i].wrapping_add(row[i - bytes_per_pixel]);
+                }
+            }
+        }
+        return Ok(out);
+    }
+
+    let mut out = Vec::with_capacity(data.len());
+    let mut previous = vec![0u8; row_length];
+    for chunk in data.chunks(row_length + 1) {
+        let (kind, row) = match chunk.split_first() {
+            Some((kind, row)) => (*kind, row),
+            None => break,
+        };
+        let mut current = row.to_vec();
+        current.resize(row_length, 0);
+        for i in 0..row_length {
+            let left = if i >= bytes_per_pixel { current[i - bytes_per_pixel] } else { 0 };
+            let up = previous[i];
+            let up_left = if i >= bytes_per_pixel { previous[i - bytes_per_pixel] } else { 0 };
+            current[i] = match kind {
+                1 => current[i].wrapping_add(left),
+                2 => current[i].wrapping_add(up),
+                3 => current[i].wrapping_add(((left as u16 + up as u16) / 2) as u8),
+                4 => current[i].wrapping_add(paeth(left, up, up_left)),
+                _ => current[i],
+            };
+        }
+        out.extend_from_slice(&current[..row.len().min(row_length)]);
+        previous = current;
+    }
+    Ok(out)
+}
+
+fn paeth(left: u8, up: u8, up_left: u8) -> u8 {
+    let estimate = left as i16 + up as i16 - up_left as i16;
+    let distance = |value: u8| (estimate - value as i16).abs();
+    if distance(left) <= distance(up) && distance(left) <= distance(up_left) {
+        left
+    } else if distance(up) <= distance(up_left) {
+        up
+    } else {
+        up_left
+    }
+}
+
+fn lzw(data: &[u8], early_change: bool) -> Vec<u8> {
+    let mut out = Vec::new();
+    let mut table: Vec<Vec<u8>> = Vec::new();
+    let reset = |table: &mut Vec<Vec<u8>>| {
+        table.clear();
+        table.extend((0..=255u16).map(|b| vec![b as u8]));
+        // 256 clears the table, 257 ends the data
+        table.push(Vec::new());
+        table.push(Vec::new());
+    };
+    reset(&mut table);
+
+    let mut code_length = 9;
+    let mut buffer: u32 = 0;
+    let mut bits = 0;
+    let mut previous: Option<Vec<u8>> = None;
+    for &byte in data {
+        buffer = (buffer << 8) | byte as u32;
+        bits += 8;
+        while bits >= code_length {
+            let code = ((buffer >> (bits - code_length)) & ((1 << code_length) - 1)) as usize;
+            bits -= code_length;
+            match code {
+                256 => {
+                    reset(&mut table);
+                    code_length = 9;
+                    previous = None;
+                    continue;
+                }
+                257 => return out,
+                _ => {}
+            }
+            let entry = match (table.get(code), &previous) {
+                (Some(entry), _) => entry.clone(),
+                (None, Some(previous)) => {
+                    let mut entry = previous.clone();
+                    entry.push(previous[0]);
+                    entry
+                }
+                (None, None) => return out,
+            };
+            out.extend_from_slice(&entry);
+            if let Some(mut previous) = previous.take() {
+                previous.push(entry[0]);
+                table.push(previous);
+            }
+            previous = Some(entry);
+            let limit = if early_change { table.len() + 1 } else { table.len() };
+            code_length = match limit {
+                0..=511 => 9,
+                512..=1023 => 10,
+                1024..=2047 => 11,
+                _ => 12,
+            };
+        }
+    }
+    out
+}
+
+fn ascii_hex(data: &[u8]) -> Vec<u8> {
+    let mut digits: Vec<u8> = data
+        .iter()
+        .take_while(|&&b| b != b'>')
+        .filter(|b| b.is_ascii_hexdigit())
+        .copied()
+        .collect();
+    if digits.len() % 2 == 1 {
+        digits.push(b'0');
+    }
+    digits
+        .chunks(2)
+        .filter_map(|pair| u8::from_str_radix(std::str::from_utf8(pair).ok()?, 16).ok())
+        .collect()
+}
+
+fn ascii85(data: &[u8]) -> Vec<u8> {
+    let mut out = Vec::new();
+    let mut group = Vec::with_capacity(5);
+    let data = data.strip_prefix(b"<~").unwrap_or(data);
+    for &byte in data {
+        match byte {
+            b'~' => break,
+            b'z' if group.is_empty() => out.extend_from_slice(&[0; 4]),
+            b'!'..=b'u' => {
+                group.push(byte - b'!');
+                if group.len() == 5 {
+                    let value = group.iter().fold(0u64, |acc, &digit| acc * 85 + digit as u64);
+                    out.extend_from_slice(&(value as u32).to_be_bytes());
+                    group.clear();
+                }
+            }
+            _ => {}
+        }
+    }
+    if !group.is_empty() {
+        let used = group.len();
+        group.resize(5, 84);
+        let value = group.iter().fold(0u64, |acc, &digit| acc * 85 + digit as u64);
+        out.extend_from_slice(&(value as u32).to_be_bytes()[..used - 1]);
+    }
+    out
+}
+
+fn run_length(data: &[u8]) -> Vec<u8> {
+    let mut out = Vec::new();
+    let mut pos = 0;
+    while pos < data.len() {
+        let length = data[pos] as usize;
+        pos += 1;
+        match length {
+            128 => break,
+            0..=127 => {
+                let end = (pos + length + 1).min(data.len());
+                out.extend_from_slice(&data[pos..end]);
+                pos = end;
+            }
+            _ => {
+                if let Some(&byte) = data.get(pos) {
+                    out.extend(std::iter::repeat_n(byte, 257 - length));
+                }
+                pos += 1;
+            }
+        }
+    }
+    out
+}
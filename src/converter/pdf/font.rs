@@ -0,0 +1,620 @@
+//! Fonts: turning string bytes into Unicode text and glyph advances.
+
+use super::file::PdfFile;
+use super::object::{Dictionary, Object, Parser};
+use std::collections::HashMap;
+
+/// One decoded character code of a shown string.
+pub struct Glyph {
+    pub text: String,
+    /// Advance in text space units per unit of font size.
+    pub width: f64,
+    /// Single-byte code 32, which gets word spacing.
+    pub is_space: bool,
+}
+
+pub struct Font {
+    pub bold: bool,
+    pub italic: bool,
+    pub monospace: bool,
+    /// Two-byte (or CMap-defined) codes, as in Type0 fonts.
+    composite: bool,
+    to_unicode: Option<CMap>,
+    /// Text for single-byte codes from the font's encoding.
+    encoding: Vec<Option<String>>,
+    /// Codes are UCS-2, as with the predefined `Uni*-UCS2-*` CMaps.
+    unicode_codes: bool,
+    widths: HashMap<u32, f64>,
+    default_width: f64,
+    /// Glyph space to text space, 1/1000 except for Type3 fonts.
+    scale: f64,
+}
+
+impl Font {
+    pub fn load(file: &PdfFile, dict: &Dictionary) -> Self {
+        let subtype = file.entry(dict, "Subtype").as_name().unwrap_or_default().to_string();
+        let base_font = file.entry(dict, "BaseFont").as_name().unwrap_or_default().to_string();
+        // Subset fonts are named like `ABCDEF+Helvetica-Bold`
+        let name = base_font.split_once('+').map_or(base_font.as_str(), |(_, name)| name).to_string();
+        let lower = name.to_lowercase();
+
+        let composite = subtype == "Type0";
+        let descendant = if composite {
+            match file.entry(dict, "DescendantFonts") {
+                Object::Array(fonts) => fonts.first().and_then(|font| file.resolve(font).as_dict().cloned()),
+                _ => None,
+            }
+        } else {
+            None
+        };
+        let descriptor = file
+            .dict_entry(descendant.as_ref().unwrap_or(dict), "FontDescriptor")
+            .unwrap_or_default();
+        let flags = file.entry(&descriptor, "Flags").as_i64().unwrap_or(0);
+        let weight = file.entry(&descriptor, "FontWeight").as_f64().unwrap_or(0.0);
+        let italic_angle = file.entry(&descriptor, "ItalicAngle").as_f64().unwrap_or(0.0);
+
+        let bold = ["bold", "black", "heavy", "semibold", "demi"].iter().any(|w| lower.contains(w))
+            || flags & (1 << 18) != 0
+            || weight >= 600.0;
+        let italic = lower.contains("italic") || lower.contains("oblique") || flags & (1 << 6) != 0 || italic_angle != 0.0;
+        let monospace = flags & 1 != 0 || ["courier", "mono", "consol", "cmtt", "sftt", "typewriter"].iter().any(|w| lower.contains(w));
+
+        let to_unicode = match file.entry(dict, "ToUnicode") {
+            Object::Stream(stream) => file.decode_stream(&stream).ok().map(|data| CMap::parse(&data)),
+            _ => None,
+        };
+
+        let mut font = Self {
+            bold,
+            italic,
+            monospace,
+            composite,
+            to_unicode,
+            encoding: Vec::new(),
+            unicode_codes: false,
+            widths: HashMap::new(),
+            default_width: 0.5,
+            scale: 0.001,
+        };
+
+        if composite {
+            let encoding = file.entry(dict, "Encoding");
+            let encoding_name = encoding.as_name().unwrap_or_default();
+            font.unicode_codes = encoding_name.contains("UCS2") || encoding_name.contains("UTF16");
+            if let Object::Stream(stream) = &encoding {
+                // An embedded CMap only tells us how codes are split
+                if let Ok(data) = file.decode_stream(stream) {
+                    let cmap = CMap::parse(&data);
+                    if font.to_unicode.is_none() {
+                        font.to_unicode = Some(CMap { mappings: HashMap::new(), ..cmap });
+                    }
+                }
+            }
+            if let Some(descendant) = &descendant {
+                font.default_width = file.entry(descendant, "DW").as_f64().unwrap_or(1000.0) * font.scale;
+                if let Object::Array(w) = file.entry(descendant, "W") {
+                    font.read_cid_widths(file, &w);
+                }
+            }
+        } else {
+            if subtype == "Type3" {
+                if let Object::Array(matrix) = file.entry(dict, "FontMatrix") {
+                    font.scale = matrix.first().and_then(|v| file.resolve(v).as_f64()).unwrap_or(0.001);
+                }
+            }
+            let symbolic = lower.contains("symbol") || lower.contains("dingbat");
+            font.encoding = simple_encoding(file, dict, symbolic);
+            let first = file.entry(dict, "FirstChar").as_i64().unwrap_or(0).max(0) as u32;
+            match file.entry(dict, "Widths") {
+                Object::Array(widths) => {
+                    for (i, width) in widths.iter().enumerate() {
+                        if let Some(width) = file.resolve(width).as_f64() {
+                            font.widths.insert(first + i as u32, width * font.scale);
+                        }
+                    }
+                    font.default_width = file.entry(&descriptor, "MissingWidth").as_f64().unwrap_or(0.0) * font.scale;
+                    if font.default_width == 0.0 {
+                        font.default_width = 0.5;
+                    }
+                }
+                _ => {
+                    // Standard 14 fonts may come without widths
+                    for code in 32u32..127 {
+                        font.widths.insert(code, standard_width(&lower, code as u8));
+                    }
+                    font.default_width = if monospace { 0.6 } else { 0.5 };
+                }
+            }
+        }
+        font
+    }
+
+    fn read_cid_widths(&mut self, file: &PdfFile, w: &[Object]) {
+        let values: Vec<Object> = w.iter().map(|v| file.resolve(v)).collect();
+        let mut i = 0;
+        while i + 1 < values.len() {
+            let Some(first) = values[i].as_i64() else {
+                i += 1;
+                continue;
+            };
+            match &values[i + 1] {
+                Object::Array(widths) => {
+                    for (offset, width) in widths.iter().enumerate() {
+                        if let Some(width) = file.resolve(width).as_f64() {
+                            self.widths.insert(first as u32 + offset as u32, width * self.scale);
+                        }
+                    }
+                    i += 2;
+                }
+                last => {
+                    let (Some(last), Some(width)) = (last.as_i64(), values.get(i + 2).and_then(Object::as_f64)) else {
+                        break;
+                    };
+                    for code in first..=last.min(first + 65535) {
+                        self.widths.insert(code as u32, width * self.scale);
+                    }
+                    i += 3;
+                }
+            }
+        }
+    }
+
+    /// Split a shown string into character codes.
+    pub fn decode(&self, bytes: &[u8]) -> Vec<Glyph> {
+        let mut glyphs = Vec::new();
+        let mut pos = 0;
+        while pos < bytes.len() {
+            let length = self
+                .to_unicode
+                .as_ref()
+                .and_then(|cmap| cmap.code_length(&bytes[pos..]))
+                .unwrap_or(if self.composite { 2 } else { 1 })
+                .min(bytes.len() - pos);
+            let code = bytes[pos..pos + length].iter().fold(0u32, |acc, &b| (acc << 8) | b as u32);
+            pos += length;
+
+            let text = self
+                .to_unicode
+                .as_ref()
+                .and_then(|cmap| cmap.mappings.get(&code).cloned())
+                .or_else(|| {
+                    if self.composite {
+                        self.unicode_codes.then(|| char::from_u32(code).map(String::from)).flatten()
+                    } else {
+                        self.encoding.get(code as usize).cloned().flatten()
+                    }
+                })
+                .unwrap_or_default();
+            glyphs.push(Glyph {
+                text,
+                width: self.widths.get(&code).copied().unwrap_or(self.default_width),
+                is_space: length == 1 && code == 32,
+            });
+        }
+        glyphs
+    }
+}
+
+/// A ToUnicode (or encoding) CMap.
+pub struct CMap {
+    /// Code lengths in bytes with their ranges.
+    codespace: Vec<(usize, u32, u32)>,
+    mappings: HashMap<u32, String>,
+}
+
+impl CMap {
+    pub fn parse(data: &[u8]) -> Self {
+        let mut cmap = Self { codespace: Vec::new(), mappings: HashMap::new() };
+        let mut parser = Parser::new(data, 0);
+        let mut operands: Vec<Object> = Vec::new();
+        while !parser.at_end() {
+            let Ok(object) = parser.object() else {
+                break;
+            };
+            let Object::Operator(operator) = object else {
+                operands.push(object);
+                continue;
+            };
+            match operator.as_str() {
+                "endcodespacerange" => {
+                    for pair in operands.chunks(2) {
+                        if let [Object::String(low), Object::String(high)] = pair {
+                            cmap.codespace.push((low.len(), code_of(low), code_of(high)));
+                        }
+                    }
+                }
+                "endbfchar" => {
+                    for pair in operands.chunks(2) {
+                        if let [Object::String(source), target] = pair {
+                            if let Some(text) = target_text(target) {
+                                cmap.mappings.insert(code_of(source), text);
+                            }
+                        }
+                    }
+                }
+                "endbfrange" => {
+                    for triple in operands.chunks(3) {
+                        let [Object::String(low), Object::String(high), target] = triple else {
+                            continue;
+                        };
+                        let (low, high) = (code_of(low), code_of(high));
+                        // Guard against absurd ranges in broken files
+                        if high < low || high - low > 65535 {
+                            continue;
+                        }
+                        match target {
+                            Object::Array(targets) => {
+                                for (offset, target) in targets.iter().enumerate() {
+                                    if let Some(text) = target_text(target) {
+                                        cmap.mappings.insert(low + offset as u32, text);
+                                    }
+                                }
+                            }
+                            Object::String(start) => {
+                                let units = utf16_units(start);
+                                for offset in 0..=(high - low) {
+                                    let mut units = units.clone();
+                                    if let Some(last) = units.last_mut() {
+                                        *last = last.wrapping_add(offset as u16);
+                                    }
+                                    cmap.mappings.insert(low + offset, String::from_utf16_lossy(&units));
+                                }
+                            }
+                            _ => {}
+                        }
+                    }
+                }
+                _ => {}
+            }
+            operands.clear();
+        }
+        cmap.codespace.sort_by_key(|(length, _, _)| *length);
+        cmap
+    }
+
+    /// Length of the code at the start of `bytes`, if a codespace range matches.
+    fn code_length(&self, bytes: &[u8]) -> Option<usize> {
+        self.codespace.iter().find_map(|&(length, low, high)| {
+            let code = code_of(bytes.get(..length)?);
+            (low..=high).contains(&code).then_some(length)
+        })
+    }
+}
+
+fn code_of(bytes: &[u8]) -> u32 {
+    bytes.iter().take(4).fold(0u32, |acc, &b| (acc << 8) | b as u32)
+}
+
+fn utf16_units(bytes: &[u8]) -> Vec<u16> {
+    bytes.chunks(2).map(|pair| ((pair[0] as u16) << 8) | pair.get(1).copied().unwrap_or(0) as u16).collect()
+}
+
+fn target_text(target: &Object) -> Option<String> {
+    match target {
+        Object::String(bytes) => Some(String::from_utf16_lossy(&utf16_units(bytes))),
+        Object::Name(name) => glyph_text(name),
+        _ => None,
+    }
+}
+
+/// Text for each single-byte code: a base encoding plus `/Differences`.
+fn simple_encoding(file: &PdfFile, dict: &Dictionary, symbolic: bool) -> Vec<Option<String>> {
+    let encoding = file.entry(dict, "Encoding");
+    let (base, differences) = match &encoding {
+        Object::Name(name) => (Some(name.clone()), None),
+        Object::Dictionary(encoding) => (
+            file.entry(encoding, "BaseEncoding").as_name().map(str::to_string),
+            match file.entry(encoding, "Differences") {
+                Object::Array(differences) => Some(differences),
+                _ => None,
+            },
+        ),
+        _ => (None, None),
+    };
+
+    let mut table: Vec<Option<String>> = (0..=255u8)
+        .map(|code| match base.as_deref() {
+            Some("WinAnsiEncoding") => win_ansi(code),
+            Some("MacRomanEncoding") => single_byte(encoding_rs::MACINTOSH, code),
+            _ if symbolic => symbol(code),
+            _ => standard(code),
+        })
+        .collect();
+
+    if let Some(differences) = differences {
+        let mut code = 0usize;
+        for item in differences {
+            match file.resolve(&item) {
+                Object::Integer(start) => code = start.max(0) as usize,
+                Object::Name(name) => {
+                    if code < 256 {
+                        // Unknown names such as `g12` keep the base encoding's text
+                        if let Some(text) = glyph_text(&name) {
+                            table[code] = Some(text);
+                        }
+                    }
+                    code += 1;
+                }
+                _ => {}
+            }
+        }
+    }
+    table
+}
+
+fn single_byte(encoding: &'static encoding_rs::Encoding, code: u8) -> Option<String> {
+    if code < 32 {
+        return None;
+    }
+    let bytes = [code];
+    let (text, _, errors) = encoding.decode(&bytes);
+    (!errors).then(|| text.to_string())
+}
+
+fn win_ansi(code: u8) -> Option<String> {
+    match code {
+        // Bullet for codes WinAnsi leaves undefined, as Acrobat does
+        0x7F | 0x81 | 0x8D | 0x8F | 0x90 | 0x9D => Some("\u{2022}".to_string()),
+        _ => single_byte(encoding_rs::WINDOWS_1252, code),
+    }
+}
+
+/// Adobe StandardEncoding, the default for Type1 fonts.
+fn standard(code: u8) -> Option<String> {
+    let c = match code {
+        0x27 => '\u{2019}',
+        0x60 => '\u{2018}',
+        0x20..=0x7E => code as char,
+        0xA1 => '¡',
+        0xA2 => '¢',
+        0xA3 => '£',
+        0xA4 => '\u{2044}',
+        0xA5 => '¥',
+        0xA6 => 'ƒ',
+        0xA7 => '§',
+        0xA8 => '¤',
+        0xA9 => '\'',
+        0xAA => '\u{201C}',
+        0xAB => '«',
+        0xAC => '\u{2039}',
+        0xAD => '\u{203A}',
+        0xAE => '\u{FB01}',
+        0xAF => '\u{FB02}',
+        0xB1 => '\u{2013}',
+        0xB2 => '\u{2020}',
+        0xB3 => '\u{2021}',
+        0xB4 => '·',
+        0xB6 => '¶',
+        0xB7 => '\u{2022}',
+        0xB8 => '\u{201A}',
+        0xB9 => '\u{201E}',
+        0xBA => '\u{201D}',
+        0xBB => '»',
+        0xBC => '\u{2026}',
+        0xBD => '\u{2030}',
+        0xBF => '¿',
+        0xC1 => '`',
+        0xC2 => '´',
+        0xC3 => 'ˆ',
+        0xC4 => '˜',
+        0xC5 => '¯',
+        0xC6 => '˘',
+        0xC7 => '˙',
+        0xC8 => '¨',
+        0xCA => '˚',
+        0xCB => '¸',
+        0xCD => '˝',
+        0xCE => '˛',
+        0xCF => 'ˇ',
+        0xD0 => '\u{2014}',
+        0xE1 => 'Æ',
+        0xE3 => 'ª',
+        0xE8 => 'Ł',
+        0xE9 => 'Ø',
+        0xEA => 'Œ',
+        0xEB => 'º',
+        0xF1 => 'æ',
+        0xF5 => 'ı',
+        0xF8 => 'ł',
+        0xF9 => 'ø',
+        0xFA => 'œ',
+        0xFB => 'ß',
+        _ => return None,
+    };
+    Some(c.to_string())
+}
+
+/// The parts of the Symbol font encoding that show up as list bullets and dashes.
+fn symbol(code: u8) -> Option<String> {
+    let c = match code {
+        0xB7 | 0x6C | 0xA8 | 0x6E | 0x71 | 0x75 | 0xD8 => '\u{2022}',
+        0xBE => '\u{2014}',
+        0x2D => '\u{2212}',
+        0x20..=0x7E => code as char,
+        _ => return None,
+    };
+    Some(c.to_string())
+}
+
+/// Unicode text for a glyph name: the Adobe Glyph List subset that matters for
+/// Latin text, plus `uniXXXX`, `uXXXX[XX]` and single-character names.
+pub fn glyph_text(name: &str) -> Option<String> {
+    let name = name.split('.').next().unwrap_or(name);
+    if let Some(hex) = name.strip_prefix("uni") {
+        if hex.len() >= 4 && hex.len() % 4 == 0 {
+            let units: Option<Vec<u16>> = (0..hex.len()).step_by(4).map(|i| u16::from_str_radix(&hex[i..i + 4], 16).ok()).collect();
+            if let Some(units) = units {
+                return Some(String::from_utf16_lossy(&units));
+            }
+        }
+    }
+    if let Some(hex) = name.strip_prefix('u') {
+        if (4..=6).contains(&hex.len()) {
+            if let Some(c) = u32::from_str_radix(hex, 16).ok().and_then(char::from_u32) {
+                return Some(c.to_string());
+            }
+        }
+    }
+    let mut chars = name.chars();
+    if let (Some(c), None) = (chars.next(), chars.next()) {
+        if c.is_ascii_alphabetic() {
+            return Some(c.to_string());
+        }
+    }
+
+    let c = match name {
+        "space" | "nbspace" | "nonbreakingspace" => ' ',
+        "exclam" => '!',
+        "quotedbl" => '"',
+        "numbersign" => '#',
+        "dollar" => '$',
+        "percent" => '%',
+        "ampersand" => '&',
+        "quotesingle" => '\'',
+        "parenleft" => '(',
+        "parenright" => ')',
+        "asterisk" => '*',
+        "plus" => '+',
+        "comma" => ',',
+        "hyphen" | "sfthyphen" => '-',
+        "period" => '.',
+        "slash" => '/',
+        "zero" => '0',
+        "one" => '1',
+        "two" => '2',
+        "three" => '3',
+        "four" => '4',
+        "five" => '5',
+        "six" => '6',
+        "seven" => '7',
+        "eight" => '8',
+        "nine" => '9',
+        "colon" => ':',
+        "semicolon" => ';',
+        "less" => '<',
+        "equal" => '=',
+        "greater" => '>',
+        "question" => '?',
+        "at" => '@',
+        "bracketleft" => '[',
+        "backslash" => '\\',
+        "bracketright" => ']',
+        "asciicircum" => '^',
+        "underscore" => '_',
+        "grave" => '`',
+        "braceleft" => '{',
+        "bar" => '|',
+        "braceright" => '}',
+        "asciitilde" => '~',
+        "quoteleft" => '\u{2018}',
+        "quoteright" => '\u{2019}',
+        "quotedblleft" => '\u{201C}',
+        "quotedblright" => '\u{201D}',
+        "quotesinglbase" => '\u{201A}',
+        "quotedblbase" => '\u{201E}',
+        "endash" => '\u{2013}',
+        "emdash" => '\u{2014}',
+        "bullet" => '\u{2022}',
+        "ellipsis" => '\u{2026}',
+        "dagger" => '\u{2020}',
+        "daggerdbl" => '\u{2021}',
+        "perthousand" => '\u{2030}',
+        "guillemotleft" => '«',
+        "guillemotright" => '»',
+        "guilsinglleft" => '\u{2039}',
+        "guilsinglright" => '\u{203A}',
+        "fi" => '\u{FB01}',
+        "fl" => '\u{FB02}',
+        "ff" => '\u{FB00}',
+        "ffi" => '\u{FB03}',
+        "ffl" => '\u{FB04}',
+        "minus" => '\u{2212}',
+        "multiply" => '×',
+        "divide" => '÷',
+        "degree" => '°',
+        "copyright" => '©',
+        "registered" => '®',
+        "trademark" => '\u{2122}',
+        "section" => '§',
+        "paragraph" => '¶',
+        "periodcentered" | "middot" => '·',
+        "cent" => '¢',
+        "sterling" => '£',
+        "yen" => '¥',
+        "Euro" | "euro" => '€',
+        "currency" => '¤',
+        "florin" => 'ƒ',
+        "exclamdown" => '¡',
+        "questiondown" => '¿',
+        "ae" => 'æ',
+        "AE" => 'Æ',
+        "oe" => 'œ',
+        "OE" => 'Œ',
+        "oslash" => 'ø',
+        "Oslash" => 'Ø',
+        "germandbls" => 'ß',
+        "eth" => 'ð',
+        "Eth" => 'Ð',
+        "thorn" => 'þ',
+        "Thorn" => 'Þ',
+        "dotlessi" => 'ı',
+        "lslash" => 'ł',
+        "Lslash" => 'Ł',
+        "ordfeminine" => 'ª',
+        "ordmasculine" => 'º',
+        "plusminus" => '±',
+        "mu" => 'µ',
+        "onehalf" => '½',
+        "onequarter" => '¼',
+        "threequarters" => '¾',
+        "arrowright" => '\u{2192}',
+        "arrowleft" => '\u{2190}',
+        "checkmark" => '\u{2713}',
+        _ => return accented(name).map(String::from),
+    };
+    Some(c.to_string())
+}
+
+/// Names such as `eacute` or `Ccedilla`.
+fn accented(name: &str) -> Option<char> {
+    const ACCENTS: &[(&str, &str, &str)] = &[
+        ("grave", "AEIOUaeiou", "ÀÈÌÒÙàèìòù"),
+        ("acute", "AEIOUYaeiouy", "ÁÉÍÓÚÝáéíóúý"),
+        ("circumflex", "AEIOUaeiou", "ÂÊÎÔÛâêîôû"),
+        ("dieresis", "AEIOUYaeiouy", "ÄËÏÖÜŸäëïöüÿ"),
+        ("tilde", "ANOano", "ÃÑÕãñõ"),
+        ("ring", "Aa", "Åå"),
+        ("cedilla", "Cc", "Çç"),
+        ("caron", "CcEeRrSsZz", "ČčĚěŘřŠšŽž"),
+    ];
+    let mut chars = name.chars();
+    let base = chars.next()?;
+    let accent = chars.as_str();
+    let (_, bases, composed) = ACCENTS.iter().find(|(name, _, _)| *name == accent)?;
+    let index = bases.chars().position(|c| c == base)?;
+    composed.chars().nth(index)
+}
+
+/// Approximate advance widths of the standard 14 fonts for ASCII codes.
+fn standard_width(font: &str, code: u8) -> f64 {
+    const HELVETICA: [u16; 95] = [
+        278, 278, 355, 556, 556, 889, 667, 191, 333, 333, 389, 584, 278, 333, 278, 278, 556, 556, 556, 556, 556, 556,
+        556, 556, 556, 556, 278, 278, 584, 584, 584, 556, 1015, 667, 667, 722, 722, 667, 611, 778, 722, 278, 500, 667,
+        556, 833, 722, 778, 667, 778, 722, 667, 611, 722, 667, 944, 667, 667, 611, 278, 278, 278, 469, 556, 333, 556,
+        556, 500, 556, 556, 278, 556, 556, 222, 222, 500, 222, 833, 556, 556, 556, 556, 333, 500, 278, 556, 500, 722,
+        500, 500, 500, 334, 260, 334, 584,
+    ];
+    const TIMES: [u16; 95] = [
+        250, 333, 408, 500, 500, 833, 778, 180, 333, 333, 500, 564, 250, 333, 250, 278, 500, 500, 500, 500, 500, 500,
+        500, 500, 500, 500, 278, 278, 564, 564, 564, 444, 921, 722, 667, 667, 722, 611, 556, 722, 722, 333, 389, 722,
+        611, 889, 722, 722, 556, 722, 667, 556, 611, 722, 722, 944, 722, 722, 611, 333, 278, 333, 469, 500, 333, 444,
+        500, 444, 500, 444, 333, 500, 500, 278, 278, 500, 278, 778, 500, 500, 500, 500, 333, 389, 278, 500, 500, 722,
+        500, 500, 444, 480, 200, 480, 541,
+    ];
+    if font.contains("courier") || font.contains("mono") {
+        return 0.6;
+    }
+    let table = if font.contains("times") || font.contains("serif") && !font.contains("sans") { &TIMES } else { &HELVETICA };
+    table.get(code.wrapping_sub(32) as usize).map_or(0.5, |&width| width as f64 / 1000.0)
+}
@@ -0,0 +1,190 @@
+//! Interactive form (AcroForm) fields and their values.
+
+use super::file::PdfFile;
+use super::object::{text_string, Dictionary, Object, ObjectId};
+use crate::document::{Block, Table};
+use crate::metadata::{FormField, FormFieldKind};
+use crate::renderer::escape_html;
+use std::collections::HashSet;
+
+/// Field trees are shallow; this bounds malformed ones.
+const MAX_DEPTH: usize = 16;
+
+/// Button field flags.
+const RADIO: i64 = 1 << 15;
+const PUSHBUTTON: i64 = 1 << 16;
+
+/// Attributes that fields inherit from their parents.
+#[derive(Clone)]
+struct Inherited {
+    name: String,
+    kind: Option<String>,
+    flags: i64,
+    value: Object,
+}
+
+/// The document's text, checkbox, radio button and choice fields in form
+/// order; push buttons and signatures have no value to report.
+pub fn read(file: &PdfFile) -> Vec<FormField> {
+    let catalog = file.catalog();
+    let Some(form) = file.dict_entry(&catalog, "AcroForm") else {
+        return Vec::new();
+    };
+    let Object::Array(fields) = file.entry(&form, "Fields") else {
+        return Vec::new();
+    };
+    let mut out = Vec::new();
+    let mut visited = HashSet::new();
+    for field in &fields {
+        let root = Inherited { name: String::new(), kind: None, flags: 0, value: Object::Null };
+        walk(file, field, &root, 0, &mut visited, &mut out);
+    }
+    out
+}
+
+fn walk(
+    file: &PdfFile,
+    node: &Object,
+    parent: &Inherited,
+    depth: usize,
+    visited: &mut HashSet<ObjectId>,
+    out: &mut Vec<FormField>,
+) {
+    if depth > MAX_DEPTH || node.as_reference().is_some_and(|id| !visited.insert(id)) {
+        return;
+    }
+    let Some(dict) = file.resolve(node).as_dict().cloned() else {
+        return;
+    };
+    let mut field = parent.clone();
+    if let Some(name) = file.entry(&dict, "T").as_bytes().map(text_string) {
+        field.name = if field.name.is_empty() { name } else { format!("{}.{}", field.name, name) };
+    }
+    if let Some(kind) = file.entry(&dict, "FT").as_name() {
+        field.kind = Some(kind.to_string());
+    }
+    if let Some(flags) = file.entry(&dict, "Ff").as_i64() {
+        field.flags = flags;
+    }
+    match file.entry(&dict, "V") {
+        Object::Null => {}
+        value => field.value = value,
+    }
+
+    // Kids with names are fields of their own; nameless kids are the
+    // widgets that show this field
+    let kids = match file.entry(&dict, "Kids") {
+        Object::Array(kids) => kids,
+        _ => Vec::new(),
+    };
+    let named = |kid: &Object| file.resolve(kid).as_dict().is_some_and(|kid| kid.get("T").is_some());
+    if kids.iter().any(named) {
+        for kid in &kids {
+            walk(file, kid, &field, depth + 1, visited, out);
+        }
+        return;
+    }
+    if field.name.is_empty() {
+        return;
+    }
+    let label = file.entry(&dict, "TU").as_bytes().map(text_string).filter(|label| !label.trim().is_empty());
+    let (kind, value) = match field.kind.as_deref() {
+        Some("Tx") => (FormFieldKind::Text, text_value(file, &field.value)),
+        Some("Ch") => (FormFieldKind::Choice, choice_value(file, &dict, &field.value)),
+        Some("Btn") if field.flags & PUSHBUTTON != 0 => return,
+        Some("Btn") if field.flags & RADIO != 0 => {
+            let state = button_state(file, &dict, &kids, &field.value);
+            let value = state.map(|state| option_label(file, &dict, &state).unwrap_or(state));
+            (FormFieldKind::Radio, value.unwrap_or_default())
+        }
+        Some("Btn") => {
+            let checked = button_state(file, &dict, &kids, &field.value).is_some();
+            (FormFieldKind::Checkbox, if checked { "Yes" } else { "No" }.to_string())
+        }
+        _ => return,
+    };
+    out.push(FormField { name: field.name, label, kind, value });
+}
+
+/// A text value, which long fields may store in a stream.
+fn text_value(file: &PdfFile, value: &Object) -> String {
+    match file.resolve(value) {
+        Object::String(bytes) => text_string(&bytes),
+        Object::Stream(stream) => file.decode_stream(&stream).map(|data| text_string(&data)).unwrap_or_default(),
+        _ => String::new(),
+    }
+}
+
+/// Selected options, shown by their display text when `/Opt` pairs it with
+/// the export value.
+fn choice_value(file: &PdfFile, dict: &Dictionary, value: &Object) -> String {
+    let selected: Vec<String> = match file.resolve(value) {
+        Object::Array(values) => values.iter().map(|value| text_value(file, value)).collect(),
+        value => vec![text_value(file, &value)],
+    };
+    let options = match file.entry(dict, "Opt") {
+        Object::Array(options) => options,
+        _ => Vec::new(),
+    };
+    selected
+        .into_iter()
+        .filter(|value| !value.is_empty())
+        .map(|value| {
+            options
+                .iter()
+                .find_map(|option| match file.resolve(option) {
+                    Object::Array(pair) if pair.len() == 2 && text_value(file, &pair[0]) == value => {
+                        Some(text_value(file, &pair[1]))
+                    }
+                    _ => None,
+                })
+                .unwrap_or(value)
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// The "on" state of a checkbox or radio group: `/V`, or else the
+/// appearance state of the field or one of its widgets; `None` when off.
+fn button_state(file: &PdfFile, dict: &Dictionary, kids: &[Object], value: &Object) -> Option<String> {
+    let state = |object: Object| object.as_name().filter(|name| *name != "Off").map(str::to_string);
+    if !matches!(value, Object::Null) {
+        return state(file.resolve(value));
+    }
+    std::iter::once(file.entry(dict, "AS"))
+        .chain(kids.iter().filter_map(|kid| Some(file.entry(file.resolve(kid).as_dict()?, "AS"))))
+        .find_map(state)
+}
+
+/// Radio buttons that name their states by index list the labels in `/Opt`.
+fn option_label(file: &PdfFile, dict: &Dictionary, state: &str) -> Option<String> {
+    let index: usize = state.parse().ok()?;
+    let options = file.entry(dict, "Opt");
+    Some(text_value(file, options.as_array()?.get(index)?))
+}
+
+/// The fields as a two-column table (`table`) or an HTML definition list
+/// (`list`), which Markdown has no syntax for.
+pub fn blocks(fields: &[FormField], style: &str) -> Vec<Block> {
+    if fields.is_empty() {
+        return Vec::new();
+    }
+    let label = |field: &FormField| field.label.clone().unwrap_or_else(|| field.name.clone());
+    // Multi-line text fields separate lines with CR
+    let value = |field: &FormField| field.value.split(['\r', '\n']).filter(|line| !line.is_empty()).collect::<Vec<_>>().join(" ");
+    let body = match style {
+        "list" => {
+            let mut html = String::from("<dl>\n");
+            for field in fields {
+                html.push_str(&format!("<dt>{}</dt>\n<dd>{}</dd>\n", escape_html(&label(field)), escape_html(&value(field))));
+            }
+            html.push_str("</dl>");
+            Block::Html(html)
+        }
+        _ => {
+            let rows: Vec<Vec<String>> = fields.iter().map(|field| vec![label(field), value(field)]).collect();
+            Block::Table(Table::from_strings(&["Field".to_string(), "Value".to_string()], &rows))
+        }
+    };
+    vec![Block::heading(2, "Form fields"), body]
+}
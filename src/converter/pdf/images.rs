@@ -0,0 +1,272 @@
+//! Image XObjects as asset files: JPEGs pass through, samples with a known
+//! color space are encoded as PNG.
+
+use super::content::PlacedImage;
+use super::file::PdfFile;
+use super::object::{Dictionary, Object, Stream};
+use super::table::Region;
+use crate::cli::Args;
+use crate::config::Config;
+use crate::converter::image::store_image;
+use crate::document::{Block, Inline};
+use image::{DynamicImage, GrayImage, ImageBuffer};
+use std::collections::{HashMap, HashSet};
+
+/// Images smaller than this on the page, in points, are rules and spacers.
+const MIN_SIZE: f64 = 8.0;
+
+/// Larger images are not decoded.
+const MAX_PIXELS: u64 = 50_000_000;
+
+/// Nested color spaces (an indexed ICC space, say) go only so deep.
+const MAX_COLOR_SPACE_DEPTH: usize = 4;
+
+/// The images of each page as figure regions, stored with the document's
+/// image mode under `{stem}-image{n}`. An image drawn on most pages, such as
+/// a logo in the page header, is decoration and left out, as are images too
+/// small to matter; images drawn more than once are stored once.
+pub fn figures(file: &PdfFile, stem: &str, pages: &[&[PlacedImage]], config: &Config, args: &Args) -> Vec<Vec<Region>> {
+    let keys: Vec<Vec<Option<md5::Digest>>> = pages
+        .iter()
+        .map(|images| {
+            images
+                .iter()
+                .map(|image| {
+                    let stream = image.stream.as_ref()?;
+                    let large = image.x1 - image.x0 >= MIN_SIZE && image.y1 - image.y0 >= MIN_SIZE;
+                    large.then(|| md5::compute(&stream.data))
+                })
+                .collect()
+        })
+        .collect();
+    let mut counts: HashMap<md5::Digest, usize> = HashMap::new();
+    for page in &keys {
+        for key in page.iter().flatten().collect::<HashSet<_>>() {
+            *counts.entry(*key).or_insert(0) += 1;
+        }
+    }
+    let running = |key: &md5::Digest| {
+        let count = counts.get(key).copied().unwrap_or(0);
+        count >= 3 && count * 2 > pages.len()
+    };
+
+    let mut stored: HashMap<md5::Digest, Option<String>> = HashMap::new();
+    let mut number = 0;
+    let mut regions = Vec::with_capacity(pages.len());
+    for (images, keys) in pages.iter().zip(&keys) {
+        let mut page = Vec::new();
+        for (image, key) in images.iter().zip(keys) {
+            let (Some(key), Some(stream)) = (key, &image.stream) else {
+                continue;
+            };
+            if running(key) {
+                continue;
+            }
+            let url = stored.entry(*key).or_insert_with(|| {
+                let Some((data, extension)) = encode(file, stream) else {
+                    tracing::debug!("Skipping a PDF image in an unsupported format");
+                    return None;
+                };
+                number += 1;
+                let file_name = format!("{}-image{}.{}", stem, number, extension);
+                match store_image(&data, &file_name, config, args) {
+                    Ok(url) => Some(url),
+                    Err(e) => {
+                        tracing::warn!("Skipping image {}: {}", file_name, e);
+                        None
+                    }
+                }
+            });
+            if let Some(url) = url {
+                let figure = Inline::Image { url: url.clone(), alt: "Image".to_string(), title: None };
+                page.push(Region { x0: image.x0, y0: image.y0, x1: image.x1, block: Block::Paragraph(vec![figure]) });
+            }
+        }
+        regions.push(page);
+    }
+    regions
+}
+
+/// The image as file contents and extension: DCT data as it is, other
+/// samples as PNG. JPEG 2000, CCITT and JBIG2 images are not supported.
+pub fn encode(file: &PdfFile, stream: &Stream) -> Option<(Vec<u8>, &'static str)> {
+    let (data, codec) = file.decode_stream_with_codec(stream).ok()?;
+    match codec.as_deref() {
+        Some("DCTDecode" | "DCT") => return Some((data, "jpg")),
+        Some(_) => return None,
+        None => {}
+    }
+    let image = decode(file, &stream.dict, &data)?;
+    let mut png = Vec::new();
+    image.write_to(&mut std::io::Cursor::new(&mut png), image::ImageOutputFormat::Png).ok()?;
+    Some((png, "png"))
+}
+
+enum ColorSpace {
+    Gray,
+    Rgb,
+    Cmyk,
+    /// A palette of colors in the base space.
+    Indexed(Box<ColorSpace>, Vec<u8>),
+}
+
+impl ColorSpace {
+    fn read(file: &PdfFile, object: &Object, depth: usize) -> Option<Self> {
+        if depth > MAX_COLOR_SPACE_DEPTH {
+            return None;
+        }
+        match file.resolve(object) {
+            Object::Name(name) => match name.as_str() {
+                "DeviceGray" | "G" | "CalGray" => Some(ColorSpace::Gray),
+                "DeviceRGB" | "RGB" | "CalRGB" => Some(ColorSpace::Rgb),
+                "DeviceCMYK" | "CMYK" => Some(ColorSpace::Cmyk),
+                _ => None,
+            },
+            Object::Array(array) => match array.first()?.as_name()? {
+                "CalGray" => Some(ColorSpace::Gray),
+                "CalRGB" => Some(ColorSpace::Rgb),
+                // The profile's component count is enough; colors are not managed
+                "ICCBased" => match file.resolve(array.get(1)?) {
+                    Object::Stream(profile) => match file.entry(&profile.dict, "N").as_i64()? {
+                        1 => Some(ColorSpace::Gray),
+                        3 => Some(ColorSpace::Rgb),
+                        4 => Some(ColorSpace::Cmyk),
+                        _ => None,
+                    },
+                    _ => None,
+                },
+                "Indexed" | "I" => {
+                    let base = ColorSpace::read(file, array.get(1)?, depth + 1)?;
+                    let palette = match file.resolve(array.get(3)?) {
+                        Object::String(bytes) => bytes,
+                        Object::Stream(stream) => file.decode_stream(&stream).ok()?,
+                        _ => return None,
+                    };
+                    Some(ColorSpace::Indexed(Box::new(base), palette))
+                }
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    fn components(&self) -> usize {
+        match self {
+            ColorSpace::Gray | ColorSpace::Indexed(..) => 1,
+            ColorSpace::Rgb => 3,
+            ColorSpace::Cmyk => 4,
+        }
+    }
+
+    /// RGB of one pixel's 8-bit components.
+    fn rgb(&self, pixel: &[u8]) -> [u8; 3] {
+        match self {
+            ColorSpace::Gray => [pixel[0]; 3],
+            ColorSpace::Rgb => [pixel[0], pixel[1], pixel[2]],
+            ColorSpace::Cmyk => {
+                let black = 255 - pixel[3] as u32;
+                let channel = |value: u8| ((255 - value as u32) * black / 255) as u8;
+                [channel(pixel[0]), channel(pixel[1]), channel(pixel[2])]
+            }
+            ColorSpace::Indexed(base, palette) => {
+                let size = base.components();
+                let start = pixel[0] as usize * size;
+                match palette.get(start..start + size) {
+                    Some(entry) => base.rgb(entry),
+                    None => [0; 3],
+                }
+            }
+        }
+    }
+}
+
+/// Decoded samples with their `/SMask` as alpha.
+fn decode(file: &PdfFile, dict: &Dictionary, data: &[u8]) -> Option<DynamicImage> {
+    let width = u32::try_from(file.entry(dict, "Width").as_i64()?).ok()?;
+    let height = u32::try_from(file.entry(dict, "Height").as_i64()?).ok()?;
+    if width == 0 || height == 0 || width as u64 * height as u64 > MAX_PIXELS {
+        return None;
+    }
+
+    let image = if matches!(file.entry(dict, "ImageMask"), Object::Boolean(true)) {
+        // Stencil masks paint where samples are 0, or 1 with /Decode [1 0]
+        let painted = if inverted(file, dict, 0) { 1 } else { 0 };
+        let samples = unpack(data, width, height, 1, 1)?;
+        let pixels = samples.iter().map(|&sample| if sample == painted { 0 } else { 255 }).collect();
+        DynamicImage::ImageLuma8(GrayImage::from_raw(width, height, pixels)?)
+    } else {
+        let space = ColorSpace::read(file, &file.entry(dict, "ColorSpace"), 0)?;
+        let bits = file.entry(dict, "BitsPerComponent").as_i64().unwrap_or(8) as u32;
+        if ![1, 2, 4, 8, 16].contains(&bits) {
+            return None;
+        }
+        let components = space.components();
+        let mut samples = unpack(data, width, height, components, bits)?;
+        if !matches!(space, ColorSpace::Indexed(..)) {
+            let max = (1u32 << bits.min(8)) - 1;
+            for (i, sample) in samples.iter_mut().enumerate() {
+                if inverted(file, dict, i % components) {
+                    *sample = max - *sample;
+                }
+                *sample = *sample * 255 / max;
+            }
+        }
+        let samples: Vec<u8> = samples.into_iter().map(|sample| sample.min(255) as u8).collect();
+        match space {
+            ColorSpace::Gray => DynamicImage::ImageLuma8(GrayImage::from_raw(width, height, samples)?),
+            _ => {
+                let pixels = samples.chunks_exact(components).flat_map(|pixel| space.rgb(pixel)).collect();
+                DynamicImage::ImageRgb8(ImageBuffer::from_raw(width, height, pixels)?)
+            }
+        }
+    };
+
+    let Object::Stream(mask) = file.entry(dict, "SMask") else {
+        return Some(image);
+    };
+    let alpha = file.decode_stream(&mask).ok().and_then(|data| decode(file, &mask.dict, &data));
+    let Some(alpha) = alpha else {
+        return Some(image);
+    };
+    let alpha = image::imageops::resize(&alpha.to_luma8(), width, height, image::imageops::FilterType::Triangle);
+    let mut rgba = image.to_rgba8();
+    for (pixel, value) in rgba.pixels_mut().zip(alpha.pixels()) {
+        pixel[3] = value[0];
+    }
+    Some(DynamicImage::ImageRgba8(rgba))
+}
+
+/// Whether `/Decode` maps the component's range backwards, as in `[1 0]`.
+fn inverted(file: &PdfFile, dict: &Dictionary, component: usize) -> bool {
+    let Object::Array(decode) = file.entry(dict, "Decode") else {
+        return false;
+    };
+    let bound = |i: usize| decode.get(i).and_then(|value| file.resolve(value).as_f64());
+    matches!((bound(2 * component), bound(2 * component + 1)), (Some(low), Some(high)) if low > high)
+}
+
+/// Samples of `bits` each, rows padded to whole bytes; 16-bit samples keep
+/// their high byte.
+fn unpack(data: &[u8], width: u32, height: u32, components: usize, bits: u32) -> Option<Vec<u32>> {
+    let per_row = width as usize * components;
+    let stride = (per_row * bits as usize).div_ceil(8);
+    if data.len() < stride * height as usize {
+        return None;
+    }
+    let mut samples = Vec::with_capacity(per_row * height as usize);
+    for row in data.chunks_exact(stride).take(height as usize) {
+        for i in 0..per_row {
+            let sample = match bits {
+                8 => row[i] as u32,
+                16 => row[2 * i] as u32,
+                _ => {
+                    let bit = i * bits as usize;
+                    let shift = 8 - bits as usize - bit % 8;
+                    ((row[bit / 8] >> shift) & ((1 << bits) - 1) as u8) as u32
+                }
+            };
+            samples.push(sample);
+        }
+    }
+    Some(samples)
+}
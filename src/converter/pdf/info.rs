@@ -0,0 +1,173 @@
+//! Document metadata from the `/Info` dictionary and the XMP packet.
+
+use super::file::PdfFile;
+use super::object::{text_string, Object};
+use crate::converter::xml::Element;
+use crate::document::Document;
+use crate::metadata::DocumentMetadata;
+use chrono::{DateTime, FixedOffset, NaiveDate, TimeZone, Utc};
+
+#[derive(Debug, Default)]
+pub struct PdfInfo {
+    pub title: Option<String>,
+    pub author: Option<String>,
+    pub subject: Option<String>,
+    pub keywords: Option<String>,
+    pub creator: Option<String>,
+    pub producer: Option<String>,
+    pub language: Option<String>,
+    pub created: Option<DateTime<Utc>>,
+    pub modified: Option<DateTime<Utc>>,
+    pub pages: u32,
+    pub encrypted: bool,
+}
+
+impl PdfInfo {
+    /// XMP values win over `/Info`, which PDF 2.0 deprecates in its favor.
+    pub fn read(file: &PdfFile) -> Self {
+        let catalog = file.catalog();
+        let mut info = Self {
+            pages: file.pages().len() as u32,
+            encrypted: file.is_encrypted(),
+            language: file.entry(&catalog, "Lang").as_bytes().map(text_string),
+            ..Self::default()
+        };
+        if let Object::Stream(stream) = file.entry(&catalog, "Metadata") {
+            let packet = file.decode_stream(&stream).ok().and_then(|data| Element::parse(&String::from_utf8_lossy(&data)).ok());
+            if let Some(packet) = packet {
+                info.read_xmp(&packet);
+            }
+        }
+        if let Some(dict) = file.dict_entry(&file.trailer, "Info") {
+            let text = |key: &str| file.entry(&dict, key).as_bytes().map(text_string).and_then(non_empty);
+            let date = |key: &str| file.entry(&dict, key).as_bytes().map(text_string).and_then(|date| parse_date(&date));
+            info.title = info.title.take().or_else(|| text("Title"));
+            info.author = info.author.take().or_else(|| text("Author"));
+            info.subject = info.subject.take().or_else(|| text("Subject"));
+            info.keywords = info.keywords.take().or_else(|| text("Keywords"));
+            info.creator = info.creator.take().or_else(|| text("Creator"));
+            info.producer = info.producer.take().or_else(|| text("Producer"));
+            info.created = info.created.or_else(|| date("CreationDate"));
+            info.modified = info.modified.or_else(|| date("ModDate"));
+        }
+        info
+    }
+
+    fn read_xmp(&mut self, packet: &Element) {
+        // Simple properties are elements or attributes of an rdf:Description
+        let descriptions = packet.find_all("Description");
+        let property = |name: &str| {
+            descriptions
+                .iter()
+                .find_map(|description| match description.child(name) {
+                    Some(element) => Some(element.text()),
+                    None => description.attr(name).map(str::to_string),
+                })
+                .and_then(|value| non_empty(value.trim().to_string()))
+        };
+        // Language alternatives, sequences and bags hold rdf:li items
+        let items = |name: &str| -> Vec<String> {
+            let Some(element) = descriptions.iter().find_map(|description| description.child(name)) else {
+                return Vec::new();
+            };
+            let items = element.find_all("li");
+            if items.is_empty() {
+                return non_empty(element.text().trim().to_string()).into_iter().collect();
+            }
+            items.iter().filter_map(|item| non_empty(item.text().trim().to_string())).collect()
+        };
+        let list = |name: &str| Some(items(name).join(", ")).filter(|list| !list.is_empty());
+
+        self.title = items("title").into_iter().next();
+        self.author = list("creator");
+        self.subject = items("description").into_iter().next();
+        self.keywords = property("Keywords").or_else(|| list("subject"));
+        self.creator = property("CreatorTool");
+        self.producer = property("Producer");
+        self.language = items("language").into_iter().next().or(self.language.take());
+        self.created = property("CreateDate").as_deref().and_then(parse_xmp_date);
+        self.modified = property("ModifyDate").as_deref().and_then(parse_xmp_date);
+    }
+
+    /// Fill the document's front matter fields.
+    pub fn apply_to_document(&self, document: &mut Document) {
+        document.title = document.title.take().or_else(|| self.title.clone());
+        document.author = document.author.take().or_else(|| self.author.clone());
+        document.date = document
+            .date
+            .take()
+            .or_else(|| self.created.map(|date| date.format("%Y-%m-%d").to_string()));
+    }
+
+    pub fn apply_to_metadata(&self, metadata: &mut DocumentMetadata) {
+        metadata.title = self.title.clone();
+        metadata.author = self.author.clone();
+        metadata.subject = self.subject.clone();
+        metadata.keywords = self.keywords.clone();
+        metadata.creator = self.creator.clone();
+        metadata.producer = self.producer.clone();
+        metadata.language = self.language.clone();
+        metadata.creation_date = self.created;
+        metadata.modification_date = self.modified.or(metadata.modification_date);
+        metadata.page_count = Some(self.pages);
+        metadata.encrypted = Some(self.encrypted);
+    }
+}
+
+fn non_empty(text: String) -> Option<String> {
+    let text = text.trim();
+    (!text.is_empty()).then(|| text.to_string())
+}
+
+/// A PDF date, `D:YYYYMMDDHHmmSSOHH'mm'`, where everything after the year is
+/// optional and a missing offset means UTC.
+fn parse_date(text: &str) -> Option<DateTime<Utc>> {
+    let text = text.trim();
+    let text = text.strip_prefix("D:").unwrap_or(text);
+    let digits: String = text.chars().take_while(char::is_ascii_digit).collect();
+    if digits.len() < 4 {
+        return None;
+    }
+    let field = |start: usize, default: u32| digits.get(start..start + 2).and_then(|value| value.parse().ok()).unwrap_or(default);
+    let year: i32 = digits[..4].parse().ok()?;
+    let date = NaiveDate::from_ymd_opt(year, field(4, 1), field(6, 1))?;
+    let time = date.and_hms_opt(field(8, 0), field(10, 0), field(12, 0))?;
+
+    let zone = &text[digits.len()..];
+    let offset = match zone.chars().next() {
+        Some(sign @ ('+' | '-')) => {
+            let numbers: Vec<i32> = zone[1..]
+                .split(|c: char| !c.is_ascii_digit())
+                .filter(|part| !part.is_empty())
+                .filter_map(|part| part.parse().ok())
+                .collect();
+            let seconds = numbers.first().copied().unwrap_or(0) * 3600 + numbers.get(1).copied().unwrap_or(0) * 60;
+            FixedOffset::east_opt(if sign == '-' { -seconds } else { seconds })?
+        }
+        _ => FixedOffset::east_opt(0)?,
+    };
+    offset.from_local_datetime(&time).single().map(|date| date.with_timezone(&Utc))
+}
+
+/// An XMP date: ISO 8601, possibly without a time zone or truncated to the
+/// year, month or day.
+fn parse_xmp_date(text: &str) -> Option<DateTime<Utc>> {
+    if let Ok(date) = DateTime::parse_from_rfc3339(text) {
+        return Some(date.with_timezone(&Utc));
+    }
+    for format in ["%Y-%m-%dT%H:%M:%S%.f", "%Y-%m-%dT%H:%M:%S", "%Y-%m-%dT%H:%M"] {
+        if let Ok(time) = chrono::NaiveDateTime::parse_from_str(text, format) {
+            return Some(time.and_utc());
+        }
+    }
+    // "2024-03-05T10:20+01:00" has no seconds
+    if let Ok(date) = DateTime::parse_from_str(text, "%Y-%m-%dT%H:%M%:z") {
+        return Some(date.with_timezone(&Utc));
+    }
+    let padded = match text.len() {
+        4 => format!("{}-01-01", text),
+        7 => format!("{}-01", text),
+        _ => text.to_string(),
+    };
+    NaiveDate::parse_from_str(&padded, "%Y-%m-%d").ok()?.and_hms_opt(0, 0, 0).map(|time| time.and_utc())
+}
@@ -0,0 +1,813 @@
+//! Layout analysis: glyphs to lines in reading order, lines to blocks.
+
+use super::annots::Mark;
+use super::content::{Rule, TextChar};
+use super::outline::OutlineEntry;
+use super::table::{aligned_tables, ruled_tables, Region};
+use crate::converter::cleanup::{Cleanup, Joint};
+use crate::converter::pages::running_lines;
+use crate::document::{build_lists, inlines_plain_text, Block, Inline, ListEntry};
+use std::collections::HashMap;
+
+/// How lines are put in reading order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Layout {
+    /// Content stream order, as the producer wrote the text.
+    Raw,
+    /// Geometric order: top to bottom, left to right.
+    Smart,
+    /// Stream order unless it jumps around the page or the page has columns.
+    Auto,
+}
+
+impl Layout {
+    pub fn parse(name: &str) -> Self {
+        match name {
+            "raw" => Layout::Raw,
+            "smart" => Layout::Smart,
+            _ => Layout::Auto,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+struct Style {
+    bold: bool,
+    italic: bool,
+    monospace: bool,
+    mark: Option<Mark>,
+}
+
+#[derive(Debug, Clone)]
+struct Span {
+    text: String,
+    style: Style,
+    /// Footnote references that follow the text.
+    notes: Vec<String>,
+}
+
+/// A run of text on one baseline.
+#[derive(Debug, Clone)]
+pub struct Line {
+    spans: Vec<Span>,
+    pub(super) x0: f64,
+    pub(super) x1: f64,
+    pub(super) y: f64,
+    pub(super) size: f64,
+    /// Characters at the dominant size, for weighting.
+    chars: usize,
+}
+
+impl Line {
+    fn new(c: &TextChar) -> Self {
+        Self { spans: Vec::new(), x0: c.x, x1: c.x, y: c.y, size: c.size, chars: 0 }
+    }
+
+    pub fn text(&self) -> String {
+        self.spans.iter().map(|span| span.text.as_str()).collect()
+    }
+
+    fn push(&mut self, text: &str, style: Style) {
+        match self.spans.last_mut() {
+            Some(span) if span.style == style && span.notes.is_empty() => span.text.push_str(text),
+            _ => self.spans.push(Span { text: text.to_string(), style, notes: Vec::new() }),
+        }
+    }
+
+    fn push_notes(&mut self, notes: &[String]) {
+        if let Some(span) = self.spans.last_mut() {
+            span.notes.extend_from_slice(notes);
+        }
+    }
+
+    fn ends_with_space(&self) -> bool {
+        self.spans.last().is_none_or(|span| span.text.ends_with(' '))
+    }
+
+    /// Append `other`, which lies to the right on the same baseline.
+    fn append(&mut self, other: Line) {
+        let gap = other.x0 - self.x1;
+        let starts_with_space = other.spans.first().is_some_and(|span| span.text.starts_with(' '));
+        if gap > 0.1 * self.size.min(other.size) && !self.ends_with_space() && !starts_with_space {
+            let style = self.spans.last().map(|span| span.style).unwrap_or_default();
+            self.push(" ", style);
+        }
+        if other.chars > self.chars {
+            self.size = other.size;
+        }
+        self.chars += other.chars;
+        self.x0 = self.x0.min(other.x0);
+        self.x1 = self.x1.max(other.x1);
+        for span in other.spans {
+            self.push(&span.text, span.style);
+            self.push_notes(&span.notes);
+        }
+    }
+
+    pub(super) fn monospace(&self) -> bool {
+        self.all(|style| style.monospace)
+    }
+
+    fn all(&self, f: impl Fn(&Style) -> bool) -> bool {
+        self.spans.iter().filter(|span| !span.text.trim().is_empty()).all(|span| f(&span.style))
+    }
+}
+
+/// Group glyphs into runs of text, in content stream order. A run ends where
+/// the baseline changes, the text moves backwards or there is a wide gap.
+pub(super) fn runs(chars: &[TextChar]) -> Vec<Line> {
+    let mut runs: Vec<Line> = Vec::new();
+    let mut last_x = f64::NAN;
+    for c in chars {
+        let style = Style { bold: c.bold, italic: c.italic, monospace: c.monospace, mark: c.mark };
+        let continues = runs.last().is_some_and(|run| {
+            (c.y - run.y).abs() < 0.3 * run.size.max(c.size) && c.x > run.x1 - 0.5 * c.size && c.x - run.x1 < c.size
+        });
+        if !continues {
+            runs.push(Line::new(c));
+            last_x = f64::NAN;
+        }
+        let run = runs.last_mut().expect("a run was just pushed");
+        // Fake bold draws the same glyph twice, slightly offset
+        if (c.x - last_x).abs() < 0.1 * c.size && run.spans.last().is_some_and(|span| span.text.ends_with(&c.text)) {
+            continue;
+        }
+        if c.x - run.x1 > 0.15 * c.size && !run.ends_with_space() && !c.text.starts_with(' ') {
+            run.push(" ", style);
+        }
+        run.push(&c.text, style);
+        run.push_notes(&c.notes);
+        if c.text.trim().is_empty() {
+            // Spaces do not count toward the run's extent or size
+            run.x1 = run.x1.max(c.x + c.width);
+            continue;
+        }
+        if run.chars == 0 {
+            run.x0 = c.x;
+            run.size = c.size;
+        }
+        run.chars += 1;
+        run.x1 = run.x1.max(c.x + c.width);
+        last_x = c.x;
+    }
+    runs.retain(|run| run.chars > 0);
+    runs
+}
+
+/// Lines in content stream order: consecutive runs on one baseline form a line.
+fn raw_lines(runs: Vec<Line>) -> Vec<Line> {
+    let mut lines: Vec<Line> = Vec::new();
+    for run in runs {
+        match lines.last_mut() {
+            Some(line) if (run.y - line.y).abs() < 0.5 * line.size.min(run.size) && run.x0 >= line.x1 - line.size => line.append(run),
+            _ => lines.push(run),
+        }
+    }
+    lines
+}
+
+/// Lines by baseline, sorted left to right, top to bottom.
+pub(super) fn baseline_lines(mut runs: Vec<Line>) -> Vec<Line> {
+    runs.sort_by(|a, b| a.y.total_cmp(&b.y));
+    let mut rows: Vec<Vec<Line>> = Vec::new();
+    for run in runs {
+        match rows.last_mut() {
+            Some(row) if (run.y - row[0].y).abs() < 0.4 * row[0].size.min(run.size) => row.push(run),
+            _ => rows.push(vec![run]),
+        }
+    }
+    rows.into_iter()
+        .filter_map(|mut row| {
+            row.sort_by(|a, b| a.x0.total_cmp(&b.x0));
+            let mut runs = row.into_iter();
+            let mut line = runs.next()?;
+            for run in runs {
+                line.append(run);
+            }
+            Some(line)
+        })
+        .collect()
+}
+
+/// Lines in geometric order. Where the page has columns, each band of
+/// columns is read column by column; text spanning a gutter, such as titles
+/// and wide captions, splits the page into bands and stays in place.
+fn smart_lines(mut runs: Vec<Line>) -> Vec<Line> {
+    let gutters = find_gutters(&runs);
+    if gutters.is_empty() {
+        return baseline_lines(runs);
+    }
+
+    runs.sort_by(|a, b| a.y.total_cmp(&b.y));
+    let spans_gutter = |run: &Line| gutters.iter().any(|&gutter| run.x0 < gutter - 1.0 && run.x1 > gutter + 1.0);
+    let mut lines = Vec::new();
+    let mut columns: Vec<Vec<Line>> = vec![Vec::new(); gutters.len() + 1];
+    let mut spanning: Vec<Line> = Vec::new();
+    for run in runs {
+        if spans_gutter(&run) {
+            for column in &mut columns {
+                lines.extend(baseline_lines(std::mem::take(column)));
+            }
+            spanning.push(run);
+        } else {
+            lines.extend(baseline_lines(std::mem::take(&mut spanning)));
+            let center = (run.x0 + run.x1) / 2.0;
+            let column = gutters.iter().filter(|&&gutter| center > gutter).count();
+            columns[column].push(run);
+        }
+    }
+    lines.extend(baseline_lines(spanning));
+    for column in columns {
+        lines.extend(baseline_lines(column));
+    }
+    lines
+}
+
+/// x positions of whitespace gutters between text columns, found from a
+/// histogram of how many runs cover each point across the page.
+fn find_gutters(runs: &[Line]) -> Vec<f64> {
+    if runs.len() < 6 {
+        return Vec::new();
+    }
+    let left = runs.iter().map(|run| run.x0).fold(f64::INFINITY, f64::min);
+    let right = runs.iter().map(|run| run.x1).fold(f64::NEG_INFINITY, f64::max);
+    let width = right - left;
+    if width <= 0.0 {
+        return Vec::new();
+    }
+    let mut sizes: Vec<f64> = runs.iter().map(|run| run.size).collect();
+    sizes.sort_by(f64::total_cmp);
+    let size = sizes[sizes.len() / 2];
+
+    // One bin per point
+    let bins = width.ceil() as usize + 1;
+    let mut coverage = vec![0usize; bins];
+    for run in runs {
+        let start = (run.x0 - left).floor().max(0.0) as usize;
+        let end = ((run.x1 - left).ceil() as usize).min(bins);
+        for count in &mut coverage[start..end] {
+            *count += 1;
+        }
+    }
+
+    // Spanning titles and captions cross gutters, so allow a few
+    let allowed = ((runs.len() as f64 * 0.15) as usize).max(1);
+    let mut candidates = Vec::new();
+    let mut start = None;
+    for (bin, &count) in coverage.iter().enumerate() {
+        match (count <= allowed, start) {
+            (true, None) => start = Some(bin),
+            (false, Some(first)) => {
+                if first > 0 && (bin - first) as f64 >= size.max(6.0) {
+                    candidates.push(left + (first + bin) as f64 / 2.0);
+                }
+                start = None;
+            }
+            _ => {}
+        }
+    }
+
+    // A gutter has columns of real text on both sides
+    candidates
+        .into_iter()
+        .filter(|&gutter| {
+            let before = runs.iter().filter(|run| run.x1 <= gutter).count();
+            let after = runs.iter().filter(|run| run.x0 >= gutter).count();
+            let minimum = (runs.len() as f64 * 0.2).max(3.0) as usize;
+            before >= minimum && after >= minimum && gutter - left >= 0.15 * width && right - gutter >= 0.15 * width
+        })
+        .collect()
+}
+
+/// Stream order reads top-down when few lines move back up the page.
+fn reads_top_down(lines: &[Line]) -> bool {
+    let backwards = lines.windows(2).filter(|pair| pair[1].y < pair[0].y - pair[0].size).count();
+    backwards * 10 <= lines.len()
+}
+
+/// A line of text or a finished block (a table, OCR text, a page marker), in
+/// reading order.
+pub enum Item {
+    Line(Line),
+    Block(Block),
+}
+
+/// The page's lines, tables and `figures` in reading order.
+pub fn page_items(chars: &[TextChar], rules: &[Rule], figures: Vec<Region>, layout: Layout, cleanup: &Cleanup) -> Vec<Item> {
+    let mut chars = chars.to_vec();
+    let mut tables = ruled_tables(&mut chars, rules, cleanup);
+    let mut runs = runs(&chars);
+    tables.extend(aligned_tables(&mut runs, cleanup));
+    tables.extend(figures);
+
+    let lines = match layout {
+        Layout::Raw => raw_lines(runs),
+        Layout::Smart => smart_lines(runs),
+        Layout::Auto => {
+            let lines = raw_lines(runs.clone());
+            // Stream order of column layouts often interleaves the columns
+            if reads_top_down(&lines) && find_gutters(&runs).is_empty() {
+                lines
+            } else {
+                smart_lines(runs)
+            }
+        }
+    };
+
+    // A table or figure goes before the first line below its top edge that shares its columns
+    tables.sort_by(|a, b| a.y0.total_cmp(&b.y0));
+    let mut items: Vec<Item> = Vec::with_capacity(lines.len() + tables.len());
+    let mut tables = tables.into_iter().peekable();
+    for line in lines {
+        while let Some(table) = tables.next_if(|table: &Region| line.y > table.y0 && line.x0 < table.x1 && line.x1 > table.x0) {
+            items.push(Item::Block(table.block));
+        }
+        items.push(Item::Line(line));
+    }
+    items.extend(tables.map(|table| Item::Block(table.block)));
+    items
+}
+
+/// Vertical slack between repeats of a running header or footer.
+const RUNNING_TOLERANCE: f64 = 3.0;
+
+/// Drop running headers, footers and page numbers; `numbers` are the pages'
+/// 1-based numbers in the file.
+pub fn strip_running(pages: &mut [Vec<Item>], numbers: &[usize]) {
+    let lines: Vec<Vec<(String, f64)>> = pages
+        .iter()
+        .map(|items| {
+            items
+                .iter()
+                .filter_map(|item| if let Item::Line(line) = item { Some((line.text(), line.y)) } else { None })
+                .collect()
+        })
+        .collect();
+    let running = running_lines(&lines, numbers, RUNNING_TOLERANCE);
+    for (page, items) in pages.iter_mut().enumerate() {
+        let mut index = 0;
+        items.retain(|item| match item {
+            Item::Line(_) => {
+                index += 1;
+                !running.contains(&(page, index - 1))
+            }
+            Item::Block(_) => true,
+        });
+    }
+}
+
+/// Lines below an outline destination searched for the entry's title.
+const OUTLINE_SEARCH_LINES: usize = 4;
+
+/// Headings wrap over at most this many lines.
+const OUTLINE_HEADING_LINES: usize = 3;
+
+/// Turn the lines outline entries point at into headings of the entries'
+/// levels. `entries` pair each entry with the index of its page in `pages`.
+/// The title is looked for in the first lines below the destination, or after
+/// the previous heading on the page; where no line matches, the title itself
+/// becomes the heading. Returns each entry's heading text.
+pub fn apply_outline(pages: &mut [Vec<Item>], entries: &[(usize, &OutlineEntry)], cleanup: &Cleanup) -> Vec<String> {
+    let mut cursors = vec![0; pages.len()];
+    let mut headings = Vec::with_capacity(entries.len());
+    for &(page, entry) in entries {
+        let items = &mut pages[page];
+        let level = entry.level.min(6) as u8;
+        let is_line = |item: &Item| matches!(item, Item::Line(_));
+        let start = match entry.top {
+            // Destinations point at or a little above the heading's baseline
+            Some(top) if items.iter().any(is_line) => items
+                .iter()
+                .position(|item| matches!(item, Item::Line(line) if line.y >= top - 1.0))
+                .unwrap_or(items.len()),
+            _ => cursors[page].min(items.len()),
+        };
+
+        let target = title_key(&entry.title);
+        let candidates: Vec<usize> = (start..items.len()).filter(|&i| is_line(&items[i])).take(OUTLINE_SEARCH_LINES).collect();
+        let found = candidates.iter().find_map(|&first| {
+            if target.is_empty() {
+                return None;
+            }
+            let mut text = String::new();
+            for (end, item) in items.iter().enumerate().skip(first).take(OUTLINE_HEADING_LINES) {
+                let Item::Line(line) = item else {
+                    break;
+                };
+                text.push_str(&title_key(&line.text()));
+                if same_title(&text, &target) {
+                    return Some((first, end));
+                }
+                if !target.contains(text.as_str()) {
+                    break;
+                }
+            }
+            None
+        });
+
+        match found {
+            Some((first, end)) => {
+                let lines: Vec<Line> = items[first..=end]
+                    .iter()
+                    .filter_map(|item| if let Item::Line(line) = item { Some(line.clone()) } else { None })
+                    .collect();
+                let content = inlines(&lines, 0, true, cleanup);
+                headings.push(inlines_plain_text(&content));
+                items.splice(first..=end, [Item::Block(Block::Heading { level, content })]);
+                cursors[page] = first + 1;
+            }
+            None => {
+                headings.push(entry.title.clone());
+                items.insert(start, Item::Block(Block::heading(level, entry.title.clone())));
+                cursors[page] = start + 1;
+            }
+        }
+    }
+    headings
+}
+
+/// Letters and digits of a title, lowercased.
+fn title_key(text: &str) -> String {
+    text.chars().filter(|c| c.is_alphanumeric()).flat_map(char::to_lowercase).collect()
+}
+
+/// Words that label numbered divisions.
+const DIVISIONS: [&str; 4] = ["chapter", "appendix", "part", "section"];
+
+/// Title keys match when equal, or when one only adds numbering to the other
+/// ("2.1 Scope" and "Scope", "Appendix A Terms" and "A Terms").
+fn same_title(a: &str, b: &str) -> bool {
+    let numbered = |long: &str, short: &str| {
+        let Some(prefix) = long.strip_suffix(short).filter(|_| !short.is_empty()) else {
+            return false;
+        };
+        let prefix = DIVISIONS.iter().find_map(|word| prefix.strip_prefix(word)).unwrap_or(prefix);
+        prefix.chars().all(|c| c.is_ascii_digit())
+    };
+    numbered(a, b) || numbered(b, a)
+}
+
+fn round_size(size: f64) -> i64 {
+    (size * 2.0).round() as i64
+}
+
+/// Font statistics of the whole document.
+struct Metrics {
+    body_size: f64,
+    /// Rounded sizes of heading text, largest first.
+    heading_sizes: Vec<i64>,
+    /// Baseline distance above which lines belong to separate paragraphs.
+    paragraph_gap: f64,
+}
+
+impl Metrics {
+    fn new(pages: &[Vec<&Line>]) -> Self {
+        let mut weights: HashMap<i64, usize> = HashMap::new();
+        for line in pages.iter().flatten() {
+            *weights.entry(round_size(line.size)).or_default() += line.chars;
+        }
+        let body = weights.iter().max_by_key(|(size, weight)| (**weight, -**size)).map_or(24, |(size, _)| *size);
+        let body_size = body as f64 / 2.0;
+
+        let mut heading_sizes: Vec<i64> = weights
+            .keys()
+            .copied()
+            .filter(|&size| size as f64 / 2.0 > body_size * 1.15)
+            .collect();
+        heading_sizes.sort_unstable_by(|a, b| b.cmp(a));
+
+        // Line spacing is the most common baseline distance in body text
+        let mut gaps: HashMap<i64, usize> = HashMap::new();
+        for pair in pages.iter().flat_map(|lines| lines.windows(2)) {
+            let gap = pair[1].y - pair[0].y;
+            if round_size(pair[0].size) == body && round_size(pair[1].size) == body && gap > 0.0 && gap < 3.0 * body_size {
+                *gaps.entry(round_size(gap)).or_default() += 1;
+            }
+        }
+        let paragraph_gap = match gaps.iter().max_by_key(|(gap, count)| (**count, -**gap)) {
+            Some((&spacing, _)) => {
+                let spacing = spacing as f64 / 2.0;
+                (spacing * 1.3).max(spacing + 0.2 * body_size)
+            }
+            None => body_size * 1.6,
+        };
+
+        Self { body_size, heading_sizes, paragraph_gap }
+    }
+
+    fn heading_level(&self, line: &Line) -> Option<u8> {
+        let position = self.heading_sizes.iter().position(|&size| size == round_size(line.size))?;
+        Some((position + 1).min(6) as u8)
+    }
+
+    /// Level for short bold lines at body size.
+    fn bold_heading_level(&self) -> u8 {
+        (self.heading_sizes.len() + 1).min(6) as u8
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Kind {
+    Heading(u8),
+    Code,
+    Text,
+}
+
+/// A list marker at the start of a line: ordered, number and length in chars.
+pub(super) fn list_marker(text: &str) -> Option<(bool, u64, usize)> {
+    let trimmed = text.trim_start();
+    let leading = text.chars().count() - trimmed.chars().count();
+    let mut chars = trimmed.chars();
+    let first = chars.next()?;
+    let rest = chars.as_str();
+    let followed_by_text = |rest: &str| rest.starts_with([' ', '\t', '\u{a0}']) && !rest.trim().is_empty();
+
+    if "•◦▪▫■□●○‣⁃∙·-–—*➢►✓".contains(first) && followed_by_text(rest) {
+        let length = leading + 1 + (rest.chars().count() - rest.trim_start().chars().count());
+        return Some((false, 1, length));
+    }
+
+    // `1.`, `1)`, `(1)`, `a)`, `iv.`
+    let body = trimmed.strip_prefix('(').unwrap_or(trimmed);
+    let label: String = body.chars().take_while(|c| c.is_alphanumeric()).collect();
+    let after = &body[label.len()..];
+    let closing = after.chars().next()?;
+    let parenthesized = body.len() != trimmed.len();
+    if !(closing == ')' || closing == '.' && !parenthesized) || !followed_by_text(&after[1..]) {
+        return None;
+    }
+    let number = if label.len() <= 3 && label.chars().all(|c| c.is_ascii_digit()) {
+        label.parse().ok()?
+    } else if let Some(number) = roman(&label) {
+        number
+    } else if label.len() == 1 && label.chars().all(|c| c.is_ascii_alphabetic()) {
+        (label.to_ascii_lowercase().as_bytes()[0] - b'a') as u64 + 1
+    } else {
+        return None;
+    };
+    let marker = trimmed.len() - after.len() + 1;
+    let spaces = after[1..].len() - after[1..].trim_start().len();
+    Some((true, number, leading + trimmed[..marker + spaces].chars().count()))
+}
+
+/// Lowercase roman numerals up to 39, as used for sub-lists.
+fn roman(label: &str) -> Option<u64> {
+    const NUMERALS: [&str; 10] = ["", "i", "ii", "iii", "iv", "v", "vi", "vii", "viii", "ix"];
+    let tens = label.chars().take_while(|&c| c == 'x').count();
+    let units = NUMERALS.iter().position(|&n| !n.is_empty() && n == &label[tens..])?;
+    (tens <= 3).then_some(tens as u64 * 10 + units as u64)
+}
+
+/// Inlines for the spans of `lines`, joined with spaces and styled.
+pub(super) fn inlines(lines: &[Line], skip: usize, plain: bool, cleanup: &Cleanup) -> Vec<Inline> {
+    fn push<'a>(spans: &'a mut Vec<Span>, text: &str, style: Style) -> &'a mut Span {
+        match spans.last_mut() {
+            Some(span) if span.notes.is_empty() && (span.style == style || text.trim().is_empty()) => {
+                span.text.push_str(text)
+            }
+            _ => spans.push(Span { text: text.to_string(), style, notes: Vec::new() }),
+        }
+        spans.last_mut().expect("a span was just pushed")
+    }
+
+    let mut spans: Vec<Span> = Vec::new();
+    for (i, line) in lines.iter().enumerate() {
+        let mut skip = if i == 0 { skip } else { 0 };
+        if i > 0 {
+            match cleanup.joint(&lines[i - 1].text(), &line.text()) {
+                Joint::Space => {
+                    push(&mut spans, " ", Style::default());
+                }
+                Joint::Join => {
+                    if let Some(span) = spans.last_mut() {
+                        let kept = span.text.trim_end().len();
+                        span.text.truncate(kept);
+                        span.text.pop();
+                    }
+                }
+                Joint::Hyphen => {}
+            }
+        }
+        for span in &line.spans {
+            let text = cleanup.chars(&span.text.chars().skip(skip).collect::<String>());
+            skip = skip.saturating_sub(span.text.chars().count());
+            if !text.is_empty() || !span.notes.is_empty() {
+                push(&mut spans, &text, span.style).notes.extend_from_slice(&span.notes);
+            }
+        }
+    }
+
+    let mut out: Vec<Inline> = Vec::new();
+    let count = spans.len();
+    for (i, span) in spans.into_iter().enumerate() {
+        let mut text = span.text.split_whitespace().collect::<Vec<_>>().join(" ");
+        if span.text.starts_with(char::is_whitespace) && i > 0 {
+            text.insert(0, ' ');
+        }
+        if span.text.ends_with(char::is_whitespace) && i + 1 < count && !text.is_empty() {
+            text.push(' ');
+        }
+        let styled = text.trim();
+        let leading = &text[..text.len() - text.trim_start().len()];
+        let trailing = &text[text.trim_end().len()..];
+        let notes = span.notes.iter().map(|label| Inline::FootnoteRef(label.clone()));
+        // Styled punctuation, such as italic dot leaders, reads as plain text
+        if plain || !styled.chars().any(char::is_alphanumeric) || span.style == Style::default() {
+            push_text(&mut out, text.trim_end());
+            out.extend(notes);
+            push_text(&mut out, trailing);
+            continue;
+        }
+        push_text(&mut out, leading);
+        let mut inline = if span.style.monospace {
+            Inline::Code(styled.to_string())
+        } else {
+            Inline::Text(styled.to_string())
+        };
+        if span.style.italic && !span.style.monospace {
+            inline = Inline::Emphasis(vec![inline]);
+        }
+        if span.style.bold && !span.style.monospace {
+            inline = Inline::Strong(vec![inline]);
+        }
+        inline = match span.style.mark {
+            Some(Mark::Highlight) => Inline::Highlight(vec![inline]),
+            Some(Mark::StrikeOut) => Inline::Strikethrough(vec![inline]),
+            None => inline,
+        };
+        out.push(inline);
+        out.extend(notes);
+        push_text(&mut out, trailing);
+    }
+    if let Some(Inline::Text(text)) = out.first_mut() {
+        *text = text.trim_start().to_string();
+    }
+    if let Some(Inline::Text(text)) = out.last_mut() {
+        *text = text.trim_end().to_string();
+    }
+    out.retain(|inline| !matches!(inline, Inline::Text(text) if text.is_empty()));
+    out
+}
+
+fn push_text(out: &mut Vec<Inline>, text: &str) {
+    if text.is_empty() {
+        return;
+    }
+    match out.last_mut() {
+        Some(Inline::Text(previous)) => previous.push_str(text),
+        _ => out.push(Inline::Text(text.to_string())),
+    }
+}
+
+/// Collects blocks, buffering list paragraphs until the list ends.
+struct Builder<'a> {
+    metrics: &'a Metrics,
+    cleanup: &'a Cleanup<'a>,
+    /// Headings come from the outline; font size and weight do not make any.
+    outline: bool,
+    blocks: Vec<Block>,
+    entries: Vec<ListEntry>,
+    /// Marker x positions of open list levels.
+    indents: Vec<f64>,
+}
+
+impl Builder<'_> {
+    fn flush_list(&mut self) {
+        if !self.entries.is_empty() {
+            self.blocks.extend(build_lists(std::mem::take(&mut self.entries)));
+        }
+        self.indents.clear();
+    }
+
+    fn list_level(&mut self, x: f64, size: f64) -> u8 {
+        let tolerance = 0.5 * size;
+        while self.indents.last().is_some_and(|&indent| indent > x + tolerance) {
+            self.indents.pop();
+        }
+        if self.indents.last().is_none_or(|&indent| indent < x - tolerance) {
+            self.indents.push(x);
+        }
+        (self.indents.len() - 1) as u8
+    }
+
+    /// Split consecutive lines into paragraphs, headings and list items.
+    fn lines(&mut self, lines: &[Line]) {
+        let metrics = self.metrics;
+        let kinds: Vec<Kind> = lines
+            .iter()
+            .map(|line| match metrics.heading_level(line).filter(|_| !self.outline) {
+                Some(level) if line.text().chars().count() < 200 => Kind::Heading(level),
+                _ if line.monospace() => Kind::Code,
+                _ => Kind::Text,
+            })
+            .collect();
+
+        let mut start = 0;
+        for i in 1..=lines.len() {
+            let breaks = i == lines.len() || {
+                let (previous, line) = (&lines[i - 1], &lines[i]);
+                let gap = line.y - previous.y;
+                kinds[i] != kinds[i - 1]
+                    || gap < 0.0
+                    || gap > metrics.paragraph_gap.max(previous.size * 1.1) * (previous.size / metrics.body_size).max(1.0)
+                    || kinds[i] == Kind::Text && list_marker(&line.text()).is_some()
+                    || kinds[i] == Kind::Text && leaves_list_item(previous, line)
+            };
+            if breaks {
+                self.group(&lines[start..i], kinds[start]);
+                start = i;
+            }
+        }
+    }
+
+    fn group(&mut self, lines: &[Line], kind: Kind) {
+        match kind {
+            Kind::Heading(level) => {
+                self.flush_list();
+                self.blocks.push(Block::Heading { level, content: inlines(lines, 0, true, self.cleanup) });
+            }
+            Kind::Code => {
+                self.flush_list();
+                let left = lines.iter().map(|line| line.x0).fold(f64::INFINITY, f64::min);
+                let code: Vec<String> = lines
+                    .iter()
+                    .map(|line| {
+                        let indent = ((line.x0 - left) / (0.6 * line.size)).round().max(0.0) as usize;
+                        format!("{}{}", " ".repeat(indent), line.text().trim_end())
+                    })
+                    .collect();
+                match self.blocks.last_mut() {
+                    Some(Block::CodeBlock { code: previous, .. }) => {
+                        previous.push_str("\n\n");
+                        previous.push_str(&code.join("\n"));
+                    }
+                    _ => self.blocks.push(Block::code(None, code.join("\n"))),
+                }
+            }
+            Kind::Text => {
+                let text = lines[0].text();
+                if let Some((ordered, number, length)) = list_marker(&text) {
+                    let level = self.list_level(lines[0].x0, lines[0].size);
+                    self.entries.push(ListEntry { level, ordered, number, content: inlines(lines, length, false, self.cleanup) });
+                    return;
+                }
+                // Text indented under the last list item continues it
+                if let (Some(entry), Some(&indent)) = (self.entries.last_mut(), self.indents.last()) {
+                    if lines[0].x0 > indent + 0.5 * lines[0].size {
+                        entry.content.push(Inline::Text(" ".to_string()));
+                        entry.content.extend(inlines(lines, 0, false, self.cleanup));
+                        return;
+                    }
+                }
+                self.flush_list();
+
+                let total: usize = lines.iter().map(|line| line.text().chars().count()).sum();
+                let ends_sentence = text.trim_end().ends_with(['.', ',', ';']);
+                if !self.outline
+                    && lines.len() <= 2
+                    && total < 100
+                    && !ends_sentence
+                    && lines.iter().all(|line| line.all(|style| style.bold)) {
+                    let level = self.metrics.bold_heading_level();
+                    self.blocks.push(Block::Heading { level, content: inlines(lines, 0, true, self.cleanup) });
+                } else {
+                    self.blocks.push(Block::Paragraph(inlines(lines, 0, false, self.cleanup)));
+                }
+            }
+        }
+    }
+}
+
+/// Blocks for the lines and tables of all pages. Paragraphs do not continue
+/// across pages. With `outline`, only outline headings are headings.
+pub fn blocks(pages: &[Vec<Item>], cleanup: &Cleanup, outline: bool) -> Vec<Block> {
+    let lines: Vec<Vec<&Line>> = pages
+        .iter()
+        .map(|items| items.iter().filter_map(|item| if let Item::Line(line) = item { Some(line) } else { None }).collect())
+        .collect();
+    let metrics = Metrics::new(&lines);
+    let mut builder = Builder { metrics: &metrics, cleanup, outline, blocks: Vec::new(), entries: Vec::new(), indents: Vec::new() };
+
+    for items in pages {
+        let mut lines: Vec<Line> = Vec::new();
+        for item in items {
+            match item {
+                Item::Line(line) => lines.push(line.clone()),
+                Item::Block(block) => {
+                    builder.lines(&std::mem::take(&mut lines));
+                    builder.flush_list();
+                    builder.blocks.push(block.clone());
+                }
+            }
+        }
+        builder.lines(&lines);
+    }
+    builder.flush_list();
+    builder.blocks
+}
+
+/// A line starting left of the previous line's text ends a list item's
+/// continuation lines.
+fn leaves_list_item(previous: &Line, line: &Line) -> bool {
+    list_marker(&previous.text()).is_some() && line.x0 < previous.x0 - 0.5 * line.size
+}
@@ -0,0 +1,290 @@
+//! Native PDF reader: text with positions from content streams, then layout
+//! analysis for reading order, headings and lists. The outline, when there is
+//! one, decides the headings instead. Scanned pages go through OCR.
+
+mod annots;
+mod content;
+mod crypt;
+mod file;
+mod filter;
+mod font;
+mod forms;
+mod images;
+mod info;
+mod layout;
+mod object;
+mod ocr;
+mod outline;
+mod table;
+
+use crate::cli::Args;
+use crate::config::Config;
+use crate::converter::cleanup::Cleanup;
+use crate::converter::pages::{self, PageSelection};
+use crate::converter::registry::FormatConverter;
+use crate::converter::text::text_to_paragraphs;
+use crate::document::{build_lists, for_each_inline_mut, inlines_plain_text, Block, Document, Footnote, Inline, ListEntry};
+use crate::error::{C2mdError, Result};
+use crate::metadata::DocumentMetadata;
+use crate::renderer::render;
+use crate::utils::slugify_heading;
+use annots::Annotation;
+use content::{Interpreter, PlacedImage};
+use file::PdfFile;
+use info::PdfInfo;
+use layout::{Item, Layout};
+use outline::OutlineEntry;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::Path;
+
+pub struct PdfConverter;
+
+impl FormatConverter for PdfConverter {
+    fn name(&self) -> &str {
+        "pdf"
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["pdf"]
+    }
+
+    fn mime_types(&self) -> &[&str] {
+        &["application/pdf"]
+    }
+
+    fn sniff(&self, header: &[u8]) -> bool {
+        header.starts_with(b"%PDF")
+    }
+
+    fn read(&self, path: &Path, config: &Config, args: &Args) -> Result<Document> {
+        read_pdf(path, config, args)
+    }
+}
+
+pub fn convert_pdf(path: &Path, config: &Config, args: &Args) -> Result<String> {
+    let document = read_pdf(path, config, args)?;
+    Ok(render(&document, config, args))
+}
+
+pub fn read_pdf(path: &Path, config: &Config, args: &Args) -> Result<Document> {
+    let password = args.pdf_password()?;
+    let file = PdfFile::open(fs::read(path)?, password.as_deref())?;
+    let layout = Layout::parse(&config.pdf.layout);
+    let forced = args.ocr || config.ocr.enabled;
+
+    let all_pages = file.pages();
+    let selection = PageSelection::configured(config)?;
+    let numbers = match &selection {
+        Some(selection) => selection.pages(all_pages.len())?,
+        None => (1..=all_pages.len()).collect(),
+    };
+
+    let mut jobs = Vec::new();
+    let mut contents: Vec<Interpreter> = numbers
+        .iter()
+        .map(|&number| {
+            let page = &all_pages[number - 1];
+            let content = Interpreter::new(&file, page).run();
+            if forced || ocr::is_scanned(page, &content.chars, &content.images) {
+                jobs.push(ocr::Job::new(&file, number, &content.images));
+            }
+            content
+        })
+        .collect();
+
+    let lines: Vec<String> = contents.iter().flat_map(|content| layout::runs(&content.chars)).map(|run| run.text()).collect();
+    let cleanup = Cleanup::new(&config.cleanup, lines.iter().map(String::as_str));
+
+    // Reviewers' highlights and comments
+    let annotations: Vec<Vec<Annotation>> = match config.pdf.annotations.as_str() {
+        "none" => Vec::new(),
+        _ => numbers.iter().map(|&number| annots::read(&file, &all_pages[number - 1])).collect(),
+    };
+    if config.pdf.annotations == "only" {
+        let mut document = Document::new();
+        PdfInfo::read(&file).apply_to_document(&mut document);
+        document.pages = selection.map(|_| pages::describe(&numbers));
+        for ((number, content), annotations) in numbers.iter().zip(&contents).zip(&annotations) {
+            let entries = annots::review(annotations, &content.chars, &cleanup);
+            if !entries.is_empty() {
+                document.push(Block::heading(2, format!("Page {}", number)));
+                document.blocks.extend(build_lists(entries));
+            }
+        }
+        if document.blocks.is_empty() {
+            tracing::warn!("{}: no annotations found", path.display());
+        }
+        return Ok(document);
+    }
+    let mut footnotes = Vec::new();
+    for (content, annotations) in contents.iter_mut().zip(&annotations) {
+        annots::apply(annotations, &mut content.chars, &mut footnotes);
+    }
+
+    // Figures, except on pages that are read as a whole by OCR
+    let scanned: HashSet<usize> = jobs.iter().map(|job| job.number).collect();
+    let placed_images: Vec<&[PlacedImage]> = numbers
+        .iter()
+        .zip(&contents)
+        .map(|(number, content)| if scanned.contains(number) { &[][..] } else { &content.images[..] })
+        .collect();
+    let stem = path.file_stem().map(|stem| stem.to_string_lossy().into_owned()).unwrap_or_default();
+    let figures = images::figures(&file, &stem, &placed_images, config, args);
+
+    let mut pages: Vec<Vec<Item>> = contents
+        .iter()
+        .zip(figures)
+        .map(|(content, figures)| layout::page_items(&content.chars, &content.rules, figures, layout, &cleanup))
+        .collect();
+    layout::strip_running(&mut pages, &numbers);
+
+    let mut recognized = false;
+    if !jobs.is_empty() {
+        if which::which("tesseract").is_err() {
+            let numbers: Vec<usize> = jobs.iter().map(|job| job.number).collect();
+            tracing::warn!(
+                "{}: tesseract is not installed, skipping OCR of pages {}",
+                path.display(),
+                pages::describe(&numbers)
+            );
+        } else {
+            let lang = args.ocr_lang.as_deref().unwrap_or(&config.ocr.lang);
+            let mut texts = Vec::new();
+            for (job, result) in jobs.iter().zip(ocr::recognize(path, password.as_deref(), &jobs, lang, &config.cleanup)) {
+                match result {
+                    Ok(text) if !text.trim().is_empty() => texts.push((job.number, text)),
+                    Ok(_) => {}
+                    Err(e) => tracing::warn!("OCR failed for page {} of {}: {}", job.number, path.display(), e),
+                }
+            }
+            let (recognized_pages, texts): (Vec<usize>, Vec<String>) = texts.into_iter().unzip();
+            let stripped = pages::strip_running_text(&texts, &recognized_pages);
+            for (number, text) in recognized_pages.into_iter().zip(stripped) {
+                let index = numbers.partition_point(|&n| n < number);
+                pages[index] = text_to_paragraphs(&text).into_iter().map(Item::Block).collect();
+                recognized = true;
+            }
+        }
+    }
+
+    // Bookmarks into the selected pages
+    let outline = outline::read(&file, &all_pages);
+    let placed: Vec<(usize, &OutlineEntry)> = outline
+        .iter()
+        .filter_map(|entry| Some((numbers.binary_search(&entry.page?).ok()?, entry)))
+        .collect();
+    let headings = layout::apply_outline(&mut pages, &placed, &cleanup);
+
+    if recognized {
+        for (number, items) in numbers.iter().zip(pages.iter_mut()) {
+            items.insert(0, Item::Block(Block::Html(format!("<!-- page {} -->", number))));
+        }
+    }
+
+    let mut document = Document::new();
+    PdfInfo::read(&file).apply_to_document(&mut document);
+    document.pages = selection.map(|_| pages::describe(&numbers));
+    document.blocks = layout::blocks(&pages, &cleanup, !placed.is_empty());
+    if config.pdf.toc && !placed.is_empty() {
+        let levels: Vec<usize> = placed.iter().map(|(_, entry)| entry.level).collect();
+        let toc = table_of_contents(&document.blocks, &levels, &headings, &config.slug);
+        document.blocks.splice(0..0, toc);
+    }
+
+    if document.blocks.is_empty() {
+        tracing::warn!("{}: no text layer found; the PDF may be scanned", path.display());
+    }
+    if config.pdf.forms != "none" {
+        document.blocks.extend(forms::blocks(&forms::read(&file), &config.pdf.forms));
+    }
+    add_footnotes(&mut document, footnotes);
+
+    Ok(document)
+}
+
+/// Attach the comments; those whose text did not make it into the document,
+/// such as notes on scanned pages, are referenced at the end.
+fn add_footnotes(document: &mut Document, footnotes: Vec<Footnote>) {
+    let mut referenced = HashSet::new();
+    for_each_inline_mut(&mut document.blocks, &mut |inline| {
+        if let Inline::FootnoteRef(label) = inline {
+            referenced.insert(label.clone());
+        }
+    });
+    let mut unanchored = Vec::new();
+    for footnote in &footnotes {
+        if !referenced.contains(&footnote.label) {
+            if !unanchored.is_empty() {
+                unanchored.push(Inline::text(" "));
+            }
+            unanchored.push(Inline::FootnoteRef(footnote.label.clone()));
+        }
+    }
+    if !unanchored.is_empty() {
+        unanchored.insert(0, Inline::text("Comments: "));
+        document.push(Block::Paragraph(unanchored));
+    }
+    document.footnotes = footnotes;
+}
+
+/// PDF metadata for `path`, used by [`crate::metadata::extract_metadata`].
+/// Encrypted files that `password` does not open only report that they are.
+pub(crate) fn read_pdf_metadata(path: &Path, password: Option<&str>, metadata: &mut DocumentMetadata) -> Result<()> {
+    match PdfFile::open(fs::read(path)?, password) {
+        Ok(file) => {
+            PdfInfo::read(&file).apply_to_metadata(metadata);
+            metadata.encrypted = Some(file.is_encrypted());
+            metadata.form_fields = forms::read(&file);
+        }
+        Err(C2mdError::Encrypted(_)) => metadata.encrypted = Some(true),
+        Err(e) => return Err(e),
+    }
+    Ok(())
+}
+
+/// Fails with [`C2mdError::Encrypted`] when `password` does not open an
+/// encrypted file; other problems are left to the reader.
+pub(crate) fn check_password(path: &Path, password: Option<&str>) -> Result<()> {
+    match PdfFile::open(fs::read(path)?, password) {
+        Err(e @ C2mdError::Encrypted(_)) => Err(e),
+        _ => Ok(()),
+    }
+}
+
+/// A "Contents" heading and a nested list of links to the outline's
+/// headings, which have the given levels and texts.
+fn table_of_contents(blocks: &[Block], levels: &[usize], headings: &[String], strategy: &str) -> Vec<Block> {
+    let heading = Block::heading(2, "Contents");
+    // Anchors follow the rendered headings, numbered when repeated
+    let mut anchors: HashMap<String, String> = HashMap::new();
+    let mut counts: HashMap<String, usize> = HashMap::new();
+    for block in std::iter::once(&heading).chain(blocks) {
+        if let Block::Heading { content, .. } = block {
+            let text = inlines_plain_text(content);
+            let slug = slugify_heading(&text, strategy);
+            let count = counts.entry(slug.clone()).or_insert(0);
+            let anchor = if *count == 0 { slug } else { format!("{}-{}", slug, count) };
+            *count += 1;
+            anchors.entry(text).or_insert(anchor);
+        }
+    }
+
+    let top = levels.iter().copied().min().unwrap_or(1);
+    let entries = levels
+        .iter()
+        .zip(headings)
+        .map(|(&level, text)| {
+            let label = Inline::text(text.clone());
+            let content = match anchors.get(text) {
+                Some(anchor) => Inline::Link { url: format!("#{}", anchor), title: None, content: vec![label] },
+                None => label,
+            };
+            ListEntry { level: (level - top).min(5) as u8, ordered: false, number: 1, content: vec![content] }
+        })
+        .collect();
+
+    let mut blocks = vec![heading];
+    blocks.extend(build_lists(entries));
+    blocks
+}
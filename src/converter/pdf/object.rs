@@ -0,0 +1,406 @@
+//! PDF objects and the lexer/parser shared by files and content streams.
+
+use crate::error::{C2mdError, Result};
+use std::collections::BTreeMap;
+
+pub type ObjectId = (u32, u16);
+pub type Dictionary = BTreeMap<String, Object>;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Object {
+    Null,
+    Boolean(bool),
+    Integer(i64),
+    Real(f64),
+    Name(String),
+    String(Vec<u8>),
+    Array(Vec<Object>),
+    Dictionary(Dictionary),
+    Stream(Stream),
+    Reference(ObjectId),
+    /// A bare keyword; only content streams have these, as operators.
+    Operator(String),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Stream {
+    pub dict: Dictionary,
+    /// Raw, still encoded data.
+    pub data: Vec<u8>,
+}
+
+impl Object {
+    pub fn as_i64(&self) -> Option<i64> {
+        match self {
+            Object::Integer(value) => Some(*value),
+            Object::Real(value) => Some(*value as i64),
+            _ => None,
+        }
+    }
+
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            Object::Integer(value) => Some(*value as f64),
+            Object::Real(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    pub fn as_name(&self) -> Option<&str> {
+        match self {
+            Object::Name(name) => Some(name),
+            _ => None,
+        }
+    }
+
+    pub fn as_bytes(&self) -> Option<&[u8]> {
+        match self {
+            Object::String(bytes) => Some(bytes),
+            _ => None,
+        }
+    }
+
+    pub fn as_array(&self) -> Option<&[Object]> {
+        match self {
+            Object::Array(items) => Some(items),
+            _ => None,
+        }
+    }
+
+    /// The dictionary of a dictionary or stream object.
+    pub fn as_dict(&self) -> Option<&Dictionary> {
+        match self {
+            Object::Dictionary(dict) => Some(dict),
+            Object::Stream(stream) => Some(&stream.dict),
+            _ => None,
+        }
+    }
+
+    pub fn as_reference(&self) -> Option<ObjectId> {
+        match self {
+            Object::Reference(id) => Some(*id),
+            _ => None,
+        }
+    }
+}
+
+/// Decode a text string: UTF-16BE or UTF-8 with a byte order mark, otherwise
+/// PDFDocEncoding.
+pub fn text_string(bytes: &[u8]) -> String {
+    if let Some(utf16) = bytes.strip_prefix(&[0xFE, 0xFF]) {
+        let units: Vec<u16> = utf16.chunks_exact(2).map(|pair| u16::from_be_bytes([pair[0], pair[1]])).collect();
+        return String::from_utf16_lossy(&units);
+    }
+    if let Some(utf8) = bytes.strip_prefix(&[0xEF, 0xBB, 0xBF]) {
+        return String::from_utf8_lossy(utf8).into_owned();
+    }
+    bytes.iter().map(|&byte| pdf_doc_char(byte)).collect()
+}
+
+/// PDFDocEncoding: Latin-1 except for accents in 0x18..=0x1F and
+/// typographic characters in 0x80..=0xA0.
+fn pdf_doc_char(byte: u8) -> char {
+    const ACCENTS: [char; 8] = ['\u{2D8}', '\u{2C7}', '\u{2C6}', '\u{2D9}', '\u{2DD}', '\u{2DB}', '\u{2DA}', '\u{2DC}'];
+    const HIGH: [char; 33] = [
+        '\u{2022}', '\u{2020}', '\u{2021}', '\u{2026}', '\u{2014}', '\u{2013}', '\u{192}', '\u{2044}', '\u{2039}', '\u{203A}',
+        '\u{2212}', '\u{2030}', '\u{201E}', '\u{201C}', '\u{201D}', '\u{2018}', '\u{2019}', '\u{201A}', '\u{2122}', '\u{FB01}',
+        '\u{FB02}', '\u{141}', '\u{152}', '\u{160}', '\u{178}', '\u{17D}', '\u{131}', '\u{142}', '\u{153}', '\u{161}', '\u{17E}',
+        '\u{FFFD}', '\u{20AC}',
+    ];
+    match byte {
+        0x18..=0x1F => ACCENTS[(byte - 0x18) as usize],
+        0x80..=0xA0 => HIGH[(byte - 0x80) as usize],
+        _ => byte as char,
+    }
+}
+
+pub fn is_whitespace(byte: u8) -> bool {
+    matches!(byte, b' ' | b'\t' | b'\n' | b'\r' | b'\x0c' | b'\0')
+}
+
+pub fn is_delimiter(byte: u8) -> bool {
+    matches!(byte, b'(' | b')' | b'<' | b'>' | b'[' | b']' | b'{' | b'}' | b'/' | b'%')
+}
+
+/// Recursive-descent parser over a byte buffer.
+pub struct Parser<'a> {
+    data: &'a [u8],
+    pub pos: usize,
+}
+
+impl<'a> Parser<'a> {
+    pub fn new(data: &'a [u8], pos: usize) -> Self {
+        Self { data, pos }
+    }
+
+    pub fn at_end(&mut self) -> bool {
+        self.skip_whitespace();
+        self.pos >= self.data.len()
+    }
+
+    fn error(&self, message: &str) -> C2mdError {
+        C2mdError::Pdf(format!("{} at offset {}", message, self.pos))
+    }
+
+    pub fn skip_whitespace(&mut self) {
+        while self.pos < self.data.len() {
+            match self.data[self.pos] {
+                b'%' => {
+                    while self.pos < self.data.len() && !matches!(self.data[self.pos], b'\r' | b'\n') {
+                        self.pos += 1;
+                    }
+                }
+                byte if is_whitespace(byte) => self.pos += 1,
+                _ => break,
+            }
+        }
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.data.get(self.pos).copied()
+    }
+
+    /// The next run of regular characters, e.g. a number or keyword.
+    pub fn word(&mut self) -> &'a [u8] {
+        self.skip_whitespace();
+        let start = self.pos;
+        while self.pos < self.data.len() && !is_whitespace(self.data[self.pos]) && !is_delimiter(self.data[self.pos]) {
+            self.pos += 1;
+        }
+        &self.data[start..self.pos]
+    }
+
+    /// Consume `keyword` if it comes next.
+    pub fn keyword(&mut self, keyword: &str) -> bool {
+        let saved = self.pos;
+        if self.word() == keyword.as_bytes() {
+            true
+        } else {
+            self.pos = saved;
+            false
+        }
+    }
+
+    /// Parse one object. In file mode `n g R` is a reference; keywords other
+    /// than `true`, `false` and `null` are operators.
+    pub fn object(&mut self) -> Result<Object> {
+        self.skip_whitespace();
+        let Some(byte) = self.peek() else {
+            return Err(self.error("Unexpected end of data"));
+        };
+        match byte {
+            b'/' => {
+                self.pos += 1;
+                Ok(Object::Name(self.name()))
+            }
+            b'(' => {
+                self.pos += 1;
+                Ok(Object::String(self.literal_string()))
+            }
+            b'<' if self.data.get(self.pos + 1) == Some(&b'<') => {
+                self.pos += 2;
+                self.dictionary()
+            }
+            b'<' => {
+                self.pos += 1;
+                Ok(Object::String(self.hex_string()))
+            }
+            b'[' => {
+                self.pos += 1;
+                let mut items = Vec::new();
+                loop {
+                    self.skip_whitespace();
+                    match self.peek() {
+                        Some(b']') => {
+                            self.pos += 1;
+                            break;
+                        }
+                        None => break,
+                        _ => items.push(self.object()?),
+                    }
+                }
+                Ok(Object::Array(items))
+            }
+            b']' | b'>' | b')' | b'{' | b'}' => {
+                // Stray delimiter: skip it rather than fail the whole stream
+                self.pos += 1;
+                Ok(Object::Null)
+            }
+            _ => {
+                let word = self.word();
+                if word.is_empty() {
+                    self.pos += 1;
+                    return Ok(Object::Null);
+                }
+                match word {
+                    b"true" => Ok(Object::Boolean(true)),
+                    b"false" => Ok(Object::Boolean(false)),
+                    b"null" => Ok(Object::Null),
+                    _ => match parse_number(word) {
+                        Some(Object::Integer(number)) => Ok(self.reference_after(number)),
+                        Some(number) => Ok(number),
+                        None => Ok(Object::Operator(String::from_utf8_lossy(word).to_string())),
+                    },
+                }
+            }
+        }
+    }
+
+    /// `n g R` following an integer `n`, or the integer itself.
+    fn reference_after(&mut self, number: i64) -> Object {
+        let saved = self.pos;
+        let generation = self.word();
+        if let Some(Object::Integer(generation)) = parse_number(generation) {
+            if self.word() == b"R" && number >= 0 && (0..=65535).contains(&generation) {
+                return Object::Reference((number as u32, generation as u16));
+            }
+        }
+        self.pos = saved;
+        Object::Integer(number)
+    }
+
+    fn name(&mut self) -> String {
+        let mut name = Vec::new();
+        while let Some(byte) = self.peek() {
+            if is_whitespace(byte) || is_delimiter(byte) {
+                break;
+            }
+            self.pos += 1;
+            let hex = self.data.get(self.pos..self.pos + 2).and_then(|hex| std::str::from_utf8(hex).ok());
+            match (byte, hex.and_then(|hex| u8::from_str_radix(hex, 16).ok())) {
+                (b'#', Some(decoded)) => {
+                    name.push(decoded);
+                    self.pos += 2;
+                }
+                (byte, _) => name.push(byte),
+            }
+        }
+        String::from_utf8_lossy(&name).to_string()
+    }
+
+    fn literal_string(&mut self) -> Vec<u8> {
+        let mut out = Vec::new();
+        let mut depth = 1;
+        while let Some(byte) = self.peek() {
+            self.pos += 1;
+            match byte {
+                b'(' => {
+                    depth += 1;
+                    out.push(byte);
+                }
+                b')' => {
+                    depth -= 1;
+                    if depth == 0 {
+                        break;
+                    }
+                    out.push(byte);
+                }
+                b'\\' => {
+                    let Some(escaped) = self.peek() else {
+                        break;
+                    };
+                    self.pos += 1;
+                    match escaped {
+                        b'n' => out.push(b'\n'),
+                        b'r' => out.push(b'\r'),
+                        b't' => out.push(b'\t'),
+                        b'b' => out.push(8),
+                        b'f' => out.push(12),
+                        b'0'..=b'7' => {
+                            let mut value = (escaped - b'0') as u32;
+                            for _ in 0..2 {
+                                match self.peek() {
+                                    Some(digit @ b'0'..=b'7') => {
+                                        value = value * 8 + (digit - b'0') as u32;
+                                        self.pos += 1;
+                                    }
+                                    _ => break,
+                                }
+                            }
+                            out.push(value as u8);
+                        }
+                        // A backslash before a line end continues the string
+                        b'\r' => {
+                            if self.peek() == Some(b'\n') {
+                                self.pos += 1;
+                            }
+                        }
+                        b'\n' => {}
+                        other => out.push(other),
+                    }
+                }
+                _ => out.push(byte),
+            }
+        }
+        out
+    }
+
+    fn hex_string(&mut self) -> Vec<u8> {
+        let mut digits = Vec::new();
+        while let Some(byte) = self.peek() {
+            self.pos += 1;
+            match byte {
+                b'>' => break,
+                byte if byte.is_ascii_hexdigit() => digits.push(byte),
+                _ => {}
+            }
+        }
+        if digits.len() % 2 == 1 {
+            digits.push(b'0');
+        }
+        digits
+            .chunks(2)
+            .filter_map(|pair| u8::from_str_radix(std::str::from_utf8(pair).ok()?, 16).ok())
+            .collect()
+    }
+
+    fn dictionary(&mut self) -> Result<Object> {
+        let mut dict = Dictionary::new();
+        loop {
+            self.skip_whitespace();
+            match self.peek() {
+                Some(b'>') => {
+                    self.pos += 1;
+                    if self.peek() == Some(b'>') {
+                        self.pos += 1;
+                    }
+                    break;
+                }
+                Some(b'/') => {
+                    self.pos += 1;
+                    let key = self.name();
+                    let value = self.object()?;
+                    dict.insert(key, value);
+                }
+                None => break,
+                _ => {
+                    // Not a key; skip the value to resynchronize
+                    self.object()?;
+                }
+            }
+        }
+        Ok(Object::Dictionary(dict))
+    }
+}
+
+pub fn parse_number(word: &[u8]) -> Option<Object> {
+    let text = std::str::from_utf8(word).ok()?;
+    if text.is_empty() || !text.bytes().all(|b| b.is_ascii_digit() || matches!(b, b'+' | b'-' | b'.')) {
+        return None;
+    }
+    if !text.contains('.') {
+        if let Ok(value) = text.parse::<i64>() {
+            return Some(Object::Integer(value));
+        }
+    }
+    // Producers write oddities such as `--5` or `1.2.3`; keep what parses
+    let cleaned: String = text.trim_start_matches(['+', '-']).to_string();
+    let negative = text.starts_with('-');
+    let mut parts = cleaned.splitn(2, '.');
+    let whole = parts.next().unwrap_or_default();
+    let fraction: String = parts.next().unwrap_or_default().chars().filter(char::is_ascii_digit).collect();
+    let value: f64 = format!("{}.{}", if whole.is_empty() { "0" } else { whole }, if fraction.is_empty() { "0" } else { &fraction })
+        .parse()
+        .ok()?;
+    Some(Object::Real(if negative { -value } else { value }))
+}
@@ -0,0 +1,113 @@
+//! OCR for scanned pages: rasterize the page with `pdftoppm`, or fall back to
+//! the page's embedded JPEG, and read the image with tesseract.
+
+use super::content::{PlacedImage, TextChar};
+use super::file::{Page, PdfFile};
+use crate::config::CleanupConfig;
+use crate::converter::image::extract_text_from_image;
+use crate::error::{C2mdError, Result};
+use rayon::prelude::*;
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+
+/// Rasterization resolution; tesseract does best around 300 dpi.
+const DPI: &str = "300";
+
+/// Pages whose images cover less than this fraction are never scanned.
+const MIN_IMAGE_COVERAGE: f64 = 0.3;
+
+/// Scans carry little or no text per unit of image coverage; a full-page
+/// image with fewer glyphs than this is taken to be a scan.
+const MIN_CHARS_PER_COVERAGE: f64 = 200.0;
+
+/// A page to OCR, by its 1-based number.
+pub struct Job {
+    pub number: usize,
+    /// The largest embedded JPEG, used when `pdftoppm` is missing.
+    jpeg: Option<Vec<u8>>,
+}
+
+impl Job {
+    pub fn new(file: &PdfFile, number: usize, images: &[PlacedImage]) -> Self {
+        let jpeg = if has_pdftoppm() { None } else { largest_jpeg(file, images) };
+        Self { number, jpeg }
+    }
+}
+
+/// Judge from the ratio of text to image coverage whether a page is a scan
+/// without a (useful) text layer.
+pub fn is_scanned(page: &Page, chars: &[TextChar], images: &[PlacedImage]) -> bool {
+    if images.is_empty() {
+        return false;
+    }
+    let text = chars.iter().filter(|c| !c.text.trim().is_empty()).count();
+    if text == 0 {
+        return true;
+    }
+    let (width, height) = page.size();
+    let area = width * height;
+    if area <= 0.0 {
+        return false;
+    }
+    let covered: f64 = images
+        .iter()
+        .map(|image| {
+            let w = image.x1.min(width) - image.x0.max(0.0);
+            let h = image.y1.min(height) - image.y0.max(0.0);
+            w.max(0.0) * h.max(0.0)
+        })
+        .sum();
+    let coverage = (covered / area).min(1.0);
+    coverage >= MIN_IMAGE_COVERAGE && (text as f64) / coverage < MIN_CHARS_PER_COVERAGE
+}
+
+/// OCR the pages in parallel; results come back in the order of `jobs`.
+/// Encrypted files are rasterized with the `password` that opened them.
+pub fn recognize(path: &Path, password: Option<&str>, jobs: &[Job], lang: &str, cleanup: &CleanupConfig) -> Vec<Result<String>> {
+    jobs.par_iter().map(|job| recognize_page(path, password, job, lang, cleanup)).collect()
+}
+
+fn recognize_page(path: &Path, password: Option<&str>, job: &Job, lang: &str, cleanup: &CleanupConfig) -> Result<String> {
+    let dir = tempfile::tempdir()?;
+    let image = match &job.jpeg {
+        Some(data) => {
+            let image = dir.path().join("page.jpg");
+            fs::write(&image, data)?;
+            image
+        }
+        None => rasterize(path, password, job.number, dir.path())?,
+    };
+    extract_text_from_image(&image, lang, cleanup)
+}
+
+fn rasterize(path: &Path, password: Option<&str>, number: usize, dir: &Path) -> Result<std::path::PathBuf> {
+    if !has_pdftoppm() {
+        return Err(C2mdError::MissingDependency(
+            "pdftoppm not found and the page has no embedded JPEG. Please install poppler-utils".to_string(),
+        ));
+    }
+    let page = number.to_string();
+    let prefix = dir.join("page");
+    let mut command = Command::new("pdftoppm");
+    command.args(["-r", DPI, "-f", &page, "-l", &page, "-singlefile", "-png"]);
+    if let Some(password) = password {
+        // Either password may be the one given; poppler tries the owner's first
+        command.args(["-opw", password, "-upw", password]);
+    }
+    let output = command.arg(path).arg(&prefix).output()?;
+    if !output.status.success() {
+        return Err(C2mdError::Ocr(format!("pdftoppm failed: {}", String::from_utf8_lossy(&output.stderr))));
+    }
+    Ok(prefix.with_extension("png"))
+}
+
+fn has_pdftoppm() -> bool {
+    which::which("pdftoppm").is_ok()
+}
+
+fn largest_jpeg(file: &PdfFile, images: &[PlacedImage]) -> Option<Vec<u8>> {
+    let image = images.iter().filter(|image| image.stream.is_some()).max_by(|a, b| a.area().total_cmp(&b.area()))?;
+    let (data, codec) = file.decode_stream_with_codec(image.stream.as_ref()?).ok()?;
+    matches!(codec.as_deref(), Some("DCTDecode" | "DCT")).then_some(data)
+}
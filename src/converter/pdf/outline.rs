@@ -0,0 +1,148 @@
+//! The document outline (bookmarks): titles, nesting and destinations.
+
+use super::file::{Page, PdfFile};
+use super::object::{text_string, Dictionary, Object, ObjectId};
+use std::collections::HashSet;
+
+/// Outlines nest a handful of levels deep; this bounds malformed trees.
+const MAX_DEPTH: usize = 16;
+
+/// One bookmark.
+#[derive(Debug, Clone)]
+pub struct OutlineEntry {
+    pub title: String,
+    /// Nesting depth, 1 for top level bookmarks.
+    pub level: usize,
+    /// Destination page, 1-based.
+    pub page: Option<usize>,
+    /// Top of the destination view in display space, when given.
+    pub top: Option<f64>,
+}
+
+/// The outline in document order; empty when the document has none.
+pub fn read(file: &PdfFile, pages: &[Page]) -> Vec<OutlineEntry> {
+    let catalog = file.catalog();
+    let Some(outlines) = file.dict_entry(&catalog, "Outlines") else {
+        return Vec::new();
+    };
+    let reader = Reader { file, pages, catalog: &catalog };
+    let mut entries = Vec::new();
+    let mut visited = HashSet::new();
+    reader.children(&outlines, 1, &mut entries, &mut visited);
+    entries
+}
+
+struct Reader<'a> {
+    file: &'a PdfFile,
+    pages: &'a [Page],
+    catalog: &'a Dictionary,
+}
+
+impl Reader<'_> {
+    fn children(&self, parent: &Dictionary, level: usize, entries: &mut Vec<OutlineEntry>, visited: &mut HashSet<ObjectId>) {
+        if level > MAX_DEPTH {
+            return;
+        }
+        let mut next = parent.get("First").cloned();
+        while let Some(node) = next {
+            if let Some(id) = node.as_reference() {
+                if !visited.insert(id) {
+                    break;
+                }
+            }
+            let Some(item) = self.file.resolve(&node).as_dict().cloned() else {
+                break;
+            };
+            let title = self.file.entry(&item, "Title").as_bytes().map(text_string).unwrap_or_default();
+            let title = title.split_whitespace().collect::<Vec<_>>().join(" ");
+            if !title.is_empty() {
+                let (page, top) = self.destination(&item).unwrap_or((None, None));
+                entries.push(OutlineEntry { title, level, page, top });
+            }
+            self.children(&item, level + 1, entries, visited);
+            next = item.get("Next").cloned();
+        }
+    }
+
+    /// The item's `/Dest`, or the destination of a `/GoTo` action.
+    fn destination(&self, item: &Dictionary) -> Option<(Option<usize>, Option<f64>)> {
+        let dest = match self.file.entry(item, "Dest") {
+            Object::Null => {
+                let action = self.file.dict_entry(item, "A")?;
+                if self.file.entry(&action, "S").as_name() != Some("GoTo") {
+                    return None;
+                }
+                self.file.entry(&action, "D")
+            }
+            dest => dest,
+        };
+        let array = match dest {
+            Object::Array(array) => array,
+            Object::Name(name) => self.named(name.as_bytes())?,
+            Object::String(name) => self.named(&name)?,
+            _ => return None,
+        };
+        Some(self.explicit(&array))
+    }
+
+    /// A named destination from the catalog's `/Dests` dictionary or the
+    /// `/Dests` name tree.
+    fn named(&self, name: &[u8]) -> Option<Vec<Object>> {
+        let found = match self.file.dict_entry(self.catalog, "Dests") {
+            Some(dests) => dests.get(&String::from_utf8_lossy(name).into_owned()).cloned(),
+            None => {
+                let names = self.file.dict_entry(self.catalog, "Names")?;
+                let tree = self.file.dict_entry(&names, "Dests")?;
+                self.lookup(&tree, name, 0)
+            }
+        }?;
+        // Either the array itself or a dictionary holding it under /D
+        match self.file.resolve(&found) {
+            Object::Array(array) => Some(array),
+            Object::Dictionary(dict) => self.file.entry(&dict, "D").as_array().map(<[Object]>::to_vec),
+            _ => None,
+        }
+    }
+
+    fn lookup(&self, node: &Dictionary, name: &[u8], depth: usize) -> Option<Object> {
+        if depth > MAX_DEPTH {
+            return None;
+        }
+        if let Object::Array(pairs) = self.file.entry(node, "Names") {
+            for pair in pairs.chunks_exact(2) {
+                if self.file.resolve(&pair[0]).as_bytes() == Some(name) {
+                    return Some(pair[1].clone());
+                }
+            }
+        }
+        if let Object::Array(kids) = self.file.entry(node, "Kids") {
+            for kid in &kids {
+                if let Some(kid) = self.file.resolve(kid).as_dict() {
+                    if let Some(found) = self.lookup(kid, name, depth + 1) {
+                        return Some(found);
+                    }
+                }
+            }
+        }
+        None
+    }
+
+    /// `[page /XYZ left top zoom]`, `[page /FitH top]` and the like.
+    fn explicit(&self, array: &[Object]) -> (Option<usize>, Option<f64>) {
+        let page = match array.first() {
+            Some(Object::Reference(id)) => self.pages.iter().position(|page| page.id == Some(*id)),
+            // Remote destinations number pages from zero
+            Some(Object::Integer(index)) => usize::try_from(*index).ok().filter(|&index| index < self.pages.len()),
+            _ => None,
+        };
+        let number = |i: usize| array.get(i).map(|value| self.file.resolve(value)).and_then(|value| value.as_f64());
+        let top = match array.get(1).and_then(Object::as_name) {
+            Some("XYZ") => number(3),
+            Some("FitH" | "FitBH") => number(2),
+            Some("FitR") => number(5),
+            _ => None,
+        };
+        let top = page.zip(top).map(|(index, top)| self.pages[index].to_display(0.0, top).1);
+        (page.map(|index| index + 1), top)
+    }
+}
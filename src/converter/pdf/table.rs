@@ -0,0 +1,370 @@
+//! Table detection: grids of ruling lines and x-aligned columns of text.
+
+use super::content::{Rule, TextChar};
+use super::layout::{baseline_lines, inlines, list_marker, runs, Line};
+use crate::document::{Alignment, Block, Inline, Table};
+use crate::converter::cleanup::Cleanup;
+use crate::renderer::render_inlines_html;
+
+/// Distance within which rulings count as touching or coinciding.
+const TOLERANCE: f64 = 2.0;
+
+/// A detected table, or an image, and the area it covers.
+pub struct Region {
+    pub x0: f64,
+    pub y0: f64,
+    pub x1: f64,
+    pub block: Block,
+}
+
+/// A cell of a ruled grid, with its span in grid rows and columns.
+struct GridCell {
+    row: usize,
+    column: usize,
+    rows: usize,
+    columns: usize,
+    content: Vec<Inline>,
+}
+
+/// Tables drawn with ruling lines. The glyphs inside them are taken out of
+/// `chars`.
+pub fn ruled_tables(chars: &mut Vec<TextChar>, rules: &[Rule], cleanup: &Cleanup) -> Vec<Region> {
+    let (horizontal, vertical): (Vec<Rule>, Vec<Rule>) = rules.iter().partition(|rule| rule.is_horizontal());
+    let horizontal = merge_rules(horizontal, true);
+    let vertical = merge_rules(vertical, false);
+
+    // Group rulings that cross or touch into candidate grids
+    let count = horizontal.len() + vertical.len();
+    let mut parent: Vec<usize> = (0..count).collect();
+    fn root(parent: &mut [usize], mut i: usize) -> usize {
+        while parent[i] != i {
+            parent[i] = parent[parent[i]];
+            i = parent[i];
+        }
+        i
+    }
+    for (i, h) in horizontal.iter().enumerate() {
+        for (j, v) in vertical.iter().enumerate() {
+            let touches = v.x0 >= h.x0 - TOLERANCE
+                && v.x0 <= h.x1 + TOLERANCE
+                && h.y0 >= v.y0 - TOLERANCE
+                && h.y0 <= v.y1 + TOLERANCE;
+            if touches {
+                let (a, b) = (root(&mut parent, i), root(&mut parent, horizontal.len() + j));
+                parent[a] = b;
+            }
+        }
+    }
+    let mut groups: Vec<(Vec<Rule>, Vec<Rule>)> = Vec::new();
+    let mut group_of: Vec<Option<usize>> = vec![None; count];
+    for i in 0..count {
+        let r = root(&mut parent, i);
+        let group = *group_of[r].get_or_insert_with(|| {
+            groups.push((Vec::new(), Vec::new()));
+            groups.len() - 1
+        });
+        if i < horizontal.len() {
+            groups[group].0.push(horizontal[i]);
+        } else {
+            groups[group].1.push(vertical[i - horizontal.len()]);
+        }
+    }
+
+    let mut tables = Vec::new();
+    for (horizontal, vertical) in groups {
+        if let Some(table) = grid_table(chars, &horizontal, &vertical, cleanup) {
+            tables.push(table);
+        }
+    }
+    tables
+}
+
+/// Join collinear, overlapping or touching rulings.
+fn merge_rules(mut rules: Vec<Rule>, horizontal: bool) -> Vec<Rule> {
+    let key = |rule: &Rule| if horizontal { (rule.y0, rule.x0) } else { (rule.x0, rule.y0) };
+    rules.sort_by(|a, b| key(a).partial_cmp(&key(b)).unwrap_or(std::cmp::Ordering::Equal));
+    let mut merged: Vec<Rule> = Vec::new();
+    for rule in rules {
+        if let Some(last) = merged.last_mut() {
+            let (same_line, touching) = if horizontal {
+                ((rule.y0 - last.y0).abs() < 1.0, rule.x0 <= last.x1 + TOLERANCE)
+            } else {
+                ((rule.x0 - last.x0).abs() < 1.0, rule.y0 <= last.y1 + TOLERANCE)
+            };
+            if same_line && touching {
+                last.x1 = last.x1.max(rule.x1);
+                last.y1 = last.y1.max(rule.y1);
+                continue;
+            }
+        }
+        merged.push(rule);
+    }
+    merged
+}
+
+/// Distinct positions, closer ones merged.
+fn positions(mut values: Vec<f64>) -> Vec<f64> {
+    values.sort_by(f64::total_cmp);
+    let mut out: Vec<f64> = Vec::new();
+    for value in values {
+        if out.last().is_none_or(|&last| value - last > 3.0) {
+            out.push(value);
+        }
+    }
+    out
+}
+
+fn grid_table(chars: &mut Vec<TextChar>, horizontal: &[Rule], vertical: &[Rule], cleanup: &Cleanup) -> Option<Region> {
+    let mut xs = positions(vertical.iter().map(|rule| rule.x0).collect());
+    let mut ys = positions(horizontal.iter().map(|rule| rule.y0).collect());
+    // Tables with outer borders only on some sides still span the rulings' extent
+    let left = horizontal.iter().map(|rule| rule.x0).chain(xs.iter().copied()).fold(f64::INFINITY, f64::min);
+    let right = horizontal.iter().map(|rule| rule.x1).chain(xs.iter().copied()).fold(f64::NEG_INFINITY, f64::max);
+    let top = vertical.iter().map(|rule| rule.y0).chain(ys.iter().copied()).fold(f64::INFINITY, f64::min);
+    let bottom = vertical.iter().map(|rule| rule.y1).chain(ys.iter().copied()).fold(f64::NEG_INFINITY, f64::max);
+    for (edges, low, high) in [(&mut xs, left, right), (&mut ys, top, bottom)] {
+        if edges.first().is_none_or(|&first| first - low > 3.0) {
+            edges.insert(0, low);
+        }
+        if edges.last().is_none_or(|&last| high - last > 3.0) {
+            edges.push(high);
+        }
+    }
+    let (columns, rows) = (xs.len().checked_sub(1)?, ys.len().checked_sub(1)?);
+    // A single box around text is a frame, not a table
+    if columns < 2 || rows < 2 {
+        return None;
+    }
+
+    let has_vertical = |x: f64, y: f64| {
+        vertical.iter().any(|rule| (rule.x0 - x).abs() <= TOLERANCE && rule.y0 - TOLERANCE <= y && y <= rule.y1 + TOLERANCE)
+    };
+    let has_horizontal = |x: f64, y: f64| {
+        horizontal.iter().any(|rule| (rule.y0 - y).abs() <= TOLERANCE && rule.x0 - TOLERANCE <= x && x <= rule.x1 + TOLERANCE)
+    };
+    let middle = |edges: &[f64], i: usize| (edges[i] + edges[i + 1]) / 2.0;
+
+    // Missing separators merge neighbouring cells
+    let mut covered = vec![vec![false; columns]; rows];
+    let mut cells: Vec<GridCell> = Vec::new();
+    for row in 0..rows {
+        for column in 0..columns {
+            if covered[row][column] {
+                continue;
+            }
+            let mut span_columns = 1;
+            while column + span_columns < columns && !has_vertical(xs[column + span_columns], middle(&ys, row)) {
+                span_columns += 1;
+            }
+            let mut span_rows = 1;
+            while row + span_rows < rows
+                && (column..column + span_columns).all(|c| !has_horizontal(middle(&xs, c), ys[row + span_rows]))
+            {
+                span_rows += 1;
+            }
+            for covered_row in covered.iter_mut().skip(row).take(span_rows) {
+                for cell in covered_row.iter_mut().skip(column).take(span_columns) {
+                    *cell = true;
+                }
+            }
+            cells.push(GridCell { row, column, rows: span_rows, columns: span_columns, content: Vec::new() });
+        }
+    }
+
+    // Hand each glyph to the cell containing its centre
+    let (x_min, x_max, y_min, y_max) = (xs[0], xs[columns], ys[0], ys[rows]);
+    let assignment: Vec<Option<usize>> = chars
+        .iter()
+        .map(|c| {
+            let (cx, cy) = (c.x + c.width / 2.0, c.y - c.size / 3.0);
+            if cx <= x_min || cx >= x_max || cy <= y_min || cy >= y_max {
+                return None;
+            }
+            cells.iter().position(|cell| {
+                cx >= xs[cell.column] && cx < xs[cell.column + cell.columns] && cy >= ys[cell.row] && cy < ys[cell.row + cell.rows]
+            })
+        })
+        .collect();
+    let mut filled: Vec<usize> = assignment.iter().flatten().copied().collect();
+    filled.sort_unstable();
+    filled.dedup();
+    if filled.len() < 2 {
+        return None;
+    }
+    let mut cell_chars: Vec<Vec<TextChar>> = vec![Vec::new(); cells.len()];
+    let mut remaining = Vec::with_capacity(chars.len());
+    for (c, cell) in chars.drain(..).zip(assignment) {
+        match cell {
+            Some(index) => cell_chars[index].push(c),
+            None => remaining.push(c),
+        }
+    }
+    *chars = remaining;
+
+    for (cell, chars) in cells.iter_mut().zip(cell_chars) {
+        let lines = baseline_lines(runs(&chars));
+        cell.content = inlines(&lines, 0, false, cleanup);
+    }
+
+    let block = if cells.iter().all(|cell| cell.rows == 1 && cell.columns == 1) {
+        let mut grid: Vec<Vec<Vec<Inline>>> = vec![vec![Vec::new(); columns]; rows];
+        for cell in cells {
+            grid[cell.row][cell.column] = cell.content;
+        }
+        let header = grid.remove(0);
+        Block::Table(Table { alignments: vec![Alignment::None; columns], header, rows: grid })
+    } else {
+        Block::Html(html_table(&cells, rows))
+    };
+    Some(Region { x0: x_min, y0: y_min, x1: x_max, block })
+}
+
+/// Merged cells have no Markdown form; write them as an HTML table.
+fn html_table(cells: &[GridCell], rows: usize) -> String {
+    let mut lines = vec!["<table>".to_string()];
+    for row in 0..rows {
+        let tag = if row == 0 { "th" } else { "td" };
+        let mut line = String::from("<tr>");
+        for cell in cells.iter().filter(|cell| cell.row == row) {
+            let mut attributes = String::new();
+            if cell.columns > 1 {
+                attributes.push_str(&format!(" colspan=\"{}\"", cell.columns));
+            }
+            if cell.rows > 1 {
+                attributes.push_str(&format!(" rowspan=\"{}\"", cell.rows));
+            }
+            line.push_str(&format!("<{}{}>{}</{}>", tag, attributes, render_inlines_html(&cell.content), tag));
+        }
+        line.push_str("</tr>");
+        lines.push(line);
+    }
+    lines.push("</table>".to_string());
+    lines.join("\n")
+}
+
+/// Tables without rulings: at least three consecutive rows of two or more
+/// runs whose x extents line up in columns. The runs used are taken out of
+/// `runs`.
+pub fn aligned_tables(runs: &mut Vec<Line>, cleanup: &Cleanup) -> Vec<Region> {
+    let mut order: Vec<usize> = (0..runs.len()).collect();
+    order.sort_by(|&a, &b| runs[a].y.total_cmp(&runs[b].y));
+    let mut rows: Vec<Vec<usize>> = Vec::new();
+    for i in order {
+        match rows.last_mut() {
+            Some(row) if (runs[i].y - runs[row[0]].y).abs() < 0.4 * runs[row[0]].size.min(runs[i].size) => row.push(i),
+            _ => rows.push(vec![i]),
+        }
+    }
+    for row in &mut rows {
+        row.sort_by(|&a, &b| runs[a].x0.total_cmp(&runs[b].x0));
+    }
+
+    let mut tables = Vec::new();
+    let mut used = vec![false; runs.len()];
+    let mut start = 0;
+    while start < rows.len() {
+        let mut end = start;
+        while end < rows.len()
+            && rows[end].len() >= 2
+            && (end == start || runs[rows[end][0]].y - runs[rows[end - 1][0]].y <= 2.5 * runs[rows[end][0]].size)
+        {
+            end += 1;
+        }
+        if end - start >= 3 {
+            let table_rows: Vec<Vec<&Line>> = rows[start..end].iter().map(|row| row.iter().map(|&i| &runs[i]).collect()).collect();
+            if let Some(table) = aligned_table(&table_rows, cleanup) {
+                tables.push(table);
+                for &i in rows[start..end].iter().flatten() {
+                    used[i] = true;
+                }
+            }
+        }
+        start = end.max(start + 1);
+    }
+    // Keep the remaining runs in stream order
+    let mut used = used.into_iter();
+    runs.retain(|_| !used.next().unwrap_or(false));
+    tables
+}
+
+fn aligned_table(rows: &[Vec<&Line>], cleanup: &Cleanup) -> Option<Region> {
+    // Columns are the union of overlapping run extents
+    let mut extents: Vec<(f64, f64)> = rows.iter().flatten().map(|run| (run.x0, run.x1)).collect();
+    extents.sort_by(|a, b| a.0.total_cmp(&b.0));
+    let mut columns: Vec<(f64, f64)> = Vec::new();
+    for (x0, x1) in extents {
+        match columns.last_mut() {
+            Some(column) if x0 <= column.1 => column.1 = column.1.max(x1),
+            _ => columns.push((x0, x1)),
+        }
+    }
+    if columns.len() < 2 {
+        return None;
+    }
+    let column_of = |run: &Line| columns.iter().position(|&(x0, x1)| run.x0 >= x0 && run.x1 <= x1).unwrap_or(0);
+
+    let mut cells: Vec<Vec<Vec<&Line>>> = vec![vec![Vec::new(); columns.len()]; rows.len()];
+    for (cells, row) in cells.iter_mut().zip(rows) {
+        for run in row {
+            cells[column_of(run)].push(run);
+        }
+    }
+
+    // Aligned monospaced text is a code listing
+    if rows.iter().flatten().all(|run| run.monospace()) {
+        return None;
+    }
+
+    // Prose in columns and list markers before items are not tables
+    for column in 0..columns.len() {
+        let mut words: Vec<usize> = cells
+            .iter()
+            .flat_map(|row| row[column].iter())
+            .map(|run| run.text().split_whitespace().count())
+            .collect();
+        words.sort_unstable();
+        if words.get(words.len() / 2).is_some_and(|&median| median > 6) {
+            return None;
+        }
+    }
+    let markers = cells.iter().all(|row| {
+        row[0].iter().all(|run| {
+            let text = run.text();
+            list_marker(&format!("{} x", text.trim())).is_some_and(|(_, _, length)| length >= text.trim().chars().count())
+        })
+    });
+    if markers {
+        return None;
+    }
+
+    let alignments: Vec<Alignment> = (0..columns.len())
+        .map(|column| {
+            let runs: Vec<&Line> = cells.iter().flat_map(|row| row[column].iter().copied()).collect();
+            let spread = |f: &dyn Fn(&Line) -> f64| {
+                let values: Vec<f64> = runs.iter().map(|run| f(run)).collect();
+                values.iter().copied().fold(f64::NEG_INFINITY, f64::max) - values.iter().copied().fold(f64::INFINITY, f64::min)
+            };
+            if runs.len() > 1 && spread(&|run| run.x1) + 1.0 < spread(&|run| run.x0) {
+                Alignment::Right
+            } else {
+                Alignment::None
+            }
+        })
+        .collect();
+
+    let mut grid: Vec<Vec<Vec<Inline>>> = cells
+        .iter()
+        .map(|row| {
+            row.iter()
+                .map(|runs| {
+                    let runs: Vec<Line> = runs.iter().map(|run| (*run).clone()).collect();
+                    inlines(&runs, 0, false, cleanup)
+                })
+                .collect()
+        })
+        .collect();
+    let header = grid.remove(0);
+    let x0 = columns[0].0;
+    let x1 = columns[columns.len() - 1].1;
+    Some(Region { x0, y0: rows[0][0].y - rows[0][0].size, x1, block: Block::Table(Table { alignments, header, rows: grid }) })
+}
@@ -0,0 +1,157 @@
+use crate::cli::Args;
+use crate::config::Config;
+use crate::document::Document;
+use crate::error::Result;
+use crate::renderer;
+use std::fs;
+use std::io::Read;
+use std::path::Path;
+use std::sync::{Arc, OnceLock, RwLock, RwLockReadGuard};
+
+/// Number of leading bytes handed to [`FormatConverter::sniff`].
+pub const SNIFF_LEN: usize = 16;
+
+/// A converter for one input format family.
+///
+/// Implement this to teach c2md a new format, then add it with
+/// [`register_converter`] (process-wide) or [`ConverterRegistry::register`].
+pub trait FormatConverter: Send + Sync {
+    /// Format family name, e.g. `pdf` or `office`. Used by `--from`,
+    /// validation and metadata extraction.
+    fn name(&self) -> &str;
+
+    /// Lower-case file extensions handled by this converter, without the dot.
+    fn extensions(&self) -> &[&str];
+
+    /// MIME types handled by this converter.
+    fn mime_types(&self) -> &[&str] {
+        &[]
+    }
+
+    /// Returns true if `header` (the first bytes of a file) looks like this format.
+    fn sniff(&self, _header: &[u8]) -> bool {
+        false
+    }
+
+    /// Read the file at `path` into the shared document model.
+    fn read(&self, path: &Path, config: &Config, args: &Args) -> Result<Document>;
+
+    /// Convert the file at `path` to Markdown.
+    fn convert(&self, path: &Path, config: &Config, args: &Args) -> Result<String> {
+        let document = self.read(path, config, args)?;
+        Ok(renderer::render(&document, config, args))
+    }
+}
+
+/// An ordered set of converters. Later registrations take precedence, so a
+/// custom converter can override a built-in one for the same extension.
+#[derive(Clone, Default)]
+pub struct ConverterRegistry {
+    converters: Vec<Arc<dyn FormatConverter>>,
+}
+
+impl ConverterRegistry {
+    /// An empty registry.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A registry holding every converter shipped with c2md.
+    pub fn with_builtins() -> Self {
+        let mut registry = Self::new();
+        registry.register(super::text::TextConverter);
+        registry.register(super::csv::CsvConverter);
+        registry.register(super::html::HtmlConverter);
+        registry.register(super::rtf::RtfConverter);
+        registry.register(super::epub::EpubConverter);
+        registry.register(super::office::WordConverter);
+        registry.register(super::office::ExcelConverter);
+        registry.register(super::office::PowerPointConverter);
+        registry.register(super::image::ImageConverter);
+        registry.register(super::pdf::PdfConverter);
+        registry
+    }
+
+    pub fn register<C: FormatConverter + 'static>(&mut self, converter: C) {
+        self.converters.push(Arc::new(converter));
+    }
+
+    pub fn converters(&self) -> impl Iterator<Item = &Arc<dyn FormatConverter>> {
+        self.converters.iter().rev()
+    }
+
+    pub fn by_name(&self, name: &str) -> Option<Arc<dyn FormatConverter>> {
+        self.converters()
+            .find(|c| c.name().eq_ignore_ascii_case(name))
+            .cloned()
+    }
+
+    pub fn by_extension(&self, ext: &str) -> Option<Arc<dyn FormatConverter>> {
+        let ext = ext.trim_start_matches('.').to_lowercase();
+        self.converters()
+            .find(|c| c.extensions().contains(&ext.as_str()))
+            .cloned()
+    }
+
+    pub fn by_mime_type(&self, mime: &str) -> Option<Arc<dyn FormatConverter>> {
+        self.converters()
+            .find(|c| c.mime_types().iter().any(|m| m.eq_ignore_ascii_case(mime)))
+            .cloned()
+    }
+
+    pub fn by_magic(&self, header: &[u8]) -> Option<Arc<dyn FormatConverter>> {
+        self.converters().find(|c| c.sniff(header)).cloned()
+    }
+
+    /// Resolve a `--from` value, which may be a format name, an extension or a MIME type.
+    pub fn by_hint(&self, hint: &str) -> Option<Arc<dyn FormatConverter>> {
+        self.by_name(hint)
+            .or_else(|| self.by_extension(hint))
+            .or_else(|| self.by_mime_type(hint))
+    }
+
+    pub fn by_path(&self, path: &Path) -> Option<Arc<dyn FormatConverter>> {
+        path.extension()
+            .and_then(|ext| self.by_extension(&ext.to_string_lossy()))
+    }
+
+    /// Detect the converter for `path`, first by extension and then by magic bytes.
+    pub fn detect(&self, path: &Path) -> Result<Option<Arc<dyn FormatConverter>>> {
+        if let Some(converter) = self.by_path(path) {
+            return Ok(Some(converter));
+        }
+        let header = read_header(path)?;
+        Ok(self.by_magic(&header))
+    }
+
+    pub fn supports_path(&self, path: &Path) -> bool {
+        self.by_path(path).is_some()
+    }
+}
+
+/// Read up to [`SNIFF_LEN`] leading bytes of a file.
+pub fn read_header(path: &Path) -> Result<Vec<u8>> {
+    let file = fs::File::open(path)?;
+    let mut header = Vec::with_capacity(SNIFF_LEN);
+    file.take(SNIFF_LEN as u64).read_to_end(&mut header)?;
+    Ok(header)
+}
+
+fn global() -> &'static RwLock<ConverterRegistry> {
+    static REGISTRY: OnceLock<RwLock<ConverterRegistry>> = OnceLock::new();
+    REGISTRY.get_or_init(|| RwLock::new(ConverterRegistry::with_builtins()))
+}
+
+/// The process-wide registry consulted by [`crate::convert`], the CLI,
+/// validation and metadata extraction.
+pub fn registry() -> RwLockReadGuard<'static, ConverterRegistry> {
+    global().read().unwrap_or_else(|e| e.into_inner())
+}
+
+/// Add a converter to the process-wide registry.
+pub fn register_converter<C: FormatConverter + 'static>(converter: C) {
+    global()
+        .write()
+        .unwrap_or_else(|e| e.into_inner())
+        .register(converter);
+}
@@ -1,38 +1,894 @@
+//! RTF reader: a tokenizer and a group-state interpreter producing the
+//! document model directly.
+
 use crate::cli::Args;
 use crate::config::Config;
-use crate::error::{C2mdError, Result};
+use crate::converter::image::store_image;
+use crate::converter::registry::FormatConverter;
+use crate::document::{build_lists, Alignment, Block, Document, Footnote, Inline, ListEntry, Table};
+use crate::error::Result;
+use crate::utils::encoding_for_label;
+use crate::metadata::DocumentMetadata;
+use crate::renderer::render;
+use encoding_rs::Encoding;
+use std::collections::HashMap;
+use std::fs;
 use std::path::Path;
 
+pub struct RtfConverter;
+
+impl FormatConverter for RtfConverter {
+    fn name(&self) -> &str {
+        "rtf"
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["rtf"]
+    }
+
+    fn mime_types(&self) -> &[&str] {
+        &["application/rtf", "text/rtf"]
+    }
+
+    fn sniff(&self, header: &[u8]) -> bool {
+        header.starts_with(b"{\\rtf")
+    }
+
+    fn read(&self, path: &Path, config: &Config, args: &Args) -> Result<Document> {
+        read_rtf(path, config, args)
+    }
+}
+
 pub fn convert_rtf(path: &Path, config: &Config, args: &Args) -> Result<String> {
-    // Placeholder for RTF conversion
-    let mut markdown = String::new();
-    
-    // Add front matter
-    if config.frontmatter != "none" {
-        markdown.push_str("---\n");
-        if let Some(title) = &args.title {
-            markdown.push_str(&format!("title: {}\n", title));
-        }
-        if let Some(author) = &args.author {
-            markdown.push_str(&format!("author: {}\n", author));
-        }
-        if let Some(date) = &args.date {
-            markdown.push_str(&format!("date: {}\n", date));
+    let document = read_rtf(path, config, args)?;
+    Ok(render(&document, config, args))
+}
+
+pub fn read_rtf(path: &Path, config: &Config, args: &Args) -> Result<Document> {
+    let data = fs::read(path)?;
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy().to_string();
+    let mut reader = RtfReader::new(Some((config, args)), stem);
+    reader.encoding = args.encoding.as_deref().map(encoding_for_label).transpose()?;
+    reader.run(&tokenize(&data));
+    Ok(reader.finish())
+}
+
+/// RTF `\info` fields for `path`, used by [`crate::metadata::extract_metadata`].
+pub(crate) fn read_rtf_metadata(path: &Path, encoding: Option<&str>, metadata: &mut DocumentMetadata) -> Result<()> {
+    let data = fs::read(path)?;
+    let mut reader = RtfReader::new(None, String::new());
+    reader.encoding = encoding.map(encoding_for_label).transpose()?;
+    reader.run(&tokenize(&data));
+    let info = std::mem::take(&mut reader.info);
+    let text = reader.finish().plain_text();
+
+    let field = |name: &str| info.get(name).map(|value| value.trim().to_string()).filter(|value| !value.is_empty());
+    metadata.title = field("title");
+    metadata.author = field("author");
+    metadata.subject = field("subject");
+    metadata.keywords = field("keywords");
+    metadata.creation_date = created_date(&info).and_then(|day| day.and_hms_opt(0, 0, 0)).map(|time| time.and_utc());
+    metadata.word_count = Some(text.split_whitespace().count() as u32);
+    metadata.character_count = Some(text.chars().count() as u32);
+    Ok(())
+}
+
+fn created_date(info: &HashMap<String, String>) -> Option<chrono::NaiveDate> {
+    chrono::NaiveDate::parse_from_str(info.get("creatim")?, "%Y-%m-%d").ok()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Token<'a> {
+    GroupStart,
+    GroupEnd,
+    /// `\word` with its optional numeric parameter.
+    Word(&'a str, Option<i32>),
+    /// `\` followed by a non-letter, e.g. `\~` or `\{`.
+    Symbol(u8),
+    /// `\'hh`, a byte in the current code page.
+    Hex(u8),
+    /// Payload of `\binN`.
+    Binary(&'a [u8]),
+    Text(&'a [u8]),
+}
+
+fn tokenize(data: &[u8]) -> Vec<Token<'_>> {
+    let mut tokens = Vec::new();
+    let mut pos = 0;
+    while pos < data.len() {
+        match data[pos] {
+            b'{' => {
+                tokens.push(Token::GroupStart);
+                pos += 1;
+            }
+            b'}' => {
+                tokens.push(Token::GroupEnd);
+                pos += 1;
+            }
+            b'\\' => {
+                pos += 1;
+                let start = pos;
+                while pos < data.len() && data[pos].is_ascii_alphabetic() {
+                    pos += 1;
+                }
+                if pos == start {
+                    let Some(&symbol) = data.get(pos) else {
+                        break;
+                    };
+                    pos += 1;
+                    match symbol {
+                        b'\'' => {
+                            let hex = data.get(pos..pos + 2).and_then(|hex| std::str::from_utf8(hex).ok());
+                            if let Some(byte) = hex.and_then(|hex| u8::from_str_radix(hex, 16).ok()) {
+                                tokens.push(Token::Hex(byte));
+                                pos += 2;
+                            }
+                        }
+                        // An escaped line end is a paragraph break
+                        b'\n' | b'\r' => tokens.push(Token::Word("par", None)),
+                        symbol => tokens.push(Token::Symbol(symbol)),
+                    }
+                    continue;
+                }
+
+                let word = std::str::from_utf8(&data[start..pos]).unwrap_or_default();
+                let number_start = pos;
+                if data.get(pos) == Some(&b'-') {
+                    pos += 1;
+                }
+                while pos < data.len() && data[pos].is_ascii_digit() {
+                    pos += 1;
+                }
+                let parameter = std::str::from_utf8(&data[number_start..pos])
+                    .ok()
+                    .and_then(|number| number.parse::<i64>().ok())
+                    .map(|number| number.clamp(i32::MIN as i64, i32::MAX as i64) as i32);
+                // A space delimits the control word and is part of it
+                if data.get(pos) == Some(&b' ') {
+                    pos += 1;
+                }
+
+                if word == "bin" {
+                    let length = parameter.unwrap_or(0).max(0) as usize;
+                    let end = (pos + length).min(data.len());
+                    tokens.push(Token::Binary(&data[pos..end]));
+                    pos = end;
+                } else {
+                    tokens.push(Token::Word(word, parameter));
+                }
+            }
+            // Line ends in the source carry no meaning
+            b'\r' | b'\n' => pos += 1,
+            _ => {
+                let start = pos;
+                while pos < data.len() && !matches!(data[pos], b'{' | b'}' | b'\\' | b'\r' | b'\n') {
+                    pos += 1;
+                }
+                tokens.push(Token::Text(&data[start..pos]));
+            }
+        }
+    }
+    tokens
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Destination {
+    Text,
+    Skip,
+    FontTable,
+    StyleSheet,
+    /// An `\info` field such as `\title`, collected by name.
+    Info(&'static str),
+    FieldInstruction,
+    Picture,
+    /// Bullet or number text Word writes before list paragraphs.
+    ListText,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+struct Format {
+    bold: bool,
+    italic: bool,
+    underline: bool,
+    strike: bool,
+    hidden: bool,
+}
+
+/// What a group started that has to be finished when it ends.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Opened {
+    Field,
+    Footnote,
+    Picture,
+}
+
+#[derive(Debug, Clone)]
+struct State {
+    destination: Destination,
+    format: Format,
+    font: Option<i32>,
+    /// Fallback characters following each `\uN`.
+    unicode_skip: usize,
+    opened: Option<Opened>,
+}
+
+#[derive(Debug, Clone, Default)]
+struct ParagraphProperties {
+    in_table: bool,
+    outline_level: Option<u8>,
+    style: Option<i32>,
+    list: Option<i32>,
+    list_level: u8,
+}
+
+#[derive(Default)]
+struct Field {
+    instruction: String,
+    /// Index of the first inline of the field result.
+    start: Option<usize>,
+}
+
+#[derive(Default)]
+struct Picture {
+    extension: &'static str,
+    hex: String,
+    data: Vec<u8>,
+}
+
+/// Main text state set aside while a footnote is read.
+struct Saved {
+    blocks: Vec<Block>,
+    inlines: Vec<Inline>,
+    list: Vec<ListEntry>,
+    paragraph: ParagraphProperties,
+}
+
+struct RtfReader<'a> {
+    /// Where pictures go; `None` when only reading metadata.
+    images: Option<(&'a Config, &'a Args)>,
+    stem: String,
+    states: Vec<State>,
+    /// Set by `\*`: an unknown destination that follows is skipped.
+    ignorable: bool,
+    /// Fallback characters still to skip after `\uN`.
+    skip: usize,
+    /// Bytes waiting to be decoded with the current font's code page.
+    bytes: Vec<u8>,
+
+    code_page: &'static Encoding,
+    /// The `--encoding` override, which wins over the document's and the
+    /// fonts' code pages.
+    encoding: Option<&'static Encoding>,
+    default_font: Option<i32>,
+    fonts: HashMap<i32, &'static Encoding>,
+    font_definition: Option<i32>,
+    styles: HashMap<i32, String>,
+    style_definition: Option<i32>,
+    info: HashMap<String, String>,
+
+    blocks: Vec<Block>,
+    inlines: Vec<Inline>,
+    run: String,
+    run_format: Format,
+    paragraph: ParagraphProperties,
+    list: Vec<ListEntry>,
+    list_marker: String,
+    counters: HashMap<(i32, u8), u64>,
+    cell: Vec<Inline>,
+    row: Vec<Vec<Inline>>,
+    rows: Vec<Vec<Vec<Inline>>>,
+    fields: Vec<Field>,
+    picture: Picture,
+    pictures: usize,
+    footnotes: Vec<Footnote>,
+    saved: Vec<Saved>,
+}
+
+impl<'a> RtfReader<'a> {
+    fn new(images: Option<(&'a Config, &'a Args)>, stem: String) -> Self {
+        Self {
+            images,
+            stem,
+            states: vec![State {
+                destination: Destination::Text,
+                format: Format::default(),
+                font: None,
+                unicode_skip: 1,
+                opened: None,
+            }],
+            ignorable: false,
+            skip: 0,
+            bytes: Vec::new(),
+            code_page: encoding_rs::WINDOWS_1252,
+            encoding: None,
+            default_font: None,
+            fonts: HashMap::new(),
+            font_definition: None,
+            styles: HashMap::new(),
+            style_definition: None,
+            info: HashMap::new(),
+            blocks: Vec::new(),
+            inlines: Vec::new(),
+            run: String::new(),
+            run_format: Format::default(),
+            paragraph: ParagraphProperties::default(),
+            list: Vec::new(),
+            list_marker: String::new(),
+            counters: HashMap::new(),
+            cell: Vec::new(),
+            row: Vec::new(),
+            rows: Vec::new(),
+            fields: Vec::new(),
+            picture: Picture::default(),
+            pictures: 0,
+            footnotes: Vec::new(),
+            saved: Vec::new(),
+        }
+    }
+
+    fn state(&mut self) -> &mut State {
+        self.states.last_mut().expect("root state")
+    }
+
+    fn destination(&self) -> Destination {
+        self.states.last().map_or(Destination::Text, |state| state.destination)
+    }
+
+    fn run(&mut self, tokens: &[Token]) {
+        for token in tokens {
+            if !matches!(token, Token::Text(_) | Token::Hex(_)) {
+                self.flush_bytes();
+            }
+            match *token {
+                Token::GroupStart => {
+                    let mut state = self.states.last().cloned().expect("root state");
+                    state.opened = None;
+                    self.states.push(state);
+                    self.skip = 0;
+                }
+                Token::GroupEnd => self.end_group(),
+                _ if self.destination() == Destination::Skip => {}
+                Token::Word(word, parameter) => {
+                    let ignorable = std::mem::take(&mut self.ignorable);
+                    self.control_word(word, parameter, ignorable);
+                }
+                Token::Symbol(symbol) => self.control_symbol(symbol),
+                Token::Hex(byte) => {
+                    if self.skip > 0 {
+                        self.skip -= 1;
+                    } else if self.destination() == Destination::Picture {
+                        self.picture.data.push(byte);
+                    } else {
+                        self.bytes.push(byte);
+                    }
+                }
+                Token::Binary(data) => {
+                    if self.destination() == Destination::Picture {
+                        self.picture.data.extend_from_slice(data);
+                    }
+                }
+                Token::Text(mut text) => {
+                    let skipped = self.skip.min(text.len());
+                    self.skip -= skipped;
+                    text = &text[skipped..];
+                    if self.destination() == Destination::Picture {
+                        self.picture.hex.extend(text.iter().map(|&b| b as char).filter(char::is_ascii_hexdigit));
+                    } else {
+                        self.bytes.extend_from_slice(text);
+                    }
+                }
+            }
+        }
+        self.flush_bytes();
+    }
+
+    fn end_group(&mut self) {
+        if self.states.len() == 1 {
+            return;
+        }
+        let state = self.states.pop().expect("group state");
+        self.skip = 0;
+        match state.opened {
+            Some(Opened::Field) => self.end_field(),
+            Some(Opened::Footnote) => self.end_footnote(),
+            Some(Opened::Picture) => self.end_picture(),
+            None => {}
+        }
+        if state.destination == Destination::FontTable {
+            self.font_definition = None;
+        }
+    }
+
+    fn set_destination(&mut self, destination: Destination) {
+        self.state().destination = destination;
+    }
+
+    fn control_word(&mut self, word: &str, parameter: Option<i32>, ignorable: bool) {
+        let on = parameter != Some(0);
+        let destination = self.destination();
+        match word {
+            // Document and font encodings
+            "ansicpg" => {
+                if let Some(encoding) = parameter.and_then(code_page_encoding) {
+                    self.code_page = encoding;
+                }
+            }
+            "mac" => self.code_page = encoding_rs::MACINTOSH,
+            "deff" => self.default_font = parameter,
+            "f" if destination == Destination::FontTable => self.font_definition = parameter,
+            "f" => self.state().font = parameter,
+            "fcharset" | "cpg" if destination == Destination::FontTable => {
+                let encoding = match word {
+                    "fcharset" => parameter.and_then(charset_encoding),
+                    _ => parameter.and_then(code_page_encoding),
+                };
+                if let (Some(font), Some(encoding)) = (self.font_definition, encoding) {
+                    self.fonts.insert(font, encoding);
+                }
+            }
+            "uc" => self.state().unicode_skip = parameter.unwrap_or(1).max(0) as usize,
+            "u" => {
+                if let Some(code) = parameter {
+                    let code = if code < 0 { code + 65536 } else { code };
+                    if let Some(c) = char::from_u32(code as u32) {
+                        self.text(&c.to_string());
+                    }
+                    self.skip = self.states.last().map_or(1, |state| state.unicode_skip);
+                }
+            }
+
+            // Destinations
+            "fonttbl" => self.set_destination(Destination::FontTable),
+            "stylesheet" => self.set_destination(Destination::StyleSheet),
+            "info" => self.set_destination(Destination::Info("")),
+            "title" | "subject" | "author" | "keywords" | "creatim" if destination == Destination::Info("") => {
+                let field = match word {
+                    "title" => "title",
+                    "subject" => "subject",
+                    "author" => "author",
+                    "keywords" => "keywords",
+                    _ => "creatim",
+                };
+                self.set_destination(Destination::Info(field));
+            }
+            "yr" | "mo" | "dy" if destination == Destination::Info("creatim") => {
+                let value = parameter.unwrap_or(1);
+                let date = self.info.entry("creatim".to_string()).or_default();
+                match word {
+                    "yr" => *date = format!("{:04}", value),
+                    _ => date.push_str(&format!("-{:02}", value)),
+                }
+            }
+            "field" => {
+                self.fields.push(Field::default());
+                self.state().opened = Some(Opened::Field);
+            }
+            "fldinst" => self.set_destination(Destination::FieldInstruction),
+            "fldrslt" => {
+                self.flush_run();
+                let start = self.inlines.len();
+                if let Some(field) = self.fields.last_mut() {
+                    field.start = Some(start);
+                }
+                self.set_destination(Destination::Text);
+            }
+            "footnote" => self.start_footnote(),
+            "pict" => {
+                self.picture = Picture { extension: "png", ..Picture::default() };
+                self.set_destination(Destination::Picture);
+                self.state().opened = Some(Opened::Picture);
+            }
+            "pngblip" => self.picture.extension = "png",
+            "jpegblip" => self.picture.extension = "jpg",
+            "emfblip" => self.picture.extension = "emf",
+            "wmetafile" => self.picture.extension = "wmf",
+            "dibitmap" | "wbitmap" => self.picture.extension = "bmp",
+            "macpict" => self.picture.extension = "pict",
+            // A container for `\pict`; its `\nonshppict` twin repeats the picture
+            "shppict" => {}
+            "listtext" | "pntext" => {
+                self.list_marker.clear();
+                self.set_destination(Destination::ListText);
+            }
+            "colortbl" | "nonshppict" | "header" | "headerl" | "headerr" | "headerf" | "footer" | "footerl"
+            | "footerr" | "footerf" | "pn" | "xe" | "tc" | "txe" | "objdata" | "private" | "bkmkstart"
+            | "bkmkend" | "generator" | "revtbl" | "rsidtbl" | "listtable" | "listoverridetable" => {
+                self.set_destination(Destination::Skip)
+            }
+            _ if ignorable => self.set_destination(Destination::Skip),
+
+            // Style sheet entries and paragraph properties
+            "s" if destination == Destination::StyleSheet => self.style_definition = parameter,
+            "s" => self.paragraph.style = parameter,
+            "pard" => {
+                self.paragraph = ParagraphProperties::default();
+            }
+            "intbl" => self.paragraph.in_table = true,
+            "outlinelevel" => {
+                self.paragraph.outline_level = parameter.filter(|level| (0..9).contains(level)).map(|level| level as u8)
+            }
+            "ls" => self.paragraph.list = parameter,
+            "ilvl" => self.paragraph.list_level = parameter.unwrap_or(0).clamp(0, 8) as u8,
+
+            // Character formatting
+            "plain" => self.state().format = Format::default(),
+            "b" => self.state().format.bold = on,
+            "i" => self.state().format.italic = on,
+            "ul" | "uld" | "uldash" | "uldb" | "ulth" | "ulw" | "ulwave" => self.state().format.underline = on,
+            "ulnone" => self.state().format.underline = false,
+            "strike" | "striked" => self.state().format.strike = on,
+            "v" => self.state().format.hidden = on,
+
+            // Breaks and special characters
+            "par" | "sect" => self.end_paragraph(),
+            "line" => {
+                self.flush_run();
+                self.inlines.push(Inline::LineBreak);
+            }
+            "tab" => self.text(" "),
+            "emdash" => self.text("\u{2014}"),
+            "endash" => self.text("\u{2013}"),
+            "bullet" => self.text("\u{2022}"),
+            "lquote" => self.text("\u{2018}"),
+            "rquote" => self.text("\u{2019}"),
+            "ldblquote" => self.text("\u{201C}"),
+            "rdblquote" => self.text("\u{201D}"),
+            "emspace" | "enspace" | "qmspace" => self.text(" "),
+
+            // Tables
+            "cell" | "nestcell" => self.end_cell(),
+            "row" => self.end_row(),
+            _ => {}
+        }
+    }
+
+    fn control_symbol(&mut self, symbol: u8) {
+        if symbol == b'*' {
+            self.ignorable = true;
+            return;
+        }
+        if self.skip > 0 {
+            self.skip -= 1;
+            return;
+        }
+        match symbol {
+            b'\\' | b'{' | b'}' => self.text(&(symbol as char).to_string()),
+            b'~' => self.text("\u{A0}"),
+            b'_' => self.text("\u{2011}"),
+            _ => {}
+        }
+    }
+
+    fn flush_bytes(&mut self) {
+        if self.bytes.is_empty() {
+            return;
+        }
+        let bytes = std::mem::take(&mut self.bytes);
+        let font = self.states.last().and_then(|state| state.font).or(self.default_font);
+        let encoding = self
+            .encoding
+            .or_else(|| font.and_then(|font| self.fonts.get(&font).copied()))
+            .unwrap_or(self.code_page);
+        let (text, _, _) = encoding.decode(&bytes);
+        self.text(&text);
+    }
+
+    /// Route decoded text to the current destination.
+    fn text(&mut self, text: &str) {
+        match self.destination() {
+            Destination::Text => {
+                let format = self.states.last().map(|state| state.format).unwrap_or_default();
+                if format.hidden {
+                    return;
+                }
+                if format != self.run_format {
+                    self.flush_run();
+                    self.run_format = format;
+                }
+                self.run.push_str(text);
+            }
+            Destination::FontTable => {}
+            Destination::StyleSheet => {
+                if let Some(style) = self.style_definition {
+                    let name = self.styles.entry(style).or_default();
+                    name.push_str(text.split(';').next().unwrap_or_default());
+                    if text.contains(';') {
+                        self.style_definition = None;
+                    }
+                }
+            }
+            Destination::Info(field) if !field.is_empty() => {
+                self.info.entry(field.to_string()).or_default().push_str(text);
+            }
+            Destination::FieldInstruction => {
+                if let Some(field) = self.fields.last_mut() {
+                    field.instruction.push_str(text);
+                }
+            }
+            Destination::ListText => self.list_marker.push_str(text),
+            Destination::Info(_) | Destination::Picture | Destination::Skip => {}
+        }
+    }
+
+    fn flush_run(&mut self) {
+        if self.run.is_empty() {
+            return;
+        }
+        let format = self.run_format;
+        let mut inline = Inline::Text(std::mem::take(&mut self.run));
+        if format.strike {
+            inline = Inline::Strikethrough(vec![inline]);
+        }
+        if format.italic {
+            inline = Inline::Emphasis(vec![inline]);
+        }
+        if format.bold {
+            inline = Inline::Strong(vec![inline]);
+        }
+        // Markdown has no underline; keep it as inline HTML
+        if format.underline {
+            self.inlines.push(Inline::Html("<u>".to_string()));
+            self.inlines.push(inline);
+            self.inlines.push(Inline::Html("</u>".to_string()));
+        } else {
+            self.inlines.push(inline);
+        }
+    }
+
+    fn end_paragraph(&mut self) {
+        self.flush_run();
+        let content = trim_inlines(std::mem::take(&mut self.inlines));
+        let marker = std::mem::take(&mut self.list_marker);
+        if self.paragraph.in_table {
+            self.flush_list();
+            if !content.is_empty() {
+                if !self.cell.is_empty() {
+                    self.cell.push(Inline::LineBreak);
+                }
+                self.cell.extend(content);
+            }
+            return;
+        }
+        self.end_table();
+        if content.is_empty() {
+            return;
+        }
+
+        if let Some(level) = self.heading_level() {
+            self.flush_list();
+            self.blocks.push(Block::Heading { level, content });
+        } else if self.paragraph.list.is_some() || !marker.trim().is_empty() {
+            let marker = marker.trim();
+            let level = self.paragraph.list_level;
+            let ordered = marker.chars().next().is_some_and(|c| c.is_ascii_alphanumeric());
+            let counter = self.counters.entry((self.paragraph.list.unwrap_or(0), level)).or_insert(1);
+            let number = marker
+                .trim_end_matches(['.', ')'])
+                .parse()
+                .unwrap_or(*counter);
+            *counter = number + 1;
+            self.list.push(ListEntry { level, ordered, number, content });
         } else {
-            markdown.push_str(&format!("date: {}\n", chrono::Utc::now().format("%Y-%m-%d")));
-        }
-        markdown.push_str("---\n\n");
-    }
-    
-    markdown.push_str("# RTF Document\n\n");
-    markdown.push_str("**Note**: RTF conversion requires external tools.\n\n");
-    markdown.push_str("To enable RTF conversion, install:\n");
-    markdown.push_str("- `pandoc` (for universal conversion)\n");
-    markdown.push_str("- `unrtf` (for RTF-specific conversion)\n\n");
-    
-    markdown.push_str("## File Information\n\n");
-    markdown.push_str(&format!("- **File**: {}\n", path.file_name().unwrap().to_string_lossy()));
-    markdown.push_str("- **Format**: RTF Document\n");
-    
-    Ok(markdown)
-}
\ No newline at end of file
+            self.flush_list();
+            self.blocks.push(Block::Paragraph(content));
+        }
+    }
+
+    /// From `\outlinelevel` or a "heading N" / "Title" paragraph style.
+    fn heading_level(&self) -> Option<u8> {
+        if let Some(level) = self.paragraph.outline_level {
+            return Some((level + 1).min(6));
+        }
+        let name = self.styles.get(&self.paragraph.style?)?.trim().to_lowercase();
+        if name == "title" {
+            return Some(1);
+        }
+        let level: u8 = name.strip_prefix("heading ")?.parse().ok()?;
+        Some(level.clamp(1, 6))
+    }
+
+    fn flush_list(&mut self) {
+        if !self.list.is_empty() {
+            let entries = std::mem::take(&mut self.list);
+            self.blocks.extend(build_lists(entries));
+        }
+    }
+
+    fn end_cell(&mut self) {
+        self.flush_run();
+        let content = trim_inlines(std::mem::take(&mut self.inlines));
+        if !content.is_empty() {
+            if !self.cell.is_empty() {
+                self.cell.push(Inline::LineBreak);
+            }
+            self.cell.extend(content);
+        }
+        self.flush_list();
+        let cell = std::mem::take(&mut self.cell);
+        self.row.push(cell);
+    }
+
+    fn end_row(&mut self) {
+        let row = std::mem::take(&mut self.row);
+        if !row.is_empty() {
+            self.rows.push(row);
+        }
+    }
+
+    /// The first row of a table becomes its header.
+    fn end_table(&mut self) {
+        if self.rows.is_empty() {
+            return;
+        }
+        self.flush_list();
+        let mut rows = std::mem::take(&mut self.rows);
+        let header = rows.remove(0);
+        let columns = rows.iter().map(Vec::len).chain(std::iter::once(header.len())).max().unwrap_or(0);
+        self.blocks.push(Block::Table(Table { alignments: vec![Alignment::None; columns], header, rows }));
+    }
+
+    /// `HYPERLINK "url"` or `HYPERLINK \l "bookmark"` fields become links;
+    /// other fields keep their result text.
+    fn end_field(&mut self) {
+        self.flush_run();
+        let Some(field) = self.fields.pop() else {
+            return;
+        };
+        let Some(start) = field.start else {
+            return;
+        };
+        let content: Vec<Inline> = self.inlines.drain(start.min(self.inlines.len())..).collect();
+        match hyperlink_target(&field.instruction) {
+            Some(url) if !content.is_empty() => self.inlines.push(Inline::Link { url, title: None, content }),
+            _ => self.inlines.extend(content),
+        }
+    }
+
+    fn start_footnote(&mut self) {
+        self.flush_run();
+        self.saved.push(Saved {
+            blocks: std::mem::take(&mut self.blocks),
+            inlines: std::mem::take(&mut self.inlines),
+            list: std::mem::take(&mut self.list),
+            paragraph: std::mem::take(&mut self.paragraph),
+        });
+        self.set_destination(Destination::Text);
+        self.state().opened = Some(Opened::Footnote);
+    }
+
+    fn end_footnote(&mut self) {
+        self.end_paragraph();
+        self.flush_list();
+        let Some(saved) = self.saved.pop() else {
+            return;
+        };
+        let blocks = std::mem::replace(&mut self.blocks, saved.blocks);
+        self.inlines = saved.inlines;
+        self.list = saved.list;
+        self.paragraph = saved.paragraph;
+
+        let label = (self.footnotes.len() + 1).to_string();
+        self.inlines.push(Inline::FootnoteRef(label.clone()));
+        self.footnotes.push(Footnote { label, blocks });
+    }
+
+    fn end_picture(&mut self) {
+        let picture = std::mem::take(&mut self.picture);
+        let Some((config, args)) = self.images else {
+            return;
+        };
+        let data = if picture.data.is_empty() {
+            picture
+                .hex
+                .as_bytes()
+                .chunks_exact(2)
+                .filter_map(|pair| u8::from_str_radix(std::str::from_utf8(pair).ok()?, 16).ok())
+                .collect()
+        } else {
+            picture.data
+        };
+        if data.is_empty() {
+            return;
+        }
+
+        self.pictures += 1;
+        let file_name = format!("{}-image{}.{}", self.stem, self.pictures, picture.extension);
+        match store_image(&data, &file_name, config, args) {
+            Ok(url) => {
+                self.flush_run();
+                self.inlines.push(Inline::Image { url, alt: "Image".to_string(), title: None });
+            }
+            Err(e) => tracing::warn!("Skipping image {}: {}", file_name, e),
+        }
+    }
+
+    fn finish(mut self) -> Document {
+        self.end_paragraph();
+        self.end_table();
+        self.flush_list();
+
+        let mut document = Document::new();
+        let info = |name: &str| self.info.get(name).map(|value| value.trim().to_string()).filter(|value| !value.is_empty());
+        document.title = info("title");
+        document.author = info("author");
+        document.date = created_date(&self.info).map(|day| day.format("%Y-%m-%d").to_string());
+        document.blocks = self.blocks;
+        document.footnotes = self.footnotes;
+        document
+    }
+}
+
+fn hyperlink_target(instruction: &str) -> Option<String> {
+    let mut words = instruction.split_whitespace();
+    if !words.next()?.eq_ignore_ascii_case("HYPERLINK") {
+        return None;
+    }
+    let rest: Vec<&str> = words.collect();
+    let rest = rest.join(" ");
+    let quoted = |text: &str| -> Option<String> {
+        let text = text.trim();
+        match text.strip_prefix('"') {
+            Some(inner) => inner.split('"').next().map(str::to_string),
+            None => text.split_whitespace().next().map(str::to_string),
+        }
+    };
+    if let Some(bookmark) = rest.strip_prefix("\\l") {
+        return quoted(bookmark).map(|bookmark| format!("#{}", bookmark));
+    }
+    let url = quoted(&rest)?;
+    // A `\l` switch after the address names a fragment
+    match rest.split_once("\\l") {
+        Some((_, bookmark)) => quoted(bookmark).map(|bookmark| format!("{}#{}", url, bookmark)),
+        None => Some(url),
+    }
+}
+
+/// Drop leading and trailing whitespace of a paragraph.
+fn trim_inlines(mut inlines: Vec<Inline>) -> Vec<Inline> {
+    if let Some(Inline::Text(text)) = inlines.first_mut() {
+        *text = text.trim_start().to_string();
+    }
+    if let Some(Inline::Text(text)) = inlines.last_mut() {
+        *text = text.trim_end().to_string();
+    }
+    inlines.retain(|inline| !matches!(inline, Inline::Text(text) if text.is_empty()));
+    while matches!(inlines.last(), Some(Inline::LineBreak)) {
+        inlines.pop();
+    }
+    inlines
+}
+
+/// Code page for an `\fcharset` value; ANSI and symbol fonts use the document's.
+fn charset_encoding(charset: i32) -> Option<&'static Encoding> {
+    Some(match charset {
+        77 => encoding_rs::MACINTOSH,
+        128 => encoding_rs::SHIFT_JIS,
+        129 => encoding_rs::EUC_KR,
+        134 => encoding_rs::GBK,
+        136 => encoding_rs::BIG5,
+        161 => encoding_rs::WINDOWS_1253,
+        162 => encoding_rs::WINDOWS_1254,
+        163 => encoding_rs::WINDOWS_1258,
+        177 => encoding_rs::WINDOWS_1255,
+        178 => encoding_rs::WINDOWS_1256,
+        186 => encoding_rs::WINDOWS_1257,
+        204 => encoding_rs::WINDOWS_1251,
+        222 => encoding_rs::WINDOWS_874,
+        238 => encoding_rs::WINDOWS_1250,
+        _ => return None,
+    })
+}
+
+fn code_page_encoding(code_page: i32) -> Option<&'static Encoding> {
+    let label = match code_page {
+        932 => "shift_jis".to_string(),
+        936 => "gbk".to_string(),
+        949 => "euc-kr".to_string(),
+        950 => "big5".to_string(),
+        10000 => "macintosh".to_string(),
+        20866 => "koi8-r".to_string(),
+        65001 => "utf-8".to_string(),
+        code_page => format!("windows-{}", code_page),
+    };
+    Encoding::for_label(label.as_bytes())
+}
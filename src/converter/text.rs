@@ -1,43 +1,51 @@
 use crate::cli::Args;
 use crate::config::Config;
-use crate::error::{C2mdError, Result};
+use crate::converter::registry::FormatConverter;
+use crate::document::{Block, Document, Inline, ListItem};
+use crate::renderer::render;
+use crate::error::Result;
+use crate::utils::decode_text;
 use std::fs;
 use std::path::Path;
 use regex::Regex;
-use chrono::{DateTime, Utc};
+
+pub struct TextConverter;
+
+impl FormatConverter for TextConverter {
+    fn name(&self) -> &str {
+        "text"
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["txt"]
+    }
+
+    fn mime_types(&self) -> &[&str] {
+        &["text/plain"]
+    }
+
+    fn read(&self, path: &Path, config: &Config, args: &Args) -> Result<Document> {
+        read_text(path, config, args)
+    }
+}
 
 pub fn convert_text(path: &Path, config: &Config, args: &Args) -> Result<String> {
-    let content = fs::read_to_string(path)?;
+    let document = read_text(path, config, args)?;
+    Ok(render(&document, config, args))
+}
+
+pub fn read_text(path: &Path, _config: &Config, args: &Args) -> Result<Document> {
+    let content = decode_text(&fs::read(path)?, args.encoding.as_deref(), None)?;
     
-    let mut markdown = String::new();
+    let mut document = Document::new();
     
-    // Add front matter
-    if config.frontmatter != "none" {
-        markdown.push_str("---\n");
-        if let Some(title) = &args.title {
-            markdown.push_str(&format!("title: {}\n", title));
-        } else {
-            // Try to extract title from content
-            if let Some(title) = extract_title_from_text(&content) {
-                markdown.push_str(&format!("title: {}\n", title));
-            }
-        }
-        if let Some(author) = &args.author {
-            markdown.push_str(&format!("author: {}\n", author));
-        }
-        if let Some(date) = &args.date {
-            markdown.push_str(&format!("date: {}\n", date));
-        } else {
-            markdown.push_str(&format!("date: {}\n", chrono::Utc::now().format("%Y-%m-%d")));
-        }
-        markdown.push_str("---\n\n");
-    }
+    // Try to extract title from content
+    document.title = extract_title_from_text(&content);
     
-    // Convert plain text to markdown
-    let processed_content = text_to_markdown(&content, config, args)?;
-    markdown.push_str(&processed_content);
+    // Convert plain text to blocks
+    document.blocks = text_to_blocks(&content)?;
     
-    Ok(markdown)
+    Ok(document)
 }
 
 fn extract_title_from_text(text: &str) -> Option<String> {
@@ -66,150 +74,169 @@ fn is_title_candidate(line: &str) -> bool {
     !line.ends_with(" ")
 }
 
-fn text_to_markdown(text: &str, config: &Config, args: &Args) -> Result<String> {
-    let mut markdown = String::new();
-    let lines: Vec<&str> = text.lines().collect();
-    let mut in_list = false;
-    let mut in_code = false;
-    let mut in_quote = false;
-    let mut current_paragraph = String::new();
+/// Detect headings, lists, quotes and code fences in plain text.
+pub fn text_to_blocks(text: &str) -> Result<Vec<Block>> {
+    let mut blocks = Vec::new();
+    let mut state = BlockState::default();
+    let numbered_re = Regex::new(r"^\d+\.\s+(.+)")?;
     
-    for line in lines {
+    for line in text.lines() {
         let trimmed = line.trim();
         
+        // Inside a code fence everything is literal until the closing fence
+        if let Some((fence, _, code)) = state.code.as_mut() {
+            if trimmed.starts_with(fence.as_str()) {
+                state.end(&mut blocks);
+            } else {
+                code.push(line.to_string());
+            }
+            continue;
+        }
+        
         if trimmed.is_empty() {
             // End current block
-            if in_list {
-                markdown.push('\n');
-                in_list = false;
-            }
-            if in_code {
-                markdown.push('\n');
-                in_code = false;
-            }
-            if in_quote {
-                markdown.push('\n');
-                in_quote = false;
-            }
-            if !current_paragraph.is_empty() {
-                markdown.push_str(&current_paragraph);
-                markdown.push_str("\n\n");
-                current_paragraph.clear();
-            }
+            state.end(&mut blocks);
             continue;
         }
         
         // Detect headings (lines that are all caps or start with numbers)
         if is_heading(trimmed) {
-            end_current_block(&mut markdown, &mut current_paragraph, &mut in_list, &mut in_code, &mut in_quote);
-            let level = heading_level(trimmed);
-            markdown.push_str(&format!("{} {}\n\n", "#".repeat(level), trimmed));
+            state.end(&mut blocks);
+            blocks.push(Block::Heading {
+                level: heading_level(trimmed),
+                content: parse_inline_code(trimmed),
+            });
             continue;
         }
         
         // Detect lists
-        if trimmed.starts_with("•") || trimmed.starts_with("-") || trimmed.starts_with("*") {
-            end_current_block(&mut markdown, &mut current_paragraph, &mut in_list, &mut in_code, &mut in_quote);
-            if !in_list {
-                markdown.push('\n');
-                in_list = true;
-            }
-            let item = trimmed.trim_start_matches(|c| c == '•' || c == '-' || c == '*').trim();
-            let bullet = "-"; // Default to dash for lists
-            markdown.push_str(&format!("{} {}\n", bullet, item));
+        if trimmed.starts_with(['•', '-', '*']) {
+            state.end_unless_list(&mut blocks, false);
+            let item = trimmed.trim_start_matches(['•', '-', '*']).trim();
+            state.list.get_or_insert((false, Vec::new())).1.push(ListItem::inlines(parse_inline_code(item)));
             continue;
         }
         
         // Detect numbered lists
-        if let Ok(re) = Regex::new(r"^\d+\.\s+(.+)") {
-            if let Some(caps) = re.captures(trimmed) {
-                end_current_block(&mut markdown, &mut current_paragraph, &mut in_list, &mut in_code, &mut in_quote);
-                if !in_list {
-                    markdown.push('\n');
-                    in_list = true;
-                }
-                let item = caps.get(1).unwrap().as_str();
-                markdown.push_str(&format!("1. {}\n", item));
-                continue;
-            }
+        if let Some(caps) = numbered_re.captures(trimmed) {
+            state.end_unless_list(&mut blocks, true);
+            let item = caps.get(1).unwrap().as_str();
+            state.list.get_or_insert((true, Vec::new())).1.push(ListItem::inlines(parse_inline_code(item)));
+            continue;
         }
         
         // Detect code blocks
         if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
-            end_current_block(&mut markdown, &mut current_paragraph, &mut in_list, &mut in_code, &mut in_quote);
-            in_code = !in_code;
-            markdown.push_str(&format!("{}\n", trimmed));
+            state.end(&mut blocks);
+            let fence: String = trimmed.chars().take_while(|c| *c == '`' || *c == '~').collect();
+            let language = trimmed[fence.len()..].trim();
+            let language = (!language.is_empty()).then(|| language.to_string());
+            state.code = Some((fence, language, Vec::new()));
             continue;
         }
         
         // Detect quotes
-        if trimmed.starts_with(">") {
-            end_current_block(&mut markdown, &mut current_paragraph, &mut in_list, &mut in_code, &mut in_quote);
-            if !in_quote {
-                markdown.push('\n');
-                in_quote = true;
+        if let Some(quote_text) = trimmed.strip_prefix('>') {
+            if state.quote.is_none() {
+                state.end(&mut blocks);
             }
-            let quote_text = trimmed.trim_start_matches('>').trim();
-            markdown.push_str(&format!("> {}\n", quote_text));
-            continue;
-        }
-        
-        // Detect inline code
-        if trimmed.contains('`') {
-            end_current_block(&mut markdown, &mut current_paragraph, &mut in_list, &mut in_code, &mut in_quote);
-            markdown.push_str(&format!("{}\n", line));
+            state.quote.get_or_insert_with(Vec::new).push(quote_text.trim().to_string());
             continue;
         }
         
         // Regular paragraph
-        if in_list || in_code || in_quote {
-            end_current_block(&mut markdown, &mut current_paragraph, &mut in_list, &mut in_code, &mut in_quote);
+        if state.list.is_some() || state.quote.is_some() {
+            state.end(&mut blocks);
         }
-        
-        // Add line to current paragraph
-        if !current_paragraph.is_empty() {
-            current_paragraph.push(' ');
-        }
-        current_paragraph.push_str(line);
+        state.paragraph.push(trimmed.to_string());
     }
     
     // Add any remaining content
-    if !current_paragraph.is_empty() {
-        markdown.push_str(&current_paragraph);
-    }
+    state.end(&mut blocks);
     
-    // Apply wrapping if requested
-    if config.wrap == "hard" {
-        markdown = wrap_text(&markdown, config.width);
+    Ok(blocks)
+}
+
+/// Split text into plain paragraphs on blank lines, without structure detection.
+pub fn text_to_paragraphs(text: &str) -> Vec<Block> {
+    let mut blocks = Vec::new();
+    let mut lines: Vec<String> = Vec::new();
+    for line in text.lines().chain(std::iter::once("")) {
+        if line.trim().is_empty() {
+            if !lines.is_empty() {
+                blocks.push(Block::Paragraph(join_lines(&lines)));
+                lines.clear();
+            }
+        } else {
+            lines.push(line.trim().to_string());
+        }
+    }
+    blocks
+}
+
+#[derive(Default)]
+struct BlockState {
+    paragraph: Vec<String>,
+    list: Option<(bool, Vec<ListItem>)>,
+    quote: Option<Vec<String>>,
+    code: Option<(String, Option<String>, Vec<String>)>,
+}
+
+impl BlockState {
+    fn end(&mut self, blocks: &mut Vec<Block>) {
+        if !self.paragraph.is_empty() {
+            blocks.push(Block::Paragraph(join_lines(&self.paragraph)));
+            self.paragraph.clear();
+        }
+        if let Some((ordered, items)) = self.list.take() {
+            blocks.push(Block::List { ordered, start: 1, items });
+        }
+        if let Some(lines) = self.quote.take() {
+            blocks.push(Block::BlockQuote(vec![Block::Paragraph(join_lines(&lines))]));
+        }
+        if let Some((_, language, lines)) = self.code.take() {
+            blocks.push(Block::code(language, lines.join("\n")));
+        }
+    }
+
+    // Keep collecting into an open list of the same kind
+    fn end_unless_list(&mut self, blocks: &mut Vec<Block>, ordered: bool) {
+        if !matches!(self.list, Some((o, _)) if o == ordered) {
+            self.end(blocks);
+        }
     }
-    
-    Ok(markdown)
 }
 
-fn end_current_block(
-    markdown: &mut String,
-    current_paragraph: &mut String,
-    in_list: &mut bool,
-    in_code: &mut bool,
-    in_quote: &mut bool,
-) {
-    if *in_list {
-        markdown.push('\n');
-        *in_list = false;
+fn join_lines(lines: &[String]) -> Vec<Inline> {
+    let mut inlines = Vec::new();
+    for (i, line) in lines.iter().enumerate() {
+        if i > 0 {
+            inlines.push(Inline::SoftBreak);
+        }
+        inlines.extend(parse_inline_code(line));
     }
-    if *in_code {
-        markdown.push('\n');
-        *in_code = false;
+    inlines
+}
+
+// Backtick pairs become code spans; an unmatched backtick stays literal
+fn parse_inline_code(text: &str) -> Vec<Inline> {
+    let parts: Vec<&str> = text.split('`').collect();
+    if parts.len() < 3 {
+        return vec![Inline::text(text)];
     }
-    if *in_quote {
-        markdown.push('\n');
-        *in_quote = false;
+    let mut inlines = Vec::new();
+    let paired = if parts.len() % 2 == 1 { parts.len() } else { parts.len() - 1 };
+    for (i, part) in parts[..paired].iter().enumerate() {
+        if i % 2 == 1 {
+            inlines.push(Inline::Code(part.to_string()));
+        } else if !part.is_empty() {
+            inlines.push(Inline::text(*part));
+        }
     }
-    if !current_paragraph.is_empty() {
-        markdown.push_str(current_paragraph);
-        markdown.push_str("\n\n");
-        current_paragraph.clear();
+    if paired < parts.len() {
+        inlines.push(Inline::text(format!("`{}", parts[paired])));
     }
+    inlines
 }
 
 fn is_heading(line: &str) -> bool {
@@ -228,7 +255,7 @@ fn is_heading(line: &str) -> bool {
     )
 }
 
-fn heading_level(line: &str) -> usize {
+fn heading_level(line: &str) -> u8 {
     if line.len() < 20 {
         1
     } else if line.len() < 40 {
@@ -239,39 +266,3 @@ fn heading_level(line: &str) -> usize {
         4
     }
 }
-
-fn wrap_text(text: &str, width: usize) -> String {
-    let mut result = String::new();
-    
-    for line in text.lines() {
-        if line.len() <= width {
-            result.push_str(line);
-            result.push('\n');
-        } else {
-            let words: Vec<&str> = line.split_whitespace().collect();
-            let mut current_line = String::new();
-            
-            for word in words {
-                if current_line.len() + word.len() + 1 <= width {
-                    if !current_line.is_empty() {
-                        current_line.push(' ');
-                    }
-                    current_line.push_str(word);
-                } else {
-                    if !current_line.is_empty() {
-                        result.push_str(&current_line);
-                        result.push('\n');
-                    }
-                    current_line = word.to_string();
-                }
-            }
-            
-            if !current_line.is_empty() {
-                result.push_str(&current_line);
-                result.push('\n');
-            }
-        }
-    }
-    
-    result
-}
\ No newline at end of file
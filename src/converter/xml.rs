@@ -0,0 +1,219 @@
+use crate::error::{C2mdError, Result};
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::Reader;
+
+/// A node in a parsed XML tree.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Node {
+    Element(Element),
+    Text(String),
+}
+
+/// A small, namespace-agnostic XML element tree, enough for the package
+/// formats c2md reads (OOXML parts, EPUB manifests, XMP packets).
+///
+/// Names keep their prefix (`w:p`); lookups by local name ignore it.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Element {
+    pub name: String,
+    pub attributes: Vec<(String, String)>,
+    pub children: Vec<Node>,
+}
+
+impl Element {
+    /// Parse a document and return its root element.
+    pub fn parse(xml: &str) -> Result<Element> {
+        let mut reader = Reader::from_str(xml);
+        let config = reader.config_mut();
+        // Be lenient about mismatched end tags; the stack below sorts them out
+        config.check_end_names = false;
+        config.allow_unmatched_ends = true;
+        let error = |reader: &Reader<&[u8]>, message: &dyn std::fmt::Display| {
+            C2mdError::Conversion(format!("XML error at byte {}: {}", reader.buffer_position(), message))
+        };
+
+        // A stack of open elements; the bottom one collects the root
+        let mut stack: Vec<Element> = vec![Element::default()];
+        loop {
+            match reader.read_event().map_err(|e| error(&reader, &e))? {
+                Event::Start(tag) => stack.push(start_element(&tag)),
+                Event::Empty(tag) => push_node(&mut stack, Node::Element(start_element(&tag))),
+                Event::End(tag) => {
+                    let name = String::from_utf8_lossy(tag.name().as_ref()).to_string();
+                    // Close up to the matching element
+                    if let Some(depth) = stack.iter().rposition(|element| element.name == name) {
+                        while stack.len() > depth.max(1) {
+                            let element = stack.pop().unwrap_or_default();
+                            push_node(&mut stack, Node::Element(element));
+                        }
+                    }
+                }
+                Event::Text(text) => push_text(&mut stack, decode_entities(&String::from_utf8_lossy(&text))),
+                Event::CData(text) => push_text(&mut stack, String::from_utf8_lossy(&text).to_string()),
+                Event::Eof => break,
+                Event::Decl(_) | Event::PI(_) | Event::Comment(_) | Event::DocType(_) => {}
+            }
+        }
+        while stack.len() > 1 {
+            let element = stack.pop().unwrap_or_default();
+            push_node(&mut stack, Node::Element(element));
+        }
+        stack
+            .pop()
+            .and_then(|document| document.elements().next().cloned())
+            .ok_or_else(|| error(&reader, &"no root element"))
+    }
+
+    /// The element name without its namespace prefix.
+    pub fn local_name(&self) -> &str {
+        local_name(&self.name)
+    }
+
+    pub fn is(&self, local: &str) -> bool {
+        self.local_name() == local
+    }
+
+    /// Look up an attribute by qualified name (`r:id`), falling back to its local name.
+    pub fn attr(&self, name: &str) -> Option<&str> {
+        self.attributes
+            .iter()
+            .find(|(key, _)| key == name)
+            .or_else(|| {
+                let local = local_name(name);
+                self.attributes.iter().find(|(key, _)| local_name(key) == local)
+            })
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// Child elements, skipping text.
+    pub fn elements(&self) -> impl Iterator<Item = &Element> {
+        self.children.iter().filter_map(|node| match node {
+            Node::Element(element) => Some(element),
+            Node::Text(_) => None,
+        })
+    }
+
+    pub fn child(&self, local: &str) -> Option<&Element> {
+        self.elements().find(|element| element.is(local))
+    }
+
+    pub fn children_named<'a>(&'a self, local: &'a str) -> impl Iterator<Item = &'a Element> + 'a {
+        self.elements().filter(move |element| element.is(local))
+    }
+
+    /// The first descendant (depth first) with the given local name.
+    pub fn find(&self, local: &str) -> Option<&Element> {
+        self.elements()
+            .find_map(|element| if element.is(local) { Some(element) } else { element.find(local) })
+    }
+
+    /// All descendants with the given local name, in document order.
+    pub fn find_all<'a>(&'a self, local: &str) -> Vec<&'a Element> {
+        let mut found = Vec::new();
+        self.collect(local, &mut found);
+        found
+    }
+
+    fn collect<'a>(&'a self, local: &str, found: &mut Vec<&'a Element>) {
+        for element in self.elements() {
+            if element.is(local) {
+                found.push(element);
+            }
+            element.collect(local, found);
+        }
+    }
+
+    /// All text below this element, concatenated.
+    pub fn text(&self) -> String {
+        let mut out = String::new();
+        self.collect_text(&mut out);
+        out
+    }
+
+    fn collect_text(&self, out: &mut String) {
+        for node in &self.children {
+            match node {
+                Node::Text(text) => out.push_str(text),
+                Node::Element(element) => element.collect_text(out),
+            }
+        }
+    }
+}
+
+fn local_name(name: &str) -> &str {
+    name.rsplit(':').next().unwrap_or(name)
+}
+
+fn start_element(tag: &BytesStart) -> Element {
+    let name = String::from_utf8_lossy(tag.name().as_ref()).to_string();
+    // HTML-style attributes (no value, unquoted) are accepted; broken ones skipped
+    let attributes = tag
+        .html_attributes()
+        .with_checks(false)
+        .flatten()
+        .map(|attribute| {
+            let key = String::from_utf8_lossy(attribute.key.as_ref()).to_string();
+            (key, decode_entities(&String::from_utf8_lossy(&attribute.value)))
+        })
+        .collect();
+    Element { name, attributes, children: Vec::new() }
+}
+
+fn push_node(stack: &mut [Element], node: Node) {
+    if let Some(parent) = stack.last_mut() {
+        parent.children.push(node);
+    }
+}
+
+fn push_text(stack: &mut [Element], text: String) {
+    if text.is_empty() {
+        return;
+    }
+    if let Some(parent) = stack.last_mut() {
+        match parent.children.last_mut() {
+            Some(Node::Text(previous)) => previous.push_str(&text),
+            _ => parent.children.push(Node::Text(text)),
+        }
+    }
+}
+
+/// Decode the predefined XML entities and numeric character references.
+pub fn decode_entities(text: &str) -> String {
+    if !text.contains('&') {
+        return text.to_string();
+    }
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find('&') {
+        out.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let decoded = rest.find(';').filter(|&end| end <= 12).and_then(|end| {
+            let entity = &rest[1..end];
+            let c = match entity {
+                "lt" => Some('<'),
+                "gt" => Some('>'),
+                "amp" => Some('&'),
+                "quot" => Some('"'),
+                "apos" => Some('\''),
+                _ if entity.starts_with("#x") || entity.starts_with("#X") => {
+                    u32::from_str_radix(&entity[2..], 16).ok().and_then(char::from_u32)
+                }
+                _ if entity.starts_with('#') => entity[1..].parse().ok().and_then(char::from_u32),
+                _ => None,
+            };
+            c.map(|c| (c, end))
+        });
+        match decoded {
+            Some((c, end)) => {
+                out.push(c);
+                rest = &rest[end + 1..];
+            }
+            None => {
+                out.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
@@ -0,0 +1,698 @@
+use pulldown_cmark::{CodeBlockKind, Event, HeadingLevel, Options as CmarkOptions, Parser, Tag, TagEnd};
+use serde::{Deserialize, Serialize};
+
+/// Intermediate representation produced by every converter and turned into
+/// Markdown by [`crate::renderer`].
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Document {
+    pub title: Option<String>,
+    pub author: Option<String>,
+    pub date: Option<String>,
+    /// Pages read from a paginated source when only some were selected, as
+    /// in `1-5,10`.
+    #[serde(default)]
+    pub pages: Option<String>,
+    pub blocks: Vec<Block>,
+    pub footnotes: Vec<Footnote>,
+    /// Parts of a multi-part source, such as EPUB chapters, for `--split`.
+    #[serde(default)]
+    pub sections: Vec<Section>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum Block {
+    Heading {
+        level: u8,
+        content: Vec<Inline>,
+    },
+    Paragraph(Vec<Inline>),
+    List {
+        ordered: bool,
+        start: u64,
+        items: Vec<ListItem>,
+    },
+    Table(Table),
+    CodeBlock {
+        language: Option<String>,
+        code: String,
+    },
+    BlockQuote(Vec<Block>),
+    Math(String),
+    Html(String),
+    ThematicBreak,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum Inline {
+    Text(String),
+    Emphasis(Vec<Inline>),
+    Strong(Vec<Inline>),
+    Strikethrough(Vec<Inline>),
+    /// Marked text, such as a reviewer's highlight.
+    Highlight(Vec<Inline>),
+    Code(String),
+    Link {
+        url: String,
+        title: Option<String>,
+        content: Vec<Inline>,
+    },
+    Image {
+        url: String,
+        alt: String,
+        title: Option<String>,
+    },
+    FootnoteRef(String),
+    Math(String),
+    Html(String),
+    SoftBreak,
+    LineBreak,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ListItem {
+    pub blocks: Vec<Block>,
+    /// `Some` for task list items.
+    pub checked: Option<bool>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Alignment {
+    #[default]
+    None,
+    Left,
+    Center,
+    Right,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Table {
+    pub alignments: Vec<Alignment>,
+    pub header: Vec<Vec<Inline>>,
+    pub rows: Vec<Vec<Vec<Inline>>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Footnote {
+    pub label: String,
+    pub blocks: Vec<Block>,
+}
+
+/// Start of a part of the document that can be written to its own file.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Section {
+    /// File stem for the part, unique within the document.
+    pub name: String,
+    /// Index of the part's first block.
+    pub start: usize,
+}
+
+impl Document {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn push(&mut self, block: Block) {
+        self.blocks.push(block);
+    }
+
+    /// Parse Markdown into a document, e.g. to normalize output from external tools.
+    pub fn from_markdown(markdown: &str) -> Self {
+        let options = CmarkOptions::ENABLE_TABLES
+            | CmarkOptions::ENABLE_FOOTNOTES
+            | CmarkOptions::ENABLE_STRIKETHROUGH
+            | CmarkOptions::ENABLE_TASKLISTS;
+        let mut builder = MarkdownBuilder::default();
+        for event in Parser::new_ext(markdown, options) {
+            builder.event(event);
+        }
+        builder.finish()
+    }
+
+    /// Concatenated plain text of all blocks, separated by blank lines.
+    pub fn plain_text(&self) -> String {
+        blocks_plain_text(&self.blocks)
+    }
+
+    /// One named document per section, each carrying the footnotes it refers to.
+    /// Blocks before the first section go with the first one.
+    pub fn split_sections(&self) -> Vec<(String, Document)> {
+        let mut parts = Vec::new();
+        for (index, section) in self.sections.iter().enumerate() {
+            let start = if index == 0 { 0 } else { section.start.min(self.blocks.len()) };
+            let end = self
+                .sections
+                .get(index + 1)
+                .map_or(self.blocks.len(), |next| next.start.min(self.blocks.len()));
+            let mut blocks = self.blocks[start..end.max(start)].to_vec();
+
+            let mut labels = Vec::new();
+            for_each_inline_mut(&mut blocks, &mut |inline| {
+                if let Inline::FootnoteRef(label) = inline {
+                    labels.push(label.clone());
+                }
+            });
+            let footnotes = self
+                .footnotes
+                .iter()
+                .filter(|footnote| labels.contains(&footnote.label))
+                .cloned()
+                .collect();
+
+            let part = Document {
+                title: self.title.clone(),
+                author: self.author.clone(),
+                date: self.date.clone(),
+                pages: self.pages.clone(),
+                blocks,
+                footnotes,
+                sections: Vec::new(),
+            };
+            parts.push((section.name.clone(), part));
+        }
+        parts
+    }
+}
+
+impl Block {
+    pub fn heading(level: u8, text: impl Into<String>) -> Self {
+        Block::Heading {
+            level: level.clamp(1, 6),
+            content: vec![Inline::text(text)],
+        }
+    }
+
+    pub fn paragraph(text: impl Into<String>) -> Self {
+        Block::Paragraph(vec![Inline::text(text)])
+    }
+
+    pub fn bullet_list(items: Vec<ListItem>) -> Self {
+        Block::List { ordered: false, start: 1, items }
+    }
+
+    pub fn ordered_list(start: u64, items: Vec<ListItem>) -> Self {
+        Block::List { ordered: true, start, items }
+    }
+
+    pub fn code(language: Option<String>, code: impl Into<String>) -> Self {
+        Block::CodeBlock { language, code: code.into() }
+    }
+}
+
+impl Inline {
+    pub fn text(text: impl Into<String>) -> Self {
+        Inline::Text(text.into())
+    }
+}
+
+impl ListItem {
+    pub fn new(blocks: Vec<Block>) -> Self {
+        Self { blocks, checked: None }
+    }
+
+    /// A list item holding a single paragraph.
+    pub fn text(text: impl Into<String>) -> Self {
+        Self::new(vec![Block::paragraph(text)])
+    }
+
+    pub fn inlines(inlines: Vec<Inline>) -> Self {
+        Self::new(vec![Block::Paragraph(inlines)])
+    }
+}
+
+impl Table {
+    pub fn from_strings(header: &[String], rows: &[Vec<String>]) -> Self {
+        Self {
+            alignments: vec![Alignment::None; header.len()],
+            header: header.iter().map(|cell| vec![Inline::text(cell.clone())]).collect(),
+            rows: rows
+                .iter()
+                .map(|row| row.iter().map(|cell| vec![Inline::text(cell.clone())]).collect())
+                .collect(),
+        }
+    }
+
+    pub fn columns(&self) -> usize {
+        self.rows
+            .iter()
+            .map(Vec::len)
+            .chain(std::iter::once(self.header.len()))
+            .max()
+            .unwrap_or(0)
+    }
+}
+
+/// Call `f` on every inline of `blocks`, including inlines nested in links,
+/// emphasis, lists, quotes and tables.
+pub fn for_each_inline_mut(blocks: &mut [Block], f: &mut impl FnMut(&mut Inline)) {
+    for block in blocks {
+        match block {
+            Block::Heading { content, .. } | Block::Paragraph(content) => inlines_mut(content, f),
+            Block::List { items, .. } => {
+                for item in items {
+                    for_each_inline_mut(&mut item.blocks, f);
+                }
+            }
+            Block::Table(table) => {
+                for cell in table.header.iter_mut().chain(table.rows.iter_mut().flatten()) {
+                    inlines_mut(cell, f);
+                }
+            }
+            Block::BlockQuote(inner) => for_each_inline_mut(inner, f),
+            Block::CodeBlock { .. } | Block::Math(_) | Block::Html(_) | Block::ThematicBreak => {}
+        }
+    }
+}
+
+fn inlines_mut(inlines: &mut [Inline], f: &mut impl FnMut(&mut Inline)) {
+    for inline in inlines {
+        f(inline);
+        match inline {
+            Inline::Emphasis(content)
+            | Inline::Strong(content)
+            | Inline::Strikethrough(content)
+            | Inline::Highlight(content)
+            | Inline::Link { content, .. } => inlines_mut(content, f),
+            _ => {}
+        }
+    }
+}
+
+/// A list paragraph before nesting, e.g. DOCX numbering, a PPTX bullet level
+/// or an RTF list paragraph.
+#[derive(Debug, Clone)]
+pub struct ListEntry {
+    pub level: u8,
+    pub ordered: bool,
+    pub number: u64,
+    pub content: Vec<Inline>,
+}
+
+/// Nest consecutive list paragraphs by level.
+pub fn build_lists(entries: Vec<ListEntry>) -> Vec<Block> {
+    let mut blocks = Vec::new();
+    let mut pos = 0;
+    while pos < entries.len() {
+        let level = entries[pos].level;
+        blocks.push(build_list(&entries, &mut pos, level));
+    }
+    blocks
+}
+
+fn build_list(entries: &[ListEntry], pos: &mut usize, level: u8) -> Block {
+    let ordered = entries[*pos].ordered;
+    let start = entries[*pos].number;
+    let mut items: Vec<ListItem> = Vec::new();
+
+    while let Some(entry) = entries.get(*pos) {
+        if entry.level < level || (entry.level == level && entry.ordered != ordered && !items.is_empty()) {
+            break;
+        }
+        if entry.level > level {
+            let nested = build_list(entries, pos, entry.level);
+            match items.last_mut() {
+                Some(item) => item.blocks.push(nested),
+                None => items.push(ListItem::new(vec![nested])),
+            }
+            continue;
+        }
+        items.push(ListItem::inlines(entry.content.clone()));
+        *pos += 1;
+    }
+
+    Block::List { ordered, start, items }
+}
+
+pub fn inlines_plain_text(inlines: &[Inline]) -> String {
+    let mut text = String::new();
+    for inline in inlines {
+        match inline {
+            Inline::Text(t) | Inline::Code(t) | Inline::Math(t) => text.push_str(t),
+            Inline::Emphasis(c) | Inline::Strong(c) | Inline::Strikethrough(c) | Inline::Highlight(c) => {
+                text.push_str(&inlines_plain_text(c))
+            }
+            Inline::Link { content, .. } => text.push_str(&inlines_plain_text(content)),
+            Inline::Image { alt, .. } => text.push_str(alt),
+            Inline::SoftBreak | Inline::LineBreak => text.push(' '),
+            Inline::FootnoteRef(_) | Inline::Html(_) => {}
+        }
+    }
+    text
+}
+
+/// Flatten blocks into one run of inlines separated by line breaks, e.g. for
+/// table cells, which cannot hold block content.
+pub fn blocks_to_inlines(blocks: &[Block]) -> Vec<Inline> {
+    let mut out: Vec<Inline> = Vec::new();
+    for block in blocks {
+        let content = match block {
+            Block::Heading { content, .. } | Block::Paragraph(content) => content.clone(),
+            Block::List { items, .. } => items
+                .iter()
+                .map(|item| blocks_to_inlines(&item.blocks))
+                .collect::<Vec<_>>()
+                .join(&Inline::LineBreak),
+            Block::BlockQuote(inner) => blocks_to_inlines(inner),
+            Block::CodeBlock { code, .. } => vec![Inline::Code(code.trim_end().to_string())],
+            Block::Math(tex) => vec![Inline::Math(tex.clone())],
+            Block::Table(_) => vec![Inline::text(blocks_plain_text(std::slice::from_ref(block)))],
+            Block::Html(_) | Block::ThematicBreak => continue,
+        };
+        if content.is_empty() {
+            continue;
+        }
+        if !out.is_empty() {
+            out.push(Inline::LineBreak);
+        }
+        out.extend(content);
+    }
+    out
+}
+
+fn blocks_plain_text(blocks: &[Block]) -> String {
+    let mut parts = Vec::new();
+    for block in blocks {
+        let part = match block {
+            Block::Heading { content, .. } | Block::Paragraph(content) => inlines_plain_text(content),
+            Block::List { items, .. } => items
+                .iter()
+                .map(|item| blocks_plain_text(&item.blocks))
+                .collect::<Vec<_>>()
+                .join("\n"),
+            Block::Table(table) => std::iter::once(&table.header)
+                .chain(table.rows.iter())
+                .map(|row| row.iter().map(|c| inlines_plain_text(c)).collect::<Vec<_>>().join("\t"))
+                .collect::<Vec<_>>()
+                .join("\n"),
+            Block::CodeBlock { code, .. } => code.clone(),
+            Block::BlockQuote(inner) => blocks_plain_text(inner),
+            Block::Math(tex) => tex.clone(),
+            Block::Html(_) | Block::ThematicBreak => continue,
+        };
+        parts.push(part);
+    }
+    parts.join("\n\n")
+}
+
+enum BlockFrame {
+    Root(Vec<Block>),
+    Quote(Vec<Block>),
+    List { ordered: bool, start: u64, items: Vec<ListItem> },
+    Item(ListItem),
+    Footnote(Footnote),
+}
+
+impl BlockFrame {
+    fn blocks_mut(&mut self) -> Option<&mut Vec<Block>> {
+        match self {
+            BlockFrame::Root(blocks) | BlockFrame::Quote(blocks) => Some(blocks),
+            BlockFrame::Item(item) => Some(&mut item.blocks),
+            BlockFrame::Footnote(footnote) => Some(&mut footnote.blocks),
+            BlockFrame::List { .. } => None,
+        }
+    }
+}
+
+enum InlineKind {
+    Paragraph,
+    Implicit,
+    Heading(u8),
+    Emphasis,
+    Strong,
+    Strikethrough,
+    Link { url: String, title: Option<String> },
+    Image { url: String, title: Option<String> },
+    Cell,
+}
+
+#[derive(Default)]
+struct TableState {
+    table: Table,
+    row: Vec<Vec<Inline>>,
+    in_head: bool,
+}
+
+#[derive(Default)]
+struct MarkdownBuilder {
+    blocks: Vec<BlockFrame>,
+    inlines: Vec<(InlineKind, Vec<Inline>)>,
+    table: Option<TableState>,
+    code: Option<(Option<String>, String)>,
+    html: Option<String>,
+    footnotes: Vec<Footnote>,
+}
+
+impl MarkdownBuilder {
+    fn push_block(&mut self, block: Block) {
+        if self.blocks.is_empty() {
+            self.blocks.push(BlockFrame::Root(Vec::new()));
+        }
+        if let Some(blocks) = self.blocks.last_mut().and_then(BlockFrame::blocks_mut) {
+            blocks.push(block);
+        }
+    }
+
+    fn push_inline(&mut self, inline: Inline) {
+        if self.inlines.is_empty() {
+            self.inlines.push((InlineKind::Implicit, Vec::new()));
+        }
+        if let Some((_, inlines)) = self.inlines.last_mut() {
+            inlines.push(inline);
+        }
+    }
+
+    // Tight list items carry text without a paragraph; close it before the next block.
+    fn flush_implicit(&mut self) {
+        if matches!(self.inlines.last(), Some((InlineKind::Implicit, _))) {
+            if let Some((_, inlines)) = self.inlines.pop() {
+                self.push_block(Block::Paragraph(inlines));
+            }
+        }
+    }
+
+    fn close_inline(&mut self) {
+        let Some((kind, content)) = self.inlines.pop() else {
+            return;
+        };
+        match kind {
+            InlineKind::Paragraph | InlineKind::Implicit => self.push_block(Block::Paragraph(content)),
+            InlineKind::Heading(level) => self.push_block(Block::Heading { level, content }),
+            InlineKind::Emphasis => self.push_inline(Inline::Emphasis(content)),
+            InlineKind::Strong => self.push_inline(Inline::Strong(content)),
+            InlineKind::Strikethrough => self.push_inline(Inline::Strikethrough(content)),
+            InlineKind::Link { url, title } => self.push_inline(Inline::Link { url, title, content }),
+            InlineKind::Image { url, title } => self.push_inline(Inline::Image {
+                url,
+                alt: inlines_plain_text(&content),
+                title,
+            }),
+            InlineKind::Cell => {
+                if let Some(state) = self.table.as_mut() {
+                    state.row.push(content);
+                }
+            }
+        }
+    }
+
+    fn start(&mut self, tag: Tag) {
+        let is_block = matches!(
+            tag,
+            Tag::Paragraph
+                | Tag::Heading { .. }
+                | Tag::BlockQuote
+                | Tag::CodeBlock(_)
+                | Tag::HtmlBlock
+                | Tag::List(_)
+                | Tag::Item
+                | Tag::FootnoteDefinition(_)
+                | Tag::Table(_)
+        );
+        if is_block {
+            self.flush_implicit();
+        }
+        match tag {
+            Tag::Paragraph => self.inlines.push((InlineKind::Paragraph, Vec::new())),
+            Tag::Heading { level, .. } => self.inlines.push((InlineKind::Heading(heading_level(level)), Vec::new())),
+            Tag::BlockQuote => self.blocks.push(BlockFrame::Quote(Vec::new())),
+            Tag::CodeBlock(kind) => {
+                let language = match kind {
+                    CodeBlockKind::Fenced(info) => info.split_whitespace().next().map(str::to_string),
+                    CodeBlockKind::Indented => None,
+                };
+                self.code = Some((language, String::new()));
+            }
+            Tag::HtmlBlock => self.html = Some(String::new()),
+            Tag::List(start) => self.blocks.push(BlockFrame::List {
+                ordered: start.is_some(),
+                start: start.unwrap_or(1),
+                items: Vec::new(),
+            }),
+            Tag::Item => self.blocks.push(BlockFrame::Item(ListItem::default())),
+            Tag::FootnoteDefinition(label) => self.blocks.push(BlockFrame::Footnote(Footnote {
+                label: label.to_string(),
+                blocks: Vec::new(),
+            })),
+            Tag::Table(alignments) => {
+                self.table = Some(TableState {
+                    table: Table {
+                        alignments: alignments.into_iter().map(Alignment::from).collect(),
+                        ..Table::default()
+                    },
+                    ..TableState::default()
+                })
+            }
+            Tag::TableHead => {
+                if let Some(state) = self.table.as_mut() {
+                    state.in_head = true;
+                }
+            }
+            Tag::TableRow => {}
+            Tag::TableCell => self.inlines.push((InlineKind::Cell, Vec::new())),
+            Tag::Emphasis => self.inlines.push((InlineKind::Emphasis, Vec::new())),
+            Tag::Strong => self.inlines.push((InlineKind::Strong, Vec::new())),
+            Tag::Strikethrough => self.inlines.push((InlineKind::Strikethrough, Vec::new())),
+            Tag::Link { dest_url, title, .. } => self.inlines.push((
+                InlineKind::Link { url: dest_url.to_string(), title: non_empty(&title) },
+                Vec::new(),
+            )),
+            Tag::Image { dest_url, title, .. } => self.inlines.push((
+                InlineKind::Image { url: dest_url.to_string(), title: non_empty(&title) },
+                Vec::new(),
+            )),
+            Tag::MetadataBlock(_) => {}
+        }
+    }
+
+    fn end(&mut self, tag: TagEnd) {
+        match tag {
+            TagEnd::Paragraph
+            | TagEnd::Heading(_)
+            | TagEnd::Emphasis
+            | TagEnd::Strong
+            | TagEnd::Strikethrough
+            | TagEnd::Link
+            | TagEnd::Image
+            | TagEnd::TableCell => self.close_inline(),
+            TagEnd::CodeBlock => {
+                if let Some((language, code)) = self.code.take() {
+                    let code = code.strip_suffix('\n').unwrap_or(&code).to_string();
+                    self.push_block(Block::CodeBlock { language, code });
+                }
+            }
+            TagEnd::HtmlBlock => {
+                if let Some(html) = self.html.take() {
+                    self.push_block(Block::Html(html.trim_end().to_string()));
+                }
+            }
+            TagEnd::BlockQuote => {
+                self.flush_implicit();
+                if let Some(BlockFrame::Quote(blocks)) = self.blocks.pop() {
+                    self.push_block(Block::BlockQuote(blocks));
+                }
+            }
+            TagEnd::Item => {
+                self.flush_implicit();
+                if let Some(BlockFrame::Item(item)) = self.blocks.pop() {
+                    if let Some(BlockFrame::List { items, .. }) = self.blocks.last_mut() {
+                        items.push(item);
+                    }
+                }
+            }
+            TagEnd::List(_) => {
+                if let Some(BlockFrame::List { ordered, start, items }) = self.blocks.pop() {
+                    self.push_block(Block::List { ordered, start, items });
+                }
+            }
+            TagEnd::FootnoteDefinition => {
+                self.flush_implicit();
+                if let Some(BlockFrame::Footnote(footnote)) = self.blocks.pop() {
+                    self.footnotes.push(footnote);
+                }
+            }
+            TagEnd::TableHead | TagEnd::TableRow => {
+                if let Some(state) = self.table.as_mut() {
+                    let row = std::mem::take(&mut state.row);
+                    if state.in_head {
+                        state.table.header = row;
+                        state.in_head = false;
+                    } else {
+                        state.table.rows.push(row);
+                    }
+                }
+            }
+            TagEnd::Table => {
+                if let Some(state) = self.table.take() {
+                    self.push_block(Block::Table(state.table));
+                }
+            }
+            TagEnd::MetadataBlock(_) => {}
+        }
+    }
+
+    fn event(&mut self, event: Event) {
+        match event {
+            Event::Start(tag) => self.start(tag),
+            Event::End(tag) => self.end(tag),
+            Event::Text(text) => {
+                if let Some((_, code)) = self.code.as_mut() {
+                    code.push_str(&text);
+                } else {
+                    self.push_inline(Inline::Text(text.to_string()));
+                }
+            }
+            Event::Code(code) => self.push_inline(Inline::Code(code.to_string())),
+            Event::Html(html) => match self.html.as_mut() {
+                Some(block) => block.push_str(&html),
+                None => self.push_block(Block::Html(html.trim_end().to_string())),
+            },
+            Event::InlineHtml(html) => self.push_inline(Inline::Html(html.to_string())),
+            Event::FootnoteReference(label) => self.push_inline(Inline::FootnoteRef(label.to_string())),
+            Event::SoftBreak => self.push_inline(Inline::SoftBreak),
+            Event::HardBreak => self.push_inline(Inline::LineBreak),
+            Event::Rule => {
+                self.flush_implicit();
+                self.push_block(Block::ThematicBreak);
+            }
+            Event::TaskListMarker(checked) => {
+                if let Some(BlockFrame::Item(item)) = self.blocks.last_mut() {
+                    item.checked = Some(checked);
+                }
+            }
+        }
+    }
+
+    fn finish(mut self) -> Document {
+        self.flush_implicit();
+        let blocks = match self.blocks.pop() {
+            Some(BlockFrame::Root(blocks)) => blocks,
+            _ => Vec::new(),
+        };
+        Document {
+            blocks,
+            footnotes: self.footnotes,
+            ..Document::default()
+        }
+    }
+}
+
+impl From<pulldown_cmark::Alignment> for Alignment {
+    fn from(alignment: pulldown_cmark::Alignment) -> Self {
+        match alignment {
+            pulldown_cmark::Alignment::None => Alignment::None,
+            pulldown_cmark::Alignment::Left => Alignment::Left,
+            pulldown_cmark::Alignment::Center => Alignment::Center,
+            pulldown_cmark::Alignment::Right => Alignment::Right,
+        }
+    }
+}
+
+fn heading_level(level: HeadingLevel) -> u8 {
+    level as u8
+}
+
+fn non_empty(value: &str) -> Option<String> {
+    if value.is_empty() {
+        None
+    } else {
+        Some(value.to_string())
+    }
+}
@@ -29,6 +29,9 @@ pub enum C2mdError {
     #[error("PDF processing error: {0}")]
     Pdf(String),
 
+    #[error("Encrypted PDF: {0}")]
+    Encrypted(String),
+
     #[error("Image processing error: {0}")]
     Image(String),
 
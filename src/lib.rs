@@ -15,14 +15,20 @@
 //!
 //! ## Example Usage
 //!
-//! ```rust
+//! ```rust,no_run
 //! use c2md::{convert, Options};
 //!
+//! # fn main() -> c2md::Result<()> {
 //! let options = Options::default();
 //! let markdown = convert("input.pdf", options)?;
 //! std::fs::write("output.md", markdown)?;
+//! # Ok(())
+//! # }
 //! ```
 //!
+//! Additional formats can be plugged in by implementing [`FormatConverter`]
+//! and calling [`register_converter`].
+//!
 //! ## Supported Formats
 //!
 //! - **PDF**: Digital and scanned (with OCR)
@@ -48,33 +54,25 @@ pub mod utils;
 pub mod validator;
 pub mod metadata;
 pub mod template;
+pub mod document;
+pub mod renderer;
 
 pub use config::Config;
+pub use document::Document;
+pub use converter::{register_converter, ConverterRegistry, FormatConverter};
 pub use error::{C2mdError, Result};
 
 /// Convert a single file to Markdown
 pub fn convert(input_path: &str, options: Options) -> Result<String> {
     let path = std::path::Path::new(input_path);
-    let config = Config::default();
+    let config = options.to_config();
     
     // Detect format and convert
-    let format = utils::get_file_extension(path)?;
-    
-    match format.as_str() {
-        "pdf" => converter::pdf::convert_pdf(path, &config, &options.to_args()),
-        "doc" | "docx" => converter::office::convert_office(path, &config, &options.to_args()),
-        "xls" | "xlsx" => converter::office::convert_excel(path, &config, &options.to_args()),
-        "ppt" | "pptx" => converter::office::convert_powerpoint(path, &config, &options.to_args()),
-        "rtf" => converter::rtf::convert_rtf(path, &config, &options.to_args()),
-        "txt" => converter::text::convert_text(path, &config, &options.to_args()),
-        "html" | "htm" => converter::html::convert_html(path, &config, &options.to_args()),
-        "epub" => converter::epub::convert_epub(path, &config, &options.to_args()),
-        "csv" => converter::csv::convert_csv(path, &config, &options.to_args()),
-        "jpg" | "jpeg" | "png" | "gif" | "bmp" | "tiff" | "webp" => {
-            converter::image::convert_image(path, &config, &options.to_args())
-        }
-        _ => Err(C2mdError::UnsupportedFormat(format)),
-    }
+    let converter = converter::registry()
+        .detect(path)?
+        .ok_or_else(|| C2mdError::UnsupportedFormat(utils::get_file_extension(path).unwrap_or_default()))?;
+
+    converter.convert(path, &config, &options.to_args())
 }
 
 /// Conversion options
@@ -83,6 +81,7 @@ pub struct Options {
     pub title: Option<String>,
     pub author: Option<String>,
     pub date: Option<String>,
+    pub to: String,
     pub frontmatter: String,
     pub wrap: String,
     pub width: usize,
@@ -94,6 +93,21 @@ pub struct Options {
     pub images: String,
     pub ocr: bool,
     pub ocr_lang: Option<String>,
+    pub pdf_layout: String,
+    pub pages: Option<String>,
+    /// PDF highlights and comments: `include`, `none` or `only`.
+    pub annotations: String,
+    /// PDF form fields: `table`, `list` or `none`.
+    pub forms: String,
+    /// Password of encrypted PDFs.
+    pub password: Option<String>,
+    pub sheet: Option<String>,
+    pub sheets: String,
+    /// Part of HTML pages to convert: `main`, `article`, `full` or
+    /// `selector:<css>`.
+    pub html_extract: String,
+    /// HTML without a Markdown equivalent: `keep`, `strip` or `escape`.
+    pub raw_html: String,
 }
 
 impl Default for Options {
@@ -102,6 +116,7 @@ impl Default for Options {
             title: None,
             author: None,
             date: None,
+            to: "gfm".to_string(),
             frontmatter: "yaml".to_string(),
             wrap: "soft".to_string(),
             width: 100,
@@ -113,11 +128,48 @@ impl Default for Options {
             images: "keep".to_string(),
             ocr: false,
             ocr_lang: None,
+            pdf_layout: "smart".to_string(),
+            pages: None,
+            annotations: "include".to_string(),
+            forms: "table".to_string(),
+            password: None,
+            sheet: None,
+            sheets: "all".to_string(),
+            html_extract: "main".to_string(),
+            raw_html: "keep".to_string(),
         }
     }
 }
 
 impl Options {
+    fn to_config(&self) -> Config {
+        Config {
+            to: self.to.clone(),
+            wrap: self.wrap.clone(),
+            width: self.width,
+            frontmatter: self.frontmatter.clone(),
+            slug: self.slug.clone(),
+            tables: self.tables.clone(),
+            images: config::ImageConfig {
+                mode: self.images.clone(),
+                ..Config::default().images
+            },
+            pdf: config::PdfConfig {
+                layout: self.pdf_layout.clone(),
+                pages: self.pages.clone(),
+                annotations: self.annotations.clone(),
+                forms: self.forms.clone(),
+                ..Config::default().pdf
+            },
+            html: config::HtmlConfig {
+                extract: self.html_extract.clone(),
+                raw_html: self.raw_html.clone(),
+                ..Config::default().html
+            },
+            ..Config::default()
+        }
+    }
+
     fn to_args(&self) -> cli::Args {
         cli::Args {
             inputs: vec![],
@@ -125,7 +177,7 @@ impl Options {
             out_dir: None,
             preserve_structure: false,
             from: None,
-            to: "gfm".to_string(),
+            to: self.to.clone(),
             encoding: None,
             headings: self.headings.clone(),
             slug: self.slug.clone(),
@@ -143,10 +195,18 @@ impl Options {
             image_max_width: None,
             ocr: self.ocr,
             ocr_lang: self.ocr_lang.clone(),
-            pdf_layout: "auto".to_string(),
+            pdf_layout: self.pdf_layout.clone(),
+            pages: self.pages.clone(),
+            annotations: self.annotations.clone(),
+            forms: self.forms.clone(),
+            password: self.password.clone(),
+            password_file: None,
             libreoffice_bin: None,
-            sheet: None,
-            sheets: "all".to_string(),
+            sheet: self.sheet.clone(),
+            sheets: self.sheets.clone(),
+            split: "none".to_string(),
+            html_extract: self.html_extract.clone(),
+            raw_html: self.raw_html.clone(),
             math: "auto".to_string(),
             math_block: "$$".to_string(),
             watch: false,
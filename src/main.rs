@@ -1,22 +1,9 @@
 use anyhow::Result;
-use clap::Parser;
-use std::path::PathBuf;
+use c2md::cli::Args;
+use c2md::config::Config;
+use c2md::converter::Converter;
+use clap::{CommandFactory, FromArgMatches};
 use tracing::{info, error};
-use tracing_subscriber;
-
-mod cli;
-mod config;
-mod converter;
-mod error;
-mod utils;
-mod validator;
-mod metadata;
-mod template;
-
-use cli::Args;
-use config::Config;
-use converter::Converter;
-use error::C2mdError;
 
 #[tokio::main]
 async fn main() -> Result<()> {
@@ -25,7 +12,8 @@ async fn main() -> Result<()> {
         .with_env_filter(tracing_subscriber::EnvFilter::from_default_env())
         .init();
 
-    let args = Args::parse();
+    let matches = Args::command().get_matches();
+    let args = Args::from_arg_matches(&matches)?;
     
     // Set up logging level based on verbosity
     if args.verbose {
@@ -35,7 +23,8 @@ async fn main() -> Result<()> {
     info!("Starting c2md v{}", env!("CARGO_PKG_VERSION"));
 
     // Load configuration
-    let config = Config::load(&args.config)?;
+    let mut config = Config::load(&args.config)?;
+    config.apply_args(&args, &matches);
     
     // Create converter instance
     let converter = Converter::new(config, args.clone())?;
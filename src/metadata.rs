@@ -1,4 +1,11 @@
-use crate::error::{C2mdError, Result};
+use crate::converter::epub::read_epub_metadata;
+use crate::converter::html::{read_html_metadata, read_page};
+use crate::converter::office::read_package_metadata;
+use crate::converter::pdf::read_pdf_metadata;
+use crate::converter::rtf::read_rtf_metadata;
+use crate::converter::registry;
+use crate::error::Result;
+use crate::utils::decode_text;
 use std::path::Path;
 use std::fs;
 use chrono::{DateTime, Utc};
@@ -15,17 +22,68 @@ pub struct DocumentMetadata {
     pub creation_date: Option<DateTime<Utc>>,
     pub modification_date: Option<DateTime<Utc>>,
     pub language: Option<String>,
+    pub publisher: Option<String>,
+    pub identifier: Option<String>,
     pub page_count: Option<u32>,
     pub word_count: Option<u32>,
     pub character_count: Option<u32>,
+    /// Whether the file is encrypted, for formats that can be.
+    pub encrypted: Option<bool>,
+    /// Filled-in form fields, such as those of a PDF form.
+    #[serde(default)]
+    pub form_fields: Vec<FormField>,
     pub file_size: u64,
     pub format: String,
 }
 
+/// One form field and its value.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FormField {
+    /// Fully qualified name: the names of enclosing fields joined with `.`.
+    pub name: String,
+    /// Name shown to the user, when the form gives one.
+    pub label: Option<String>,
+    pub kind: FormFieldKind,
+    /// Text, the selected options joined with `, `, or `Yes`/`No` for
+    /// checkboxes; empty when the field is not filled in.
+    pub value: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FormFieldKind {
+    Text,
+    Checkbox,
+    Radio,
+    Choice,
+}
+
+/// Settings for [`extract_metadata_with`].
+#[derive(Debug, Clone, Default)]
+pub struct MetadataOptions {
+    /// Converter name to read metadata as, such as `pdf`; detected from the
+    /// file when unset.
+    pub format: Option<String>,
+    /// Password of encrypted PDFs.
+    pub password: Option<String>,
+    /// The `--encoding` override for text, HTML and RTF files.
+    pub encoding: Option<String>,
+}
+
 pub fn extract_metadata(path: &Path) -> Result<DocumentMetadata> {
+    extract_metadata_with(path, &MetadataOptions::default())
+}
+
+/// Like [`extract_metadata`], but reads `options.format` when given, opens
+/// encrypted PDFs with `options.password` and decodes text with
+/// `options.encoding`.
+pub fn extract_metadata_with(path: &Path, options: &MetadataOptions) -> Result<DocumentMetadata> {
     let metadata = fs::metadata(path)?;
     let file_size = metadata.len();
-    let format = detect_format(path)?;
+    let format = match &options.format {
+        Some(format) => format.clone(),
+        None => detect_format(path)?,
+    };
     
     let mut doc_metadata = DocumentMetadata {
         title: None,
@@ -37,22 +95,27 @@ pub fn extract_metadata(path: &Path) -> Result<DocumentMetadata> {
         creation_date: None,
         modification_date: Some(DateTime::<Utc>::from(metadata.modified()?)),
         language: None,
+        publisher: None,
+        identifier: None,
         page_count: None,
         word_count: None,
         character_count: None,
+        encrypted: None,
+        form_fields: Vec::new(),
         file_size,
         format: format.clone(),
     };
     
     // Extract format-specific metadata
     match format.as_str() {
-        "pdf" => extract_pdf_metadata(path, &mut doc_metadata)?,
+        "pdf" => extract_pdf_metadata(path, options.password.as_deref(), &mut doc_metadata)?,
         "office" => extract_office_metadata(path, &mut doc_metadata)?,
         "excel" => extract_excel_metadata(path, &mut doc_metadata)?,
         "powerpoint" => extract_powerpoint_metadata(path, &mut doc_metadata)?,
         "epub" => extract_epub_metadata(path, &mut doc_metadata)?,
-        "html" => extract_html_metadata(path, &mut doc_metadata)?,
-        "text" => extract_text_metadata(path, &mut doc_metadata)?,
+        "html" => extract_html_metadata(path, options.encoding.as_deref(), &mut doc_metadata)?,
+        "rtf" => extract_rtf_metadata(path, options.encoding.as_deref(), &mut doc_metadata)?,
+        "text" => extract_text_metadata(path, options.encoding.as_deref(), &mut doc_metadata)?,
         _ => {}
     }
     
@@ -60,76 +123,41 @@ pub fn extract_metadata(path: &Path) -> Result<DocumentMetadata> {
 }
 
 fn detect_format(path: &Path) -> Result<String> {
-    if let Some(ext) = path.extension() {
-        let ext_str = ext.to_string_lossy().to_lowercase();
-        match ext_str.as_str() {
-            "pdf" => return Ok("pdf".to_string()),
-            "doc" | "docx" => return Ok("office".to_string()),
-            "xls" | "xlsx" => return Ok("excel".to_string()),
-            "ppt" | "pptx" => return Ok("powerpoint".to_string()),
-            "epub" => return Ok("epub".to_string()),
-            "html" | "htm" => return Ok("html".to_string()),
-            "txt" => return Ok("text".to_string()),
-            _ => {}
-        }
-    }
-    Ok("unknown".to_string())
+    let format = registry()
+        .detect(path)?
+        .map(|converter| converter.name().to_string());
+    Ok(format.unwrap_or_else(|| "unknown".to_string()))
 }
 
-fn extract_pdf_metadata(path: &Path, metadata: &mut DocumentMetadata) -> Result<()> {
-    // Placeholder for PDF metadata extraction
-    metadata.title = Some("PDF Document".to_string());
-    metadata.author = Some("Unknown".to_string());
-    Ok(())
+fn extract_pdf_metadata(path: &Path, password: Option<&str>, metadata: &mut DocumentMetadata) -> Result<()> {
+    read_pdf_metadata(path, password, metadata)
 }
 
 fn extract_office_metadata(path: &Path, metadata: &mut DocumentMetadata) -> Result<()> {
-    // Placeholder for Office metadata extraction
-    metadata.title = Some("Office Document".to_string());
-    metadata.author = Some("Unknown".to_string());
-    Ok(())
+    read_package_metadata(path, metadata)
 }
 
 fn extract_excel_metadata(path: &Path, metadata: &mut DocumentMetadata) -> Result<()> {
-    // Placeholder for Excel metadata extraction
-    metadata.title = Some("Excel Spreadsheet".to_string());
-    Ok(())
+    read_package_metadata(path, metadata)
 }
 
 fn extract_powerpoint_metadata(path: &Path, metadata: &mut DocumentMetadata) -> Result<()> {
-    // Placeholder for PowerPoint metadata extraction
-    metadata.title = Some("PowerPoint Presentation".to_string());
-    metadata.author = Some("Unknown".to_string());
-    Ok(())
+    read_package_metadata(path, metadata)
 }
 
 fn extract_epub_metadata(path: &Path, metadata: &mut DocumentMetadata) -> Result<()> {
-    // Placeholder for EPUB metadata extraction
-    metadata.title = Some("EPUB Book".to_string());
-    metadata.author = Some("Unknown".to_string());
-    Ok(())
+    read_epub_metadata(path, metadata)
 }
 
-fn extract_html_metadata(path: &Path, metadata: &mut DocumentMetadata) -> Result<()> {
-    let content = fs::read_to_string(path)?;
-    
-    // Extract title from HTML
-    if let Some(title) = extract_html_tag(&content, "title") {
-        metadata.title = Some(title);
-    }
-    
-    // Extract meta tags
-    if let Some(author) = extract_html_meta(&content, "author") {
-        metadata.author = Some(author);
-    }
-    if let Some(description) = extract_html_meta(&content, "description") {
-        metadata.subject = Some(description);
-    }
-    if let Some(keywords) = extract_html_meta(&content, "keywords") {
-        metadata.keywords = Some(keywords);
-    }
+fn extract_rtf_metadata(path: &Path, encoding: Option<&str>, metadata: &mut DocumentMetadata) -> Result<()> {
+    read_rtf_metadata(path, encoding, metadata)
+}
+
+fn extract_html_metadata(path: &Path, encoding: Option<&str>, metadata: &mut DocumentMetadata) -> Result<()> {
+    read_html_metadata(path, encoding, metadata)?;
     
     // Count words and characters
+    let content = read_page(path, encoding)?;
     let text_content = strip_html_tags(&content);
     metadata.word_count = Some(count_words(&text_content));
     metadata.character_count = Some(text_content.len() as u32);
@@ -137,8 +165,8 @@ fn extract_html_metadata(path: &Path, metadata: &mut DocumentMetadata) -> Result
     Ok(())
 }
 
-fn extract_text_metadata(path: &Path, metadata: &mut DocumentMetadata) -> Result<()> {
-    let content = fs::read_to_string(path)?;
+fn extract_text_metadata(path: &Path, encoding: Option<&str>, metadata: &mut DocumentMetadata) -> Result<()> {
+    let content = decode_text(&fs::read(path)?, encoding, None)?;
     
     // Try to extract title from first line
     if let Some(first_line) = content.lines().next() {
@@ -155,26 +183,6 @@ fn extract_text_metadata(path: &Path, metadata: &mut DocumentMetadata) -> Result
     Ok(())
 }
 
-fn extract_html_tag(html: &str, tag: &str) -> Option<String> {
-    let pattern = format!("<{}[^>]*>([^<]+)</{}>", tag, tag);
-    if let Ok(re) = regex::Regex::new(&pattern) {
-        if let Some(caps) = re.captures(html) {
-            return Some(caps.get(1)?.as_str().trim().to_string());
-        }
-    }
-    None
-}
-
-fn extract_html_meta(html: &str, name: &str) -> Option<String> {
-    let pattern = format!(r#"<meta[^>]*name=["']{}["'][^>]*content=["']([^"']+)["'][^>]*>"#, name);
-    if let Ok(re) = regex::Regex::new(&pattern) {
-        if let Some(caps) = re.captures(html) {
-            return Some(caps.get(1)?.as_str().to_string());
-        }
-    }
-    None
-}
-
 fn strip_html_tags(html: &str) -> String {
     let re = regex::Regex::new(r"<[^>]*>").unwrap_or_else(|_| regex::Regex::new("").unwrap());
     re.replace_all(html, "").to_string()
@@ -225,6 +233,14 @@ pub fn format_metadata_as_markdown(metadata: &DocumentMetadata) -> String {
         markdown.push_str(&format!("- **Language**: {}\n", language));
     }
     
+    if let Some(publisher) = &metadata.publisher {
+        markdown.push_str(&format!("- **Publisher**: {}\n", publisher));
+    }
+    
+    if let Some(identifier) = &metadata.identifier {
+        markdown.push_str(&format!("- **Identifier**: {}\n", identifier));
+    }
+    
     if let Some(page_count) = &metadata.page_count {
         markdown.push_str(&format!("- **Pages**: {}\n", page_count));
     }
@@ -237,6 +253,17 @@ pub fn format_metadata_as_markdown(metadata: &DocumentMetadata) -> String {
         markdown.push_str(&format!("- **Characters**: {}\n", character_count));
     }
     
+    if let Some(encrypted) = metadata.encrypted {
+        markdown.push_str(&format!("- **Encrypted**: {}\n", if encrypted { "yes" } else { "no" }));
+    }
+
+    if !metadata.form_fields.is_empty() {
+        markdown.push_str("- **Form fields**:\n");
+        for field in &metadata.form_fields {
+            markdown.push_str(&format!("  - **{}**: {}\n", field.name, field.value));
+        }
+    }
+    
     markdown.push_str(&format!("- **File Size**: {}\n", format_file_size(metadata.file_size)));
     markdown.push_str(&format!("- **Format**: {}\n", metadata.format));
     
@@ -0,0 +1,734 @@
+use crate::cli::Args;
+use crate::config::Config;
+use crate::document::{Alignment, Block, Document, Footnote, Inline, ListItem, Table};
+use crate::utils::yaml_string;
+
+// Marks a hard line break until the flavor decides how to spell it
+const HARD_BREAK: char = '\u{E000}';
+
+/// Target Markdown dialect selected with `--to`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Flavor {
+    /// Conservative, portable Markdown: HTML for tables, strikethrough and footnotes.
+    Markdown,
+    /// GitHub Flavored Markdown: pipe tables, task lists, strikethrough, footnotes and extended autolinks.
+    #[default]
+    Gfm,
+    /// Strict CommonMark: no extensions, HTML or plain text fallbacks.
+    CommonMark,
+}
+
+impl Flavor {
+    pub fn from_name(name: &str) -> Self {
+        match name.to_lowercase().as_str() {
+            "md" | "markdown" => Flavor::Markdown,
+            "commonmark" | "cmark" => Flavor::CommonMark,
+            _ => Flavor::Gfm,
+        }
+    }
+
+    /// pulldown-cmark options that parse this flavor back.
+    pub fn parser_options(self) -> pulldown_cmark::Options {
+        use pulldown_cmark::Options;
+        match self {
+            Flavor::Gfm => {
+                Options::ENABLE_TABLES
+                    | Options::ENABLE_FOOTNOTES
+                    | Options::ENABLE_STRIKETHROUGH
+                    | Options::ENABLE_TASKLISTS
+            }
+            Flavor::Markdown | Flavor::CommonMark => Options::empty(),
+        }
+    }
+
+    fn is_gfm(self) -> bool {
+        self == Flavor::Gfm
+    }
+}
+
+/// Style options applied by the Markdown renderer.
+#[derive(Debug, Clone)]
+pub struct RenderOptions {
+    pub flavor: Flavor,
+    pub frontmatter: String,
+    pub title: Option<String>,
+    pub author: Option<String>,
+    pub date: Option<String>,
+    pub headings: String,
+    pub list_style: String,
+    pub code_fence: String,
+    pub tables: String,
+    pub wrap: String,
+    pub width: usize,
+    pub math: String,
+    pub math_block: String,
+}
+
+impl Default for RenderOptions {
+    fn default() -> Self {
+        Self {
+            flavor: Flavor::Gfm,
+            frontmatter: "yaml".to_string(),
+            title: None,
+            author: None,
+            date: None,
+            headings: "atx".to_string(),
+            list_style: "dash".to_string(),
+            code_fence: "```".to_string(),
+            tables: "auto".to_string(),
+            wrap: "soft".to_string(),
+            width: 100,
+            math: "auto".to_string(),
+            math_block: "$$".to_string(),
+        }
+    }
+}
+
+impl RenderOptions {
+    pub fn new(config: &Config, args: &Args) -> Self {
+        Self {
+            flavor: Flavor::from_name(&config.to),
+            frontmatter: config.frontmatter.clone(),
+            title: args.title.clone(),
+            author: args.author.clone(),
+            date: args.date.clone(),
+            headings: args.headings.clone(),
+            list_style: args.list_style.clone(),
+            code_fence: args.code_fence.clone(),
+            tables: config.tables.clone(),
+            wrap: config.wrap.clone(),
+            width: config.width,
+            math: args.math.clone(),
+            math_block: args.math_block.clone(),
+        }
+    }
+}
+
+/// Render a document, including front matter, with the options from `config` and `args`.
+pub fn render(document: &Document, config: &Config, args: &Args) -> String {
+    MarkdownRenderer::new(RenderOptions::new(config, args)).render(document)
+}
+
+pub struct MarkdownRenderer {
+    options: RenderOptions,
+}
+
+impl MarkdownRenderer {
+    pub fn new(options: RenderOptions) -> Self {
+        Self { options }
+    }
+
+    pub fn render(&self, document: &Document) -> String {
+        let mut markdown = String::new();
+
+        if self.options.frontmatter != "none" {
+            markdown.push_str(&self.front_matter(document));
+        }
+
+        let body = self.render_body(document);
+        markdown.push_str(&body);
+        if !body.is_empty() {
+            markdown.push('\n');
+        }
+        markdown
+    }
+
+    /// Render only the blocks and footnotes, without front matter.
+    pub fn render_body(&self, document: &Document) -> String {
+        let mut parts = vec![self.render_blocks(&document.blocks, self.options.width)];
+        if !document.footnotes.is_empty() {
+            parts.push(self.render_footnotes(&document.footnotes));
+        }
+        parts.retain(|part| !part.is_empty());
+        parts.join("\n\n")
+    }
+
+    fn front_matter(&self, document: &Document) -> String {
+        let mut front = String::from("---\n");
+        if let Some(title) = self.options.title.as_ref().or(document.title.as_ref()) {
+            front.push_str(&format!("title: {}\n", yaml_string(title)));
+        }
+        if let Some(author) = self.options.author.as_ref().or(document.author.as_ref()) {
+            front.push_str(&format!("author: {}\n", yaml_string(author)));
+        }
+        match self.options.date.as_ref().or(document.date.as_ref()) {
+            Some(date) => front.push_str(&format!("date: {}\n", yaml_string(date))),
+            None => front.push_str(&format!("date: \"{}\"\n", chrono::Utc::now().format("%Y-%m-%d"))),
+        }
+        if let Some(pages) = &document.pages {
+            front.push_str(&format!("pages: {}\n", yaml_string(pages)));
+        }
+        front.push_str("---\n\n");
+        front
+    }
+
+    fn render_blocks(&self, blocks: &[Block], width: usize) -> String {
+        let mut out = String::new();
+        let mut previous: Option<&Block> = None;
+        for block in blocks {
+            let rendered = self.render_block(block, width);
+            if rendered.is_empty() {
+                continue;
+            }
+            if let Some(prev) = previous {
+                // Adjacent lists of the same kind would merge into one
+                let separator = match (prev, block) {
+                    (Block::List { ordered: a, .. }, Block::List { ordered: b, .. }) if a == b => "\n\n<!-- -->\n\n",
+                    _ => "\n\n",
+                };
+                out.push_str(separator);
+            }
+            out.push_str(&rendered);
+            previous = Some(block);
+        }
+        out
+    }
+
+    fn render_block(&self, block: &Block, width: usize) -> String {
+        match block {
+            Block::Heading { level, content } => self.render_heading(*level, content),
+            Block::Paragraph(content) => self.render_paragraph(content, width),
+            Block::List { ordered, start, items } => self.render_list(*ordered, *start, items, width),
+            Block::Table(table) => self.render_table(table),
+            Block::CodeBlock { language, code } => self.render_code(language.as_deref(), code),
+            Block::BlockQuote(blocks) => {
+                let inner = self.render_blocks(blocks, width.saturating_sub(2));
+                prefix_lines(&inner, "> ", "> ")
+            }
+            Block::Math(tex) => self.render_math_block(tex),
+            Block::Html(html) => html.clone(),
+            Block::ThematicBreak => "---".to_string(),
+        }
+    }
+
+    fn render_heading(&self, level: u8, content: &[Inline]) -> String {
+        let text = self.render_inlines(content).replace('\n', " ");
+        let text = text.trim();
+        if self.options.headings == "setext" && level <= 2 && !text.is_empty() {
+            let underline = if level == 1 { '=' } else { '-' };
+            format!("{}\n{}", text, underline.to_string().repeat(text.chars().count().max(3)))
+        } else {
+            format!("{} {}", "#".repeat(level.clamp(1, 6) as usize), text)
+        }
+    }
+
+    fn render_paragraph(&self, content: &[Inline], width: usize) -> String {
+        let text = self.render_inlines(content);
+        let text = text.trim_matches(|c| c == ' ' || c == '\n');
+        let segments: Vec<String> = text
+            .split(HARD_BREAK)
+            .map(|segment| {
+                let segment = segment.trim_start_matches('\n');
+                match self.options.wrap.as_str() {
+                    "hard" => wrap_line(&segment.replace('\n', " "), width),
+                    "none" => segment.replace('\n', " "),
+                    _ => segment.to_string(),
+                }
+            })
+            .collect();
+        let hard_break = match self.options.flavor {
+            Flavor::Markdown => "  \n",
+            _ => "\\\n",
+        };
+        segments
+            .join(hard_break)
+            .split('\n')
+            .map(|line| escape_line_start(line.trim_start()))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    fn bullet(&self) -> &str {
+        match self.options.list_style.as_str() {
+            "asterisk" | "*" => "*",
+            "plus" | "+" => "+",
+            _ => "-",
+        }
+    }
+
+    fn render_list(&self, ordered: bool, start: u64, items: &[ListItem], width: usize) -> String {
+        let mut rendered_items = Vec::new();
+        for (i, item) in items.iter().enumerate() {
+            let mut marker = if ordered {
+                format!("{}.", start + i as u64)
+            } else {
+                self.bullet().to_string()
+            };
+            if let Some(checked) = item.checked {
+                // Outside GFM the checkbox is plain text
+                marker.push_str(match (self.options.flavor.is_gfm(), checked) {
+                    (true, true) => " [x]",
+                    (true, false) => " [ ]",
+                    (false, true) => " \\[x\\]",
+                    (false, false) => " \\[ \\]",
+                });
+            }
+            let indent = " ".repeat(if ordered { marker.find(' ').unwrap_or(marker.len()) + 1 } else { 2 });
+            let body = self.render_item_blocks(&item.blocks, width.saturating_sub(indent.len()));
+            let first = format!("{} ", marker);
+            rendered_items.push(prefix_lines(&body, &first, &indent).trim_end().to_string());
+        }
+
+        let loose = rendered_items.iter().any(|item| item.contains("\n\n"));
+        rendered_items.join(if loose { "\n\n" } else { "\n" })
+    }
+
+    // A paragraph followed by a nested list stays tight
+    fn render_item_blocks(&self, blocks: &[Block], width: usize) -> String {
+        let mut out = String::new();
+        for (i, block) in blocks.iter().enumerate() {
+            let rendered = self.render_block(block, width);
+            if i > 0 {
+                let tight = matches!((&blocks[i - 1], block), (Block::Paragraph(_), Block::List { .. }));
+                out.push_str(if tight { "\n" } else { "\n\n" });
+            }
+            out.push_str(&rendered);
+        }
+        out
+    }
+
+    fn render_code(&self, language: Option<&str>, code: &str) -> String {
+        let fence_char = if self.options.code_fence.starts_with('~') { '~' } else { '`' };
+        let longest_run = longest_run_of(code, fence_char);
+        let fence = fence_char.to_string().repeat(self.options.code_fence.len().max(3).max(longest_run + 1));
+        format!("{}{}\n{}\n{}", fence, language.unwrap_or(""), code.trim_end_matches('\n'), fence)
+    }
+
+    fn render_math_block(&self, tex: &str) -> String {
+        if self.options.math == "none" {
+            return self.render_code(Some("math"), tex);
+        }
+        match self.options.math_block.as_str() {
+            "\\[" | "\\[\\]" => format!("\\[\n{}\n\\]", tex.trim()),
+            "```math" | "fence" => self.render_code(Some("math"), tex),
+            delimiter => format!("{}\n{}\n{}", delimiter, tex.trim(), delimiter),
+        }
+    }
+
+    pub fn render_table(&self, table: &Table) -> String {
+        let columns = table.columns();
+        if columns == 0 {
+            return String::new();
+        }
+        if !self.options.flavor.is_gfm() {
+            return self.render_html_table(table);
+        }
+
+        let render_row = |row: &[Vec<Inline>]| -> Vec<String> {
+            let mut cells: Vec<String> = row.iter().map(|cell| self.render_cell(cell)).collect();
+            cells.resize(columns, String::new());
+            cells
+        };
+        let header = render_row(&table.header);
+        let rows: Vec<Vec<String>> = table.rows.iter().map(|row| render_row(row)).collect();
+        let alignments: Vec<Alignment> = (0..columns)
+            .map(|i| table.alignments.get(i).copied().unwrap_or_default())
+            .collect();
+
+        let mut widths = vec![3; columns];
+        for row in std::iter::once(&header).chain(rows.iter()) {
+            for (i, cell) in row.iter().enumerate() {
+                widths[i] = widths[i].max(cell.chars().count());
+            }
+        }
+
+        let style = match self.options.tables.as_str() {
+            "auto" => {
+                let total: usize = widths.iter().map(|w| w + 3).sum::<usize>() + 1;
+                if total <= self.options.width { "grid" } else { "pipe" }
+            }
+            other => other,
+        };
+
+        match style {
+            "grid" => format_table_rows(&header, &rows, &alignments, Some(&widths), true),
+            "simple" => format_table_rows(&header, &rows, &alignments, None, false),
+            _ => format_table_rows(&header, &rows, &alignments, None, true),
+        }
+    }
+
+    fn render_cell(&self, cell: &[Inline]) -> String {
+        self.render_inlines(cell)
+            .trim()
+            .replace(HARD_BREAK, "<br>")
+            .replace('\n', " ")
+            .replace('|', "\\|")
+    }
+
+    /// Tables as raw HTML, for flavors without pipe tables.
+    pub fn render_html_table(&self, table: &Table) -> String {
+        let columns = table.columns();
+        let cell = |tag: &str, i: usize, content: Option<&Vec<Inline>>| -> String {
+            let align = match table.alignments.get(i).copied().unwrap_or_default() {
+                Alignment::Left => " align=\"left\"",
+                Alignment::Center => " align=\"center\"",
+                Alignment::Right => " align=\"right\"",
+                Alignment::None => "",
+            };
+            let content = content.map(|c| render_inlines_html(c)).unwrap_or_default();
+            format!("<{}{}>{}</{}>", tag, align, content, tag)
+        };
+        let row = |tag: &str, cells: &[Vec<Inline>]| -> String {
+            let cells: String = (0..columns).map(|i| cell(tag, i, cells.get(i))).collect();
+            format!("<tr>{}</tr>", cells)
+        };
+
+        let mut lines = vec!["<table>".to_string()];
+        if !table.header.is_empty() {
+            lines.push("<thead>".to_string());
+            lines.push(row("th", &table.header));
+            lines.push("</thead>".to_string());
+        }
+        lines.push("<tbody>".to_string());
+        lines.extend(table.rows.iter().map(|cells| row("td", cells)));
+        lines.push("</tbody>".to_string());
+        lines.push("</table>".to_string());
+        lines.join("\n")
+    }
+
+    fn render_footnotes(&self, footnotes: &[Footnote]) -> String {
+        if !self.options.flavor.is_gfm() {
+            // Without footnote syntax, list the notes with anchors the references link to
+            return footnotes
+                .iter()
+                .map(|footnote| {
+                    let body = self.render_blocks(&footnote.blocks, self.options.width);
+                    let anchor = format!("<a id=\"fn-{}\"></a>{}. ", footnote_id(&footnote.label), escape_text(&footnote.label));
+                    prefix_lines(&body, &anchor, "")
+                })
+                .collect::<Vec<_>>()
+                .join("\n\n");
+        }
+        footnotes
+            .iter()
+            .map(|footnote| {
+                let body = self.render_blocks(&footnote.blocks, self.options.width.saturating_sub(4));
+                prefix_lines(&body, &format!("[^{}]: ", footnote.label), "    ")
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    pub fn render_inlines(&self, inlines: &[Inline]) -> String {
+        let mut out = String::new();
+        for inline in inlines {
+            match inline {
+                Inline::Text(text) => out.push_str(&escape_text(&text.replace(HARD_BREAK, ""))),
+                Inline::Emphasis(content) => wrap_delimited(&mut out, "*", &self.render_inlines(content)),
+                Inline::Strong(content) => wrap_delimited(&mut out, "**", &self.render_inlines(content)),
+                Inline::Strikethrough(content) => {
+                    if self.options.flavor.is_gfm() {
+                        wrap_delimited(&mut out, "~~", &self.render_inlines(content));
+                    } else {
+                        out.push_str(&format!("<del>{}</del>", render_inlines_html(content)));
+                    }
+                }
+                Inline::Highlight(content) => {
+                    if self.options.flavor.is_gfm() {
+                        wrap_delimited(&mut out, "==", &self.render_inlines(content));
+                    } else {
+                        out.push_str(&format!("<mark>{}</mark>", render_inlines_html(content)));
+                    }
+                }
+                Inline::Code(code) => out.push_str(&code_span(code)),
+                Inline::Link { url, title, content } => out.push_str(&self.render_link(url, title, content)),
+                Inline::Image { url, alt, title } => {
+                    out.push_str(&format!("![{}]({}{})", escape_text(alt), link_destination(url), link_title(title)));
+                }
+                Inline::FootnoteRef(label) => {
+                    if self.options.flavor.is_gfm() {
+                        out.push_str(&format!("[^{}]", label));
+                    } else {
+                        let id = footnote_id(label);
+                        out.push_str(&format!("<sup><a href=\"#fn-{}\">{}</a></sup>", id, escape_html(label)));
+                    }
+                }
+                Inline::Math(tex) => {
+                    if self.options.math == "none" {
+                        out.push_str(&code_span(tex));
+                    } else {
+                        out.push_str(&format!("${}$", tex.trim()));
+                    }
+                }
+                Inline::Html(html) => out.push_str(html),
+                Inline::SoftBreak => out.push('\n'),
+                Inline::LineBreak => {
+                    out.push(HARD_BREAK);
+                    out.push('\n');
+                }
+            }
+        }
+        out
+    }
+
+    fn render_link(&self, url: &str, title: &Option<String>, content: &[Inline]) -> String {
+        let text = self.render_inlines(content);
+        let is_autolink = title.is_none()
+            && matches!(content, [Inline::Text(t)] if t == url || format!("mailto:{}", t) == url)
+            && !url.contains(char::is_whitespace);
+        if is_autolink {
+            match self.options.flavor {
+                // GFM links bare URLs by itself
+                Flavor::Gfm if url.starts_with("http://") || url.starts_with("https://") || url.starts_with("www.") => {
+                    return url.to_string();
+                }
+                Flavor::Markdown if url.contains(':') => return format!("<{}>", url),
+                _ => {}
+            }
+        }
+        format!("[{}]({}{})", text, link_destination(url), link_title(title))
+    }
+}
+
+/// Inline content as HTML, for use inside HTML fallbacks where Markdown is not parsed.
+pub fn render_inlines_html(inlines: &[Inline]) -> String {
+    let mut out = String::new();
+    for inline in inlines {
+        match inline {
+            Inline::Text(text) => out.push_str(&escape_html(text)),
+            Inline::Emphasis(content) => out.push_str(&format!("<em>{}</em>", render_inlines_html(content))),
+            Inline::Strong(content) => out.push_str(&format!("<strong>{}</strong>", render_inlines_html(content))),
+            Inline::Strikethrough(content) => out.push_str(&format!("<del>{}</del>", render_inlines_html(content))),
+            Inline::Highlight(content) => out.push_str(&format!("<mark>{}</mark>", render_inlines_html(content))),
+            Inline::Code(code) => out.push_str(&format!("<code>{}</code>", escape_html(code))),
+            Inline::Link { url, title, content } => {
+                let title = title
+                    .as_ref()
+                    .map(|t| format!(" title=\"{}\"", escape_html(t)))
+                    .unwrap_or_default();
+                out.push_str(&format!("<a href=\"{}\"{}>{}</a>", escape_html(url), title, render_inlines_html(content)));
+            }
+            Inline::Image { url, alt, title } => {
+                let title = title
+                    .as_ref()
+                    .map(|t| format!(" title=\"{}\"", escape_html(t)))
+                    .unwrap_or_default();
+                out.push_str(&format!("<img src=\"{}\" alt=\"{}\"{}>", escape_html(url), escape_html(alt), title));
+            }
+            Inline::FootnoteRef(label) => {
+                out.push_str(&format!("<sup><a href=\"#fn-{}\">{}</a></sup>", footnote_id(label), escape_html(label)))
+            }
+            Inline::Math(tex) => out.push_str(&format!("<code>{}</code>", escape_html(tex))),
+            Inline::Html(html) => out.push_str(html),
+            Inline::SoftBreak => out.push(' '),
+            Inline::LineBreak => out.push_str("<br>"),
+        }
+    }
+    out
+}
+
+pub fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn footnote_id(label: &str) -> String {
+    label
+        .chars()
+        .map(|c| if c.is_alphanumeric() || c == '-' || c == '_' { c } else { '-' })
+        .collect()
+}
+
+fn format_table_rows(
+    header: &[String],
+    rows: &[Vec<String>],
+    alignments: &[Alignment],
+    widths: Option<&[usize]>,
+    outer_pipes: bool,
+) -> String {
+    let pad = |cell: &str, i: usize| -> String {
+        match widths {
+            Some(widths) => {
+                let fill = widths[i].saturating_sub(cell.chars().count());
+                match alignments[i] {
+                    Alignment::Right => format!("{}{}", " ".repeat(fill), cell),
+                    Alignment::Center => format!("{}{}{}", " ".repeat(fill / 2), cell, " ".repeat(fill - fill / 2)),
+                    _ => format!("{}{}", cell, " ".repeat(fill)),
+                }
+            }
+            None => cell.to_string(),
+        }
+    };
+    let line = |cells: Vec<String>| -> String {
+        let joined = cells.join(" | ");
+        if outer_pipes {
+            format!("| {} |", joined)
+        } else {
+            joined
+        }
+    };
+
+    let mut lines = Vec::new();
+    lines.push(line(header.iter().enumerate().map(|(i, c)| pad(c, i)).collect()));
+    lines.push(line(
+        alignments
+            .iter()
+            .enumerate()
+            .map(|(i, alignment)| {
+                let width = widths.map(|w| w[i]).unwrap_or(3);
+                match alignment {
+                    Alignment::None => "-".repeat(width),
+                    Alignment::Left => format!(":{}", "-".repeat(width - 1)),
+                    Alignment::Right => format!("{}:", "-".repeat(width - 1)),
+                    Alignment::Center => format!(":{}:", "-".repeat(width.saturating_sub(2).max(1))),
+                }
+            })
+            .collect(),
+    ));
+    for row in rows {
+        lines.push(line(row.iter().enumerate().map(|(i, c)| pad(c, i)).collect()));
+    }
+    lines.join("\n")
+}
+
+fn wrap_delimited(out: &mut String, delimiter: &str, content: &str) {
+    let trimmed = content.trim();
+    if trimmed.is_empty() {
+        return;
+    }
+    // Delimiters must hug the text, so move surrounding spaces outside
+    if content.starts_with(char::is_whitespace) {
+        out.push(' ');
+    }
+    out.push_str(delimiter);
+    out.push_str(trimmed);
+    out.push_str(delimiter);
+    if content.ends_with(char::is_whitespace) {
+        out.push(' ');
+    }
+}
+
+fn code_span(code: &str) -> String {
+    let ticks = "`".repeat(longest_run_of(code, '`') + 1);
+    if code.starts_with('`') || code.ends_with('`') {
+        format!("{} {} {}", ticks, code, ticks)
+    } else {
+        format!("{}{}{}", ticks, code, ticks)
+    }
+}
+
+fn link_destination(url: &str) -> String {
+    if url.is_empty() || url.contains([' ', '(', ')']) {
+        format!("<{}>", url.replace('<', "%3C").replace('>', "%3E"))
+    } else {
+        url.to_string()
+    }
+}
+
+fn link_title(title: &Option<String>) -> String {
+    match title {
+        Some(title) if !title.is_empty() => format!(" \"{}\"", title.replace('"', "\\\"")),
+        _ => String::new(),
+    }
+}
+
+fn longest_run_of(text: &str, ch: char) -> usize {
+    let mut longest = 0;
+    let mut current = 0;
+    for c in text.chars() {
+        if c == ch {
+            current += 1;
+            longest = longest.max(current);
+        } else {
+            current = 0;
+        }
+    }
+    longest
+}
+
+/// Escape characters that Markdown would otherwise interpret inside running text.
+pub fn escape_text(text: &str) -> String {
+    let chars: Vec<char> = text.chars().collect();
+    let mut out = String::with_capacity(text.len());
+    for (i, &c) in chars.iter().enumerate() {
+        let prev = if i > 0 { chars.get(i - 1).copied() } else { None };
+        let next = chars.get(i + 1).copied();
+        let escape = match c {
+            '\\' | '`' | '*' | '[' | ']' | '<' => true,
+            // Intra-word underscores (snake_case) cannot open emphasis
+            '_' => !(prev.is_some_and(char::is_alphanumeric) && next.is_some_and(char::is_alphanumeric)),
+            '~' => next == Some('~') || prev == Some('~'),
+            '&' => next.is_some_and(|n| n == '#' || n.is_ascii_alphabetic()),
+            _ => false,
+        };
+        if escape {
+            out.push('\\');
+        }
+        out.push(c);
+    }
+    out
+}
+
+// A paragraph line must not be read as a heading, list, quote or setext underline
+fn escape_line_start(line: &str) -> String {
+    let mut chars = line.chars();
+    let first = match chars.next() {
+        Some(c) => c,
+        None => return String::new(),
+    };
+    let rest = chars.as_str();
+    let needs_escape = match first {
+        '#' | '>' => true,
+        '-' | '+' => rest.is_empty() || rest.starts_with(' ') || rest.chars().all(|c| c == first),
+        '=' => rest.chars().all(|c| c == '=' || c == ' '),
+        '0'..='9' => {
+            let digits = line.chars().take_while(char::is_ascii_digit).count();
+            let after = &line[digits..];
+            (after.starts_with(". ") || after.starts_with(") ") || after == "." || after == ")") && digits <= 9
+        }
+        _ => false,
+    };
+    if !needs_escape {
+        return line.to_string();
+    }
+    if first.is_ascii_digit() {
+        let digits = line.chars().take_while(char::is_ascii_digit).count();
+        format!("{}\\{}", &line[..digits], &line[digits..])
+    } else {
+        format!("\\{}", line)
+    }
+}
+
+fn prefix_lines(text: &str, first: &str, rest: &str) -> String {
+    let mut out = String::new();
+    for (i, line) in text.split('\n').enumerate() {
+        if i > 0 {
+            out.push('\n');
+        }
+        let prefix = if i == 0 { first } else { rest };
+        if line.is_empty() {
+            out.push_str(prefix.trim_end());
+        } else {
+            out.push_str(prefix);
+            out.push_str(line);
+        }
+    }
+    if text.is_empty() {
+        out.push_str(first.trim_end());
+    }
+    out
+}
+
+/// Greedy word wrap that never starts a line with a token Markdown would treat as a block marker.
+pub fn wrap_line(line: &str, width: usize) -> String {
+    let mut lines: Vec<String> = Vec::new();
+    let mut current = String::new();
+    for word in line.split_whitespace() {
+        let fits = current.is_empty() || current.chars().count() + 1 + word.chars().count() <= width;
+        if fits || escape_line_start(word) != word {
+            if !current.is_empty() {
+                current.push(' ');
+            }
+            current.push_str(word);
+        } else {
+            lines.push(std::mem::take(&mut current));
+            current.push_str(word);
+        }
+    }
+    if !current.is_empty() {
+        lines.push(current);
+    }
+    lines.join("\n")
+}
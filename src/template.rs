@@ -1,4 +1,5 @@
 use crate::error::{C2mdError, Result};
+use crate::utils::yaml_string;
 use handlebars::{Handlebars, RenderError};
 use serde::{Serialize, Deserialize};
 use std::collections::HashMap;
@@ -10,6 +11,8 @@ pub struct TemplateContext {
     pub title: Option<String>,
     pub author: Option<String>,
     pub date: Option<String>,
+    /// Selected pages when only part of the source was converted.
+    pub pages: Option<String>,
     pub content: String,
     pub metadata: Option<HashMap<String, String>>,
     pub file_info: Option<FileInfo>,
@@ -31,6 +34,9 @@ impl TemplateEngine {
     pub fn new() -> Result<Self> {
         let mut handlebars = Handlebars::new();
         
+        // Output is Markdown, not HTML
+        handlebars.register_escape_fn(handlebars::no_escape);
+        
         // Register built-in templates
         Self::register_builtin_templates(&mut handlebars)?;
         
@@ -43,9 +49,10 @@ impl TemplateEngine {
     fn register_builtin_templates(handlebars: &mut Handlebars) -> Result<()> {
         // Default template
         let default_template = r#"---
-{{#if title}}title: {{title}}{{/if}}
-{{#if author}}author: {{author}}{{/if}}
-{{#if date}}date: {{date}}{{/if}}
+{{#if title}}title: {{yaml title}}{{/if}}
+{{#if author}}author: {{yaml author}}{{/if}}
+{{#if date}}date: {{yaml date}}{{/if}}
+{{#if pages}}pages: {{yaml pages}}{{/if}}
 ---
 
 {{content}}
@@ -76,10 +83,11 @@ impl TemplateEngine {
         
         // Academic template
         let academic_template = r#"---
-title: {{#if title}}{{title}}{{else}}Untitled Document{{/if}}
-author: {{#if author}}{{author}}{{else}}Unknown{{/if}}
-date: {{#if date}}{{date}}{{else}}{{now}}{{/if}}
-abstract: |
+title: {{#if title}}{{yaml title}}{{else}}"Untitled Document"{{/if}}
+author: {{#if author}}{{yaml author}}{{else}}"Unknown"{{/if}}
+date: {{#if date}}{{yaml date}}{{else}}"{{now}}"{{/if}}
+{{#if pages}}pages: {{yaml pages}}
+{{/if}}abstract: |
   This document was converted from {{#if file_info}}{{file_info.format}}{{else}}unknown format{{/if}}.
 ---
 
@@ -123,6 +131,9 @@ abstract: |
         // Word count helper
         handlebars.register_helper("word_count", Box::new(word_count_helper));
         
+        // Quoted YAML string helper for front matter values
+        handlebars.register_helper("yaml", Box::new(yaml_helper));
+        
         Ok(())
     }
     
@@ -183,6 +194,23 @@ fn word_count_helper(
     Ok(())
 }
 
+fn yaml_helper(
+    h: &handlebars::Helper,
+    _: &Handlebars,
+    _: &handlebars::Context,
+    _: &mut handlebars::RenderContext,
+    out: &mut dyn handlebars::Output,
+) -> std::result::Result<(), RenderError> {
+    if let Some(value) = h.param(0).map(|v| v.value()) {
+        let text = match value.as_str() {
+            Some(text) => text.to_string(),
+            None => value.to_string(),
+        };
+        out.write(&yaml_string(&text))?;
+    }
+    Ok(())
+}
+
 fn format_file_size(bytes: u64) -> String {
     const UNITS: &[&str] = &["B", "KB", "MB", "GB", "TB"];
     let mut size = bytes as f64;
@@ -204,6 +232,7 @@ pub fn create_template_context(
     title: Option<String>,
     author: Option<String>,
     date: Option<String>,
+    pages: Option<String>,
     content: String,
     metadata: Option<HashMap<String, String>>,
     file_info: Option<FileInfo>,
@@ -212,6 +241,7 @@ pub fn create_template_context(
         title,
         author,
         date,
+        pages,
         content,
         metadata,
         file_info,
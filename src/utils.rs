@@ -1,4 +1,5 @@
 use crate::error::{C2mdError, Result};
+use encoding_rs::Encoding;
 use std::path::Path;
 use slug::slugify;
 
@@ -43,7 +44,7 @@ pub fn get_file_extension(path: &Path) -> Result<String> {
 pub fn is_image_file(path: &Path) -> bool {
     if let Some(ext) = path.extension() {
         let ext_str = ext.to_string_lossy().to_lowercase();
-        matches!(ext_str.as_str(), "jpg" | "jpeg" | "png" | "gif" | "bmp" | "tiff" | "webp")
+        matches!(ext_str.as_str(), "jpg" | "jpeg" | "png" | "gif" | "bmp" | "tif" | "tiff" | "webp")
     } else {
         false
     }
@@ -84,21 +85,187 @@ pub fn format_file_size(bytes: u64) -> String {
     }
 }
 
+/// Quote a front matter value as a YAML double-quoted string, so titles
+/// containing `:`, `#` or leading quotes stay valid.
+pub fn yaml_string(value: &str) -> String {
+    let mut quoted = String::with_capacity(value.len() + 2);
+    quoted.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\r' => quoted.push_str("\\r"),
+            '\t' => quoted.push_str("\\t"),
+            c if c.is_control() => quoted.push_str(&format!("\\u{:04X}", c as u32)),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+/// Decode text: an explicit `encoding` label (from `--encoding`) wins, then
+/// a byte order mark, then the encoding the content `declared`, such as an
+/// HTML `<meta charset>`, then the likeliest encoding from
+/// [`detect_encoding`]. Undecodable bytes become U+FFFD.
+pub fn decode_text(content: &[u8], encoding: Option<&str>, declared: Option<&'static Encoding>) -> Result<String> {
+    let encoding = match encoding {
+        Some(label) => encoding_for_label(label)?,
+        None => match Encoding::for_bom(content) {
+            Some((bom, _)) => bom,
+            None => declared.unwrap_or_else(|| guess_encoding(content)),
+        },
+    };
+    // The BOM, when there is one for this encoding, is not text
+    let (text, _) = encoding.decode_with_bom_removal(content);
+    Ok(text.into_owned())
+}
+
+/// The encoding an `--encoding` label names, such as `shift_jis` or `cp1252`.
+pub fn encoding_for_label(label: &str) -> Result<&'static Encoding> {
+    Encoding::for_label(label.trim().as_bytes()).ok_or_else(|| C2mdError::Config(format!("Unknown encoding: {}", label)))
+}
+
+/// The name of the likeliest encoding of `content`: a byte order mark, UTF-8
+/// when it is valid, or else the legacy encoding that decodes into the most
+/// plausible text.
 pub fn detect_encoding(content: &[u8]) -> Result<String> {
-    // Simple encoding detection - in a real implementation, you'd use chardet or similar
-    if content.starts_with(&[0xEF, 0xBB, 0xBF]) {
-        Ok("utf-8".to_string())
-    } else if content.starts_with(&[0xFF, 0xFE]) {
-        Ok("utf-16le".to_string())
-    } else if content.starts_with(&[0xFE, 0xFF]) {
-        Ok("utf-16be".to_string())
+    let encoding = Encoding::for_bom(content).map_or_else(|| guess_encoding(content), |(bom, _)| bom);
+    Ok(encoding.name().to_string())
+}
+
+/// Legacy encodings tried in order; ties go to the earlier one.
+const CANDIDATE_ENCODINGS: &[&Encoding] = &[
+    encoding_rs::WINDOWS_1252,
+    encoding_rs::WINDOWS_1250,
+    encoding_rs::WINDOWS_1251,
+    encoding_rs::KOI8_R,
+    encoding_rs::WINDOWS_1253,
+    encoding_rs::WINDOWS_1255,
+    encoding_rs::WINDOWS_1256,
+    encoding_rs::SHIFT_JIS,
+    encoding_rs::EUC_JP,
+    encoding_rs::EUC_KR,
+    encoding_rs::GBK,
+    encoding_rs::BIG5,
+];
+
+/// Only the start of large files is looked at.
+const GUESS_BYTES: usize = 64 * 1024;
+
+fn guess_encoding(content: &[u8]) -> &'static Encoding {
+    let sample = &content[..content.len().min(GUESS_BYTES)];
+    // A multi-byte sequence may be cut at the end of the sample
+    match std::str::from_utf8(sample) {
+        Ok(_) => return encoding_rs::UTF_8,
+        Err(e) if e.error_len().is_none() => return encoding_rs::UTF_8,
+        Err(_) => {}
+    }
+    // UTF-16 without a BOM: ASCII text has every other byte zero
+    let zeros = |offset: usize| sample.iter().skip(offset).step_by(2).filter(|&&byte| byte == 0).count();
+    let half = sample.len() / 2;
+    if half > 0 {
+        let (even, odd) = (zeros(0), zeros(1));
+        if odd * 4 > half && even * 20 < half {
+            return encoding_rs::UTF_16LE;
+        }
+        if even * 4 > half && odd * 20 < half {
+            return encoding_rs::UTF_16BE;
+        }
+    }
+
+    let mut best = (encoding_rs::WINDOWS_1252, f64::MIN);
+    for &encoding in CANDIDATE_ENCODINGS {
+        let (text, malformed) = encoding.decode_without_bom_handling(sample);
+        let chars: Vec<char> = text.chars().collect();
+        let errors = chars.iter().filter(|&&c| c == char::REPLACEMENT_CHARACTER).count();
+        // A cut-off character at the end of the sample is not evidence
+        if malformed && errors > 1 {
+            continue;
+        }
+        let score: f64 = (0..chars.len()).filter(|&i| !chars[i].is_ascii()).map(|i| plausibility(&chars, i)).sum();
+        if score > best.1 {
+            best = (encoding, score);
+        }
+    }
+    best.0
+}
+
+/// Common letters of scripts whose encodings share byte ranges.
+const COMMON_LETTERS: &str = "éèàáóíúüöäçãñâêôß\
+    оеаинтсвлркдмупяыь\
+    αοιετσνηυρκμπλ\
+    יוהלארמתבנש\
+    اليمنوهربتعدس";
+
+/// How plausible the non-ASCII character at `i` is as text: letters of one
+/// script in a row and common punctuation score, stray symbols and control
+/// characters count against the encoding that produced them.
+fn plausibility(chars: &[char], i: usize) -> f64 {
+    let c = chars[i];
+    if c == char::REPLACEMENT_CHARACTER || ('\u{80}'..='\u{9f}').contains(&c) || ('\u{e000}'..='\u{f8ff}').contains(&c) {
+        return -10.0;
+    }
+    // Korean mixes Hangul with ideographs rarely, Chinese decoded as EUC-KR
+    // all the time
+    let hangul = |c: &char| ('\u{ac00}'..='\u{d7af}').contains(c);
+    let ideograph = |c: &char| ('\u{3040}'..='\u{30ff}').contains(c) || ('\u{4e00}'..='\u{9fff}').contains(c);
+    let mixed = |cjk: &dyn Fn(&char) -> bool| {
+        [i.checked_sub(1).map(|j| chars[j]), chars.get(i + 1).copied()].iter().flatten().any(cjk)
+    };
+    match c {
+        c if hangul(&c) => return if mixed(&ideograph) { -2.0 } else { 2.0 },
+        '\u{3040}'..='\u{30ff}' => return 2.0,
+        '\u{4e00}'..='\u{9fff}' => return if mixed(&hangul) { -2.0 } else { 1.0 },
+        '\u{ff61}'..='\u{ff9f}' => return -1.0,
+        _ => {}
+    }
+    if !c.is_alphabetic() {
+        return match c {
+            '\u{a0}' | '’' | '‘' | '“' | '”' | '–' | '—' | '…' | '«' | '»' | '·' | '•' | '°' | '€' | '¡' | '¿'
+            | '©' | '®' | '§' | '\u{3000}'..='\u{303f}' | '\u{ff01}'..='\u{ff5e}' => 0.0,
+            c if c.is_whitespace() => 0.0,
+            _ => -1.0,
+        };
+    }
+
+    let script = |c: char| match c {
+        'a'..='z' | 'A'..='Z' | '\u{c0}'..='\u{24f}' => 'L',
+        '\u{370}'..='\u{3ff}' => 'G',
+        '\u{400}'..='\u{4ff}' => 'C',
+        '\u{590}'..='\u{5ff}' => 'H',
+        '\u{600}'..='\u{6ff}' => 'A',
+        _ => '?',
+    };
+    let neighbors = [i.checked_sub(1).map(|j| chars[j]), chars.get(i + 1).copied()];
+    let letters: Vec<char> = neighbors.into_iter().flatten().filter(|n| n.is_alphabetic()).collect();
+    let mut score = if letters.is_empty() {
+        0.0
+    } else if script(c) == 'L' {
+        // Accented Latin letters sit among plain ones; a run of them is
+        // another script decoded with the wrong code page
+        if letters.iter().any(char::is_ascii_alphabetic) {
+            1.0
+        } else {
+            -1.0
+        }
+    } else if letters.iter().all(|&n| script(n) == script(c)) {
+        1.0
     } else {
-        // Try UTF-8 first
-        match std::str::from_utf8(content) {
-            Ok(_) => Ok("utf-8".to_string()),
-            Err(_) => Ok("latin1".to_string()), // Fallback
+        -1.0
+    };
+    // Text is mostly lower case, and a capital does not follow a small letter
+    if c.is_uppercase() {
+        score -= 0.5;
+        if neighbors[0].is_some_and(char::is_lowercase) {
+            score -= 1.0;
         }
     }
+    if COMMON_LETTERS.contains(c.to_lowercase().next().unwrap_or(c)) {
+        score += 0.5;
+    }
+    score
 }
 
 pub fn normalize_path(path: &Path) -> Result<std::path::PathBuf> {
@@ -1,4 +1,8 @@
-use crate::error::{C2mdError, Result};
+use crate::converter::html::read_page;
+use crate::converter::pdf::check_password;
+use crate::converter::registry;
+use crate::error::Result;
+use crate::utils::decode_text;
 use std::path::Path;
 use std::fs;
 use std::io::Read;
@@ -13,18 +17,42 @@ pub struct FileInfo {
     pub error: Option<String>,
 }
 
+/// Settings for [`validate_file_with`].
+#[derive(Debug, Clone, Default)]
+pub struct ValidateOptions {
+    /// Password of encrypted PDFs.
+    pub password: Option<String>,
+    /// Converter name to validate against, such as `pdf`; detected from the
+    /// file when unset.
+    pub format: Option<String>,
+    /// The `--encoding` override for HTML and CSV files.
+    pub encoding: Option<String>,
+}
+
+/// Check a file before conversion. Encrypted PDFs fail with
+/// [`crate::C2mdError::Encrypted`].
 pub fn validate_file(path: &Path) -> Result<FileInfo> {
+    validate_file_with(path, &ValidateOptions::default())
+}
+
+/// Like [`validate_file`], but opens encrypted PDFs with
+/// `options.password`, checks `options.format` and decodes text with
+/// `options.encoding` when given.
+pub fn validate_file_with(path: &Path, options: &ValidateOptions) -> Result<FileInfo> {
     let metadata = fs::metadata(path)?;
     let size = metadata.len();
     
-    // Detect format
-    let format = detect_format(path)?;
+    // Detect format unless the caller already knows it
+    let format = match &options.format {
+        Some(format) => format.clone(),
+        None => detect_format(path)?,
+    };
     
     // Detect MIME type
     let mime_type = detect_mime_type(path)?;
     
     // Validate file
-    let (is_valid, error) = validate_format(path, &format)?;
+    let (is_valid, error) = validate_format(path, &format, options)?;
     
     Ok(FileInfo {
         path: path.to_path_buf(),
@@ -37,60 +65,13 @@ pub fn validate_file(path: &Path) -> Result<FileInfo> {
 }
 
 pub fn detect_format(path: &Path) -> Result<String> {
-    // First try extension
-    if let Some(ext) = path.extension() {
-        let ext_str = ext.to_string_lossy().to_lowercase();
-        match ext_str.as_str() {
-            "pdf" => return Ok("pdf".to_string()),
-            "doc" | "docx" => return Ok("office".to_string()),
-            "xls" | "xlsx" => return Ok("excel".to_string()),
-            "ppt" | "pptx" => return Ok("powerpoint".to_string()),
-            "rtf" => return Ok("rtf".to_string()),
-            "txt" => return Ok("text".to_string()),
-            "html" | "htm" => return Ok("html".to_string()),
-            "epub" => return Ok("epub".to_string()),
-            "csv" => return Ok("csv".to_string()),
-            "jpg" | "jpeg" | "png" | "gif" | "bmp" | "tiff" | "webp" => return Ok("image".to_string()),
-            _ => {}
-        }
-    }
-    
-    // Try magic number detection
-    detect_by_magic_number(path)
-}
+    // Extension first, then magic number detection
+    let format = registry()
+        .detect(path)?
+        .map(|converter| converter.name().to_string());
 
-fn detect_by_magic_number(path: &Path) -> Result<String> {
-    let mut file = fs::File::open(path)?;
-    let mut buffer = [0; 16];
-    file.read_exact(&mut buffer)?;
-    
-    // Check magic numbers
-    if buffer.starts_with(b"%PDF") {
-        return Ok("pdf".to_string());
-    }
-    
-    if buffer.starts_with(b"\x89PNG") {
-        return Ok("image".to_string());
-    }
-    
-    if buffer.starts_with(b"\xFF\xD8\xFF") {
-        return Ok("image".to_string());
-    }
-    
-    if buffer.starts_with(b"GIF87a") || buffer.starts_with(b"GIF89a") {
-        return Ok("image".to_string());
-    }
-    
-    if buffer.starts_with(b"BM") {
-        return Ok("image".to_string());
-    }
-    
-    if buffer.starts_with(b"{\\rtf") {
-        return Ok("rtf".to_string());
-    }
-    
     // Default to text if we can't determine
-    Ok("text".to_string())
+    Ok(format.unwrap_or_else(|| "text".to_string()))
 }
 
 fn detect_mime_type(path: &Path) -> Result<Option<String>> {
@@ -105,26 +86,27 @@ fn detect_mime_type(path: &Path) -> Result<Option<String>> {
     }
 }
 
-fn validate_format(path: &Path, format: &str) -> Result<(bool, Option<String>)> {
+fn validate_format(path: &Path, format: &str, options: &ValidateOptions) -> Result<(bool, Option<String>)> {
     match format {
-        "pdf" => validate_pdf(path),
+        "pdf" => validate_pdf(path, options.password.as_deref()),
         "office" | "excel" | "powerpoint" => validate_office(path),
         "rtf" => validate_rtf(path),
-        "html" => validate_html(path),
+        "html" => validate_html(path, options.encoding.as_deref()),
         "epub" => validate_epub(path),
-        "csv" => validate_csv(path),
+        "csv" => validate_csv(path, options.encoding.as_deref()),
         "image" => validate_image(path),
         "text" => validate_text(path),
         _ => Ok((true, None)), // Unknown format, assume valid
     }
 }
 
-fn validate_pdf(path: &Path) -> Result<(bool, Option<String>)> {
+fn validate_pdf(path: &Path, password: Option<&str>) -> Result<(bool, Option<String>)> {
     let mut file = fs::File::open(path)?;
     let mut buffer = [0; 4];
     file.read_exact(&mut buffer)?;
     
     if buffer.starts_with(b"%PDF") {
+        check_password(path, password)?;
         Ok((true, None))
     } else {
         Ok((false, Some("Invalid PDF file".to_string())))
@@ -155,8 +137,8 @@ fn validate_rtf(path: &Path) -> Result<(bool, Option<String>)> {
     }
 }
 
-fn validate_html(path: &Path) -> Result<(bool, Option<String>)> {
-    let content = fs::read_to_string(path)?;
+fn validate_html(path: &Path, encoding: Option<&str>) -> Result<(bool, Option<String>)> {
+    let content = read_page(path, encoding)?;
     
     // Check for HTML tags
     let re = regex::Regex::new(r"<[^>]+>")?;
@@ -180,8 +162,8 @@ fn validate_epub(path: &Path) -> Result<(bool, Option<String>)> {
     }
 }
 
-fn validate_csv(path: &Path) -> Result<(bool, Option<String>)> {
-    let content = fs::read_to_string(path)?;
+fn validate_csv(path: &Path, encoding: Option<&str>) -> Result<(bool, Option<String>)> {
+    let content = decode_text(&fs::read(path)?, encoding, None)?;
     
     // Check for CSV structure (comma-separated values)
     let lines: Vec<&str> = content.lines().take(3).collect();
@@ -200,11 +182,20 @@ fn validate_csv(path: &Path) -> Result<(bool, Option<String>)> {
 fn validate_image(path: &Path) -> Result<(bool, Option<String>)> {
     match image::open(path) {
         Ok(_) => Ok((true, None)),
+        // Bilevel scans are TIFFs the image crate cannot open but OCR reads
+        Err(_) if is_readable_tiff(path) => Ok((true, None)),
         Err(e) => Ok((false, Some(format!("Invalid image: {}", e)))),
     }
 }
 
-fn validate_text(path: &Path) -> Result<(bool, Option<String>)> {
+fn is_readable_tiff(path: &Path) -> bool {
+    let Ok(file) = fs::File::open(path) else {
+        return false;
+    };
+    tiff::decoder::Decoder::new(std::io::BufReader::new(file)).and_then(|mut decoder| decoder.colortype()).is_ok()
+}
+
+fn validate_text(_path: &Path) -> Result<(bool, Option<String>)> {
     // Text files are always valid
     Ok((true, None))
 }
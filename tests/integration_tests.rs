@@ -110,7 +110,7 @@ ignore:
 "#;
     
     std::fs::write(&config_file, config_content).unwrap();
-    std::fs::write(&input_file, "This is a very long line that should be wrapped according to the configuration settings").unwrap();
+    std::fs::write(&input_file, "Configuration Test\n\nThis is a very long line that should be wrapped according to the configuration settings").unwrap();
     
     let mut cmd = Command::cargo_bin("c2md").unwrap();
     cmd.arg(input_file.to_str().unwrap())
@@ -175,7 +175,1967 @@ fn test_frontmatter_options() {
     cmd.assert().success();
     
     let output = std::fs::read_to_string(&output_file).unwrap();
-    assert!(output.contains("title: Test Document"));
-    assert!(output.contains("author: Test Author"));
-    assert!(output.contains("date: 2024-01-01"));
-}
\ No newline at end of file
+    assert!(output.contains("title: \"Test Document\""));
+    assert!(output.contains("author: \"Test Author\""));
+    assert!(output.contains("date: \"2024-01-01\""));
+}
+
+#[test]
+fn test_frontmatter_yaml_quoting() {
+    let temp_dir = TempDir::new().unwrap();
+    let input_file = temp_dir.path().join("test.txt");
+    std::fs::write(&input_file, "Test content").unwrap();
+
+    for template in ["default", "academic"] {
+        let mut cmd = Command::cargo_bin("c2md").unwrap();
+        let output = cmd.arg(input_file.to_str().unwrap())
+            .arg("--template")
+            .arg(template)
+            .arg("--title")
+            .arg("\"Quoted\" part 1: setup #2")
+            .arg("--author")
+            .arg("C:\\Users\\ada")
+            .output()
+            .unwrap();
+        assert!(output.status.success());
+
+        let output = String::from_utf8(output.stdout).unwrap();
+        let front = output.split("---\n").nth(1).unwrap();
+        let front: serde_yaml::Value = serde_yaml::from_str(front).unwrap();
+        assert_eq!(front["title"].as_str(), Some("\"Quoted\" part 1: setup #2"), "{}", output);
+        assert_eq!(front["author"].as_str(), Some("C:\\Users\\ada"));
+    }
+}
+
+struct NotesConverter;
+
+impl c2md::FormatConverter for NotesConverter {
+    fn name(&self) -> &str {
+        "notes"
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["notes"]
+    }
+
+    fn sniff(&self, header: &[u8]) -> bool {
+        header.starts_with(b"NOTES")
+    }
+
+    fn read(&self, path: &std::path::Path, _config: &c2md::Config, _args: &c2md::cli::Args) -> c2md::Result<c2md::Document> {
+        let content = std::fs::read_to_string(path)?;
+        let mut document = c2md::Document::new();
+        document.push(c2md::document::Block::heading(1, "Notes"));
+        document.push(c2md::document::Block::paragraph(content.trim_start_matches("NOTES").trim()));
+        Ok(document)
+    }
+}
+
+#[test]
+fn test_from_sets_metadata_format() {
+    let temp_dir = TempDir::new().unwrap();
+    let input_file = temp_dir.path().join("saved.txt");
+    std::fs::write(&input_file, "<html><head><title>Saved Page</title></head><body><p>Hello page.</p></body></html>").unwrap();
+
+    // Validation and metadata follow --from, not the .txt extension
+    let mut cmd = Command::cargo_bin("c2md").unwrap();
+    cmd.arg(&input_file).args(["--from", "html"]);
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("- **format**: html"))
+        .stdout(predicate::str::contains("- **title**: Saved Page"))
+        .stdout(predicate::str::contains("<title>").not());
+}
+
+#[test]
+fn test_registry_builtins() {
+    let registry = c2md::ConverterRegistry::with_builtins();
+    assert_eq!(registry.by_extension("DOCX").unwrap().name(), "office");
+    assert_eq!(registry.by_extension(".htm").unwrap().name(), "html");
+    assert_eq!(registry.by_extension("tif").unwrap().name(), "image");
+    assert_eq!(registry.by_magic(b"%PDF-1.7").unwrap().name(), "pdf");
+    assert_eq!(registry.by_magic(b"{\\rtf1\\ansi").unwrap().name(), "rtf");
+    assert_eq!(registry.by_hint("text/csv").unwrap().name(), "csv");
+    assert!(registry.by_extension("xyz").is_none());
+    // Legacy binary Office formats are not read
+    for (extension, mime_type) in [("doc", "application/msword"), ("xls", "application/vnd.ms-excel"), ("ppt", "application/vnd.ms-powerpoint")] {
+        assert!(registry.by_extension(extension).is_none());
+        assert!(registry.by_hint(mime_type).is_none());
+    }
+}
+
+#[test]
+fn test_registry_later_registration_wins() {
+    let mut registry = c2md::ConverterRegistry::with_builtins();
+    registry.register(NotesConverter);
+    assert_eq!(registry.by_magic(b"NOTES v1").unwrap().name(), "notes");
+    assert_eq!(registry.by_name("text").unwrap().name(), "text");
+}
+
+#[test]
+fn test_custom_converter_registration() {
+    let temp_dir = TempDir::new().unwrap();
+    let input_file = temp_dir.path().join("meeting.notes");
+    let sniffed_file = temp_dir.path().join("meeting");
+
+    std::fs::write(&input_file, "NOTES\nShip the registry").unwrap();
+    std::fs::write(&sniffed_file, "NOTES\nSniffed by magic bytes").unwrap();
+
+    c2md::register_converter(NotesConverter);
+
+    let options = c2md::Options {
+        frontmatter: "none".to_string(),
+        ..c2md::Options::default()
+    };
+    let markdown = c2md::convert(input_file.to_str().unwrap(), options).unwrap();
+    assert_eq!(markdown, "# Notes\n\nShip the registry\n");
+
+    let markdown = c2md::convert(sniffed_file.to_str().unwrap(), c2md::Options::default()).unwrap();
+    assert!(markdown.contains("Sniffed by magic bytes"));
+
+    let format = c2md::validator::detect_format(&input_file).unwrap();
+    assert_eq!(format, "notes");
+}
+
+fn render_with(document: &c2md::Document, configure: impl FnOnce(&mut c2md::renderer::RenderOptions)) -> String {
+    let mut options = c2md::renderer::RenderOptions {
+        frontmatter: "none".to_string(),
+        ..Default::default()
+    };
+    configure(&mut options);
+    c2md::renderer::MarkdownRenderer::new(options).render(document)
+}
+
+#[test]
+fn test_markdown_round_trip() {
+    let source = "# Title\n\nSome *emphasis*, **strong** and `code` with a [link](https://example.com \"Example\").\n\n- one\n- two\n  1. nested\n\n> quoted\n\n| A | B |\n| --- | ---: |\n| 1 | 2 |\n\n```rust\nfn main() {}\n```\n\nA note[^1].\n\n[^1]: The footnote.\n";
+    let document = c2md::Document::from_markdown(source);
+    let rendered = render_with(&document, |_| {});
+
+    assert_eq!(c2md::Document::from_markdown(&rendered), document);
+    assert!(rendered.contains("[link](https://example.com \"Example\")"));
+    assert!(rendered.contains("- two\n  1. nested"));
+    assert!(rendered.contains("[^1]: The footnote."));
+}
+
+#[test]
+fn test_render_style_options() {
+    use c2md::document::{Block, ListItem};
+
+    let mut document = c2md::Document::new();
+    document.push(Block::heading(1, "Heading"));
+    document.push(Block::bullet_list(vec![ListItem::text("a"), ListItem::text("b")]));
+    document.push(Block::code(Some("sh".to_string()), "echo ```"));
+    document.push(Block::paragraph("one two three four five six seven eight nine ten"));
+
+    let rendered = render_with(&document, |options| {
+        options.headings = "setext".to_string();
+        options.list_style = "asterisk".to_string();
+        options.code_fence = "~~~".to_string();
+        options.wrap = "hard".to_string();
+        options.width = 20;
+    });
+
+    assert!(rendered.starts_with("Heading\n=======\n"));
+    assert!(rendered.contains("* a\n* b"));
+    assert!(rendered.contains("~~~sh\necho ```\n~~~"));
+    assert!(rendered.contains("one two three four\nfive six seven eight\nnine ten"));
+
+    let rendered = render_with(&document, |_| {});
+    assert!(rendered.starts_with("# Heading\n"));
+    assert!(rendered.contains("- a\n- b"));
+    assert!(rendered.contains("````sh\necho ```\n````"));
+}
+
+#[test]
+fn test_render_table_styles() {
+    use c2md::document::{Block, Table};
+
+    let header = vec!["Name".to_string(), "City".to_string()];
+    let rows = vec![vec!["Jane|Doe".to_string(), "London".to_string()]];
+    let mut document = c2md::Document::new();
+    document.push(Block::Table(Table::from_strings(&header, &rows)));
+
+    let grid = render_with(&document, |options| options.tables = "grid".to_string());
+    assert!(grid.contains("| Name      | City   |\n| --------- | ------ |\n| Jane\\|Doe | London |"));
+
+    let pipe = render_with(&document, |options| options.tables = "pipe".to_string());
+    assert!(pipe.contains("| Name | City |\n| --- | --- |\n| Jane\\|Doe | London |"));
+
+    let simple = render_with(&document, |options| options.tables = "simple".to_string());
+    assert!(simple.contains("Name | City\n--- | ---\nJane\\|Doe | London"));
+}
+
+#[test]
+fn test_render_escapes_text() {
+    let mut document = c2md::Document::new();
+    document.push(c2md::document::Block::paragraph("1. not a list and *not emphasis* in snake_case"));
+
+    let rendered = render_with(&document, |_| {});
+    assert_eq!(rendered, "1\\. not a list and \\*not emphasis\\* in snake_case\n");
+}
+
+#[test]
+fn test_cli_style_flags_override_config() {
+    let temp_dir = TempDir::new().unwrap();
+    let input_file = temp_dir.path().join("list.txt");
+    let output_file = temp_dir.path().join("list.md");
+
+    std::fs::write(&input_file, "Shopping\n\n- apples\n- pears").unwrap();
+
+    let mut cmd = Command::cargo_bin("c2md").unwrap();
+    cmd.arg(input_file.to_str().unwrap())
+        .arg("-o")
+        .arg(output_file.to_str().unwrap())
+        .arg("--list-style")
+        .arg("plus")
+        .arg("--frontmatter")
+        .arg("none");
+
+    cmd.assert().success();
+
+    let output = std::fs::read_to_string(&output_file).unwrap();
+    assert!(output.contains("+ apples\n+ pears"));
+    assert!(!output.contains("---"));
+}
+
+fn flavor_sample() -> c2md::Document {
+    c2md::Document::from_markdown(
+        "Visit <https://example.com> or read ~~old~~ news.\n\n- [x] done\n- [ ] todo\n\n| Name | Qty |\n| :--- | ---: |\n| Tea | 2 |\n\nSee the note[^n].\n\n[^n]: A footnote.\n",
+    )
+}
+
+fn parse_events(markdown: &str, flavor: c2md::renderer::Flavor) -> Vec<pulldown_cmark::Event<'_>> {
+    pulldown_cmark::Parser::new_ext(markdown, flavor.parser_options()).collect()
+}
+
+#[test]
+fn test_gfm_flavor_uses_extensions() {
+    use c2md::renderer::Flavor;
+    use pulldown_cmark::{Event, Tag};
+
+    let rendered = render_with(&flavor_sample(), |o| o.flavor = Flavor::Gfm);
+    let events = parse_events(&rendered, Flavor::Gfm);
+
+    assert!(rendered.contains("Visit https://example.com or"));
+    assert!(rendered.contains("| Name"));
+    assert!(events.iter().any(|e| matches!(e, Event::Start(Tag::Table(_)))));
+    assert!(events.iter().any(|e| matches!(e, Event::Start(Tag::Strikethrough))));
+    assert!(events.iter().any(|e| matches!(e, Event::TaskListMarker(true))));
+    assert!(events.iter().any(|e| matches!(e, Event::FootnoteReference(_))));
+}
+
+#[test]
+fn test_commonmark_flavor_avoids_extensions() {
+    use c2md::renderer::Flavor;
+    use pulldown_cmark::{Event, Tag};
+
+    for flavor in [Flavor::CommonMark, Flavor::Markdown] {
+        let rendered = render_with(&flavor_sample(), |o| o.flavor = flavor);
+        let events = parse_events(&rendered, flavor);
+
+        assert!(!rendered.contains("~~"), "{:?}: {}", flavor, rendered);
+        assert!(!rendered.contains("[^n]"), "{:?}: {}", flavor, rendered);
+        assert!(rendered.contains("<del>old</del>"));
+        assert!(rendered.contains("<th align=\"left\">Name</th>"));
+        assert!(events.iter().any(|e| matches!(e, Event::Html(html) if html.contains("<table>"))));
+        let text: String = events
+            .iter()
+            .filter_map(|e| match e {
+                Event::Text(t) => Some(t.as_ref()),
+                _ => None,
+            })
+            .collect();
+        assert!(text.contains("[x] done"));
+        assert!(events
+            .iter()
+            .any(|e| matches!(e, Event::Start(Tag::Link { dest_url, .. }) if dest_url.as_ref() == "https://example.com")));
+    }
+
+    let portable = render_with(&flavor_sample(), |o| o.flavor = Flavor::Markdown);
+    assert!(portable.contains("<https://example.com>"));
+    let strict = render_with(&flavor_sample(), |o| o.flavor = Flavor::CommonMark);
+    assert!(strict.contains("[https://example.com](https://example.com)"));
+}
+
+#[test]
+fn test_cli_to_commonmark() {
+    let temp_dir = TempDir::new().unwrap();
+    let input_file = temp_dir.path().join("data.csv");
+    let output_file = temp_dir.path().join("data.md");
+
+    std::fs::write(&input_file, "Name,Age\nAlice,30\n").unwrap();
+
+    let mut cmd = Command::cargo_bin("c2md").unwrap();
+    cmd.arg(input_file.to_str().unwrap())
+        .arg("-o")
+        .arg(output_file.to_str().unwrap())
+        .arg("--to")
+        .arg("commonmark")
+        .arg("--frontmatter")
+        .arg("none");
+
+    cmd.assert().success();
+
+    let output = std::fs::read_to_string(&output_file).unwrap();
+    assert!(output.contains("<table>"));
+    assert!(output.contains("<td>Alice</td>"));
+    assert!(!output.contains("| Name"));
+}
+
+/// Write a zip archive with stored (uncompressed) entries.
+fn write_zip(path: &std::path::Path, entries: &[(&str, &[u8])]) {
+    write_zip_with(path, entries, |content| (0, content.to_vec(), content.len() as u32));
+}
+
+/// `pack` turns each entry into its compression method, stored bytes and
+/// declared size.
+fn write_zip_with(path: &std::path::Path, entries: &[(&str, &[u8])], pack: impl Fn(&[u8]) -> (u16, Vec<u8>, u32)) {
+    let mut data = Vec::new();
+    let mut central = Vec::new();
+    for (name, content) in entries {
+        let mut crc = flate2::Crc::new();
+        crc.update(content);
+        let (method, stored, size) = pack(content);
+        let offset = data.len() as u32;
+        // Version needed, flags, method, time and date
+        let mut header = vec![20, 0, 0, 0];
+        header.extend_from_slice(&method.to_le_bytes());
+        header.extend_from_slice(&[0; 4]);
+        header.extend_from_slice(&crc.sum().to_le_bytes());
+        header.extend_from_slice(&(stored.len() as u32).to_le_bytes());
+        header.extend_from_slice(&size.to_le_bytes());
+        header.extend_from_slice(&(name.len() as u16).to_le_bytes());
+        header.extend_from_slice(&0u16.to_le_bytes());
+
+        data.extend_from_slice(&0x0403_4b50u32.to_le_bytes());
+        data.extend_from_slice(&header);
+        data.extend_from_slice(name.as_bytes());
+        data.extend_from_slice(&stored);
+
+        central.extend_from_slice(&0x0201_4b50u32.to_le_bytes());
+        central.extend_from_slice(&20u16.to_le_bytes());
+        central.extend_from_slice(&header);
+        central.extend_from_slice(&[0; 10]);
+        central.extend_from_slice(&offset.to_le_bytes());
+        central.extend_from_slice(name.as_bytes());
+    }
+    let central_offset = data.len() as u32;
+    data.extend_from_slice(&central);
+    data.extend_from_slice(&0x0605_4b50u32.to_le_bytes());
+    data.extend_from_slice(&[0; 4]);
+    data.extend_from_slice(&(entries.len() as u16).to_le_bytes());
+    data.extend_from_slice(&(entries.len() as u16).to_le_bytes());
+    data.extend_from_slice(&(central.len() as u32).to_le_bytes());
+    data.extend_from_slice(&central_offset.to_le_bytes());
+    data.extend_from_slice(&0u16.to_le_bytes());
+    std::fs::write(path, data).unwrap();
+}
+
+const W_NS: &str = "xmlns:w=\"http://schemas.openxmlformats.org/wordprocessingml/2006/main\" xmlns:r=\"http://schemas.openxmlformats.org/officeDocument/2006/relationships\"";
+
+fn write_docx(path: &std::path::Path, body: &str) {
+    let document = format!("<?xml version=\"1.0\" encoding=\"UTF-8\"?><w:document {}><w:body>{}</w:body></w:document>", W_NS, body);
+    let styles = format!(
+        "<w:styles {}><w:style w:type=\"paragraph\" w:styleId=\"Heading1\"><w:name w:val=\"heading 1\"/></w:style>\
+         <w:style w:type=\"paragraph\" w:styleId=\"Sub\"><w:name w:val=\"Sub\"/><w:basedOn w:val=\"Heading2\"/></w:style>\
+         <w:style w:type=\"paragraph\" w:styleId=\"Heading2\"><w:name w:val=\"heading 2\"/></w:style></w:styles>",
+        W_NS
+    );
+    let numbering = format!(
+        "<w:numbering {}><w:abstractNum w:abstractNumId=\"0\"><w:lvl w:ilvl=\"0\"><w:numFmt w:val=\"bullet\"/></w:lvl>\
+         <w:lvl w:ilvl=\"1\"><w:start w:val=\"1\"/><w:numFmt w:val=\"decimal\"/></w:lvl></w:abstractNum>\
+         <w:abstractNum w:abstractNumId=\"1\"><w:lvl w:ilvl=\"0\"><w:start w:val=\"3\"/><w:numFmt w:val=\"decimal\"/></w:lvl></w:abstractNum>\
+         <w:num w:numId=\"1\"><w:abstractNumId w:val=\"0\"/></w:num><w:num w:numId=\"2\"><w:abstractNumId w:val=\"1\"/></w:num></w:numbering>",
+        W_NS
+    );
+    let footnotes = format!(
+        "<w:footnotes {}><w:footnote w:type=\"separator\" w:id=\"-1\"><w:p/></w:footnote>\
+         <w:footnote w:id=\"1\"><w:p><w:r><w:t>A footnote.</w:t></w:r></w:p></w:footnote></w:footnotes>",
+        W_NS
+    );
+    let rels = "<Relationships xmlns=\"http://schemas.openxmlformats.org/package/2006/relationships\">\
+        <Relationship Id=\"rId1\" Type=\"http://schemas.openxmlformats.org/officeDocument/2006/relationships/hyperlink\" Target=\"https://example.com/\" TargetMode=\"External\"/>\
+        <Relationship Id=\"rId2\" Type=\"http://schemas.openxmlformats.org/officeDocument/2006/relationships/image\" Target=\"media/image1.png\"/></Relationships>";
+    let core = "<cp:coreProperties xmlns:cp=\"http://schemas.openxmlformats.org/package/2006/metadata/core-properties\" xmlns:dc=\"http://purl.org/dc/elements/1.1/\" xmlns:dcterms=\"http://purl.org/dc/terms/\">\
+        <dc:title>Quarterly Report</dc:title><dc:creator>Jane Doe</dc:creator><dcterms:created>2024-03-01T10:00:00Z</dcterms:created></cp:coreProperties>";
+
+    write_zip(
+        path,
+        &[
+            ("word/document.xml", document.as_bytes()),
+            ("word/styles.xml", styles.as_bytes()),
+            ("word/numbering.xml", numbering.as_bytes()),
+            ("word/footnotes.xml", footnotes.as_bytes()),
+            ("word/_rels/document.xml.rels", rels.as_bytes()),
+            ("word/media/image1.png", b"\x89PNG not really"),
+            ("docProps/core.xml", core.as_bytes()),
+        ],
+    );
+}
+
+fn docx_paragraph(style: &str, num: Option<(u8, u8)>, runs: &str) -> String {
+    let mut properties = String::new();
+    if !style.is_empty() {
+        properties.push_str(&format!("<w:pStyle w:val=\"{}\"/>", style));
+    }
+    if let Some((id, level)) = num {
+        properties.push_str(&format!("<w:numPr><w:ilvl w:val=\"{}\"/><w:numId w:val=\"{}\"/></w:numPr>", level, id));
+    }
+    format!("<w:p><w:pPr>{}</w:pPr>{}</w:p>", properties, runs)
+}
+
+fn docx_run(text: &str) -> String {
+    format!("<w:r><w:t xml:space=\"preserve\">{}</w:t></w:r>", text)
+}
+
+#[test]
+fn test_docx_conversion() {
+    let temp_dir = TempDir::new().unwrap();
+    let input_file = temp_dir.path().join("report.docx");
+    let output_file = temp_dir.path().join("report.md");
+    let assets_dir = temp_dir.path().join("assets");
+
+    let body = [
+        docx_paragraph("Heading1", None, &docx_run("Overview")),
+        docx_paragraph(
+            "",
+            None,
+            &format!(
+                "{}<w:r><w:rPr><w:b/></w:rPr><w:t>bold</w:t></w:r><w:r><w:rPr><w:b/></w:rPr><w:t xml:space=\"preserve\"> text</w:t></w:r>{}<w:hyperlink r:id=\"rId1\">{}</w:hyperlink>{}<w:r><w:footnoteReference w:id=\"1\"/></w:r>",
+                docx_run("Some "),
+                docx_run(" and a "),
+                docx_run("link"),
+                docx_run(".")
+            ),
+        ),
+        docx_paragraph("Sub", None, &docx_run("Details")),
+        docx_paragraph("", Some((1, 0)), &docx_run("First bullet")),
+        docx_paragraph("", Some((1, 1)), &docx_run("Nested step")),
+        docx_paragraph("", Some((1, 0)), &docx_run("Second bullet")),
+        docx_paragraph("", Some((2, 0)), &docx_run("Third")),
+        docx_paragraph("", Some((2, 0)), &docx_run("Fourth")),
+        "<w:tbl><w:tr><w:tc><w:p><w:r><w:t>Name</w:t></w:r></w:p></w:tc><w:tc><w:p><w:r><w:t>Qty</w:t></w:r></w:p></w:tc></w:tr>\
+         <w:tr><w:tc><w:p><w:r><w:t>Tea</w:t></w:r></w:p></w:tc><w:tc><w:p><w:r><w:t>2</w:t></w:r></w:p></w:tc></w:tr></w:tbl>"
+            .to_string(),
+        docx_paragraph(
+            "",
+            None,
+            "<w:r><w:drawing><wp:inline xmlns:wp=\"wp\"><wp:docPr id=\"1\" name=\"Picture 1\" descr=\"Sales chart\"/>\
+             <a:graphic xmlns:a=\"a\"><a:graphicData><pic:pic xmlns:pic=\"pic\"><pic:blipFill><a:blip r:embed=\"rId2\"/></pic:blipFill></pic:pic></a:graphicData></a:graphic></wp:inline></w:drawing></w:r>",
+        ),
+    ]
+    .concat();
+    write_docx(&input_file, &body);
+
+    let mut cmd = Command::cargo_bin("c2md").unwrap();
+    cmd.arg(input_file.to_str().unwrap())
+        .arg("-o")
+        .arg(output_file.to_str().unwrap())
+        .arg("--images")
+        .arg("download")
+        .arg("--assets-dir")
+        .arg(assets_dir.to_str().unwrap())
+        .arg("--tables")
+        .arg("pipe");
+
+    cmd.assert().success();
+
+    let output = std::fs::read_to_string(&output_file).unwrap();
+    assert!(output.contains("title: \"Quarterly Report\""), "{}", output);
+    assert!(output.contains("# Overview"));
+    assert!(output.contains("Some **bold text** and a [link](https://example.com/).[^1]"));
+    assert!(output.contains("## Details"));
+    assert!(output.contains("- First bullet\n  1. Nested step\n- Second bullet"));
+    assert!(output.contains("3. Third\n4. Fourth"));
+    assert!(output.contains("| Name | Qty |"));
+    assert!(output.contains("| Tea | 2 |"));
+    assert!(output.contains("![Sales chart]("));
+    assert!(output.contains("[^1]: A footnote."));
+    assert!(assets_dir.join("report-image1.png").exists());
+}
+
+#[test]
+fn test_docx_table_span() {
+    let temp_dir = TempDir::new().unwrap();
+    let input = temp_dir.path().join("span.docx");
+    let cell = |span: &str, text: &str| {
+        format!("<w:tc><w:tcPr><w:gridSpan w:val=\"{}\"/></w:tcPr><w:p><w:r><w:t>{}</w:t></w:r></w:p></w:tc>", span, text)
+    };
+    // The span would claim billions of columns in a two-column grid
+    let body = format!(
+        "<w:tbl><w:tblGrid><w:gridCol/><w:gridCol/></w:tblGrid><w:tr>{}</w:tr><w:tr>{}{}</w:tr></w:tbl>",
+        cell("4000000000", "Total"),
+        cell("1", "Tea"),
+        cell("1", "2")
+    );
+    write_docx(&input, &body);
+
+    let mut cmd = Command::cargo_bin("c2md").unwrap();
+    cmd.arg(&input).args(["--frontmatter", "none", "--tables", "pipe"]);
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("| Total |  |"))
+        .stdout(predicate::str::contains("| Tea | 2 |"));
+}
+
+fn deflate(content: &[u8]) -> Vec<u8> {
+    use std::io::Write;
+    let mut encoder = flate2::write::DeflateEncoder::new(Vec::new(), flate2::Compression::default());
+    encoder.write_all(content).unwrap();
+    encoder.finish().unwrap()
+}
+
+#[test]
+fn test_docx_zip_bomb() {
+    let temp_dir = TempDir::new().unwrap();
+    let paragraph = docx_paragraph("", None, &docx_run("Filler text."));
+    let small = format!("<w:document {}><w:body>{}</w:body></w:document>", W_NS, paragraph);
+    let large = format!("<w:document {}><w:body>{}</w:body></w:document>", W_NS, paragraph.repeat(200_000));
+
+    // Deflated parts read normally
+    let input = temp_dir.path().join("small.docx");
+    write_zip_with(&input, &[("word/document.xml", small.as_bytes())], |content| (8, deflate(content), content.len() as u32));
+    let mut cmd = Command::cargo_bin("c2md").unwrap();
+    cmd.arg(&input).args(["--frontmatter", "none"]);
+    cmd.assert().success().stdout(predicate::str::contains("Filler text."));
+
+    // A part that inflates past its declared size stops there
+    let input = temp_dir.path().join("bomb.docx");
+    write_zip_with(&input, &[("word/document.xml", large.as_bytes())], |content| (8, deflate(content), 4096));
+    let mut cmd = Command::cargo_bin("c2md").unwrap();
+    cmd.arg(&input);
+    cmd.assert().failure().stdout(predicate::str::contains("Archive entry too large: word/document.xml"));
+}
+
+#[test]
+fn test_docx_library_conversion() {
+    let temp_dir = TempDir::new().unwrap();
+    let input_file = temp_dir.path().join("memo.docx");
+    let field_link = "<w:r><w:fldChar w:fldCharType=\"begin\"/></w:r><w:r><w:instrText xml:space=\"preserve\"> HYPERLINK \"https://example.org\" </w:instrText></w:r>\
+        <w:r><w:fldChar w:fldCharType=\"separate\"/></w:r><w:r><w:t>site</w:t></w:r><w:r><w:fldChar w:fldCharType=\"end\"/></w:r>";
+    write_docx(&input_file, &docx_paragraph("", None, &format!("{}{}", docx_run("Visit the "), field_link)));
+
+    let options = c2md::Options {
+        frontmatter: "none".to_string(),
+        ..Default::default()
+    };
+    let markdown = c2md::convert(input_file.to_str().unwrap(), options).unwrap();
+    assert_eq!(markdown.trim(), "Visit the [site](https://example.org)");
+}
+
+fn write_xlsx(path: &std::path::Path) {
+    let ns = "xmlns=\"http://schemas.openxmlformats.org/spreadsheetml/2006/main\" xmlns:r=\"http://schemas.openxmlformats.org/officeDocument/2006/relationships\"";
+    let workbook = format!(
+        "<workbook {}><sheets><sheet name=\"Sales\" sheetId=\"1\" r:id=\"rId1\"/><sheet name=\"Staff\" sheetId=\"2\" r:id=\"rId2\"/>\
+         <sheet name=\"Lookup\" sheetId=\"3\" state=\"hidden\" r:id=\"rId3\"/></sheets></workbook>",
+        ns
+    );
+    let rels = "<Relationships xmlns=\"http://schemas.openxmlformats.org/package/2006/relationships\">\
+        <Relationship Id=\"rId1\" Type=\"http://schemas.openxmlformats.org/officeDocument/2006/relationships/worksheet\" Target=\"worksheets/sheet1.xml\"/>\
+        <Relationship Id=\"rId2\" Type=\"http://schemas.openxmlformats.org/officeDocument/2006/relationships/worksheet\" Target=\"/xl/worksheets/sheet2.xml\"/>\
+        <Relationship Id=\"rId3\" Type=\"http://schemas.openxmlformats.org/officeDocument/2006/relationships/worksheet\" Target=\"worksheets/sheet3.xml\"/></Relationships>";
+    let shared = format!(
+        "<sst {}><si><t>Region</t></si><si><t>Date</t></si><si><t>Revenue</t></si><si><r><t>No</t></r><r><t>rth</t></r></si>\
+         <si><t>Name</t></si><si><t>Ada</t></si></sst>",
+        ns
+    );
+    let styles = format!(
+        "<styleSheet {}><numFmts count=\"1\"><numFmt numFmtId=\"164\" formatCode=\"dd/mm/yyyy\"/></numFmts>\
+         <cellXfs count=\"4\"><xf numFmtId=\"0\"/><xf numFmtId=\"164\"/><xf numFmtId=\"4\"/><xf numFmtId=\"10\"/></cellXfs></styleSheet>",
+        ns
+    );
+    let sheet1 = format!(
+        "<worksheet {}><sheetData>\
+         <row r=\"1\"><c r=\"A1\" t=\"s\"><v>0</v></c><c r=\"B1\" t=\"s\"><v>1</v></c><c r=\"C1\" t=\"s\"><v>2</v></c><c r=\"D1\" t=\"inlineStr\"><is><t>Share</t></is></c></row>\
+         <row r=\"2\"><c r=\"A2\" t=\"s\"><v>3</v></c><c r=\"B2\" s=\"1\"><v>45292</v></c><c r=\"C2\" s=\"2\"><v>1234.5</v></c><c r=\"D2\" s=\"3\"><v>0.125</v></c></row>\
+         <row r=\"3\"><c r=\"B3\" s=\"1\"><v>45293</v></c><c r=\"C3\"><v>99</v></c><c r=\"D3\" t=\"b\"><v>1</v></c></row>\
+         </sheetData><mergeCells count=\"1\"><mergeCell ref=\"A2:A3\"/></mergeCells></worksheet>",
+        ns
+    );
+    let sheet2 = format!(
+        "<worksheet {}><sheetData><row r=\"1\"><c r=\"A1\" t=\"s\"><v>4</v></c></row><row r=\"2\"><c r=\"A2\" t=\"s\"><v>5</v></c></row></sheetData></worksheet>",
+        ns
+    );
+    let sheet3 = format!("<worksheet {}><sheetData><row r=\"1\"><c r=\"A1\"><v>1</v></c></row></sheetData></worksheet>", ns);
+
+    write_zip(
+        path,
+        &[
+            ("xl/workbook.xml", workbook.as_bytes()),
+            ("xl/_rels/workbook.xml.rels", rels.as_bytes()),
+            ("xl/sharedStrings.xml", shared.as_bytes()),
+            ("xl/styles.xml", styles.as_bytes()),
+            ("xl/worksheets/sheet1.xml", sheet1.as_bytes()),
+            ("xl/worksheets/sheet2.xml", sheet2.as_bytes()),
+            ("xl/worksheets/sheet3.xml", sheet3.as_bytes()),
+        ],
+    );
+}
+
+fn convert_xlsx(path: &std::path::Path, flags: &[&str]) -> String {
+    let mut cmd = Command::cargo_bin("c2md").unwrap();
+    cmd.arg(path.to_str().unwrap())
+        .args(["--frontmatter", "none", "--tables", "pipe"])
+        .args(flags);
+    String::from_utf8(cmd.assert().success().get_output().stdout.clone()).unwrap()
+}
+
+#[test]
+fn test_xlsx_conversion() {
+    let temp_dir = TempDir::new().unwrap();
+    let input_file = temp_dir.path().join("finance.xlsx");
+    write_xlsx(&input_file);
+
+    let output = convert_xlsx(&input_file, &[]);
+    assert!(output.contains("## Sales"));
+    assert!(output.contains("| Region | Date | Revenue | Share |"));
+    assert!(output.contains("| North | 2024-01-01 | 1,234.50 | 12.50% |"));
+    assert!(output.contains("| North | 2024-01-02 | 99 | TRUE |"));
+    assert!(output.contains("## Staff"));
+    assert!(output.contains("| Ada |"));
+    assert!(!output.contains("## Lookup"));
+}
+
+#[test]
+fn test_xlsx_sheet_selection() {
+    let temp_dir = TempDir::new().unwrap();
+    let input_file = temp_dir.path().join("finance.xlsx");
+    write_xlsx(&input_file);
+
+    let output = convert_xlsx(&input_file, &["--sheets", "2"]);
+    assert!(output.contains("## Staff"));
+    assert!(!output.contains("## Sales"));
+
+    let output = convert_xlsx(&input_file, &["--sheets", "S*"]);
+    assert!(output.contains("## Sales") && output.contains("## Staff"));
+
+    let output = convert_xlsx(&input_file, &["--sheet", "Lookup", "--sheets", "1"]);
+    assert!(output.contains("## Lookup"));
+    assert!(!output.contains("## Sales"));
+}
+
+const P_NS: &str = "xmlns:p=\"http://schemas.openxmlformats.org/presentationml/2006/main\" xmlns:a=\"http://schemas.openxmlformats.org/drawingml/2006/main\" xmlns:r=\"http://schemas.openxmlformats.org/officeDocument/2006/relationships\"";
+
+fn pptx_shape(placeholder: &str, paragraphs: &[(u8, &str)]) -> String {
+    let ph = if placeholder.is_empty() { String::new() } else { format!("<p:ph type=\"{}\"/>", placeholder) };
+    let body: String = paragraphs
+        .iter()
+        .map(|(level, text)| format!("<a:p><a:pPr lvl=\"{}\"/><a:r><a:rPr lang=\"en-US\"/><a:t>{}</a:t></a:r></a:p>", level, text))
+        .collect();
+    format!("<p:sp><p:nvSpPr><p:cNvPr id=\"2\" name=\"Shape\"/><p:cNvSpPr/><p:nvPr>{}</p:nvPr></p:nvSpPr><p:txBody><a:bodyPr/>{}</p:txBody></p:sp>", ph, body)
+}
+
+fn pptx_slide(shapes: &str) -> String {
+    format!("<p:sld {}><p:cSld><p:spTree><p:nvGrpSpPr/><p:grpSpPr/>{}</p:spTree></p:cSld></p:sld>", P_NS, shapes)
+}
+
+fn write_pptx(path: &std::path::Path) {
+    let rel = |id: &str, kind: &str, target: &str| {
+        format!("<Relationship Id=\"{}\" Type=\"http://schemas.openxmlformats.org/officeDocument/2006/relationships/{}\" Target=\"{}\"/>", id, kind, target)
+    };
+    let rels = |items: Vec<String>| {
+        format!("<Relationships xmlns=\"http://schemas.openxmlformats.org/package/2006/relationships\">{}</Relationships>", items.concat())
+    };
+
+    // Presentation order lists slide2.xml first
+    let presentation = format!("<p:presentation {}><p:sldIdLst><p:sldId id=\"256\" r:id=\"rId2\"/><p:sldId id=\"257\" r:id=\"rId1\"/></p:sldIdLst></p:presentation>", P_NS);
+    let presentation_rels = rels(vec![rel("rId1", "slide", "slides/slide1.xml"), rel("rId2", "slide", "slides/slide2.xml")]);
+
+    let intro = pptx_slide(&[pptx_shape("ctrTitle", &[(0, "Welcome")]), pptx_shape("subTitle", &[(0, "Kickoff meeting")])].concat());
+    let intro_rels = rels(vec![rel("rId1", "notesSlide", "../notesSlides/notesSlide1.xml")]);
+    let notes = format!(
+        "<p:notes {}><p:cSld><p:spTree>{}{}</p:spTree></p:cSld></p:notes>",
+        P_NS,
+        pptx_shape("sldImg", &[]),
+        pptx_shape("body", &[(0, "Greet everyone.")])
+    );
+
+    let table = "<p:graphicFrame><a:graphic><a:graphicData><a:tbl>\
+        <a:tr><a:tc><a:txBody><a:p><a:r><a:t>Goal</a:t></a:r></a:p></a:txBody></a:tc><a:tc><a:txBody><a:p><a:r><a:t>Owner</a:t></a:r></a:p></a:txBody></a:tc></a:tr>\
+        <a:tr><a:tc><a:txBody><a:p><a:r><a:t>Ship</a:t></a:r></a:p></a:txBody></a:tc><a:tc><a:txBody><a:p><a:r><a:t>Ana</a:t></a:r></a:p></a:txBody></a:tc></a:tr>\
+        </a:tbl></a:graphicData></a:graphic></p:graphicFrame>";
+    let picture = "<p:pic><p:nvPicPr><p:cNvPr id=\"4\" name=\"Picture 3\" descr=\"Roadmap\"/></p:nvPicPr><p:blipFill><a:blip r:embed=\"rId2\"/></p:blipFill></p:pic>";
+    let plan = pptx_slide(
+        &[
+            pptx_shape("title", &[(0, "Plan")]),
+            pptx_shape("body", &[(0, "Research"), (1, "Interviews"), (0, "Build")]),
+            table.to_string(),
+            picture.to_string(),
+        ]
+        .concat(),
+    );
+    let plan_rels = rels(vec![rel("rId2", "image", "../media/image1.png")]);
+
+    write_zip(
+        path,
+        &[
+            ("ppt/presentation.xml", presentation.as_bytes()),
+            ("ppt/_rels/presentation.xml.rels", presentation_rels.as_bytes()),
+            ("ppt/slides/slide1.xml", plan.as_bytes()),
+            ("ppt/slides/_rels/slide1.xml.rels", plan_rels.as_bytes()),
+            ("ppt/slides/slide2.xml", intro.as_bytes()),
+            ("ppt/slides/_rels/slide2.xml.rels", intro_rels.as_bytes()),
+            ("ppt/notesSlides/notesSlide1.xml", notes.as_bytes()),
+            ("ppt/media/image1.png", b"\x89PNG not really"),
+        ],
+    );
+}
+
+#[test]
+fn test_pptx_conversion() {
+    let temp_dir = TempDir::new().unwrap();
+    let input_file = temp_dir.path().join("deck.pptx");
+    let assets_dir = temp_dir.path().join("assets");
+    write_pptx(&input_file);
+
+    let mut cmd = Command::cargo_bin("c2md").unwrap();
+    cmd.arg(input_file.to_str().unwrap())
+        .args(["--frontmatter", "none", "--tables", "pipe", "--images", "download"])
+        .arg("--assets-dir")
+        .arg(assets_dir.to_str().unwrap());
+    let output = String::from_utf8(cmd.assert().success().get_output().stdout.clone()).unwrap();
+
+    let welcome = output.find("## <a id=\"slide-1\"></a>Welcome").expect(&output);
+    let plan = output.find("## <a id=\"slide-2\"></a>Plan").expect(&output);
+    assert!(welcome < plan);
+    assert!(output.contains("Kickoff meeting"));
+    assert!(output.contains("> Greet everyone."));
+    assert!(output.contains("- Research\n  - Interviews\n- Build"));
+    assert!(output.contains("| Goal | Owner |\n| --- | --- |\n| Ship | Ana |"));
+    assert!(output.contains("![Roadmap]("));
+    assert!(assets_dir.join("deck-image1.png").exists());
+}
+
+#[test]
+fn test_pptx_notes_section() {
+    let temp_dir = TempDir::new().unwrap();
+    let input_file = temp_dir.path().join("deck.pptx");
+    let config_file = temp_dir.path().join("c2md.yaml");
+    write_pptx(&input_file);
+
+    let mut config = serde_yaml::to_value(c2md::Config::default()).unwrap();
+    config["slides"]["notes"] = "section".into();
+    config["frontmatter"] = "none".into();
+    std::fs::write(&config_file, serde_yaml::to_string(&config).unwrap()).unwrap();
+
+    let mut cmd = Command::cargo_bin("c2md").unwrap();
+    cmd.arg(input_file.to_str().unwrap())
+        .arg("-c")
+        .arg(config_file.to_str().unwrap())
+        .arg("--assets-dir")
+        .arg(temp_dir.path().join("assets"));
+    let output = String::from_utf8(cmd.assert().success().get_output().stdout.clone()).unwrap();
+
+    assert!(output.contains("### Notes\n\nGreet everyone."));
+    assert!(!output.contains("> Greet"));
+}
+
+fn write_epub(path: &std::path::Path) {
+    let container = r#"<?xml version="1.0"?><container version="1.0" xmlns="urn:oasis:names:tc:opendocument:xmlns:container"><rootfiles><rootfile full-path="OEBPS/content.opf" media-type="application/oebps-package+xml"/></rootfiles></container>"#;
+    let opf = r#"<?xml version="1.0"?>
+<package xmlns="http://www.idpf.org/2007/opf" version="3.0" unique-identifier="uid">
+  <metadata xmlns:dc="http://purl.org/dc/elements/1.1/">
+    <dc:identifier id="uid">urn:isbn:9780000000001</dc:identifier>
+    <dc:title>The Sample Book</dc:title>
+    <dc:creator>Ada Writer</dc:creator>
+    <dc:language>en</dc:language>
+    <dc:publisher>Example Press</dc:publisher>
+  </metadata>
+  <manifest>
+    <item id="nav" href="nav.xhtml" media-type="application/xhtml+xml" properties="nav"/>
+    <item id="c2" href="text/ch2.xhtml" media-type="application/xhtml+xml"/>
+    <item id="c1" href="text/ch1.xhtml" media-type="application/xhtml+xml"/>
+    <item id="fig" href="images/fig.png" media-type="image/png"/>
+  </manifest>
+  <spine><itemref idref="nav"/><itemref idref="c1"/><itemref idref="c2"/></spine>
+</package>"#;
+    let nav = r#"<html xmlns="http://www.w3.org/1999/xhtml" xmlns:epub="http://www.idpf.org/2007/ops"><body>
+<nav epub:type="toc"><ol><li><a href="text/ch1.xhtml">Getting Started</a></li><li><a href="text/ch2.xhtml#more">Going Further</a></li></ol></nav>
+</body></html>"#;
+    let ch1 = r#"<html xmlns="http://www.w3.org/1999/xhtml"><head><title>One</title></head><body>
+<h1>Getting Started</h1><p>See <a href="ch2.xhtml#detail">the details</a> later.</p>
+<p><img src="../images/fig.png" alt="Figure"/></p>
+</body></html>"#;
+    let ch2 = r#"<html xmlns="http://www.w3.org/1999/xhtml"><head><title>Two</title></head><body>
+<h1 id="more">Going Further</h1><p id="detail">Details live here.</p>
+</body></html>"#;
+    write_zip(
+        path,
+        &[
+            ("mimetype", b"application/epub+zip"),
+            ("META-INF/container.xml", container.as_bytes()),
+            ("OEBPS/content.opf", opf.as_bytes()),
+            ("OEBPS/nav.xhtml", nav.as_bytes()),
+            ("OEBPS/text/ch1.xhtml", ch1.as_bytes()),
+            ("OEBPS/text/ch2.xhtml", ch2.as_bytes()),
+            ("OEBPS/images/fig.png", b"\x89PNG\r\n\x1a\nfake"),
+        ],
+    );
+}
+
+#[test]
+fn test_epub_conversion() {
+    let temp_dir = TempDir::new().unwrap();
+    let input_file = temp_dir.path().join("book.epub");
+    let assets_dir = temp_dir.path().join("assets");
+    write_epub(&input_file);
+
+    let mut cmd = Command::cargo_bin("c2md").unwrap();
+    cmd.arg(input_file.to_str().unwrap()).arg("--assets-dir").arg(assets_dir.to_str().unwrap());
+    let output = String::from_utf8(cmd.assert().success().get_output().stdout.clone()).unwrap();
+
+    assert!(output.contains("title: \"The Sample Book\""));
+    assert!(output.contains("author: \"Ada Writer\""));
+    assert!(output.contains("Example Press"));
+    assert!(output.contains("urn:isbn:9780000000001"));
+    assert!(output.contains("- [Going Further](#going-further)"));
+    let first = output.find("# Getting Started").unwrap();
+    let second = output.find("# Going Further").unwrap();
+    assert!(first < second);
+    assert!(output.contains("[the details](#going-further)"));
+    assert!(output.contains("![Figure]("));
+    assert!(assets_dir.join("book-fig.png").exists());
+}
+
+#[test]
+fn test_epub_split_chapters() {
+    let temp_dir = TempDir::new().unwrap();
+    let input_file = temp_dir.path().join("book.epub");
+    let output_file = temp_dir.path().join("out").join("book.md");
+    write_epub(&input_file);
+
+    let mut cmd = Command::cargo_bin("c2md").unwrap();
+    cmd.arg(input_file.to_str().unwrap())
+        .arg("-o")
+        .arg(output_file.to_str().unwrap())
+        .arg("--split")
+        .arg("chapters")
+        .arg("--frontmatter")
+        .arg("none")
+        .arg("--assets-dir")
+        .arg(temp_dir.path().join("assets"));
+    cmd.assert().success();
+
+    let chapters = temp_dir.path().join("out").join("book");
+    let index = std::fs::read_to_string(chapters.join("index.md")).unwrap();
+    let ch1 = std::fs::read_to_string(chapters.join("ch1.md")).unwrap();
+    let ch2 = std::fs::read_to_string(chapters.join("ch2.md")).unwrap();
+    assert!(index.contains("[Going Further](ch2.md#going-further)"));
+    assert!(ch1.contains("[the details](ch2.md#going-further)"));
+    assert!(ch2.starts_with("# Going Further"));
+}
+
+const HTML_SAMPLE: &str = r##"<!DOCTYPE html>
+<html><head><title>Release Notes</title></head>
+<body>
+<h1 class="title" id="top">Release <em>notes</em></h1>
+<p>Read   the <a href="https://example.com/docs" title="The docs">docs</a> &amp; more.<sup><a href="#fn1" id="fnref1">1</a></sup></p>
+<p><img src="images/chart.png" alt="Chart"></p>
+<ol start="3"><li>Third
+  <ul><li>Nested <b>one</b></li><li>Nested two</li></ul></li>
+<li>Fourth</li></ol>
+<blockquote><p>Quoted.</p></blockquote>
+<pre><code class="language-rust">fn main() {
+    println!("&lt;hi&gt;");
+}
+</code></pre>
+<table><thead><tr><th align="left">Name</th><th>Qty</th></tr></thead>
+<tbody><tr><td>Widget</td><td>4</td></tr><tr><td colspan="2">Total</td></tr></tbody></table>
+<dl><dt>Term</dt><dd>The definition.</dd></dl>
+<section class="footnotes"><hr><ol><li id="fn1"><p>The note.<a href="#fnref1">↩</a></p></li></ol></section>
+</body></html>"##;
+
+#[test]
+fn test_html_conversion() {
+    let temp_dir = TempDir::new().unwrap();
+    let input_file = temp_dir.path().join("notes.html");
+    std::fs::write(&input_file, HTML_SAMPLE).unwrap();
+
+    let mut cmd = Command::cargo_bin("c2md").unwrap();
+    cmd.arg(input_file.to_str().unwrap()).arg("--frontmatter").arg("none");
+    let output = String::from_utf8(cmd.assert().success().get_output().stdout.clone()).unwrap();
+
+    assert!(output.starts_with("# Release *notes*\n"));
+    assert!(output.contains("Read the [docs](https://example.com/docs \"The docs\") & more.[^1]"));
+    assert!(output.contains("![Chart](images/chart.png)"));
+    assert!(output.contains("3. Third\n   - Nested **one**\n   - Nested two\n4. Fourth"));
+    assert!(output.contains("> Quoted."));
+    assert!(output.contains("```rust\nfn main() {\n    println!(\"<hi>\");\n}\n```"));
+    assert!(output.contains("| Name"));
+    assert!(output.contains("| :---"));
+    assert!(output.contains("| Total"));
+    assert!(output.contains("- **Term**\n\n  The definition."));
+    assert!(output.trim_end().ends_with("[^1]: The note."));
+    assert!(!output.contains("Release Notes"));
+    assert!(!output.contains("\n---\n"));
+}
+
+const WEB_PAGE_SAMPLE: &str = r#"<!DOCTYPE html>
+<html lang="en">
+<head>
+<title>Launch | Example News</title>
+<meta property="og:title" content="We launched">
+<meta name="author" content="Ada Writer">
+<meta property="article:published_time" content="2024-03-05T09:00:00Z">
+</head>
+<body>
+<nav><a href="/">Home</a> <a href="/blog">Blog</a></nav>
+<div class="cookie-banner">We use cookies. <button>Accept</button></div>
+<div class="layout">
+<div class="related"><p>Related: <a href="/a">Another story</a>, <a href="/b">and another one</a></p></div>
+<div class="story">
+<h1>We launched</h1>
+<p>After two years of work, the product is out today, and it is available to everyone, everywhere, at no cost.</p>
+<p>Early users wrote in with feedback, bug reports, and ideas, which shaped the release in many small ways.</p>
+<p>The next release, planned for the autumn, brings offline support, sharing, and a faster search index.</p>
+</div>
+</div>
+<footer>Copyright Example News</footer>
+</body>
+</html>"#;
+
+#[test]
+fn test_html_extract() {
+    let temp_dir = TempDir::new().unwrap();
+    let input_file = temp_dir.path().join("page.html");
+    std::fs::write(&input_file, WEB_PAGE_SAMPLE).unwrap();
+    let convert = |extract: Option<&str>| {
+        let mut cmd = Command::cargo_bin("c2md").unwrap();
+        cmd.arg(input_file.to_str().unwrap());
+        if let Some(extract) = extract {
+            cmd.arg("--html-extract").arg(extract);
+        }
+        String::from_utf8(cmd.assert().success().get_output().stdout.clone()).unwrap()
+    };
+
+    let main = convert(None);
+    assert!(main.contains("title: \"We launched\""));
+    assert!(main.contains("author: \"Ada Writer\""));
+    assert!(main.contains("2024-03-05"));
+    assert!(main.contains("# We launched"));
+    assert!(!main.contains("Blog"));
+    assert!(!main.contains("cookies"));
+    assert!(!main.contains("Copyright"));
+
+    let full = convert(Some("full"));
+    assert!(full.contains("Blog"));
+    assert!(full.contains("Copyright"));
+
+    let article = convert(Some("article"));
+    assert!(article.contains("After two years of work"));
+    assert!(article.contains("faster search index"));
+    assert!(!article.contains("Another story"));
+
+    let selected = convert(Some("selector:div.story > p"));
+    assert!(selected.contains("Early users wrote in"));
+    assert!(!selected.contains("# We launched"));
+
+    let mut cmd = Command::cargo_bin("c2md").unwrap();
+    cmd.arg(input_file.to_str().unwrap()).arg("--html-extract").arg("selector:div[");
+    cmd.assert().failure().stdout(predicate::str::contains("Invalid CSS selector `div[`"));
+}
+
+#[test]
+fn test_html_raw_html_policy() {
+    let temp_dir = TempDir::new().unwrap();
+    let input_file = temp_dir.path().join("raw.html");
+    std::fs::write(
+        &input_file,
+        r#"<p>H<sub>2</sub>O, <a href="ftp://example.com/f">files</a> and <em>more</em>.</p>
+<details><summary>More</summary><p>Hidden text.</p></details>
+<video src="clip.mp4" data-id="7">No video.</video>"#,
+    )
+    .unwrap();
+    let convert = |args: &[&str]| {
+        let mut cmd = Command::cargo_bin("c2md").unwrap();
+        cmd.arg(input_file.to_str().unwrap()).arg("--frontmatter").arg("none").args(args);
+        String::from_utf8(cmd.assert().success().get_output().stdout.clone()).unwrap()
+    };
+
+    let kept = convert(&[]);
+    assert!(kept.contains("H<sub>2</sub>O, [files](ftp://example.com/f) and *more*."));
+    assert!(kept.contains("<details>\n<summary>More</summary>\n\nHidden text.\n\n</details>"));
+    assert!(kept.contains("No video."));
+    assert!(!kept.contains("<video"));
+
+    let stripped = convert(&["--raw-html", "strip"]);
+    assert!(stripped.contains("H2O, [files]"));
+    assert!(stripped.contains("More\n\nHidden text."));
+    assert!(!stripped.contains("<details>"));
+
+    let escaped = convert(&["--raw-html", "escape"]);
+    assert!(escaped.contains("H\\<sub>2\\</sub>O"));
+    assert!(escaped.contains("\\<details>"));
+
+    let config = temp_dir.path().join("c2md.yaml");
+    let mut settings = c2md::Config::default();
+    settings.html.tags = Some(["p", "a", "video", "details", "summary"].map(String::from).to_vec());
+    settings.html.attributes.insert("video".to_string(), vec!["src".to_string()]);
+    settings.html.url_schemes = Some(vec!["https".to_string()]);
+    settings.save(&config).unwrap();
+    let configured = convert(&["--config", config.to_str().unwrap()]);
+    assert!(configured.contains("H2O, files and more."));
+    assert!(configured.contains("<video src=\"clip.mp4\" data-id=\"7\">No video.</video>"));
+}
+
+#[test]
+fn test_text_encodings() {
+    let temp_dir = TempDir::new().unwrap();
+    let convert = |name: &str, data: &[u8], args: &[&str]| {
+        let input_file = temp_dir.path().join(name);
+        std::fs::write(&input_file, data).unwrap();
+        let mut cmd = Command::cargo_bin("c2md").unwrap();
+        cmd.arg(input_file.to_str().unwrap()).arg("--frontmatter").arg("none").args(args);
+        String::from_utf8(cmd.assert().success().get_output().stdout.clone()).unwrap()
+    };
+
+    // Declared in a http-equiv content type
+    let (page, _, _) = encoding_rs::SHIFT_JIS.encode(
+        r#"<html><head><meta http-equiv="Content-Type" content="text/html; charset=Shift_JIS"></head>
+<body><p>日本語のページです。</p></body></html>"#,
+    );
+    assert!(convert("sjis.html", &page, &[]).contains("日本語のページです。"));
+
+    // Undeclared, told apart by the text
+    let (page, _, _) = encoding_rs::WINDOWS_1252.encode("<p>Café crème brûlée à Noël, déjà vu.</p>");
+    assert!(convert("latin.html", &page, &[]).contains("Café crème brûlée à Noël, déjà vu."));
+    let (text, _, _) = encoding_rs::WINDOWS_1251.encode("Привет, мир! Это простой текст на русском языке.");
+    assert!(convert("russian.txt", &text, &[]).contains("Привет, мир!"));
+    let (table, _, _) = encoding_rs::WINDOWS_1252.encode("Name,City\nAndré,Montréal\n");
+    assert!(convert("cities.csv", &table, &[]).contains("| André | Montréal |"));
+    let mut bom = vec![0xFF, 0xFE];
+    bom.extend("Héllo".encode_utf16().flat_map(u16::to_le_bytes));
+    assert!(convert("utf16.txt", &bom, &[]).contains("Héllo"));
+
+    // An explicit encoding wins, in RTF over \ansicpg too
+    let (text, _, _) = encoding_rs::KOI8_R.encode("Привет, мир!");
+    assert!(convert("koi8.txt", &text, &["--encoding", "koi8-r"]).contains("Привет, мир!"));
+    let rtf = br"{\rtf1\ansi\ansicpg1252 {\pard \'cf\'f0\'e8\'e2\'e5\'f2\par}}";
+    assert!(convert("cyrillic.rtf", rtf, &[]).contains("Ïðèâåò"));
+    assert!(convert("cyrillic.rtf", rtf, &["--encoding", "windows-1251"]).contains("Привет"));
+
+    // Metadata and validation decode with the override as well
+    let metadata = |name: &str, data: &[u8], args: &[&str]| {
+        let input_file = temp_dir.path().join(name);
+        std::fs::write(&input_file, data).unwrap();
+        let mut cmd = Command::cargo_bin("c2md").unwrap();
+        cmd.arg(&input_file).args(args);
+        String::from_utf8(cmd.assert().success().get_output().stdout.clone()).unwrap()
+    };
+    let (page, _, _) = encoding_rs::KOI8_R.encode("<html><head><title>Привет, мир</title></head><body><p>Текст.</p></body></html>");
+    assert!(metadata("koi8.html", &page, &["--encoding", "koi8-r"]).contains("- **title**: Привет, мир"));
+    let rtf = br"{\rtf1\ansi\ansicpg1252 {\info{\title \'cf\'f0\'e8\'e2\'e5\'f2}}{\pard Text\par}}";
+    assert!(metadata("titled.rtf", rtf, &["--encoding", "windows-1251"]).contains("- **title**: Привет"));
+}
+
+const RTF_SAMPLE: &str = r#"{\rtf1\ansi\ansicpg1252\deff0
+{\fonttbl{\f0\fswiss\fcharset0 Arial;}{\f1\fswiss\fcharset204 Arial Cyr;}{\f2\fnil\fcharset2 Symbol;}}
+{\stylesheet{\s0 Normal;}{\s1\b heading 1;}}
+{\info{\title Customer Export}{\author CRM}{\creatim\yr2021\mo4\dy9}}
+{\pard\s1 Account Notes\par}
+\pard Caf\'e9 {\f1 \'cf\'f0\'e8\'e2\'e5\'f2} \u8364? total: {\b bold} {\i italic} {\ul under}\par
+\pard See {\field{\*\fldinst HYPERLINK "https://example.com/crm"}{\fldrslt our portal}} for details.\par
+{\listtext\f2 \'b7\tab}\pard\ls1 First item\par
+{\listtext\f2 \'b7\tab}\pard\ls1 Second item\par
+\trowd\cellx2000\cellx4000
+\pard\intbl Name\cell Amount\cell\row
+\trowd\cellx2000\cellx4000
+\pard\intbl Widget\cell 42\cell\row
+\pard {\*\shppict{\pict\pngblip\picw1\pich1 89504e470d0a1a0a}}{\nonshppict{\pict\wmetafile8 0102}}\par
+\pard Closing{\super\chftn}{\footnote\pard{\super\chftn} A footnote.}\par
+}"#;
+
+#[test]
+fn test_rtf_conversion() {
+    let temp_dir = TempDir::new().unwrap();
+    let input_file = temp_dir.path().join("export.rtf");
+    let assets_dir = temp_dir.path().join("assets");
+    std::fs::write(&input_file, RTF_SAMPLE).unwrap();
+
+    let mut cmd = Command::cargo_bin("c2md").unwrap();
+    cmd.arg(input_file.to_str().unwrap()).arg("--assets-dir").arg(assets_dir.to_str().unwrap());
+    let output = String::from_utf8(cmd.assert().success().get_output().stdout.clone()).unwrap();
+
+    assert!(output.contains("title: \"Customer Export\""));
+    assert!(output.contains("# Account Notes"));
+    assert!(output.contains("Café Привет € total: **bold** *italic* <u>under</u>"));
+    assert!(output.contains("[our portal](https://example.com/crm)"));
+    assert!(output.contains("- First item\n- Second item"));
+    assert!(output.contains("| Widget | 42     |"));
+    assert!(output.contains("![Image]("));
+    assert!(assets_dir.join("export-image1.png").exists());
+    assert!(!assets_dir.join("export-image2.wmf").exists());
+    assert!(output.contains("Closing[^1]"));
+    assert!(output.contains("[^1]: A footnote."));
+}
+
+/// A minimal PDF with one page per content stream; `/F1` is Helvetica,
+/// `/F2` Helvetica-Bold and `/F3` Courier.
+fn write_pdf(path: &std::path::Path, pages: &[&str]) {
+    write_pdf_with(path, pages, "", &[]);
+}
+
+/// Like `write_pdf`, with extra catalog entries and extra objects numbered
+/// after the pages' (from `7 + 2 * pages.len()`).
+fn write_pdf_with(path: &std::path::Path, pages: &[&str], catalog: &str, extra: &[&str]) {
+    let pages: Vec<(&str, &str, &str)> = pages.iter().map(|content| (*content, "", "")).collect();
+    write_pdf_pages(path, &pages, catalog, extra);
+}
+
+/// Like `write_pdf_with`, with pages given as their content, extra resources
+/// and extra entries of the page dictionary.
+fn write_pdf_pages(path: &std::path::Path, pages: &[(&str, &str, &str)], catalog: &str, extra: &[&str]) {
+    let fonts = [
+        ("Helvetica", "WinAnsiEncoding"),
+        ("Helvetica-Bold", "WinAnsiEncoding"),
+        ("Courier", "WinAnsiEncoding"),
+        ("Times-Roman", "StandardEncoding"),
+    ];
+    let first_font = 3;
+    let first_page = first_font + fonts.len();
+    let kids: Vec<String> = (0..pages.len()).map(|i| format!("{} 0 R", first_page + 2 * i)).collect();
+    let font_refs: Vec<String> = (0..fonts.len()).map(|i| format!("/F{} {} 0 R", i + 1, first_font + i)).collect();
+
+    let mut objects = vec![
+        format!("<< /Type /Catalog /Pages 2 0 R {}>>", catalog),
+        format!("<< /Type /Pages /Kids [{}] /Count {} >>", kids.join(" "), pages.len()),
+    ];
+    for (font, encoding) in fonts {
+        objects.push(format!("<< /Type /Font /Subtype /Type1 /BaseFont /{} /Encoding /{} >>", font, encoding));
+    }
+    for (i, (content, resources, entries)) in pages.iter().enumerate() {
+        objects.push(format!(
+            "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 612 792] /Resources << /Font << {} >> {}>> /Contents {} 0 R {}>>",
+            font_refs.join(" "),
+            resources,
+            first_page + 2 * i + 1,
+            entries
+        ));
+        objects.push(format!("<< /Length {} >>\nstream\n{}\nendstream", content.len() + 1, content));
+    }
+    objects.extend(extra.iter().map(|object| object.to_string()));
+
+    let mut pdf = b"%PDF-1.4\n".to_vec();
+    let mut offsets = Vec::new();
+    for (i, object) in objects.iter().enumerate() {
+        offsets.push(pdf.len());
+        pdf.extend_from_slice(format!("{} 0 obj\n{}\nendobj\n", i + 1, object).as_bytes());
+    }
+    let xref = pdf.len();
+    pdf.extend_from_slice(format!("xref\n0 {}\n0000000000 65535 f \n", objects.len() + 1).as_bytes());
+    for offset in offsets {
+        pdf.extend_from_slice(format!("{:010} 00000 n \n", offset).as_bytes());
+    }
+    pdf.extend_from_slice(format!("trailer\n<< /Size {} /Root 1 0 R >>\nstartxref\n{}\n%%EOF\n", objects.len() + 1, xref).as_bytes());
+    std::fs::write(path, pdf).unwrap();
+}
+
+fn convert_pdf(path: &std::path::Path, flags: &[&str]) -> String {
+    let mut cmd = Command::cargo_bin("c2md").unwrap();
+    cmd.arg(path.to_str().unwrap()).args(["--frontmatter", "none"]).args(flags);
+    String::from_utf8(cmd.assert().success().get_output().stdout.clone()).unwrap()
+}
+
+#[test]
+fn test_pdf_text_extraction() {
+    let temp_dir = TempDir::new().unwrap();
+    let input_file = temp_dir.path().join("report.pdf");
+    write_pdf(
+        &input_file,
+        &[r"BT /F1 24 Tf 72 720 Td (Quarterly Report) Tj ET
+BT /F1 16 Tf 72 684 Td (Overview) Tj ET
+BT /F1 11 Tf 72 656 Td (Revenue grew in every region this quarter, driven by) Tj
+0 -14 Td (strong demand for ) Tj /F2 11 Tf (new) Tj /F1 11 Tf ( products and services.) Tj ET
+BT /F1 11 Tf 72 610 Td (\225 First point about the results) Tj 0 -14 Td (\225 Second point about the results) Tj ET
+BT /F1 11 Tf 72 570 Td (1. Review the regional numbers) Tj 0 -14 Td (2. Plan the next quarter) Tj ET
+BT /F3 10 Tf 72 530 Td (let total = 42;) Tj ET
+BT /F2 11 Tf 72 500 Td (Outlook) Tj ET
+BT /F1 11 Tf 72 480 Td [(Growth is expected to contin) 20 (ue.)] TJ ET"],
+    );
+
+    let output = convert_pdf(&input_file, &[]);
+    assert!(output.contains("# Quarterly Report\n\n## Overview\n"));
+    assert!(output.contains("Revenue grew in every region this quarter, driven by strong demand for **new** products and services."));
+    assert!(output.contains("- First point about the results\n- Second point about the results"));
+    assert!(output.contains("1. Review the regional numbers\n2. Plan the next quarter"));
+    assert!(output.contains("```\nlet total = 42;\n```"));
+    assert!(output.contains("### Outlook"));
+    assert!(output.contains("Growth is expected to continue."));
+}
+
+#[test]
+fn test_pdf_layout_modes() {
+    let temp_dir = TempDir::new().unwrap();
+    let input_file = temp_dir.path().join("order.pdf");
+    // The second line is drawn first
+    write_pdf(
+        &input_file,
+        &["BT /F1 11 Tf 72 680 Td (Drawn first, shown second.) Tj ET\nBT /F1 11 Tf 72 720 Td (Drawn second, shown first.) Tj ET"],
+    );
+
+    let smart = convert_pdf(&input_file, &["--pdf-layout", "smart"]);
+    assert!(smart.find("shown first").unwrap() < smart.find("shown second").unwrap());
+    let raw = convert_pdf(&input_file, &["--pdf-layout", "raw"]);
+    assert!(raw.find("shown second").unwrap() < raw.find("shown first").unwrap());
+}
+
+#[test]
+fn test_pdf_columns() {
+    let temp_dir = TempDir::new().unwrap();
+    let input_file = temp_dir.path().join("paper.pdf");
+    // Both columns are drawn row by row, as many producers do
+    let mut content = String::from("BT /F1 18 Tf 72 740 Td (A Study in Two Columns) Tj ET\n");
+    for (i, y) in [700, 686, 672].iter().enumerate() {
+        content.push_str(&format!("BT /F1 10 Tf 72 {} Td (Left column line {} of the first part) Tj ET\n", y, i + 1));
+        content.push_str(&format!("BT /F1 10 Tf 320 {} Td (Right column line {} of the second part) Tj ET\n", y, i + 1));
+    }
+    content.push_str("BT /F1 10 Tf 72 630 Td (Figure 1: A caption that spans the full width of the page below both columns.) Tj ET");
+    write_pdf(&input_file, &[&content]);
+
+    let smart = convert_pdf(&input_file, &["--pdf-layout", "smart"]);
+    assert!(smart.contains(
+        "Left column line 1 of the first part Left column line 2 of the first part Left column line 3 of the first part"
+    ));
+    assert!(smart.contains("Right column line 1 of the second part Right column line 2"));
+    let title = smart.find("# A Study").unwrap();
+    let right = smart.find("Right column line 1").unwrap();
+    assert!(title < smart.find("Left column line 1").unwrap());
+    assert!(smart.find("Left column line 3").unwrap() < right);
+    assert!(right < smart.find("Figure 1").unwrap());
+
+    let raw = convert_pdf(&input_file, &["--pdf-layout", "raw"]);
+    assert!(raw.find("Right column line 1").unwrap() < raw.find("Left column line 2").unwrap());
+}
+
+/// A ruled 3x3 grid from x 72 to 372 and y 700 down to 640; `bottom_middle`
+/// leaves out the bottom row's first inner border.
+fn pdf_grid(cells: [[&str; 3]; 3], bottom_middle: bool) -> String {
+    let mut content = String::from("0.5 w\n");
+    for y in [700, 680, 660, 640] {
+        content.push_str(&format!("72 {} m 372 {} l S\n", y, y));
+    }
+    for x in [72, 172, 272, 372] {
+        let bottom = if x == 172 && !bottom_middle { 660 } else { 640 };
+        content.push_str(&format!("{} 700 m {} {} l S\n", x, x, bottom));
+    }
+    for (row, y) in cells.iter().zip([686, 666, 646]) {
+        for (cell, x) in row.iter().zip([76, 176, 276]) {
+            if !cell.is_empty() {
+                content.push_str(&format!("BT /F1 10 Tf {} {} Td ({}) Tj ET\n", x, y, cell));
+            }
+        }
+    }
+    content
+}
+
+#[test]
+fn test_pdf_tables() {
+    let temp_dir = TempDir::new().unwrap();
+    let input_file = temp_dir.path().join("tables.pdf");
+    let ruled = pdf_grid([["Item", "Qty", "Price"], ["Apples", "3", "1.20"], ["Pears", "5", "0.80"]], true);
+    let merged = pdf_grid([["Item", "Qty", "Price"], ["Apples", "3", "1.20"], ["Total", "", "2.00"]], false);
+    let aligned = "BT /F1 10 Tf 72 700 Td (Region) Tj 120 0 Td (Units) Tj 80 0 Td (Revenue) Tj ET
+BT /F1 10 Tf 72 686 Td (North) Tj 120 0 Td (12) Tj 80 0 Td (1,200) Tj ET
+BT /F1 10 Tf 72 672 Td (South) Tj 120 0 Td (7) Tj 80 0 Td (700) Tj ET
+BT /F1 10 Tf 72 640 Td (Totals are unaudited and may change.) Tj ET";
+    write_pdf(&input_file, &[&ruled, &merged, aligned]);
+
+    let output = convert_pdf(&input_file, &["--tables", "pipe"]);
+    assert!(output.contains("| Item | Qty | Price |\n| --- | --- | --- |\n| Apples | 3 | 1.20 |\n| Pears | 5 | 0.80 |"));
+    assert!(output.contains("<tr><td colspan=\"2\">Total</td><td>2.00</td></tr>"));
+    assert!(output.contains("| Region | Units | Revenue |"));
+    assert!(output.contains("| South | 7 | 700 |"));
+    assert!(output.contains("Totals are unaudited and may change."));
+}
+
+#[test]
+fn test_pdf_damaged_xref_count() {
+    let temp_dir = TempDir::new().unwrap();
+    let input_file = temp_dir.path().join("damaged.pdf");
+    write_pdf(&input_file, &["BT /F1 12 Tf 72 720 Td (Still readable.) Tj ET"]);
+    let pdf = std::fs::read_to_string(&input_file).unwrap();
+    let xref = pdf.find("xref\n0 ").unwrap() + "xref\n0 ".len();
+    let count_end = xref + pdf[xref..].find('\n').unwrap();
+    std::fs::write(&input_file, format!("{}139999999999{}", &pdf[..xref], &pdf[count_end..])).unwrap();
+
+    let mut cmd = Command::cargo_bin("c2md").unwrap();
+    cmd.arg(&input_file).timeout(std::time::Duration::from_secs(20));
+    cmd.assert().success().stdout(predicate::str::contains("Still readable."));
+}
+
+#[test]
+fn test_pdf_without_text() {
+    let temp_dir = TempDir::new().unwrap();
+    let input_file = temp_dir.path().join("blank.pdf");
+    write_pdf(&input_file, &["0 0 m 100 100 l S"]);
+
+    let mut cmd = Command::cargo_bin("c2md").unwrap();
+    cmd.arg(&input_file).args(["--frontmatter", "none"]).env("RUST_LOG", "off");
+    cmd.assert().success().stdout("");
+
+    let mut cmd = Command::cargo_bin("c2md").unwrap();
+    cmd.arg(&input_file).args(["--frontmatter", "none"]).env("RUST_LOG", "warn");
+    cmd.assert().success().stdout(predicate::str::contains("no text layer found"));
+}
+
+#[test]
+fn test_pdf_scanned_pages() {
+    let temp_dir = TempDir::new().unwrap();
+    let input = temp_dir.path().join("scan.pdf");
+    let image = |x: u32, y: u32, w: u32, h: u32| format!("q {} 0 0 {} {} {} cm BI /W 1 /H 1 /CS /G /BPC 8 ID \x7f EI Q", w, h, x, y);
+    let pages = [
+        "BT /F1 12 Tf 72 720 Td (A page with a real text layer.) Tj ET".to_string(),
+        image(0, 0, 612, 792),
+        format!("BT /F1 12 Tf 72 720 Td (A page with a small figure.) Tj ET {}", image(72, 400, 100, 100)),
+        format!("{} BT /F1 12 Tf 72 40 Td (Scan 4) Tj ET", image(0, 0, 612, 792)),
+    ];
+    let pages: Vec<&str> = pages.iter().map(String::as_str).collect();
+    write_pdf(&input, &pages);
+
+    // Without tesseract on the PATH the scanned pages are reported, not OCRed
+    let empty_path = temp_dir.path().join("bin");
+    std::fs::create_dir(&empty_path).unwrap();
+    let run = |flags: &[&str]| {
+        let mut cmd = Command::cargo_bin("c2md").unwrap();
+        cmd.arg(&input)
+            .args(["--frontmatter", "none"])
+            .args(flags)
+            .env("PATH", &empty_path)
+            .env("RUST_LOG", "warn");
+        String::from_utf8(cmd.assert().success().get_output().stdout.clone()).unwrap()
+    };
+
+    let output = run(&[]);
+    assert!(output.contains("A page with a real text layer."));
+    assert!(output.contains("A page with a small figure."));
+    assert!(output.contains("tesseract is not installed, skipping OCR of pages 2,4"), "{}", output);
+    assert!(!output.contains("<!-- page"));
+    assert!(!output.contains("OCR functionality"));
+
+    let output = run(&["--ocr"]);
+    assert!(output.contains("skipping OCR of pages 1-4"), "{}", output);
+}
+
+/// An 8x1 bilevel TIFF with one packed row of pixels.
+fn write_bilevel_tiff(path: &std::path::Path, photometric: u16, row: u8) {
+    let mut data = b"II*\0".to_vec();
+    data.extend(10u32.to_le_bytes());
+    data.extend([row, 0]);
+    // Width, height, bits per sample, compression, photometric
+    // interpretation, strip offset, rows per strip and strip byte count
+    let entries: [(u16, u16, u32); 8] =
+        [(256, 3, 8), (257, 3, 1), (258, 3, 1), (259, 3, 1), (262, 3, photometric as u32), (273, 4, 8), (278, 3, 1), (279, 4, 1)];
+    data.extend((entries.len() as u16).to_le_bytes());
+    for (tag, kind, value) in entries {
+        data.extend(tag.to_le_bytes());
+        data.extend(kind.to_le_bytes());
+        data.extend(1u32.to_le_bytes());
+        data.extend(value.to_le_bytes());
+    }
+    data.extend(0u32.to_le_bytes());
+    std::fs::write(path, data).unwrap();
+}
+
+#[cfg(unix)]
+#[test]
+fn test_tiff_photometric_interpretation() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let temp_dir = TempDir::new().unwrap();
+    let seen = temp_dir.path().join("seen.png");
+    // A stand-in tesseract that keeps the page it was given
+    let bin = temp_dir.path().join("bin");
+    std::fs::create_dir(&bin).unwrap();
+    let tesseract = bin.join("tesseract");
+    std::fs::write(&tesseract, format!("#!/bin/sh\ncp \"$1\" '{}'\necho 'Scanned.'\n", seen.display())).unwrap();
+    std::fs::set_permissions(&tesseract, std::fs::Permissions::from_mode(0o755)).unwrap();
+    let path = std::env::join_paths(std::iter::once(bin).chain(std::env::split_paths(&std::env::var_os("PATH").unwrap()))).unwrap();
+
+    // The same row, black then seven white pixels, in both interpretations
+    for (photometric, row) in [(0, 0b1000_0000), (1, 0b0111_1111)] {
+        let input = temp_dir.path().join(format!("fax-{}.tif", photometric));
+        write_bilevel_tiff(&input, photometric, row);
+        let mut cmd = Command::cargo_bin("c2md").unwrap();
+        cmd.arg(&input).args(["--frontmatter", "none", "--ocr", "--pages", "1"]).env("PATH", &path);
+        cmd.assert().success().stdout(predicate::str::contains("Scanned."));
+
+        let page = image::open(&seen).unwrap().to_luma8();
+        assert_eq!(page.get_pixel(0, 0).0, [0], "photometric {}", photometric);
+        assert_eq!(page.get_pixel(1, 0).0, [255], "photometric {}", photometric);
+    }
+}
+
+#[test]
+fn test_pdf_page_selection() {
+    let temp_dir = TempDir::new().unwrap();
+    let input = temp_dir.path().join("report.pdf");
+    let pages: Vec<String> = (1..=4).map(|n| format!("BT /F1 12 Tf 72 720 Td (Text of page {}.) Tj ET", n)).collect();
+    let pages: Vec<&str> = pages.iter().map(String::as_str).collect();
+    write_pdf(&input, &pages);
+
+    let output = convert_pdf(&input, &["--pages", "1,3-"]);
+    assert!(output.contains("Text of page 1."));
+    assert!(!output.contains("Text of page 2."));
+    assert!(output.contains("Text of page 3."));
+    assert!(output.contains("Text of page 4."));
+
+    let mut cmd = Command::cargo_bin("c2md").unwrap();
+    cmd.arg(&input).args(["--pages", "2-3"]);
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("pages: \"2-3\""))
+        .stdout(predicate::str::contains("Text of page 2."))
+        .stdout(predicate::str::contains("Text of page 1.").not());
+
+    let mut cmd = Command::cargo_bin("c2md").unwrap();
+    cmd.arg(&input).args(["--pages", "5-"]);
+    cmd.assert().failure().stdout(predicate::str::contains("No selected pages; the document has 4 pages"));
+
+    let mut cmd = Command::cargo_bin("c2md").unwrap();
+    cmd.arg(&input).args(["--pages", "3-1"]);
+    cmd.assert().failure().stdout(predicate::str::contains("Invalid page range: 3-1"));
+}
+
+#[test]
+fn test_pdf_running_headers() {
+    let temp_dir = TempDir::new().unwrap();
+    let input = temp_dir.path().join("confidential.pdf");
+    let topics = ["Revenue grew in every region.", "Costs were flat.", "Hiring slowed down.", "Outlook remains positive."];
+    let pages: Vec<String> = (1..=4)
+        .map(|n| {
+            format!(
+                "BT /F1 9 Tf 72 770 Td (ACME Corp Confidential) Tj ET \
+                 BT /F1 12 Tf 72 680 Td ({}) Tj ET \
+                 BT /F1 9 Tf 72 30 Td (Company Confidential - Page {n} of 4) Tj ET",
+                topics[n - 1]
+            )
+        })
+        .collect();
+    let pages: Vec<&str> = pages.iter().map(String::as_str).collect();
+    write_pdf(&input, &pages);
+
+    let output = convert_pdf(&input, &[]);
+    assert!(!output.contains("ACME Corp Confidential"));
+    assert!(!output.contains("Company Confidential"));
+    for topic in topics {
+        assert!(output.contains(topic));
+    }
+
+    // Lone page numbers go once they follow the pages, wherever they sit
+    let input = temp_dir.path().join("numbered.pdf");
+    let folios = ["- i -", "ii", "iii"];
+    let pages: Vec<String> = folios
+        .iter()
+        .zip([770, 30, 30])
+        .zip(topics)
+        .map(|((folio, y), topic)| format!("BT /F1 12 Tf 72 700 Td ({}) Tj ET BT /F1 9 Tf 300 {} Td ({}) Tj ET", topic, y, folio))
+        .collect();
+    let pages: Vec<&str> = pages.iter().map(String::as_str).collect();
+    write_pdf(&input, &pages);
+    let output = convert_pdf(&input, &[]);
+    assert!(output.contains("Hiring slowed down."));
+    for folio in folios {
+        assert!(!output.contains(&format!("{}\n", folio)), "{}", output);
+    }
+
+    // A number or word ending a single page is content
+    let input = temp_dir.path().join("answers.pdf");
+    write_pdf(
+        &input,
+        &[
+            "BT /F1 12 Tf 72 700 Td (The answer is) Tj ET BT /F1 12 Tf 72 680 Td (42) Tj ET",
+            "BT /F1 12 Tf 72 700 Td (Stir the) Tj ET BT /F1 12 Tf 72 680 Td (mix) Tj ET",
+        ],
+    );
+    let output = convert_pdf(&input, &[]);
+    assert!(output.contains("42"), "{}", output);
+    assert!(output.contains("mix"), "{}", output);
+
+    let input = temp_dir.path().join("single.pdf");
+    write_pdf(&input, &["BT /F1 12 Tf 72 700 Td (Only page.) Tj ET BT /F1 9 Tf 300 30 Td (- 7 -) Tj ET"]);
+    let output = convert_pdf(&input, &[]);
+    assert!(output.contains("Only page."));
+    assert!(output.contains("- 7 -"), "{}", output);
+}
+
+#[test]
+fn test_pdf_outline_headings() {
+    let temp_dir = TempDir::new().unwrap();
+    let input = temp_dir.path().join("manual.pdf");
+    // Pages are objects 7 and 9; the outline starts at 11
+    write_pdf_with(
+        &input,
+        &[
+            "BT /F1 24 Tf 72 740 Td (Draft Copy) Tj ET BT /F1 11 Tf 72 720 Td (Getting Started) Tj ET \
+             BT /F1 11 Tf 72 690 Td (Install the tool first.) Tj ET",
+            "BT /F1 11 Tf 72 700 Td (1.1 Details) Tj ET BT /F1 11 Tf 72 680 Td (Details follow here.) Tj ET \
+             BT /F1 11 Tf 72 400 Td (Closing words.) Tj ET",
+        ],
+        "/Outlines 11 0 R /Names << /Dests << /Names [(details) [9 0 R /XYZ 0 712 0]] >> >> ",
+        &[
+            "<< /Type /Outlines /First 12 0 R /Last 14 0 R /Count 3 >>",
+            "<< /Title (Getting Started) /Parent 11 0 R /Next 14 0 R /First 13 0 R /Last 13 0 R /Dest [7 0 R /XYZ 0 730 0] >>",
+            "<< /Title <FEFF00440065007400610069006C0073> /Parent 12 0 R /A << /S /GoTo /D (details) >> >>",
+            "<< /Title (Summary) /Parent 11 0 R /Prev 12 0 R /Dest [9 0 R /FitH 500] >>",
+        ],
+    );
+
+    let output = convert_pdf(&input, &[]);
+    assert!(output.starts_with(
+        "## Contents\n\n- [Getting Started](#getting-started)\n  - [1.1 Details](#1-1-details)\n- [Summary](#summary)\n"
+    ));
+    // The outline alone decides the headings; large text is not one
+    assert!(output.contains(
+        "Draft Copy\n\n# Getting Started\n\nInstall the tool first.\n\n## 1.1 Details\n\nDetails follow here.\n\n# Summary\n\nClosing words."
+    ));
+}
+
+#[test]
+fn test_pdf_metadata() {
+    let temp_dir = TempDir::new().unwrap();
+    let input = temp_dir.path().join("review.pdf");
+    let xmp = r#"<?xpacket begin="" id="W5M0MpCehiHzreSzNTczkc9d"?>
+<x:xmpmeta xmlns:x="adobe:ns:meta/"><rdf:RDF xmlns:rdf="http://www.w3.org/1999/02/22-rdf-syntax-ns#">
+<rdf:Description rdf:about="" xmlns:dc="http://purl.org/dc/elements/1.1/" xmlns:pdf="http://ns.adobe.com/pdf/1.3/" pdf:Producer="Report Writer 2">
+<dc:title><rdf:Alt><rdf:li xml:lang="x-default">Annual Review</rdf:li></rdf:Alt></dc:title>
+<dc:subject><rdf:Bag><rdf:li>finance</rdf:li><rdf:li>annual</rdf:li></rdf:Bag></dc:subject>
+</rdf:Description></rdf:RDF></x:xmpmeta>
+<?xpacket end="w"?>"#;
+    let metadata = format!("<< /Type /Metadata /Subtype /XML /Length {} >>\nstream\n{}\nendstream", xmp.len() + 1, xmp);
+    write_pdf_with(
+        &input,
+        &["BT /F1 12 Tf 72 700 Td (Results were good.) Tj ET"],
+        "/Metadata 9 0 R ",
+        &[
+            metadata.as_str(),
+            "<< /Title (Old Title) /Author <FEFF005A006F00EB0020005700720069007400650072> /CreationDate (D:20230405233000-02'00') >>",
+        ],
+    );
+    // The trailer follows the cross-reference table, so offsets stay valid
+    let pdf = std::fs::read(&input).unwrap();
+    let pdf = String::from_utf8_lossy(&pdf).replace("/Root 1 0 R", "/Root 1 0 R /Info 10 0 R");
+    std::fs::write(&input, pdf).unwrap();
+
+    let mut cmd = Command::cargo_bin("c2md").unwrap();
+    cmd.arg(input.to_str().unwrap());
+    let output = String::from_utf8(cmd.assert().success().get_output().stdout.clone()).unwrap();
+    // XMP wins over /Info, which fills in the rest
+    assert!(output.contains("title: \"Annual Review\""), "{}", output);
+    assert!(output.contains("author: \"Zoë Writer\""));
+    assert!(output.contains("date: \"2023-04-06\""));
+    assert!(output.contains("- **keywords**: finance, annual"));
+    assert!(output.contains("- **producer**: Report Writer 2"));
+    assert!(output.contains("- **pages**: 1"));
+    assert!(output.contains("- **encrypted**: false"));
+    assert!(!output.contains("PDF Document"));
+}
+
+fn rc4(key: &[u8], data: &[u8]) -> Vec<u8> {
+    let mut state: Vec<u8> = (0..=255).collect();
+    let mut j = 0u8;
+    for i in 0..256 {
+        j = j.wrapping_add(state[i]).wrapping_add(key[i % key.len()]);
+        state.swap(i, j as usize);
+    }
+    let (mut i, mut j) = (0u8, 0u8);
+    data.iter()
+        .map(|byte| {
+            i = i.wrapping_add(1);
+            j = j.wrapping_add(state[i as usize]);
+            state.swap(i as usize, j as usize);
+            byte ^ state[state[i as usize].wrapping_add(state[j as usize]) as usize]
+        })
+        .collect()
+}
+
+/// A one-page PDF encrypted with 128-bit RC4 (revision 3) by the Standard
+/// security handler.
+fn write_encrypted_pdf(path: &std::path::Path, text: &str, user: &str, owner: &str) {
+    const PADDING: [u8; 32] = [
+        0x28, 0xBF, 0x4E, 0x5E, 0x4E, 0x75, 0x8A, 0x41, 0x64, 0x00, 0x4E, 0x56, 0xFF, 0xFA, 0x01, 0x08, 0x2E, 0x2E, 0x00,
+        0xB6, 0xD0, 0x68, 0x3E, 0x80, 0x2F, 0x0C, 0xA9, 0xFE, 0x64, 0x53, 0x69, 0x7A,
+    ];
+    let pad = |password: &str| [password.as_bytes(), &PADDING].concat()[..32].to_vec();
+    let xor = |key: &[u8], i: u8| key.iter().map(|b| b ^ i).collect::<Vec<u8>>();
+    let id = b"0123456789abcdef";
+    let permissions: i32 = -4;
+
+    let mut digest = md5::compute(pad(owner)).0;
+    for _ in 0..50 {
+        digest = md5::compute(digest).0;
+    }
+    let mut o = rc4(&digest, &pad(user));
+    for i in 1..20 {
+        o = rc4(&xor(&digest, i), &o);
+    }
+    let mut key = md5::compute([pad(user), o.clone(), permissions.to_le_bytes().to_vec(), id.to_vec()].concat()).0;
+    for _ in 0..50 {
+        key = md5::compute(key).0;
+    }
+    let mut u = md5::compute([&PADDING[..], id].concat()).0.to_vec();
+    for i in 0..20 {
+        u = rc4(&xor(&key, i), &u);
+    }
+    u.extend([0; 16]);
+    let encrypt = |number: u32, data: &[u8]| {
+        let object_key = md5::compute([&key[..], &number.to_le_bytes()[..3], &[0, 0]].concat()).0;
+        rc4(&object_key, data)
+    };
+
+    let content = format!("BT /F1 12 Tf 72 700 Td ({}) Tj ET", text);
+    let content = encrypt(5, content.as_bytes());
+    let title = encrypt(6, b"Board Minutes");
+    let encrypt = format!("<< /Filter /Standard /V 2 /R 3 /Length 128 /O <{}> /U <{}> /P {} >>", hex(&o), hex(&u), permissions);
+    write_encrypted_objects(path, &content, &title, &encrypt, id);
+}
+
+/// A one-page PDF whose content stream (object 5) and `/Info` title
+/// (object 6) are already encrypted under the `/Encrypt` dictionary.
+fn write_encrypted_objects(path: &std::path::Path, content: &[u8], title: &[u8], encrypt: &str, id: &[u8]) {
+    let mut objects: Vec<Vec<u8>> = vec![
+        b"<< /Type /Catalog /Pages 2 0 R >>".to_vec(),
+        b"<< /Type /Pages /Kids [3 0 R] /Count 1 >>".to_vec(),
+        b"<< /Type /Page /Parent 2 0 R /MediaBox [0 0 612 792] /Resources << /Font << /F1 4 0 R >> >> /Contents 5 0 R >>".to_vec(),
+        b"<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica /Encoding /WinAnsiEncoding >>".to_vec(),
+        [format!("<< /Length {} >>\nstream\n", content.len()).as_bytes(), content, b"\nendstream"].concat(),
+        format!("<< /Title <{}> >>", hex(title)).into_bytes(),
+        encrypt.as_bytes().to_vec(),
+    ];
+
+    let mut pdf = b"%PDF-1.7\n".to_vec();
+    let mut offsets = Vec::new();
+    for (i, object) in objects.iter_mut().enumerate() {
+        offsets.push(pdf.len());
+        pdf.extend_from_slice(format!("{} 0 obj\n", i + 1).as_bytes());
+        pdf.append(object);
+        pdf.extend_from_slice(b"\nendobj\n");
+    }
+    let xref = pdf.len();
+    pdf.extend_from_slice(format!("xref\n0 {}\n0000000000 65535 f \n", offsets.len() + 1).as_bytes());
+    for offset in &offsets {
+        pdf.extend_from_slice(format!("{:010} 00000 n \n", offset).as_bytes());
+    }
+    pdf.extend_from_slice(
+        format!(
+            "trailer\n<< /Size {} /Root 1 0 R /Info 6 0 R /Encrypt 7 0 R /ID [<{}> <{}>] >>\nstartxref\n{}\n%%EOF\n",
+            offsets.len() + 1,
+            hex(id),
+            hex(id),
+            xref
+        )
+        .as_bytes(),
+    );
+    std::fs::write(path, pdf).unwrap();
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn unhex(hex: &str) -> Vec<u8> {
+    (0..hex.len()).step_by(2).map(|i| u8::from_str_radix(&hex[i..i + 2], 16).unwrap()).collect()
+}
+
+#[test]
+fn test_pdf_encrypted() {
+    let temp_dir = TempDir::new().unwrap();
+    let input = temp_dir.path().join("minutes.pdf");
+    write_encrypted_pdf(&input, "The board approved the budget.", "reader", "chair");
+
+    let mut cmd = Command::cargo_bin("c2md").unwrap();
+    cmd.arg(&input).env_remove("C2MD_PDF_PASSWORD");
+    cmd.assert()
+        .failure()
+        .stdout(predicate::str::contains("Encrypted PDF: a password is required"))
+        .stdout(predicate::str::contains("The board approved").not());
+
+    let mut cmd = Command::cargo_bin("c2md").unwrap();
+    cmd.arg(&input).args(["--password", "wrong"]);
+    cmd.assert().failure().stdout(predicate::str::contains("Encrypted PDF: incorrect password"));
+
+    // The user password, the owner password from the environment, a password file
+    let mut cmd = Command::cargo_bin("c2md").unwrap();
+    cmd.arg(&input).args(["--password", "reader"]);
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("title: \"Board Minutes\""))
+        .stdout(predicate::str::contains("- **title**: Board Minutes"))
+        .stdout(predicate::str::contains("- **encrypted**: true"))
+        .stdout(predicate::str::contains("The board approved the budget."));
+
+    let mut cmd = Command::cargo_bin("c2md").unwrap();
+    cmd.arg(&input).env("C2MD_PDF_PASSWORD", "chair");
+    cmd.assert().success().stdout(predicate::str::contains("The board approved the budget."));
+
+    let password_file = temp_dir.path().join("password.txt");
+    std::fs::write(&password_file, "reader\n").unwrap();
+    let mut cmd = Command::cargo_bin("c2md").unwrap();
+    cmd.arg(&input).arg("--password-file").arg(&password_file).env_remove("C2MD_PDF_PASSWORD");
+    cmd.assert().success().stdout(predicate::str::contains("The board approved the budget."));
+}
+
+#[test]
+fn test_validate_encrypted_pdf() {
+    use c2md::validator::{validate_file, validate_file_with, ValidateOptions};
+
+    let temp_dir = TempDir::new().unwrap();
+    let input = temp_dir.path().join("minutes.pdf");
+    write_encrypted_pdf(&input, "The board approved the budget.", "reader", "chair");
+
+    assert!(matches!(validate_file(&input), Err(c2md::C2mdError::Encrypted(_))));
+    let options = ValidateOptions { password: Some("reader".to_string()), ..Default::default() };
+    let info = validate_file_with(&input, &options).unwrap();
+    assert_eq!(info.format, "pdf");
+    assert!(info.is_valid);
+}
+
+#[cfg(unix)]
+#[test]
+fn test_pdf_encrypted_ocr() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let temp_dir = TempDir::new().unwrap();
+    let input = temp_dir.path().join("scan.pdf");
+    write_encrypted_pdf(&input, "Text layer.", "reader", "chair");
+
+    // Stand-ins that only rasterize when given the password, and "read" any image
+    let bin = temp_dir.path().join("bin");
+    std::fs::create_dir(&bin).unwrap();
+    let scripts = [
+        ("pdftoppm", "#!/bin/sh\nfor arg; do last=$arg; done\ncase \" $* \" in\n  *\" -upw reader \"*) : > \"$last.png\" ;;\n  *) echo 'Incorrect password' >&2; exit 1 ;;\nesac\n"),
+        ("tesseract", "#!/bin/sh\necho 'Recognized scan text.'\n"),
+    ];
+    for (name, script) in scripts {
+        let path = bin.join(name);
+        std::fs::write(&path, script).unwrap();
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755)).unwrap();
+    }
+
+    let mut cmd = Command::cargo_bin("c2md").unwrap();
+    cmd.arg(&input)
+        .args(["--frontmatter", "none", "--ocr", "--password", "reader"])
+        .env("PATH", &bin)
+        .env("RUST_LOG", "warn");
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("Recognized scan text."))
+        .stdout(predicate::str::contains("OCR failed").not());
+}
+
+// Encrypted with Python's `cryptography` package rather than our own code:
+// user password "reader", owner password "chair", /P -4, the /ID below.
+// AESV2 derives per-object keys from the file key; AESV3 (revision 6) uses
+// the 32-byte file key 0x40..0x5f directly, unwrapped from /UE or /OE.
+const AES_ID: &[u8] = b"0123456789abcdef";
+const AESV2_ENCRYPT: &str = "<< /Filter /Standard /V 4 /R 4 /Length 128 \
+    /CF << /StdCF << /CFM /AESV2 /AuthEvent /DocOpen /Length 16 >> >> /StmF /StdCF /StrF /StdCF \
+    /O <9eb4274c564523e9f6f4bd9c64801374935acc9d0911cfa2c27697b39d338a59> \
+    /U <a877cd47932565b0f2ba30dddfe1693c00000000000000000000000000000000> /P -4 >>";
+const AESV2_CONTENT: &str = "000102030405060708090a0b0c0d0e0f2a7b383d3158b7f5ea911802f36682f781dc25af39e307d5c3008a4b0532691c\
+    0fdc2ace8395bbf9659113e81a5b621a9f9534ab603b68e956500d5db6df43d2ce4d5017a93b46ecf348c1e8f6612845";
+const AESV2_TITLE: &str = "101112131415161718191a1b1c1d1e1fa5941439bcf0ea813b3a0f97eb3b88b1";
+const AESV3_ENCRYPT: &str = "<< /Filter /Standard /V 5 /R 6 /Length 256 \
+    /CF << /StdCF << /CFM /AESV3 /AuthEvent /DocOpen /Length 32 >> >> /StmF /StdCF /StrF /StdCF \
+    /O <521b65a44e442fee98d84bd506af287c5d23654eaec91993556453e5ce83e5526f7673616c7430316f6b73616c743031> \
+    /U <44bf1bda4c983116ef52435312df8add8c1f197c42c01d30099f3c123661a151757673616c743031756b73616c743031> \
+    /OE <97b39ba807574e799c52f0ec5642d6037ae1ff2cb9e817f620462ff3f20f26a4> \
+    /UE <e3a1dea60e36ca0cf2eaa69855649cd0b1fdf596e136f97601bee4775ad42cc5> \
+    /Perms <c87af0d672aa235ecd6dd15d888ee837> /P -4 >>";
+const AESV3_CONTENT: &str = "000102030405060708090a0b0c0d0e0f1057fc05ce096a2a01fc7c102d8cc911487a2512f72443a5452fad10247ad670\
+    c46ab9119fe7dcd6b236c9d7e0652c834a4edb22370c47ad031a1f8d6a35674bf126cd92e6b90b2bccadddce4833c289";
+const AESV3_TITLE: &str = "101112131415161718191a1b1c1d1e1f4b8d1f525d493f54511ae3a8dd16efc2fbbcb74741d0041f31b5224ef94d5416";
+
+#[test]
+fn test_pdf_encrypted_aes() {
+    let temp_dir = TempDir::new().unwrap();
+    let fixtures = [
+        ("aes128.pdf", AESV2_ENCRYPT, AESV2_CONTENT, AESV2_TITLE, "Finance Minutes", "The treasurer presented the accounts."),
+        ("aes256.pdf", AESV3_ENCRYPT, AESV3_CONTENT, AESV3_TITLE, "Committee Minutes", "The committee adopted the new bylaws."),
+    ];
+
+    for (name, encrypt, content, title, expected_title, expected_text) in fixtures {
+        let input = temp_dir.path().join(name);
+        write_encrypted_objects(&input, &unhex(content), &unhex(title), encrypt, AES_ID);
+
+        let mut cmd = Command::cargo_bin("c2md").unwrap();
+        cmd.arg(&input).args(["--password", "wrong"]).env_remove("C2MD_PDF_PASSWORD");
+        cmd.assert().failure().stdout(predicate::str::contains("Encrypted PDF: incorrect password"));
+
+        for password in ["reader", "chair"] {
+            let mut cmd = Command::cargo_bin("c2md").unwrap();
+            cmd.arg(&input).args(["--password", password]);
+            cmd.assert()
+                .success()
+                .stdout(predicate::str::contains(format!("title: \"{}\"", expected_title)))
+                .stdout(predicate::str::contains(expected_text));
+        }
+    }
+}
+
+#[test]
+fn test_pdf_annotations() {
+    let temp_dir = TempDir::new().unwrap();
+    let input = temp_dir.path().join("reviewed.pdf");
+    // The page is object 7; its annotations start at 9
+    write_pdf_pages(
+        &input,
+        &[(
+            "BT /F1 11 Tf 72 700 Td (The budget grew by ) Tj ET BT /F1 11 Tf 170 700 Td (ten percent) Tj ET \
+             BT /F1 11 Tf 72 680 Td (Costs stayed flat this year.) Tj ET",
+            "",
+            "/Annots [9 0 R 10 0 R 11 0 R] ",
+        )],
+        "",
+        &[
+            "<< /Type /Annot /Subtype /Highlight /Rect [168 696 230 711] \
+             /QuadPoints [168 711 230 711 168 696 230 696] /T (Alice) /Contents (Source?) >>",
+            "<< /Type /Annot /Subtype /Text /Rect [20 676 40 696] /T (Bob) /Contents (Check last year's figures.) >>",
+            "<< /Type /Annot /Subtype /Text /Rect [20 676 40 696] /IRT 10 0 R /T (Alice) /Contents (Done.) >>",
+        ],
+    );
+
+    let output = convert_pdf(&input, &[]);
+    assert!(output.contains("The budget grew by ==ten percent==[^1] Costs stayed flat this year.[^2]"));
+    assert!(output.contains("[^1]: **Alice:** Source?"));
+    assert!(output.contains("[^2]: **Bob:** Check last year's figures.\n\n    **Alice:** Done."));
+
+    let output = convert_pdf(&input, &["--to", "commonmark"]);
+    assert!(output.contains("<mark>ten percent</mark>"));
+
+    let output = convert_pdf(&input, &["--annotations", "only"]);
+    assert_eq!(
+        output.trim(),
+        "## Page 1\n\n- ==ten percent== — **Alice:** Source?\n- **Bob:** Check last year's figures.\n  - **Alice:** Done."
+    );
+
+    let output = convert_pdf(&input, &["--annotations", "none"]);
+    assert!(output.contains("grew by ten percent"));
+    assert!(!output.contains("Bob"));
+
+    // A PDF without review notes gives an empty document
+    let plain = temp_dir.path().join("plain.pdf");
+    write_pdf(&plain, &["BT /F1 11 Tf 72 700 Td (Nothing to review.) Tj ET"]);
+    assert_eq!(convert_pdf(&plain, &["--annotations", "only"]), "");
+}
+
+#[test]
+fn test_pdf_form_fields() {
+    let temp_dir = TempDir::new().unwrap();
+    let input = temp_dir.path().join("application.pdf");
+    // The page is object 7; the fields start at 9
+    write_pdf_with(
+        &input,
+        &["BT /F1 11 Tf 72 700 Td (Application form) Tj ET"],
+        "/AcroForm << /Fields [9 0 R 10 0 R 11 0 R 12 0 R 13 0 R] >> ",
+        &[
+            "<< /FT /Tx /T (name) /TU (Full name) /V (Ada Lovelace) >>",
+            "<< /FT /Btn /T (subscribe) /V /Yes /AS /Yes >>",
+            "<< /FT /Btn /Ff 49152 /T (plan) /V /Pro /Kids [14 0 R 15 0 R] >>",
+            "<< /FT /Ch /T (country) /V (tr) /Opt [[(tr) (Turkey)] [(de) (Germany)]] >>",
+            "<< /T (address) /Kids [16 0 R] >>",
+            "<< /Subtype /Widget /Parent 11 0 R /AS /Off >>",
+            "<< /Subtype /Widget /Parent 11 0 R /AS /Pro >>",
+            "<< /FT /Tx /T (city) /Parent 13 0 R /V (London) >>",
+        ],
+    );
+
+    let output = convert_pdf(&input, &[]);
+    assert!(output.contains("## Form fields"));
+    assert!(output.contains("Ada Lovelace"));
+    for (field, value) in [("Full name", "Ada Lovelace"), ("subscribe", "Yes"), ("plan", "Pro"), ("country", "Turkey"), ("address.city", "London")] {
+        let row = output.lines().find(|line| line.contains(field)).unwrap();
+        assert!(row.starts_with('|') && row.contains(value), "{}", row);
+    }
+
+    let output = convert_pdf(&input, &["--forms", "list"]);
+    assert!(output.contains("<dl>\n<dt>Full name</dt>\n<dd>Ada Lovelace</dd>\n"));
+
+    // The values are part of the metadata too
+    let mut cmd = Command::cargo_bin("c2md").unwrap();
+    cmd.arg(input.to_str().unwrap()).args(["--forms", "none"]);
+    let output = String::from_utf8(cmd.assert().success().get_output().stdout.clone()).unwrap();
+    assert!(!output.contains("## Form fields"));
+    assert!(output.contains("- **form.address.city**: London"));
+}
+
+#[test]
+fn test_pdf_images() {
+    let temp_dir = TempDir::new().unwrap();
+    let input = temp_dir.path().join("chart.pdf");
+    let assets = temp_dir.path().join("assets");
+    let hex = |data: &[u8]| data.iter().map(|byte| format!("{:02X}", byte)).collect::<String>();
+    let image = |dict: &str, data: &[u8]| {
+        let data = hex(data);
+        format!("<< /Type /XObject /Subtype /Image {} /Length {} >>\nstream\n{}>\nendstream", dict, data.len() + 1, data)
+    };
+    let pixels: Vec<u8> = (0..2).flat_map(|_| (0..40u8).flat_map(|x| [x * 6, 0, 255 - x * 6])).collect();
+    let mut jpeg = Vec::new();
+    image::codecs::jpeg::JpegEncoder::new(&mut jpeg).encode(&[128; 16 * 16], 16, 16, image::ColorType::L8).unwrap();
+    // The page is object 7; the images are 9 and 10
+    write_pdf_pages(
+        &input,
+        &[(
+            "BT /F1 11 Tf 72 700 Td (Before the chart.) Tj ET q 100 0 0 50 72 600 cm /Im1 Do Q \
+             BT /F1 11 Tf 72 500 Td (After the chart.) Tj ET q 60 0 0 60 72 300 cm /Im2 Do Q",
+            "/XObject << /Im1 9 0 R /Im2 10 0 R >> ",
+            "",
+        )],
+        "",
+        &[
+            &image("/Width 40 /Height 2 /ColorSpace /DeviceRGB /BitsPerComponent 8 /Filter /ASCIIHexDecode", &pixels),
+            &image("/Width 16 /Height 16 /ColorSpace /DeviceGray /BitsPerComponent 8 /Filter [/ASCIIHexDecode /DCTDecode]", &jpeg),
+        ],
+    );
+
+    let output = convert_pdf(&input, &["--assets-dir", assets.to_str().unwrap(), "--image-max-width", "20"]);
+    let png = assets.join("chart-image1.png");
+    let jpg = assets.join("chart-image2.jpg");
+    assert!(output.contains(&format!(
+        "Before the chart.\n\n![Image]({})\n\nAfter the chart.\n\n![Image]({})",
+        png.display(),
+        jpg.display()
+    )));
+    // Flate and uncompressed samples become PNGs, resized to the maximum width
+    let decoded = image::open(&png).unwrap().to_rgb8();
+    assert_eq!(decoded.dimensions(), (20, 1));
+    assert!(decoded.get_pixel(0, 0)[2] > 200);
+    // JPEGs are copied as they are
+    assert_eq!(std::fs::read(&jpg).unwrap(), jpeg);
+}
+
+#[test]
+fn test_pdf_text_cleanup() {
+    let temp_dir = TempDir::new().unwrap();
+    let input = temp_dir.path().join("hyphens.pdf");
+    let lines = [
+        "(The conver-) Tj",
+        "(sion of documents is a well-) Tj",
+        "(known problem for e-) Tj",
+        "(mail archives and Anglo-) Tj",
+        "(Saxon texts alike; hyphen\\255) Tj",
+        "(ation and non\\240breaking spaces) Tj",
+        "/F4 12 Tf (need \\256xing too.) Tj",
+    ];
+    let body: Vec<String> = lines.iter().map(|line| format!("{} T*", line)).collect();
+    let page = format!(
+        "BT /F1 12 Tf 14 TL 72 720 Td {} ET BT /F1 12 Tf 72 600 Td (A well-known tool.) Tj ET",
+        body.join(" ")
+    );
+    write_pdf(&input, &[&page]);
+
+    let output = convert_pdf(&input, &[]);
+    assert!(output.contains(
+        "The conversion of documents is a well-known problem for e-mail archives and Anglo-Saxon texts alike; \
+         hyphenation and non breaking spaces need fixing too."
+    ));
+
+    let config = temp_dir.path().join("c2md.yaml");
+    let mut settings = c2md::Config::default();
+    settings.cleanup.dehyphenate = false;
+    settings.cleanup.ligatures = false;
+    settings.save(&config).unwrap();
+    let output = convert_pdf(&input, &["--config", config.to_str().unwrap()]);
+    assert!(output.contains("The conver- sion of documents"));
+    assert!(output.contains("\u{fb01}xing"));
+}